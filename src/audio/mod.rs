@@ -0,0 +1,106 @@
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+/// Turns a per-cycle output level into PCM samples at a lower rate.
+///
+/// Every sample is the average of the cycle levels since the previous one, which acts
+/// as a simple low-pass filter against aliasing. Levels are nominally in -1.0..=1.0 and
+/// are clipped to that range when converted.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Downsampler {
+    clock_hz: u32,
+    sample_rate: u32,
+    phase: u64,
+    sum: f32,
+    count: u32,
+}
+
+impl Downsampler {
+    /// Produce `sample_rate` samples per second from a chip clocked at `clock_hz`
+    pub fn new(clock_hz: u32, sample_rate: u32) -> Self {
+        Self {
+            clock_hz,
+            sample_rate,
+            phase: 0,
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Feed one cycle's output level, returning a sample when one is due
+    pub fn push(&mut self, level: f32) -> Option<i16> {
+        self.sum += level;
+        self.count += 1;
+        self.phase += self.sample_rate as u64;
+        if self.phase < self.clock_hz as u64 {
+            return None;
+        }
+        self.phase -= self.clock_hz as u64;
+        let average = self.sum / self.count as f32;
+        self.sum = 0.0;
+        self.count = 0;
+        Some((average.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+    }
+}
+
+/// Write 16-bit PCM samples as a WAV file. Multi-channel samples are interleaved.
+pub fn write_wav<W: Write>(
+    mut writer: W,
+    sample_rate: u32,
+    channels: u16,
+    samples: &[i16],
+) -> io::Result<()> {
+    let data_size = samples.len() as u32 * 2;
+    let block_align = channels * 2;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // Uncompressed PCM
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downsampler_and_wav_header() {
+        let mut downsampler = Downsampler::new(1000, 100);
+        let samples: Vec<i16> = (0..1000)
+            .filter_map(|cycle| downsampler.push(if cycle % 10 < 5 { 1.0 } else { 0.0 }))
+            .collect();
+        assert_eq!(samples.len(), 100);
+        assert!(samples.iter().all(|&sample| sample == i16::MAX / 2));
+        assert_eq!(downsampler.push(2.0), None);
+
+        let mut wav = Vec::new();
+        write_wav(&mut wav, 44_100, 2, &[1, -1]).unwrap();
+        assert_eq!(wav.len(), 48);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()), 40);
+        assert_eq!(u16::from_le_bytes(wav[22..24].try_into().unwrap()), 2);
+        assert_eq!(
+            u32::from_le_bytes(wav[28..32].try_into().unwrap()),
+            44_100 * 4
+        );
+        assert_eq!(&wav[44..], [0x01, 0x00, 0xFF, 0xFF]);
+    }
+}
@@ -0,0 +1,89 @@
+//! Run a cc65 program built for sim65, with the same options and exit codes.
+//!
+//! Everything after the program file is passed to it as arguments. The exit code is
+//! the program's own, or 0x7F for errors and 0x7E when the cycle limit is reached.
+
+use std::process::ExitCode;
+
+use mos6502_emulator::error::SimError;
+use mos6502_emulator::sim65::{SimProgram, Simulator, SIM65_ERROR, SIM65_ERROR_TIMEOUT};
+
+const USAGE: &str = "usage: mos6502-sim [-c] [-v] [-x CYCLES] FILE [ARGS...]";
+
+struct Options {
+    print_cycles: bool,
+    verbose: bool,
+    max_cycles: Option<u64>,
+    /// Program file followed by its arguments
+    args: Vec<String>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        print_cycles: false,
+        verbose: false,
+        max_cycles: None,
+        args: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "--cycles" => options.print_cycles = true,
+            "-v" | "--verbose" => options.verbose = true,
+            "-x" | "--max-cycles" => {
+                let value = args.next().ok_or(format!("{arg} needs a value"))?;
+                options.max_cycles = Some(value.parse().map_err(|_| "invalid cycle count")?);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unexpected argument {arg}")),
+            _ => {
+                options.args.push(arg);
+                options.args.extend(args);
+                break;
+            }
+        }
+    }
+    if options.args.is_empty() {
+        return Err(USAGE.into());
+    }
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<u8, Box<dyn std::error::Error>> {
+    let path = &options.args[0];
+    let program = SimProgram::parse(&std::fs::read(path)?)?;
+    if options.verbose {
+        eprintln!(
+            "Loaded '{path}' at ${:04X}-${:04X}, reset ${:04X}, {:?}",
+            program.load_address,
+            program.load_address as usize + program.data.len().max(1) - 1,
+            program.reset_address,
+            program.cpu
+        );
+    }
+    let mut simulator = Simulator::new(&program, options.args);
+    let result = simulator.run(options.max_cycles);
+    if options.print_cycles {
+        eprintln!("{} cycles", simulator.cycles());
+    }
+    Ok(result?)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(SIM65_ERROR);
+        }
+    };
+    match run(options) {
+        Ok(code) => ExitCode::from(code),
+        Err(error) => {
+            eprintln!("mos6502-sim: {error}");
+            match error.downcast_ref::<SimError>() {
+                Some(SimError::CycleLimit) => ExitCode::from(SIM65_ERROR_TIMEOUT),
+                _ => ExitCode::from(SIM65_ERROR),
+            }
+        }
+    }
+}
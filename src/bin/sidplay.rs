@@ -0,0 +1,118 @@
+//! Play a PSID/RSID file on the emulated CPU.
+//!
+//! Prints every SID register write as `cycle chip register value`, or renders the tune
+//! to a WAV file with `--wav`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+use mos6502_emulator::audio::write_wav;
+use mos6502_emulator::device::{Sid6581, SidModel};
+use mos6502_emulator::sid::{SidFile, SidPlayer};
+
+const USAGE: &str = "usage: sidplay FILE [--song N] [--seconds S] [--wav OUT] [--rate HZ] \
+                     [--model 6581|8580]";
+
+struct Options {
+    path: String,
+    song: u16,
+    seconds: f64,
+    wav: Option<String>,
+    rate: u32,
+    model: Option<SidModel>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        path: String::new(),
+        song: 0,
+        seconds: 10.0,
+        wav: None,
+        rate: 44_100,
+        model: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--song" => options.song = value()?.parse().map_err(|_| "invalid song")?,
+            "--seconds" => options.seconds = value()?.parse().map_err(|_| "invalid length")?,
+            "--wav" => options.wav = Some(value()?),
+            "--rate" => options.rate = value()?.parse().map_err(|_| "invalid rate")?,
+            "--model" => {
+                options.model = Some(match value()?.as_str() {
+                    "6581" => SidModel::Mos6581,
+                    "8580" => SidModel::Mos8580,
+                    _ => return Err("model must be 6581 or 8580".into()),
+                })
+            }
+            _ if arg.starts_with("--") || !options.path.is_empty() => {
+                return Err(format!("unexpected argument {arg}"))
+            }
+            _ => options.path = arg,
+        }
+    }
+    if options.path.is_empty() {
+        return Err(USAGE.into());
+    }
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), Box<dyn std::error::Error>> {
+    let file = SidFile::parse(&std::fs::read(&options.path)?)?;
+    let header = &file.header;
+    let clock = header.clock();
+    eprintln!(
+        "{} / {} / {} ({} songs, {:?})",
+        header.name, header.author, header.released, header.songs, clock
+    );
+
+    let sid = options.wav.as_ref().map(|_| {
+        let model = options.model.or(header.sid_model()).unwrap_or_default();
+        Sid6581::new(model, clock.clock_hz(), options.rate)
+    });
+    let mut player = SidPlayer::new(&file, options.song, sid)?;
+    player.run_cycles((options.seconds * clock.clock_hz() as f64) as u64)?;
+
+    if let Some(path) = &options.wav {
+        let samples = player
+            .sid_mut()
+            .map(Sid6581::take_samples)
+            .unwrap_or_default();
+        write_wav(
+            BufWriter::new(File::create(path)?),
+            options.rate,
+            1,
+            &samples,
+        )?;
+        return Ok(());
+    }
+    let mut out = BufWriter::new(io::stdout().lock());
+    for write in player.writes() {
+        writeln!(
+            out,
+            "{} {} {:02X} {:02X}",
+            write.cycle, write.chip, write.register, write.value
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("sidplay: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
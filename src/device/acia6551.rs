@@ -0,0 +1,470 @@
+use crate::device::serial::SerialBackend;
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Register offsets
+const DATA: u16 = 0;
+const STATUS: u16 = 1;
+const COMMAND: u16 = 2;
+const CONTROL: u16 = 3;
+
+// Status register bits
+const STATUS_PARITY_ERROR: u8 = 1 << 0;
+const STATUS_FRAMING_ERROR: u8 = 1 << 1;
+const STATUS_OVERRUN: u8 = 1 << 2;
+const STATUS_RECEIVE_FULL: u8 = 1 << 3;
+const STATUS_TRANSMIT_EMPTY: u8 = 1 << 4;
+const STATUS_CARRIER_LOST: u8 = 1 << 5;
+const STATUS_DATA_SET_NOT_READY: u8 = 1 << 6;
+const STATUS_IRQ: u8 = 1 << 7;
+
+// Command register bits
+const COMMAND_DTR: u8 = 1 << 0;
+const COMMAND_RECEIVE_IRQ_DISABLE: u8 = 1 << 1;
+const COMMAND_TRANSMIT_CONTROL: u8 = 0b11 << 2;
+const COMMAND_TRANSMIT_IRQ: u8 = 0b01 << 2;
+const COMMAND_ECHO: u8 = 1 << 4;
+const COMMAND_PARITY_ENABLE: u8 = 1 << 5;
+
+/// Baud rates selected by control register bits 0-3. Rate 0 uses the external 16x
+/// clock, which is the 1.8432 MHz crystal on nearly every board.
+const BAUD_RATES: [f64; 16] = [
+    115_200.0, 50.0, 75.0, 109.92, 134.58, 150.0, 300.0, 600.0, 1200.0, 1800.0, 2400.0, 3600.0,
+    4800.0, 7200.0, 9600.0, 19_200.0,
+];
+
+/// MOS 6551 Asynchronous Communications Interface Adapter, or the WDC 65C51.
+///
+/// Characters take as many CPU cycles as the selected baud rate and frame format need
+/// on the wire. The receiver polls the backend once per character time while DTR is on,
+/// and the transmitter only runs while RTS is low.
+///
+/// The WDC 65C51 built with [`Acia6551::wdc65c51`] has the transmit-empty bug of the
+/// real part: the TDRE bit always reads as set, transmit interrupts never fire, and
+/// writing the data register while a character is being sent cuts that character off.
+/// Software has to wait out each character itself.
+pub struct Acia6551<B: SerialBackend> {
+    backend: B,
+    clock_hz: u32,
+    transmit_bug: bool,
+    receive_data: u8,
+    transmit_data: Option<u8>,
+    shifting: Option<u8>,
+    transmit_cycles: u32,
+    receive_cycles: u32,
+    status: u8,
+    command: u8,
+    control: u8,
+    carrier_lost: bool,
+    data_set_not_ready: bool,
+}
+
+impl<B: SerialBackend> Acia6551<B> {
+    /// Create a 6551 on a CPU running at `clock_hz`
+    pub fn new(backend: B, clock_hz: u32) -> Self {
+        Self {
+            backend,
+            clock_hz,
+            transmit_bug: false,
+            receive_data: 0,
+            transmit_data: None,
+            shifting: None,
+            transmit_cycles: 0,
+            receive_cycles: 0,
+            status: 0,
+            command: 0,
+            control: 0,
+            carrier_lost: false,
+            data_set_not_ready: false,
+        }
+    }
+
+    /// Create a WDC 65C51 with its transmit-empty bug
+    pub fn wdc65c51(backend: B, clock_hz: u32) -> Self {
+        Self {
+            transmit_bug: true,
+            ..Self::new(backend, clock_hz)
+        }
+    }
+
+    #[inline]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    #[inline]
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Drive the DCD input. A high level means the carrier is lost.
+    #[inline]
+    pub fn set_dcd(&mut self, level: bool) {
+        self.carrier_lost = level;
+    }
+
+    /// Drive the DSR input. A high level means the data set is not ready.
+    #[inline]
+    pub fn set_dsr(&mut self, level: bool) {
+        self.data_set_not_ready = level;
+    }
+
+    /// CPU cycles one character takes on the wire with the current settings
+    pub fn character_cycles(&self) -> u32 {
+        let data_bits = 8 - (self.control >> 5 & 0b11) as u32;
+        let parity_bits = (self.command & COMMAND_PARITY_ENABLE != 0) as u32;
+        let stop_bits = 1 + (self.control >> 7) as u32;
+        let bits = 1 + data_bits + parity_bits + stop_bits;
+        let baud = BAUD_RATES[(self.control & 0x0F) as usize];
+        ((self.clock_hz as f64 * bits as f64 / baud).round() as u32).max(1)
+    }
+
+    #[inline]
+    fn transmitter_enabled(&self) -> bool {
+        self.command & COMMAND_TRANSMIT_CONTROL != 0
+    }
+
+    #[inline]
+    fn transmit_empty(&self) -> bool {
+        self.transmit_bug || self.transmit_data.is_none()
+    }
+
+    fn tick_transmitter(&mut self, mut cycles: u32) {
+        loop {
+            if self.shifting.is_none() {
+                if !self.transmitter_enabled() {
+                    return;
+                }
+                let Some(byte) = self.transmit_data.take() else {
+                    return;
+                };
+                self.shifting = Some(byte);
+                self.transmit_cycles = self.character_cycles();
+                let interrupt = self.command & COMMAND_TRANSMIT_CONTROL == COMMAND_TRANSMIT_IRQ;
+                if interrupt && !self.transmit_bug {
+                    self.status |= STATUS_IRQ;
+                }
+            }
+            if cycles < self.transmit_cycles {
+                self.transmit_cycles -= cycles;
+                return;
+            }
+            cycles -= self.transmit_cycles;
+            if let Some(byte) = self.shifting.take() {
+                self.backend.transmit(byte);
+            }
+        }
+    }
+
+    fn tick_receiver(&mut self, mut cycles: u32) {
+        if self.command & COMMAND_DTR == 0 {
+            return;
+        }
+        while cycles >= self.receive_cycles {
+            cycles -= self.receive_cycles;
+            self.receive_cycles = self.character_cycles();
+            self.receive_character();
+        }
+        self.receive_cycles -= cycles;
+    }
+
+    fn receive_character(&mut self) {
+        let Some(byte) = self.backend.receive() else {
+            return;
+        };
+        if self.command & (COMMAND_ECHO | COMMAND_TRANSMIT_CONTROL) == COMMAND_ECHO {
+            self.backend.transmit(byte);
+        }
+        if self.status & STATUS_RECEIVE_FULL != 0 {
+            // The unread character is kept and the new one is lost
+            self.status |= STATUS_OVERRUN;
+        } else {
+            self.receive_data = byte;
+            self.status |= STATUS_RECEIVE_FULL;
+        }
+        if self.command & COMMAND_RECEIVE_IRQ_DISABLE == 0 {
+            self.status |= STATUS_IRQ;
+        }
+    }
+}
+
+impl<B: SerialBackend> Device for Acia6551<B> {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        Ok(match offset & 0x03 {
+            DATA => {
+                self.status &= !(STATUS_RECEIVE_FULL
+                    | STATUS_OVERRUN
+                    | STATUS_FRAMING_ERROR
+                    | STATUS_PARITY_ERROR);
+                self.receive_data
+            }
+            STATUS => {
+                let mut status = self.status;
+                if self.transmit_empty() {
+                    status |= STATUS_TRANSMIT_EMPTY;
+                }
+                if self.carrier_lost {
+                    status |= STATUS_CARRIER_LOST;
+                }
+                if self.data_set_not_ready {
+                    status |= STATUS_DATA_SET_NOT_READY;
+                }
+                self.status &= !STATUS_IRQ;
+                status
+            }
+            COMMAND => self.command,
+            _ => self.control,
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        match offset & 0x03 {
+            DATA => {
+                if self.transmit_bug && self.shifting.is_some() {
+                    self.shifting = None;
+                }
+                self.transmit_data = Some(value);
+            }
+            STATUS => {
+                // Programmed reset keeps the parity settings and the control register
+                self.command &= 0xE0;
+                self.status &= !STATUS_OVERRUN;
+            }
+            COMMAND => self.command = value,
+            CONTROL => self.control = value,
+            _ => unreachable!("offset is masked to two bits"),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.tick_transmitter(cycles);
+        self.tick_receiver(cycles);
+    }
+
+    fn irq(&self) -> bool {
+        self.status & STATUS_IRQ != 0
+    }
+
+    fn reset(&mut self) {
+        self.status = 0;
+        self.command = 0;
+        self.control = 0;
+        self.transmit_data = None;
+        self.shifting = None;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(&(
+            self.receive_data,
+            self.transmit_data,
+            self.shifting,
+            self.transmit_cycles,
+            self.receive_cycles,
+            self.status,
+            self.command,
+            self.control,
+        ))?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        (
+            self.receive_data,
+            self.transmit_data,
+            self.shifting,
+            self.transmit_cycles,
+            self.receive_cycles,
+            self.status,
+            self.command,
+            self.control,
+        ) = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::serial::BufferBackend;
+    use crate::machine::Machine;
+
+    const CLOCK_HZ: u32 = 1_000_000;
+    /// 19200 baud, 8 data bits, 1 stop bit
+    const CONTROL_19200_8N1: u8 = 0x1F;
+    /// DTR on, RTS low, no interrupts
+    const COMMAND_POLLED: u8 = 0x0B;
+
+    type Acia = Acia6551<BufferBackend>;
+
+    fn acia(machine: &mut Machine) -> &mut Acia {
+        machine.device_mut().expect("ACIA is mapped")
+    }
+
+    /// Machine with the ACIA at $5000, `program` at $F000 and `irq_handler` at $F800
+    fn machine(acia: Acia, program: &[u8], irq_handler: &[u8]) -> Machine {
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(program);
+        rom[0x800..0x800 + irq_handler.len()].copy_from_slice(irq_handler);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF8]);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x07FF)
+            .device(0x5000..=0x5003, acia)
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().expect("Failed to reset machine");
+        machine
+    }
+
+    #[test]
+    fn test_polled_echo() {
+        #[rustfmt::skip]
+        let program = [
+            0xA9, CONTROL_19200_8N1, // LDA #control
+            0x8D, 0x03, 0x50,        // STA control
+            0xA9, COMMAND_POLLED,    // LDA #command
+            0x8D, 0x02, 0x50,        // STA command
+            0xAD, 0x01, 0x50,        // LDA status (receive loop)
+            0x29, 0x08,              // AND #RDRF
+            0xF0, 0xF9,              // BEQ receive loop
+            0xAE, 0x00, 0x50,        // LDX data
+            0xAD, 0x01, 0x50,        // LDA status (transmit loop)
+            0x29, 0x10,              // AND #TDRE
+            0xF0, 0xF9,              // BEQ transmit loop
+            0x8E, 0x00, 0x50,        // STX data
+            0x4C, 0x0A, 0xF0,        // JMP receive loop
+        ];
+        let mut backend = BufferBackend::new();
+        backend.push_input(b"HELLO\r");
+        let mut machine = machine(Acia::new(backend, CLOCK_HZ), &program, &[]);
+
+        // Ten bits at 19200 baud take 521 cycles per character
+        machine.run_cycles(100).unwrap();
+        assert_eq!(acia(&mut machine).character_cycles(), 521);
+        machine.run_cycles(521 * 8).unwrap();
+        assert_eq!(acia(&mut machine).backend().output(), b"HELLO\r");
+    }
+
+    #[test]
+    fn test_transmit_timing_and_interrupt() {
+        let mut acia = Acia::new(BufferBackend::new(), CLOCK_HZ);
+        acia.write(CONTROL, CONTROL_19200_8N1).unwrap();
+        acia.write(
+            COMMAND,
+            COMMAND_DTR | COMMAND_RECEIVE_IRQ_DISABLE | COMMAND_TRANSMIT_IRQ,
+        )
+        .unwrap();
+
+        acia.write(DATA, b'A').unwrap();
+        assert_eq!(acia.read(STATUS).unwrap() & STATUS_TRANSMIT_EMPTY, 0);
+        acia.write(DATA, b'B').unwrap();
+        assert!(!acia.irq());
+
+        // 'B' replaced 'A' in the data register before the shifter took it
+        acia.tick(1);
+        assert!(acia.irq(), "the data register emptied into the shifter");
+        assert_ne!(acia.read(STATUS).unwrap() & STATUS_TRANSMIT_EMPTY, 0);
+        assert!(!acia.irq(), "reading status acknowledges the interrupt");
+        acia.write(DATA, b'C').unwrap();
+        acia.tick(519);
+        assert!(acia.backend().output().is_empty());
+        acia.tick(1);
+        assert_eq!(acia.backend().output(), b"B");
+        assert!(acia.irq());
+        acia.tick(521);
+        assert_eq!(acia.backend().output(), b"BC");
+
+        // RTS high disables the transmitter
+        acia.write(COMMAND, COMMAND_DTR).unwrap();
+        acia.write(DATA, b'D').unwrap();
+        acia.tick(10_000);
+        assert_eq!(acia.backend().output(), b"BC");
+    }
+
+    #[test]
+    fn test_receive_interrupt_and_overrun() {
+        #[rustfmt::skip]
+        let program = [
+            0x78,                    // SEI
+            0xA9, CONTROL_19200_8N1, // LDA #control
+            0x8D, 0x03, 0x50,        // STA control
+            0xA9, 0x09,              // LDA #command (receive IRQ enabled)
+            0x8D, 0x02, 0x50,        // STA command
+            0xA2, 0x00,              // LDX #$00
+            0x58,                    // CLI
+            0x4C, 0x0E, 0xF0,        // JMP *
+        ];
+        #[rustfmt::skip]
+        let handler = [
+            0x48,                    // PHA
+            0xAD, 0x01, 0x50,        // LDA status (acknowledge)
+            0xAD, 0x00, 0x50,        // LDA data
+            0x95, 0x10,              // STA $10,X
+            0xE8,                    // INX
+            0x68,                    // PLA
+            0x40,                    // RTI
+        ];
+        let mut backend = BufferBackend::new();
+        backend.push_input(b"6502");
+        let mut machine = machine(Acia::new(backend, CLOCK_HZ), &program, &handler);
+        machine.run_cycles(521 * 6).unwrap();
+        let received: Vec<u8> = (0x10..0x14)
+            .map(|address| machine.bus().read(address).unwrap())
+            .collect();
+        assert_eq!(received, b"6502");
+        assert_eq!(machine.cpu().x_register(), 4);
+
+        // A character arriving before the last one was read overruns
+        let acia = acia(&mut machine);
+        acia.write(COMMAND, COMMAND_POLLED).unwrap();
+        acia.backend_mut().push_input(b"XY");
+        acia.tick(521 * 2);
+        let status = acia.read(STATUS).unwrap();
+        assert_eq!(status & STATUS_OVERRUN, STATUS_OVERRUN);
+        assert_eq!(acia.read(DATA).unwrap(), b'X');
+        assert_eq!(acia.read(STATUS).unwrap() & STATUS_OVERRUN, 0);
+    }
+
+    #[test]
+    fn test_wdc65c51_transmit_bug() {
+        let mut acia = Acia::wdc65c51(BufferBackend::new(), CLOCK_HZ);
+        acia.write(CONTROL, CONTROL_19200_8N1).unwrap();
+        acia.write(COMMAND, COMMAND_DTR | COMMAND_TRANSMIT_IRQ)
+            .unwrap();
+
+        acia.write(DATA, b'A').unwrap();
+        assert_ne!(acia.read(STATUS).unwrap() & STATUS_TRANSMIT_EMPTY, 0);
+        acia.tick(100);
+        assert!(!acia.irq(), "transmit interrupts never fire");
+        acia.write(DATA, b'B').unwrap();
+        acia.tick(521 * 3);
+        assert_eq!(acia.backend().output(), b"B");
+
+        // Waiting out each character works
+        acia.write(DATA, b'C').unwrap();
+        acia.tick(521);
+        acia.write(DATA, b'D').unwrap();
+        acia.tick(521);
+        assert_eq!(acia.backend().output(), b"BCD");
+    }
+
+    #[test]
+    fn test_programmed_reset_and_modem_lines() {
+        let mut acia = Acia::new(BufferBackend::new(), CLOCK_HZ);
+        acia.write(CONTROL, 0x9E).unwrap();
+        acia.write(COMMAND, 0xEB).unwrap();
+        // 9600 baud, eight data bits, parity and two stop bits make 12 bits
+        assert_eq!(acia.character_cycles(), 1250);
+
+        acia.write(STATUS, 0).unwrap();
+        assert_eq!(acia.read(COMMAND).unwrap(), 0xE0);
+        assert_eq!(acia.read(CONTROL).unwrap(), 0x9E);
+
+        acia.set_dcd(true);
+        acia.set_dsr(true);
+        let status = acia.read(STATUS).unwrap();
+        assert_eq!(
+            status,
+            STATUS_TRANSMIT_EMPTY | STATUS_CARRIER_LOST | STATUS_DATA_SET_NOT_READY
+        );
+    }
+}
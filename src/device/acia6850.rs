@@ -0,0 +1,448 @@
+use crate::device::serial::SerialBackend;
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Register select: control/status at 0, data at 1
+const RS: u16 = 1 << 0;
+
+// Status register bits
+const STATUS_RECEIVE_FULL: u8 = 1 << 0;
+const STATUS_TRANSMIT_EMPTY: u8 = 1 << 1;
+const STATUS_CARRIER_LOST: u8 = 1 << 2;
+const STATUS_CLEAR_TO_SEND: u8 = 1 << 3;
+const STATUS_OVERRUN: u8 = 1 << 5;
+const STATUS_IRQ: u8 = 1 << 7;
+
+// Control register fields
+const CONTROL_DIVIDE: u8 = 0b11;
+const CONTROL_MASTER_RESET: u8 = 0b11;
+const CONTROL_WORD_SELECT: u8 = 0b111 << 2;
+const CONTROL_TRANSMIT: u8 = 0b11 << 5;
+const CONTROL_TRANSMIT_IRQ: u8 = 0b01 << 5;
+const CONTROL_RTS_HIGH: u8 = 0b10 << 5;
+const CONTROL_BREAK: u8 = 0b11 << 5;
+const CONTROL_RECEIVE_IRQ: u8 = 1 << 7;
+
+/// Clock dividers selected by control bits 0-1; the fourth setting is master reset
+const DIVIDERS: [u32; 3] = [1, 16, 64];
+
+/// Motorola 6850 Asynchronous Communications Interface Adapter.
+///
+/// The transmit and receive clocks come from a separate oscillator, divided by 1, 16 or
+/// 64. Characters take as many CPU cycles as the resulting baud rate and the selected
+/// word format need on the wire.
+///
+/// The chip comes out of power-on in master reset, so software has to write a control
+/// value with divide bits other than `11` before it does anything. The receiver only
+/// takes characters from the backend while RTS is low, like a host that honours
+/// hardware flow control. Unlike the 6551, the IRQ output is simply the level of the
+/// enabled conditions: received data, overrun or lost carrier when receive interrupts
+/// are on, and an empty transmit register when transmit interrupts are on.
+pub struct Acia6850<B: SerialBackend> {
+    backend: B,
+    clock_hz: u32,
+    serial_clock_hz: u32,
+    control: u8,
+    receive_data: u8,
+    receive_full: bool,
+    overrun: bool,
+    transmit_data: Option<u8>,
+    shifting: Option<u8>,
+    transmit_cycles: u32,
+    receive_cycles: u32,
+    carrier_lost: bool,
+    carrier_latched: bool,
+    clear_to_send_high: bool,
+}
+
+impl<B: SerialBackend> Acia6850<B> {
+    /// Create a 6850 on a CPU running at `clock_hz`, with `serial_clock_hz` on its
+    /// TX and RX clock inputs. Grant Searle's boards use 1.8432 MHz, giving 115200 baud
+    /// with the divide-by-16 setting.
+    pub fn new(backend: B, clock_hz: u32, serial_clock_hz: u32) -> Self {
+        Self {
+            backend,
+            clock_hz,
+            serial_clock_hz,
+            control: CONTROL_MASTER_RESET,
+            receive_data: 0,
+            receive_full: false,
+            overrun: false,
+            transmit_data: None,
+            shifting: None,
+            transmit_cycles: 0,
+            receive_cycles: 0,
+            carrier_lost: false,
+            carrier_latched: false,
+            clear_to_send_high: false,
+        }
+    }
+
+    #[inline]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    #[inline]
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Drive the DCD input. A rising edge is latched in the status register until the
+    /// status and then the data register are read.
+    pub fn set_dcd(&mut self, level: bool) {
+        if level && !self.carrier_lost {
+            self.carrier_latched = true;
+        }
+        self.carrier_lost = level;
+    }
+
+    /// Drive the CTS input. While it is high the transmit register reads as full and
+    /// nothing new is sent.
+    #[inline]
+    pub fn set_cts(&mut self, level: bool) {
+        self.clear_to_send_high = level;
+    }
+
+    /// Level of the RTS output
+    #[inline]
+    pub fn rts(&self) -> bool {
+        self.control & CONTROL_TRANSMIT == CONTROL_RTS_HIGH
+    }
+
+    /// CPU cycles one character takes on the wire with the current settings
+    pub fn character_cycles(&self) -> u32 {
+        let divider = DIVIDERS
+            .get((self.control & CONTROL_DIVIDE) as usize)
+            .copied()
+            .unwrap_or(1);
+        let (data_bits, parity_bits, stop_bits) = self.word_format();
+        let bits = 1 + data_bits + parity_bits + stop_bits;
+        let cycles = self.clock_hz as u64 * bits as u64 * divider as u64;
+        (cycles.div_ceil(self.serial_clock_hz as u64) as u32).max(1)
+    }
+
+    /// Data, parity and stop bits of the word format selected by control bits 2-4
+    fn word_format(&self) -> (u32, u32, u32) {
+        match (self.control & CONTROL_WORD_SELECT) >> 2 {
+            0 | 1 => (7, 1, 2),
+            2 | 3 => (7, 1, 1),
+            4 => (8, 0, 2),
+            5 => (8, 0, 1),
+            _ => (8, 1, 1),
+        }
+    }
+
+    #[inline]
+    fn data_mask(&self) -> u8 {
+        if self.word_format().0 == 7 {
+            0x7F
+        } else {
+            0xFF
+        }
+    }
+
+    #[inline]
+    fn in_reset(&self) -> bool {
+        self.control & CONTROL_DIVIDE == CONTROL_MASTER_RESET
+    }
+
+    #[inline]
+    fn transmit_empty(&self) -> bool {
+        self.transmit_data.is_none() && !self.clear_to_send_high
+    }
+
+    fn status(&self) -> u8 {
+        let mut status = 0;
+        if self.receive_full {
+            status |= STATUS_RECEIVE_FULL;
+        }
+        if self.transmit_empty() {
+            status |= STATUS_TRANSMIT_EMPTY;
+        }
+        if self.carrier_latched {
+            status |= STATUS_CARRIER_LOST;
+        }
+        if self.clear_to_send_high {
+            status |= STATUS_CLEAR_TO_SEND;
+        }
+        if self.overrun {
+            status |= STATUS_OVERRUN;
+        }
+        if self.irq() {
+            status |= STATUS_IRQ;
+        }
+        status
+    }
+
+    fn master_reset(&mut self) {
+        self.receive_full = false;
+        self.overrun = false;
+        self.transmit_data = None;
+        self.shifting = None;
+        self.transmit_cycles = 0;
+        self.receive_cycles = 0;
+        self.carrier_latched = self.carrier_lost;
+    }
+
+    fn tick_transmitter(&mut self, mut cycles: u32) {
+        loop {
+            if self.shifting.is_none() {
+                if self.clear_to_send_high || self.control & CONTROL_TRANSMIT == CONTROL_BREAK {
+                    return;
+                }
+                let Some(byte) = self.transmit_data.take() else {
+                    return;
+                };
+                self.shifting = Some(byte & self.data_mask());
+                self.transmit_cycles = self.character_cycles();
+            }
+            if cycles < self.transmit_cycles {
+                self.transmit_cycles -= cycles;
+                return;
+            }
+            cycles -= self.transmit_cycles;
+            if let Some(byte) = self.shifting.take() {
+                self.backend.transmit(byte);
+            }
+        }
+    }
+
+    fn tick_receiver(&mut self, mut cycles: u32) {
+        if self.rts() {
+            return;
+        }
+        while cycles >= self.receive_cycles {
+            cycles -= self.receive_cycles;
+            self.receive_cycles = self.character_cycles();
+            self.receive_character();
+        }
+        self.receive_cycles -= cycles;
+    }
+
+    fn receive_character(&mut self) {
+        if self.carrier_lost {
+            return;
+        }
+        let Some(byte) = self.backend.receive() else {
+            return;
+        };
+        if self.receive_full {
+            // The unread character is kept and the new one is lost
+            self.overrun = true;
+        } else {
+            self.receive_data = byte & self.data_mask();
+            self.receive_full = true;
+        }
+    }
+}
+
+impl<B: SerialBackend> Device for Acia6850<B> {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        if offset & RS == 0 {
+            return Ok(self.status());
+        }
+        self.receive_full = false;
+        self.overrun = false;
+        if !self.carrier_lost {
+            self.carrier_latched = false;
+        }
+        Ok(self.receive_data)
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        if offset & RS != 0 {
+            if !self.in_reset() {
+                self.transmit_data = Some(value);
+            }
+            return Ok(());
+        }
+        self.control = value;
+        if self.in_reset() {
+            self.master_reset();
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        if self.in_reset() {
+            return;
+        }
+        self.tick_transmitter(cycles);
+        self.tick_receiver(cycles);
+    }
+
+    fn irq(&self) -> bool {
+        if self.in_reset() {
+            return false;
+        }
+        let receive = self.control & CONTROL_RECEIVE_IRQ != 0
+            && (self.receive_full || self.overrun || self.carrier_latched);
+        let transmit =
+            self.control & CONTROL_TRANSMIT == CONTROL_TRANSMIT_IRQ && self.transmit_empty();
+        receive || transmit
+    }
+
+    fn reset(&mut self) {
+        self.control = CONTROL_MASTER_RESET;
+        self.master_reset();
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(&(
+            self.control,
+            self.receive_data,
+            self.receive_full,
+            self.overrun,
+            self.transmit_data,
+            self.shifting,
+            self.transmit_cycles,
+            self.receive_cycles,
+            self.carrier_latched,
+        ))?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        (
+            self.control,
+            self.receive_data,
+            self.receive_full,
+            self.overrun,
+            self.transmit_data,
+            self.shifting,
+            self.transmit_cycles,
+            self.receive_cycles,
+            self.carrier_latched,
+        ) = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::serial::BufferBackend;
+    use crate::machine::Machine;
+
+    const CLOCK_HZ: u32 = 1_000_000;
+    const SERIAL_CLOCK_HZ: u32 = 1_843_200;
+    const STATUS: u16 = 0;
+    const DATA: u16 = 1;
+    /// Divide by 16, 8N1, RTS low, no interrupts
+    const CONTROL_POLLED: u8 = 0x15;
+
+    type Acia = Acia6850<BufferBackend>;
+
+    #[test]
+    fn test_polled_echo() {
+        // The serial routines of Grant Searle's 6502 board, ACIA at $A000
+        #[rustfmt::skip]
+        let program = [
+            0xA9, 0x03,       // LDA #$03 (master reset)
+            0x8D, 0x00, 0xA0, // STA control
+            0xA9, 0x15,       // LDA #$15
+            0x8D, 0x00, 0xA0, // STA control
+            0xAD, 0x00, 0xA0, // LDA status (receive loop)
+            0x4A,             // LSR
+            0x90, 0xFA,       // BCC receive loop
+            0xAE, 0x01, 0xA0, // LDX data
+            0xAD, 0x00, 0xA0, // LDA status (transmit loop)
+            0x29, 0x02,       // AND #TDRE
+            0xF0, 0xF9,       // BEQ transmit loop
+            0x8E, 0x01, 0xA0, // STX data
+            0x4C, 0x0A, 0xF0, // JMP receive loop
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+        let mut backend = BufferBackend::new();
+        backend.push_input(b"10 PRINT\r");
+        let mut machine = Machine::builder()
+            .device(
+                0xA000..=0xA001,
+                Acia::new(backend, CLOCK_HZ, SERIAL_CLOCK_HZ),
+            )
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+
+        // Ten bits at 115200 baud take 87 cycles per character
+        machine.run_cycles(87 * 11).unwrap();
+        let acia = machine.device::<Acia>().unwrap();
+        assert_eq!(acia.character_cycles(), 87);
+        assert_eq!(acia.backend().output(), b"10 PRINT\r");
+    }
+
+    #[test]
+    fn test_master_reset_and_word_select() {
+        let mut acia = Acia::new(BufferBackend::new(), CLOCK_HZ, SERIAL_CLOCK_HZ);
+        acia.backend_mut().push_input(&[0xC1]);
+        acia.write(DATA, b'X').unwrap();
+        acia.tick(10_000);
+        assert_eq!(acia.read(STATUS).unwrap(), STATUS_TRANSMIT_EMPTY);
+        assert!(acia.backend().output().is_empty(), "held in master reset");
+
+        // Divide by 64, 7 data bits, even parity, 1 stop bit: ten bits at 28800 baud
+        acia.write(STATUS, 0x0A).unwrap();
+        assert_eq!(acia.character_cycles(), 348);
+        acia.tick(1);
+        assert_eq!(
+            acia.read(STATUS).unwrap(),
+            STATUS_RECEIVE_FULL | STATUS_TRANSMIT_EMPTY
+        );
+        assert_eq!(acia.read(DATA).unwrap(), 0x41, "seven data bits");
+        acia.write(DATA, 0xC2).unwrap();
+        acia.tick(1 + 348);
+        assert_eq!(acia.backend().output(), [0x42]);
+
+        // RTS high holds off the host
+        acia.write(STATUS, 0x4A).unwrap();
+        assert!(acia.rts());
+        acia.backend_mut().push_input(b"AB");
+        acia.tick(1_000);
+        assert_eq!(acia.read(STATUS).unwrap() & STATUS_RECEIVE_FULL, 0);
+        acia.write(STATUS, 0x0A).unwrap();
+        acia.tick(348 * 2);
+        assert_eq!(acia.read(STATUS).unwrap() & STATUS_OVERRUN, STATUS_OVERRUN);
+        assert_eq!(acia.read(DATA).unwrap(), b'A');
+    }
+
+    #[test]
+    fn test_interrupts_and_modem_lines() {
+        let mut acia = Acia::new(BufferBackend::new(), CLOCK_HZ, SERIAL_CLOCK_HZ);
+        acia.write(STATUS, CONTROL_POLLED | CONTROL_RECEIVE_IRQ)
+            .unwrap();
+        assert!(!acia.irq());
+        acia.backend_mut().push_input(b"Z");
+        acia.tick(1);
+        assert!(acia.irq());
+        assert_eq!(acia.read(STATUS).unwrap() & STATUS_IRQ, STATUS_IRQ);
+        acia.read(DATA).unwrap();
+        assert!(!acia.irq());
+
+        // Transmit interrupt while the data register is empty
+        acia.write(STATUS, CONTROL_POLLED | CONTROL_TRANSMIT_IRQ)
+            .unwrap();
+        assert!(acia.irq());
+        acia.write(DATA, b'Q').unwrap();
+        assert!(!acia.irq());
+        acia.tick(1);
+        assert!(acia.irq(), "the data register emptied into the shifter");
+
+        // CTS high blocks the transmitter, a lost carrier is latched
+        acia.set_cts(true);
+        assert!(!acia.irq());
+        acia.set_cts(false);
+        acia.write(STATUS, CONTROL_POLLED | CONTROL_RECEIVE_IRQ)
+            .unwrap();
+        acia.set_dcd(true);
+        assert!(acia.irq());
+        acia.set_dcd(false);
+        assert_eq!(
+            acia.read(STATUS).unwrap() & STATUS_CARRIER_LOST,
+            STATUS_CARRIER_LOST
+        );
+        acia.read(DATA).unwrap();
+        assert!(!acia.irq());
+    }
+}
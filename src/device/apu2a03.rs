@@ -0,0 +1,1038 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Downsampler;
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+use crate::mos6502::Bus;
+
+// Register offsets from $4000
+const PULSE_1: u16 = 0x00;
+const PULSE_2: u16 = 0x04;
+const TRIANGLE_LINEAR: u16 = 0x08;
+const TRIANGLE_LOW: u16 = 0x0A;
+const TRIANGLE_HIGH: u16 = 0x0B;
+const NOISE_VOLUME: u16 = 0x0C;
+const NOISE_PERIOD: u16 = 0x0E;
+const NOISE_LENGTH: u16 = 0x0F;
+const DMC_CONTROL: u16 = 0x10;
+const DMC_LOAD: u16 = 0x11;
+const DMC_ADDRESS: u16 = 0x12;
+const DMC_LENGTH: u16 = 0x13;
+const STATUS: u16 = 0x15;
+const FRAME_COUNTER: u16 = 0x17;
+
+// Status register bits
+const STATUS_DMC: u8 = 1 << 4;
+const STATUS_FRAME_IRQ: u8 = 1 << 6;
+const STATUS_DMC_IRQ: u8 = 1 << 7;
+
+// Frame counter register bits
+const FRAME_IRQ_INHIBIT: u8 = 1 << 6;
+const FRAME_FIVE_STEP: u8 = 1 << 7;
+
+// DMC control bits
+const DMC_IRQ_ENABLE: u8 = 1 << 7;
+const DMC_LOOP: u8 = 1 << 6;
+
+/// CPU cycles a DMC sample fetch halts the CPU for
+const DMC_DMA_CYCLES: u32 = 4;
+
+const LENGTHS: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+    192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+const DUTY_CYCLES: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+const TRIANGLE_STEPS: [u8; 32] = [
+    15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12,
+    13, 14, 15,
+];
+
+/// Noise and DMC timer periods in CPU cycles
+const NOISE_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+const DMC_PERIODS: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+
+/// Frame sequencer steps in CPU cycles after a reset of the sequencer
+const STEP_1: u32 = 7457;
+const STEP_2: u32 = 14913;
+const STEP_3: u32 = 22371;
+const STEP_4: u32 = 29829;
+const STEP_5: u32 = 37281;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Envelope {
+    start: bool,
+    looping: bool,
+    constant: bool,
+    volume: u8,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    fn write(&mut self, value: u8) {
+        self.looping = value & 0x20 != 0;
+        self.constant = value & 0x10 != 0;
+        self.volume = value & 0x0F;
+    }
+
+    fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if self.looping {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    #[inline]
+    fn output(&self) -> u8 {
+        if self.constant {
+            self.volume
+        } else {
+            self.decay
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Pulse {
+    /// Pulse 1 negates the sweep change in ones' complement, pulse 2 in two's complement
+    ones_complement: bool,
+    enabled: bool,
+    duty: u8,
+    step: u8,
+    period: u16,
+    timer: u16,
+    length: u8,
+    halt: bool,
+    envelope: Envelope,
+    sweep_enabled: bool,
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_divider: u8,
+    sweep_reload: bool,
+}
+
+impl Pulse {
+    fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.duty = value >> 6;
+                self.halt = value & 0x20 != 0;
+                self.envelope.write(value);
+            }
+            1 => {
+                self.sweep_enabled = value & 0x80 != 0;
+                self.sweep_period = value >> 4 & 0x07;
+                self.sweep_negate = value & 0x08 != 0;
+                self.sweep_shift = value & 0x07;
+                self.sweep_reload = true;
+            }
+            2 => self.period = self.period & 0x700 | value as u16,
+            _ => {
+                self.period = self.period & 0xFF | (value as u16 & 0x07) << 8;
+                if self.enabled {
+                    self.length = LENGTHS[value as usize >> 3];
+                }
+                self.step = 0;
+                self.envelope.start = true;
+            }
+        }
+    }
+
+    fn sweep_target(&self) -> u16 {
+        let change = self.period >> self.sweep_shift;
+        if !self.sweep_negate {
+            self.period + change
+        } else if self.ones_complement {
+            self.period.saturating_sub(change + 1)
+        } else {
+            self.period.saturating_sub(change)
+        }
+    }
+
+    #[inline]
+    fn muted(&self) -> bool {
+        self.period < 8 || self.sweep_target() > 0x7FF
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 1) & 7;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_half_frame(&mut self) {
+        if self.sweep_divider == 0 && self.sweep_enabled && self.sweep_shift > 0 && !self.muted() {
+            self.period = self.sweep_target();
+        }
+        if self.sweep_divider == 0 || self.sweep_reload {
+            self.sweep_divider = self.sweep_period;
+            self.sweep_reload = false;
+        } else {
+            self.sweep_divider -= 1;
+        }
+        if self.length > 0 && !self.halt {
+            self.length -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.length == 0
+            || self.muted()
+            || DUTY_CYCLES[self.duty as usize][self.step as usize] == 0
+        {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Triangle {
+    enabled: bool,
+    step: u8,
+    period: u16,
+    timer: u16,
+    length: u8,
+    control: bool,
+    linear_reload_value: u8,
+    linear_counter: u8,
+    linear_reload: bool,
+}
+
+impl Triangle {
+    fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.length > 0 && self.linear_counter > 0 {
+                self.step = (self.step + 1) & 31;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    fn clock_quarter_frame(&mut self) {
+        if self.linear_reload {
+            self.linear_counter = self.linear_reload_value;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+        if !self.control {
+            self.linear_reload = false;
+        }
+    }
+
+    #[inline]
+    fn output(&self) -> u8 {
+        TRIANGLE_STEPS[self.step as usize]
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Noise {
+    enabled: bool,
+    shift: u16,
+    short_mode: bool,
+    period: u16,
+    timer: u16,
+    length: u8,
+    halt: bool,
+    envelope: Envelope,
+}
+
+impl Noise {
+    fn clock_timer(&mut self) {
+        if self.timer > 1 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period;
+        let tap = if self.short_mode { 6 } else { 1 };
+        let feedback = (self.shift ^ self.shift >> tap) & 1;
+        self.shift = self.shift >> 1 | feedback << 14;
+    }
+
+    #[inline]
+    fn output(&self) -> u8 {
+        if self.length == 0 || self.shift & 1 != 0 {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Dmc {
+    irq_enabled: bool,
+    looping: bool,
+    period: u16,
+    timer: u16,
+    level: u8,
+    sample_address: u16,
+    sample_length: u16,
+    address: u16,
+    bytes_remaining: u16,
+    buffer: Option<u8>,
+    shift: u8,
+    bits_remaining: u8,
+    silence: bool,
+    irq: bool,
+}
+
+impl Dmc {
+    fn restart(&mut self) {
+        self.address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    fn clock_timer(&mut self) {
+        if self.timer > 1 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period;
+        if !self.silence {
+            if self.shift & 1 != 0 {
+                if self.level <= 125 {
+                    self.level += 2;
+                }
+            } else if self.level >= 2 {
+                self.level -= 2;
+            }
+        }
+        self.shift >>= 1;
+        self.bits_remaining = self.bits_remaining.saturating_sub(1);
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.buffer.take() {
+                Some(value) => {
+                    self.silence = false;
+                    self.shift = value;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+
+    fn fill_buffer(&mut self, value: u8) {
+        self.buffer = Some(value);
+        self.address = self.address.checked_add(1).unwrap_or(0x8000);
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.irq = true;
+            }
+        }
+    }
+}
+
+/// Ricoh 2A03 audio processing unit, the NES's sound hardware, with NTSC timing.
+///
+/// Map it at $4000-$4017 and tick it with the cycle counts from `MOS6502::step`. The
+/// DMC fetches its samples itself, halting the CPU: the [`Machine`](crate::machine::Machine)
+/// does this through [`Device::dma_request`], and a host driving a bare CPU can call
+/// [`Apu2A03::clock`] instead, adding the stall cycles it returns to the CPU's count.
+///
+/// The `Machine` ticks it on every bus cycle, so register writes land on their cycle.
+/// Ticked after each instruction instead, writes take effect at the start of the
+/// instruction that makes them, and the frame counter reset after a $4017 write is
+/// three or four cycles late against the exact write cycle.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Apu2A03 {
+    pulses: [Pulse; 2],
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+    frame_cycle: u32,
+    five_step: bool,
+    irq_inhibit: bool,
+    frame_irq: bool,
+    frame_reset_delay: u8,
+    odd_cycle: bool,
+    downsampler: Downsampler,
+    #[serde(skip)]
+    samples: Vec<i16>,
+}
+
+impl Apu2A03 {
+    /// APU on a CPU at `clock_hz`, producing `sample_rate` samples per second
+    pub fn new(clock_hz: u32, sample_rate: u32) -> Self {
+        let mut apu = Self {
+            pulses: [Pulse::default(), Pulse::default()],
+            triangle: Triangle::default(),
+            noise: Noise {
+                enabled: false,
+                shift: 1,
+                short_mode: false,
+                period: NOISE_PERIODS[0],
+                timer: 0,
+                length: 0,
+                halt: false,
+                envelope: Envelope::default(),
+            },
+            dmc: Dmc {
+                period: DMC_PERIODS[0],
+                bits_remaining: 8,
+                silence: true,
+                ..Dmc::default()
+            },
+            frame_cycle: 0,
+            five_step: false,
+            irq_inhibit: false,
+            frame_irq: false,
+            frame_reset_delay: 0,
+            odd_cycle: false,
+            downsampler: Downsampler::new(clock_hz, sample_rate),
+            samples: Vec::new(),
+        };
+        apu.pulses[0].ones_complement = true;
+        apu
+    }
+
+    /// Samples rendered so far
+    #[inline]
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Take the samples rendered so far
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+
+    /// Advance by `cycles` CPU cycles, performing any DMC sample fetches on `bus`.
+    /// Returns the cycles the fetches halted the CPU for, which have also been run.
+    pub fn clock<B: Bus>(&mut self, cycles: u32, bus: &mut B) -> Result<u32, BusError> {
+        self.tick(cycles);
+        let mut stall = 0;
+        while let Some(address) = self.dma_request() {
+            let cycles = self.dma_complete(bus.read(address)?);
+            self.tick(cycles);
+            stall += cycles;
+        }
+        Ok(stall)
+    }
+
+    fn status(&self) -> u8 {
+        let mut status = 0;
+        for (bit, length) in [
+            self.pulses[0].length,
+            self.pulses[1].length,
+            self.triangle.length,
+            self.noise.length,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if length > 0 {
+                status |= 1 << bit;
+            }
+        }
+        if self.dmc.bytes_remaining > 0 {
+            status |= STATUS_DMC;
+        }
+        if self.frame_irq {
+            status |= STATUS_FRAME_IRQ;
+        }
+        if self.dmc.irq {
+            status |= STATUS_DMC_IRQ;
+        }
+        status
+    }
+
+    fn write_status(&mut self, value: u8) {
+        self.pulses[0].enabled = value & 0x01 != 0;
+        self.pulses[1].enabled = value & 0x02 != 0;
+        self.triangle.enabled = value & 0x04 != 0;
+        self.noise.enabled = value & 0x08 != 0;
+        for pulse in &mut self.pulses {
+            if !pulse.enabled {
+                pulse.length = 0;
+            }
+        }
+        if !self.triangle.enabled {
+            self.triangle.length = 0;
+        }
+        if !self.noise.enabled {
+            self.noise.length = 0;
+        }
+        if value & STATUS_DMC == 0 {
+            self.dmc.bytes_remaining = 0;
+        } else if self.dmc.bytes_remaining == 0 {
+            self.dmc.restart();
+        }
+        self.dmc.irq = false;
+    }
+
+    fn write_frame_counter(&mut self, value: u8) {
+        self.five_step = value & FRAME_FIVE_STEP != 0;
+        self.irq_inhibit = value & FRAME_IRQ_INHIBIT != 0;
+        if self.irq_inhibit {
+            self.frame_irq = false;
+        }
+        // The sequencer restarts on the second APU cycle boundary after the write
+        self.frame_reset_delay = if self.odd_cycle { 4 } else { 3 };
+    }
+
+    fn clock_quarter_frame(&mut self) {
+        self.pulses[0].envelope.clock();
+        self.pulses[1].envelope.clock();
+        self.noise.envelope.clock();
+        self.triangle.clock_quarter_frame();
+    }
+
+    fn clock_half_frame(&mut self) {
+        self.pulses[0].clock_half_frame();
+        self.pulses[1].clock_half_frame();
+        if self.triangle.length > 0 && !self.triangle.control {
+            self.triangle.length -= 1;
+        }
+        if self.noise.length > 0 && !self.noise.halt {
+            self.noise.length -= 1;
+        }
+    }
+
+    fn clock_frame_counter(&mut self) {
+        if self.frame_reset_delay > 0 {
+            self.frame_reset_delay -= 1;
+            if self.frame_reset_delay == 0 {
+                self.frame_cycle = 0;
+                if self.five_step {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+            }
+        }
+
+        self.frame_cycle += 1;
+        match (self.five_step, self.frame_cycle) {
+            (_, STEP_1) | (_, STEP_3) => self.clock_quarter_frame(),
+            (_, STEP_2) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
+            (false, cycle) if (STEP_4 - 1..=STEP_4 + 1).contains(&cycle) => {
+                if cycle == STEP_4 {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+                if !self.irq_inhibit {
+                    self.frame_irq = true;
+                }
+                if cycle == STEP_4 + 1 {
+                    self.frame_cycle = 0;
+                }
+            }
+            (true, STEP_5) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
+            (true, cycle) if cycle == STEP_5 + 1 => self.frame_cycle = 0,
+            _ => {}
+        }
+    }
+
+    /// Output level from the non-linear DAC mixer, 0.0 to about 1.0
+    fn output(&self) -> f32 {
+        let pulse = (self.pulses[0].output() + self.pulses[1].output()) as f32;
+        let pulse_out = if pulse == 0.0 {
+            0.0
+        } else {
+            95.88 / (8128.0 / pulse + 100.0)
+        };
+        let tnd = self.triangle.output() as f32 / 8227.0
+            + self.noise.output() as f32 / 12241.0
+            + self.dmc.level as f32 / 22638.0;
+        let tnd_out = if tnd == 0.0 {
+            0.0
+        } else {
+            159.79 / (1.0 / tnd + 100.0)
+        };
+        pulse_out + tnd_out
+    }
+
+    fn cycle(&mut self) {
+        self.clock_frame_counter();
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+        if self.odd_cycle {
+            self.pulses[0].clock_timer();
+            self.pulses[1].clock_timer();
+        }
+        self.odd_cycle = !self.odd_cycle;
+
+        if let Some(sample) = self.downsampler.push(self.output()) {
+            self.samples.push(sample);
+        }
+    }
+}
+
+impl Device for Apu2A03 {
+    /// Only $4015 is readable; the other registers read as zero
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        if offset != STATUS {
+            return Ok(0);
+        }
+        let status = self.status();
+        self.frame_irq = false;
+        Ok(status)
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        match offset {
+            PULSE_1..=0x03 => self.pulses[0].write(offset - PULSE_1, value),
+            PULSE_2..=0x07 => self.pulses[1].write(offset - PULSE_2, value),
+            TRIANGLE_LINEAR => {
+                self.triangle.control = value & 0x80 != 0;
+                self.triangle.linear_reload_value = value & 0x7F;
+            }
+            TRIANGLE_LOW => self.triangle.period = self.triangle.period & 0x700 | value as u16,
+            TRIANGLE_HIGH => {
+                self.triangle.period = self.triangle.period & 0xFF | (value as u16 & 0x07) << 8;
+                if self.triangle.enabled {
+                    self.triangle.length = LENGTHS[value as usize >> 3];
+                }
+                self.triangle.linear_reload = true;
+            }
+            NOISE_VOLUME => {
+                self.noise.halt = value & 0x20 != 0;
+                self.noise.envelope.write(value);
+            }
+            NOISE_PERIOD => {
+                self.noise.short_mode = value & 0x80 != 0;
+                self.noise.period = NOISE_PERIODS[value as usize & 0x0F];
+            }
+            NOISE_LENGTH => {
+                if self.noise.enabled {
+                    self.noise.length = LENGTHS[value as usize >> 3];
+                }
+                self.noise.envelope.start = true;
+            }
+            DMC_CONTROL => {
+                self.dmc.irq_enabled = value & DMC_IRQ_ENABLE != 0;
+                self.dmc.looping = value & DMC_LOOP != 0;
+                self.dmc.period = DMC_PERIODS[value as usize & 0x0F];
+                if !self.dmc.irq_enabled {
+                    self.dmc.irq = false;
+                }
+            }
+            DMC_LOAD => self.dmc.level = value & 0x7F,
+            DMC_ADDRESS => self.dmc.sample_address = 0xC000 | (value as u16) << 6,
+            DMC_LENGTH => self.dmc.sample_length = (value as u16) << 4 | 1,
+            STATUS => self.write_status(value),
+            FRAME_COUNTER => self.write_frame_counter(value),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.frame_irq || self.dmc.irq
+    }
+
+    fn dma_request(&self) -> Option<u16> {
+        (self.dmc.buffer.is_none() && self.dmc.bytes_remaining > 0).then_some(self.dmc.address)
+    }
+
+    fn dma_complete(&mut self, value: u8) -> u32 {
+        self.dmc.fill_buffer(value);
+        DMC_DMA_CYCLES
+    }
+
+    /// Reset silences the channels and restarts the frame counter as if $4017 was
+    /// rewritten with its last value
+    fn reset(&mut self) {
+        self.write_status(0);
+        self.frame_irq = false;
+        self.frame_reset_delay = 3;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let samples = std::mem::take(&mut self.samples);
+        *self = bincode::deserialize(state)?;
+        self.samples = samples;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    const CLOCK_HZ: u32 = 1_789_773;
+
+    /// Stand-in for the PPU that reports vertical blank on every other status read
+    #[derive(Default)]
+    struct PpuStub {
+        vblank: bool,
+    }
+
+    impl Device for PpuStub {
+        fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+            if offset & 7 != 2 {
+                return Ok(0);
+            }
+            self.vblank = !self.vblank;
+            Ok(if self.vblank { 0x80 } else { 0x00 })
+        }
+
+        fn write(&mut self, _offset: u16, _value: u8) -> Result<(), BusError> {
+            Ok(())
+        }
+    }
+
+    /// Sample memory for DMC fetches on a bare APU
+    struct Rom;
+
+    impl Bus for Rom {
+        fn read(&mut self, _: u16) -> Result<u8, BusError> {
+            Ok(0xFF)
+        }
+
+        fn write(&mut self, address: u16, _: u8) -> Result<(), BusError> {
+            Err(BusError::ReadOnlyAddress(address))
+        }
+    }
+
+    #[test]
+    fn test_frame_counter_and_length_counters() {
+        let mut apu = Apu2A03::new(CLOCK_HZ, 44_100);
+        apu.write(FRAME_COUNTER, 0).unwrap();
+        apu.write(STATUS, 0x01).unwrap();
+        apu.write(PULSE_1 + 3, 0x18).unwrap();
+        apu.write(PULSE_2 + 3, 0x18).unwrap();
+        assert_eq!(
+            apu.read(STATUS).unwrap(),
+            0x01,
+            "disabled channels load no length"
+        );
+
+        // The 4-step sequence sets the IRQ flag on three consecutive cycles
+        apu.tick(29_829);
+        assert!(!apu.irq());
+        apu.tick(1);
+        assert!(apu.irq());
+        assert_eq!(apu.read(STATUS).unwrap(), STATUS_FRAME_IRQ | 0x01);
+        assert!(!apu.irq());
+        apu.tick(1);
+        assert!(apu.irq());
+        apu.tick(1);
+        assert_eq!(apu.read(STATUS).unwrap(), STATUS_FRAME_IRQ);
+        apu.tick(29_000);
+        assert!(!apu.irq());
+
+        // Two half frames per sequence clocked out the length of 2
+        assert_eq!(apu.read(STATUS).unwrap() & 0x01, 0);
+
+        // 5-step mode clocks the length counters at once and never interrupts
+        apu.write(PULSE_1 + 3, 0x18).unwrap();
+        apu.write(FRAME_COUNTER, FRAME_FIVE_STEP).unwrap();
+        apu.tick(4);
+        assert_eq!(apu.pulses[0].length, 1);
+        apu.tick(40_000);
+        assert!(!apu.irq());
+        assert_eq!(apu.pulses[0].length, 0);
+
+        apu.write(FRAME_COUNTER, 0).unwrap();
+        apu.tick(30_000);
+        assert!(apu.irq());
+        apu.write(FRAME_COUNTER, FRAME_IRQ_INHIBIT).unwrap();
+        assert!(!apu.irq());
+    }
+
+    #[test]
+    fn test_pulse_sweep_and_triangle() {
+        let mut apu = Apu2A03::new(CLOCK_HZ, 44_100);
+        apu.write(STATUS, 0x0F).unwrap();
+        // 50% duty, halted length, constant volume 15, period 253: 16 * 254 cycles
+        apu.write(PULSE_1, 0xBF).unwrap();
+        apu.write(PULSE_1 + 2, 0xFD).unwrap();
+        apu.write(PULSE_1 + 3, 0x00).unwrap();
+        let mut rising = 0;
+        let mut last = apu.pulses[0].output();
+        for _ in 0..16 * 254 * 10 {
+            apu.tick(1);
+            let output = apu.pulses[0].output();
+            if output > last {
+                rising += 1;
+            }
+            last = output;
+        }
+        assert_eq!(rising, 10);
+        assert_eq!(apu.pulses[0].period, 0xFD);
+
+        // Periods under 8 mute the channel
+        apu.write(PULSE_1 + 2, 0x07).unwrap();
+        apu.tick(100);
+        assert!((0..100).all(|_| {
+            apu.tick(1);
+            apu.pulses[0].output() == 0
+        }));
+
+        // The sweep units differ in how they negate
+        for register in [PULSE_1, PULSE_2] {
+            apu.write(register + 1, 0x89).unwrap();
+            apu.write(register + 2, 0x00).unwrap();
+            apu.write(register + 3, 0x01).unwrap();
+        }
+        apu.write(FRAME_COUNTER, FRAME_FIVE_STEP).unwrap();
+        apu.tick(4);
+        assert_eq!(apu.pulses[0].period, 0x7F);
+        assert_eq!(apu.pulses[1].period, 0x80);
+
+        // The triangle only steps while both its counters are non-zero
+        apu.write(TRIANGLE_LINEAR, 0x05).unwrap();
+        apu.write(TRIANGLE_LOW, 0x00).unwrap();
+        apu.write(TRIANGLE_HIGH, 0x08).unwrap();
+        apu.tick(20);
+        assert_eq!(apu.triangle.step, 0);
+        apu.tick(STEP_1);
+        let step = apu.triangle.step;
+        apu.tick(1);
+        assert_ne!(apu.triangle.step, step);
+        // Five quarter frames after the reload the linear counter has run out
+        apu.tick(STEP_5 * 2);
+        let step = apu.triangle.step;
+        apu.tick(10);
+        assert_eq!(apu.triangle.step, step);
+    }
+
+    #[test]
+    fn test_dmc_dma_and_irq() {
+        #[rustfmt::skip]
+        let program = [
+            0x58,             // CLI
+            0xA9, 0x8F,       // LDA #$8F (IRQ, rate 15)
+            0x8D, 0x10, 0x40, // STA $4010
+            0xA9, 0xFC,       // LDA #$FC ($FF00)
+            0x8D, 0x12, 0x40, // STA $4012
+            0xA9, 0x01,       // LDA #$01 (17 bytes)
+            0x8D, 0x13, 0x40, // STA $4013
+            0xA9, 0x10,       // LDA #$10
+            0x8D, 0x15, 0x40, // STA $4015
+            0x4C, 0x15, 0xF0, // JMP *
+        ];
+        #[rustfmt::skip]
+        let handler = [
+            0xE6, 0x00,       // INC $00
+            0xAD, 0x15, 0x40, // LDA $4015
+            0x85, 0x01,       // STA $01
+            0xA9, 0x00,       // LDA #$00
+            0x8D, 0x15, 0x40, // STA $4015
+            0x40,             // RTI
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x800..0x800 + handler.len()].copy_from_slice(&handler);
+        rom[0xF00..0xF11].fill(0xFF);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF8]);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x07FF)
+            .device(0x4000..=0x4017, Apu2A03::new(CLOCK_HZ, 44_100))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+        machine
+            .device_mut::<Apu2A03>()
+            .unwrap()
+            .write(FRAME_COUNTER, FRAME_IRQ_INHIBIT)
+            .unwrap();
+
+        machine.run_cycles(17 * 8 * 54 + 200).unwrap();
+        let apu = machine.device::<Apu2A03>().unwrap();
+        assert_eq!(apu.dmc.level, 126);
+        assert_eq!(apu.status() & STATUS_DMC, 0);
+        assert_eq!(machine.bus().read(0x0000).unwrap(), 1);
+        assert_eq!(machine.bus().read(0x0001).unwrap(), STATUS_DMC_IRQ);
+
+        // Driven by hand, each fetch reports its stall
+        let mut apu = Apu2A03::new(CLOCK_HZ, 44_100);
+        apu.write(DMC_ADDRESS, 0xFC).unwrap();
+        apu.write(DMC_LENGTH, 0x00).unwrap();
+        apu.write(STATUS, STATUS_DMC).unwrap();
+        assert_eq!(apu.clock(2, &mut Rom).unwrap(), DMC_DMA_CYCLES);
+        assert_eq!(apu.dmc.buffer, Some(0xFF));
+        assert_eq!(apu.clock(2, &mut Rom).unwrap(), 0);
+    }
+
+    /// Cycles ticked one at a time until the IRQ line goes high
+    fn cycles_until_irq(apu: &mut Apu2A03) -> u32 {
+        let mut cycles = 0;
+        while !apu.irq() {
+            apu.tick(1);
+            cycles += 1;
+        }
+        cycles
+    }
+
+    #[test]
+    fn test_frame_irq_exact_cycles() {
+        // Written on an even cycle, the sequencer restarts three cycles later and
+        // raises IRQ on the last cycle of step 4
+        let mut apu = Apu2A03::new(CLOCK_HZ, 44_100);
+        apu.write(FRAME_COUNTER, 0).unwrap();
+        assert_eq!(cycles_until_irq(&mut apu), 29_830);
+        // Held for three cycles, then a new sequence starts
+        apu.tick(2);
+        apu.read(STATUS).unwrap();
+        assert_eq!(cycles_until_irq(&mut apu), 29_830 - 2);
+
+        // Written on an odd cycle, the restart waits one more cycle
+        let mut apu = Apu2A03::new(CLOCK_HZ, 44_100);
+        apu.tick(1);
+        apu.write(FRAME_COUNTER, 0).unwrap();
+        assert_eq!(cycles_until_irq(&mut apu), 29_831);
+    }
+
+    #[test]
+    fn test_dmc_stall_exact_cycles() {
+        // Rate 15 plays a bit every 54 cycles, so after the first fetch the buffer
+        // empties every 8 * 54 cycles
+        let mut apu = Apu2A03::new(CLOCK_HZ, 44_100);
+        apu.write(FRAME_COUNTER, FRAME_IRQ_INHIBIT).unwrap();
+        apu.write(DMC_CONTROL, 0x0F).unwrap();
+        apu.write(DMC_ADDRESS, 0xFC).unwrap();
+        apu.write(DMC_LENGTH, 0x01).unwrap();
+        apu.write(STATUS, STATUS_DMC).unwrap();
+        let mut cycle = 0;
+        let mut fetches = Vec::new();
+        while fetches.len() < 3 {
+            cycle += 1;
+            let stall = apu.clock(1, &mut Rom).unwrap();
+            if stall > 0 {
+                fetches.push((cycle, stall));
+                cycle += stall;
+            }
+        }
+        assert_eq!(
+            fetches,
+            [
+                (1, DMC_DMA_CYCLES),
+                (379, DMC_DMA_CYCLES),
+                (811, DMC_DMA_CYCLES)
+            ]
+        );
+
+        // The Machine adds the stall to the instruction whose read cycle it delayed
+        let mut rom = vec![0xEA; 0x1000];
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x07FF)
+            .device(0x4000..=0x4017, Apu2A03::new(CLOCK_HZ, 44_100))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+        let apu = machine.device_mut::<Apu2A03>().unwrap();
+        apu.write(FRAME_COUNTER, FRAME_IRQ_INHIBIT).unwrap();
+        apu.write(DMC_ADDRESS, 0xFC).unwrap();
+        apu.write(DMC_LENGTH, 0x00).unwrap();
+        apu.write(STATUS, STATUS_DMC).unwrap();
+        let start = machine.cycles();
+        assert_eq!(machine.step().unwrap(), 2 + DMC_DMA_CYCLES);
+        assert_eq!(machine.step().unwrap(), 2);
+        assert_eq!(machine.cycles() - start, 4 + DMC_DMA_CYCLES as u64);
+    }
+
+    /// Runs blargg's apu_test ROMs as NROM cartridges. Copy the `rom_singles` ROMs into
+    /// `apu_test` to enable it. The APU has not been run against them yet, so it is not
+    /// known to pass.
+    #[test]
+    #[ignore]
+    fn test_blargg_apu_test() {
+        let mut paths: Vec<_> = std::fs::read_dir("apu_test")
+            .expect("Failed to find apu_test ROMs")
+            .map(|entry| entry.expect("Failed to list apu_test ROMs").path())
+            .filter(|path| path.extension().is_some_and(|e| e == "nes"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let image = std::fs::read(&path).expect("Failed to load ROM");
+            assert_eq!(
+                &image[..4],
+                b"NES\x1A",
+                "{} is not an iNES file",
+                path.display()
+            );
+            let trainer = if image[6] & 0x04 != 0 { 512 } else { 0 };
+            let prg_size = image[4] as usize * 0x4000;
+            let prg = image[16 + trainer..16 + trainer + prg_size].to_vec();
+
+            let mut builder = Machine::builder()
+                .ram(0x0000..=0x07FF)
+                .ram(0x6000..=0x7FFF)
+                .device(0x2000..=0x3FFF, PpuStub::default())
+                .device(0x4000..=0x4017, Apu2A03::new(CLOCK_HZ, 44_100))
+                .rom(0x8000, prg.clone());
+            if prg_size == 0x4000 {
+                builder = builder.rom(0xC000, prg);
+            }
+            let mut machine = builder.build();
+            machine.reset().unwrap();
+
+            // Results are reported at $6000 once the signature at $6001 is written
+            let mut status = 0x80;
+            for _ in 0..60 * 60 {
+                machine.run_cycles(CLOCK_HZ as u64 / 60).unwrap();
+                let bus = machine.bus();
+                let signature = [0x6001, 0x6002, 0x6003].map(|a| bus.read(a).unwrap());
+                status = bus.read(0x6000).unwrap();
+                if signature == [0xDE, 0xB0, 0x61] && status < 0x80 {
+                    break;
+                }
+            }
+            let text: Vec<u8> = (0x6004..0x8000)
+                .map(|address| machine.bus().read(address).unwrap())
+                .take_while(|&byte| byte != 0)
+                .collect();
+            assert_eq!(
+                status,
+                0,
+                "{}: {}",
+                path.display(),
+                String::from_utf8_lossy(&text)
+            );
+        }
+    }
+}
@@ -0,0 +1,664 @@
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Downsampler;
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Register numbers
+const NOISE_PERIOD: usize = 6;
+const MIXER: usize = 7;
+const AMPLITUDE_A: usize = 8;
+const ENVELOPE_FINE: usize = 11;
+const ENVELOPE_COARSE: usize = 12;
+const ENVELOPE_SHAPE: usize = 13;
+const PORT_A: usize = 14;
+const PORT_B: usize = 15;
+
+/// Bits kept when each register is written
+const REGISTER_MASKS: [u8; 16] = [
+    0xFF, 0x0F, 0xFF, 0x0F, 0xFF, 0x0F, 0x1F, 0xFF, 0x1F, 0x1F, 0x1F, 0xFF, 0xFF, 0x0F, 0xFF, 0xFF,
+];
+
+// Mixer bits
+const MIXER_PORT_A_OUTPUT: u8 = 1 << 6;
+const MIXER_PORT_B_OUTPUT: u8 = 1 << 7;
+
+// Amplitude register bits
+const AMPLITUDE_ENVELOPE: u8 = 1 << 4;
+
+// Envelope shape bits
+const SHAPE_HOLD: u8 = 1 << 0;
+const SHAPE_ALTERNATE: u8 = 1 << 1;
+const SHAPE_ATTACK: u8 = 1 << 2;
+const SHAPE_CONTINUE: u8 = 1 << 3;
+
+/// Output levels of the AY-3-8910's 16 volume steps, normalized to full scale
+const AY_LEVELS: [f32; 16] = [
+    0.0, 0.0106, 0.0150, 0.0222, 0.0320, 0.0466, 0.0665, 0.1039, 0.1237, 0.1986, 0.2803, 0.3548,
+    0.4702, 0.6030, 0.7530, 1.0,
+];
+
+// VGM file layout
+const VGM_HEADER_SIZE: usize = 0x100;
+const VGM_VERSION: u32 = 0x171;
+const VGM_SAMPLE_RATE: u64 = 44_100;
+const VGM_AY8910_WRITE: u8 = 0xA0;
+const VGM_WAIT: u8 = 0x61;
+const VGM_END: u8 = 0x66;
+
+/// Which PSG is emulated
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PsgModel {
+    /// General Instrument AY-3-8910, with a 16-step envelope
+    #[default]
+    Ay38910,
+    /// Yamaha YM2149, with a 32-step envelope at twice the step rate and finer volume
+    Ym2149,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Envelope {
+    counter: u16,
+    step: u8,
+    invert: u8,
+    holding: bool,
+}
+
+/// General Instrument AY-3-8910 and Yamaha YM2149 programmable sound generators.
+///
+/// The chip has 16 registers behind an address latch. Mapped on a bus, offset 0 latches
+/// the register number and offset 1 writes data, and both read the selected register.
+/// Boards that drive the chip from VIA ports, such as the Oric and the Mockingboard,
+/// translate the port and control line levels with [`Ay38910::bus_control`] instead.
+///
+/// Tone and noise generators run at a sixteenth of the PSG clock, which may differ
+/// from the CPU clock. A channel with both tone and noise disabled in the mixer outputs
+/// its volume level constantly, which is how samples are played through the volume
+/// registers. The output is unipolar, like the chip's.
+///
+/// [`Ay38910::start_vgm_log`] records every register write for export as a VGM file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ay38910 {
+    model: PsgModel,
+    cpu_clock_hz: u32,
+    clock_hz: u32,
+    phase: u64,
+    prescaler: u8,
+    registers: [u8; 16],
+    address: u8,
+    tone_counters: [u16; 3],
+    tone_outputs: [bool; 3],
+    noise_counter: u8,
+    noise_half: bool,
+    noise_shift: u32,
+    envelope: Envelope,
+    port_a_pins: u8,
+    port_b_pins: u8,
+    cycles: u64,
+    downsampler: Downsampler,
+    #[serde(skip)]
+    samples: Vec<i16>,
+    #[serde(skip)]
+    vgm_log: Option<Vec<(u64, u8, u8)>>,
+}
+
+impl Ay38910 {
+    /// Create a PSG clocked at `clock_hz` on a CPU running at `cpu_clock_hz`, producing
+    /// `sample_rate` samples per second
+    pub fn new(model: PsgModel, cpu_clock_hz: u32, clock_hz: u32, sample_rate: u32) -> Self {
+        Self {
+            model,
+            cpu_clock_hz,
+            clock_hz,
+            phase: 0,
+            prescaler: 0,
+            registers: [0; 16],
+            address: 0,
+            tone_counters: [0; 3],
+            tone_outputs: [false; 3],
+            noise_counter: 0,
+            noise_half: false,
+            noise_shift: 1,
+            envelope: Envelope::default(),
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            cycles: 0,
+            downsampler: Downsampler::new(cpu_clock_hz, sample_rate),
+            samples: Vec::new(),
+            vgm_log: None,
+        }
+    }
+
+    #[inline]
+    pub fn model(&self) -> PsgModel {
+        self.model
+    }
+
+    /// Samples rendered so far
+    #[inline]
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Take the samples rendered so far
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+
+    /// Register `index` as the chip would return it
+    pub fn register(&self, index: usize) -> u8 {
+        match index {
+            PORT_A if self.registers[MIXER] & MIXER_PORT_A_OUTPUT == 0 => self.port_a_pins,
+            PORT_B if self.registers[MIXER] & MIXER_PORT_B_OUTPUT == 0 => self.port_b_pins,
+            _ => self.registers[index & 0x0F],
+        }
+    }
+
+    /// Write register `index`
+    pub fn set_register(&mut self, index: usize, value: u8) {
+        let index = index & 0x0F;
+        let value = value & REGISTER_MASKS[index];
+        self.registers[index] = value;
+        if index == ENVELOPE_SHAPE {
+            self.restart_envelope();
+        }
+        if let Some(log) = &mut self.vgm_log {
+            log.push((self.cycles, index as u8, value));
+        }
+    }
+
+    /// Levels on the port A pins: the register when it is an output, else from outside
+    #[inline]
+    pub fn port_a(&self) -> u8 {
+        self.register(PORT_A)
+    }
+
+    #[inline]
+    pub fn port_b(&self) -> u8 {
+        self.register(PORT_B)
+    }
+
+    /// Drive the port A pins, seen while port A is an input
+    #[inline]
+    pub fn set_port_a(&mut self, value: u8) {
+        self.port_a_pins = value;
+    }
+
+    #[inline]
+    pub fn set_port_b(&mut self, value: u8) {
+        self.port_b_pins = value;
+    }
+
+    /// Drive the BDIR and BC1 control pins with `data` on the data bus. BC2 is assumed
+    /// tied high, as on nearly every board. Returns the data the chip drives when
+    /// reading.
+    pub fn bus_control(&mut self, bdir: bool, bc1: bool, data: u8) -> Option<u8> {
+        match (bdir, bc1) {
+            (false, false) => None,
+            (false, true) => Some(self.register(self.address as usize)),
+            (true, false) => {
+                self.set_register(self.address as usize, data);
+                None
+            }
+            (true, true) => {
+                self.address = data & 0x0F;
+                None
+            }
+        }
+    }
+
+    /// Start recording register writes for [`Ay38910::write_vgm`], dropping any
+    /// earlier recording. The current registers are recorded first so the log plays
+    /// back from the same state.
+    pub fn start_vgm_log(&mut self) {
+        let log = (0..16)
+            .filter(|&index| index != ENVELOPE_SHAPE)
+            .map(|index| (self.cycles, index as u8, self.registers[index]))
+            .collect();
+        self.vgm_log = Some(log);
+    }
+
+    /// Write the register writes recorded since [`Ay38910::start_vgm_log`] as a VGM
+    /// file, ending at the current cycle
+    pub fn write_vgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let log = self.vgm_log.as_deref().unwrap_or_default();
+        let start = log.first().map_or(self.cycles, |&(cycle, ..)| cycle);
+        let to_samples = |cycle: u64| (cycle - start) * VGM_SAMPLE_RATE / self.cpu_clock_hz as u64;
+
+        let mut commands = Vec::new();
+        let mut position = 0;
+        let mut wait_until = |commands: &mut Vec<u8>, target: u64| {
+            while position < target {
+                let wait = (target - position).min(u16::MAX as u64);
+                commands.push(VGM_WAIT);
+                commands.extend((wait as u16).to_le_bytes());
+                position += wait;
+            }
+        };
+        for &(cycle, register, value) in log {
+            wait_until(&mut commands, to_samples(cycle));
+            commands.extend([VGM_AY8910_WRITE, register, value]);
+        }
+        let total = to_samples(self.cycles);
+        wait_until(&mut commands, total);
+        commands.push(VGM_END);
+
+        let mut header = vec![0; VGM_HEADER_SIZE];
+        let mut put = |offset: usize, value: u32| {
+            header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        put(0x00, u32::from_le_bytes(*b"Vgm "));
+        put(0x04, (VGM_HEADER_SIZE + commands.len() - 4) as u32);
+        put(0x08, VGM_VERSION);
+        put(0x18, total as u32);
+        put(0x34, (VGM_HEADER_SIZE - 0x34) as u32);
+        put(0x74, self.clock_hz);
+        header[0x78] = match self.model {
+            PsgModel::Ay38910 => 0x00,
+            PsgModel::Ym2149 => 0x10,
+        };
+        header[0x79] = 0x01;
+        writer.write_all(&header)?;
+        writer.write_all(&commands)?;
+        writer.flush()
+    }
+
+    #[inline]
+    fn envelope_max(&self) -> u8 {
+        match self.model {
+            PsgModel::Ay38910 => 15,
+            PsgModel::Ym2149 => 31,
+        }
+    }
+
+    fn restart_envelope(&mut self) {
+        let max = self.envelope_max();
+        let shape = self.registers[ENVELOPE_SHAPE];
+        self.envelope = Envelope {
+            counter: 0,
+            step: max,
+            invert: if shape & SHAPE_ATTACK != 0 { max } else { 0 },
+            holding: false,
+        };
+    }
+
+    /// Current envelope level, 0-15 on the AY and 0-31 on the YM
+    #[inline]
+    fn envelope_level(&self) -> u8 {
+        self.envelope.step ^ self.envelope.invert
+    }
+
+    fn clock_envelope(&mut self) {
+        let period = u16::from_le_bytes([
+            self.registers[ENVELOPE_FINE],
+            self.registers[ENVELOPE_COARSE],
+        ]);
+        self.envelope.counter += 1;
+        if self.envelope.counter < period.max(1) {
+            return;
+        }
+        self.envelope.counter = 0;
+        if self.envelope.holding {
+            return;
+        }
+        if self.envelope.step > 0 {
+            self.envelope.step -= 1;
+            return;
+        }
+
+        // End of a ramp
+        let max = self.envelope_max();
+        let shape = self.registers[ENVELOPE_SHAPE];
+        if shape & SHAPE_CONTINUE == 0 {
+            self.envelope.holding = true;
+            self.envelope.invert = 0;
+        } else if shape & SHAPE_HOLD != 0 {
+            self.envelope.holding = true;
+            if shape & SHAPE_ALTERNATE != 0 {
+                self.envelope.invert ^= max;
+            }
+        } else {
+            self.envelope.step = max;
+            if shape & SHAPE_ALTERNATE != 0 {
+                self.envelope.invert ^= max;
+            }
+        }
+    }
+
+    /// Advance the generators by eight PSG clocks
+    fn clock_generators(&mut self) {
+        for channel in 0..3 {
+            let period =
+                u16::from_le_bytes([self.registers[channel * 2], self.registers[channel * 2 + 1]]);
+            self.tone_counters[channel] += 1;
+            if self.tone_counters[channel] >= period.max(1) {
+                self.tone_counters[channel] = 0;
+                self.tone_outputs[channel] = !self.tone_outputs[channel];
+            }
+        }
+
+        self.noise_half = !self.noise_half;
+        if self.model == PsgModel::Ym2149 {
+            self.clock_envelope();
+        }
+        if !self.noise_half {
+            return;
+        }
+        if self.model == PsgModel::Ay38910 {
+            self.clock_envelope();
+        }
+        self.noise_counter += 1;
+        if self.noise_counter >= self.registers[NOISE_PERIOD].max(1) {
+            self.noise_counter = 0;
+            let bit = (self.noise_shift ^ self.noise_shift >> 3) & 1;
+            self.noise_shift = self.noise_shift >> 1 | bit << 16;
+        }
+    }
+
+    fn output(&self) -> f32 {
+        let mixer = self.registers[MIXER];
+        let noise = self.noise_shift & 1 != 0;
+        let mut output = 0.0;
+        for channel in 0..3 {
+            let tone_off = mixer & (1 << channel) != 0;
+            let noise_off = mixer & (8 << channel) != 0;
+            if !((self.tone_outputs[channel] || tone_off) && (noise || noise_off)) {
+                continue;
+            }
+            let amplitude = self.registers[AMPLITUDE_A + channel];
+            output += match (self.model, amplitude & AMPLITUDE_ENVELOPE != 0) {
+                (PsgModel::Ay38910, true) => AY_LEVELS[self.envelope_level() as usize],
+                (PsgModel::Ay38910, false) => AY_LEVELS[amplitude as usize & 0x0F],
+                (PsgModel::Ym2149, true) => ym_level(self.envelope_level()),
+                (PsgModel::Ym2149, false) => ym_level((amplitude & 0x0F) << 1 | 1),
+            };
+        }
+        output / 3.0
+    }
+
+    fn cycle(&mut self) {
+        self.cycles += 1;
+        self.phase += self.clock_hz as u64;
+        while self.phase >= self.cpu_clock_hz as u64 {
+            self.phase -= self.cpu_clock_hz as u64;
+            self.prescaler += 1;
+            if self.prescaler == 8 {
+                self.prescaler = 0;
+                self.clock_generators();
+            }
+        }
+        if let Some(sample) = self.downsampler.push(self.output()) {
+            self.samples.push(sample);
+        }
+    }
+}
+
+/// Output of the YM2149's 32 volume steps, 1.5 dB apart
+fn ym_level(level: u8) -> f32 {
+    if level == 0 {
+        0.0
+    } else {
+        (2.0f32).powf((level as f32 - 31.0) / 4.0)
+    }
+}
+
+impl Device for Ay38910 {
+    fn read(&mut self, _offset: u16) -> Result<u8, BusError> {
+        Ok(self.register(self.address as usize))
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        if offset & 1 == 0 {
+            self.address = value & 0x0F;
+        } else {
+            self.set_register(self.address as usize, value);
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.registers = [0; 16];
+        self.address = 0;
+        self.restart_envelope();
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let samples = std::mem::take(&mut self.samples);
+        let vgm_log = self.vgm_log.take();
+        *self = bincode::deserialize(state)?;
+        self.samples = samples;
+        self.vgm_log = vgm_log;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::Via6522;
+    use crate::machine::Machine;
+
+    const CLOCK_HZ: u32 = 1_000_000;
+
+    fn new_psg(model: PsgModel) -> Ay38910 {
+        Ay38910::new(model, CLOCK_HZ, CLOCK_HZ, 44_100)
+    }
+
+    /// Envelope levels at each step for `shape`, with an envelope period of 1
+    fn envelope_levels(model: PsgModel, shape: u8, steps: usize) -> Vec<u8> {
+        let mut psg = new_psg(model);
+        psg.set_register(ENVELOPE_FINE, 1);
+        psg.set_register(ENVELOPE_SHAPE, shape);
+        let cycles_per_step = if model == PsgModel::Ay38910 { 16 } else { 8 };
+        (0..steps)
+            .map(|_| {
+                let level = psg.envelope_level();
+                psg.tick(cycles_per_step);
+                level
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_registers_ports_and_bus_control() {
+        let mut psg = new_psg(PsgModel::Ay38910);
+        psg.write(0, 1).unwrap();
+        psg.write(1, 0xFF).unwrap();
+        assert_eq!(psg.read(0).unwrap(), 0x0F, "coarse tone is four bits");
+        psg.write(0, 0x18).unwrap();
+        psg.write(1, 0xFF).unwrap();
+        assert_eq!(psg.register(AMPLITUDE_A), 0x1F, "address wraps at 16");
+
+        // Ports read the pins as inputs and the register as outputs
+        psg.set_port_a(0x5A);
+        psg.set_register(PORT_A, 0x33);
+        assert_eq!(psg.port_a(), 0x5A);
+        psg.set_register(MIXER, MIXER_PORT_A_OUTPUT);
+        assert_eq!(psg.port_a(), 0x33);
+
+        assert_eq!(psg.bus_control(true, true, PORT_B as u8), None);
+        assert_eq!(psg.bus_control(false, false, 0x00), None);
+        psg.set_port_b(0xC3);
+        assert_eq!(psg.bus_control(false, true, 0x00), Some(0xC3));
+        psg.bus_control(true, false, 0x77);
+        psg.set_register(MIXER, MIXER_PORT_B_OUTPUT);
+        assert_eq!(psg.port_b(), 0x77);
+    }
+
+    #[test]
+    fn test_tone_noise_and_mixer() {
+        let mut psg = new_psg(PsgModel::Ay38910);
+        // Tone A with period 100 toggles every 800 clocks: 625 Hz
+        psg.set_register(0, 100);
+        psg.set_register(MIXER, 0b111_110);
+        psg.set_register(AMPLITUDE_A, 0x0F);
+        psg.tick(799);
+        assert!(!psg.tone_outputs[0]);
+        assert_eq!(psg.output(), 0.0);
+        psg.tick(1);
+        assert!(psg.tone_outputs[0]);
+        assert_eq!(psg.output(), 1.0 / 3.0);
+
+        // Both disabled: the volume comes straight through
+        psg.set_register(MIXER, 0b111_111);
+        psg.tick(800);
+        assert_eq!(psg.output(), 1.0 / 3.0);
+
+        // The noise LFSR shifts every 16 clocks times the period
+        let mut psg = new_psg(PsgModel::Ay38910);
+        psg.set_register(NOISE_PERIOD, 2);
+        psg.tick(24);
+        let before = psg.noise_shift;
+        assert_ne!(before, 1);
+        psg.tick(31);
+        assert_eq!(psg.noise_shift, before);
+        psg.tick(1);
+        assert_ne!(psg.noise_shift, before);
+
+        // Square wave at 625 Hz comes out of the downsampler at the same rate
+        let mut psg = Ay38910::new(PsgModel::Ym2149, CLOCK_HZ, CLOCK_HZ, 10_000);
+        psg.set_register(0, 100);
+        psg.set_register(MIXER, 0b111_110);
+        psg.set_register(AMPLITUDE_A, 0x0F);
+        psg.tick(CLOCK_HZ / 10);
+        let samples = psg.take_samples();
+        assert_eq!(samples.len(), 1_000);
+        let half = i16::MAX / 6;
+        let rising = samples
+            .windows(2)
+            .filter(|w| w[0] < half && w[1] >= half)
+            .count();
+        assert_eq!(rising, 62);
+    }
+
+    #[test]
+    fn test_envelope_shapes() {
+        let ramp_down: Vec<u8> = (0..16).rev().collect();
+        let ramp_up: Vec<u8> = (0..16).collect();
+
+        // \___ and /___
+        let levels = envelope_levels(PsgModel::Ay38910, 0x00, 20);
+        assert_eq!(levels[..16], ramp_down);
+        assert_eq!(levels[16..], [0; 4]);
+        let levels = envelope_levels(PsgModel::Ay38910, 0x04, 20);
+        assert_eq!(levels[..16], ramp_up);
+        assert_eq!(levels[16..], [0; 4]);
+
+        // \\\\ repeats, \/\/ alternates, \``` and /``` hold at the top
+        let levels = envelope_levels(PsgModel::Ay38910, 0x08, 32);
+        assert_eq!(levels[16..], ramp_down);
+        let levels = envelope_levels(PsgModel::Ay38910, 0x0A, 32);
+        assert_eq!(levels[16..], ramp_up);
+        let levels = envelope_levels(PsgModel::Ay38910, 0x0B, 20);
+        assert_eq!(levels[16..], [15; 4]);
+        let levels = envelope_levels(PsgModel::Ay38910, 0x0D, 20);
+        assert_eq!(levels[16..], [15; 4]);
+        // /\/\ and /___ after the hold on /```
+        let levels = envelope_levels(PsgModel::Ay38910, 0x0E, 32);
+        assert_eq!(levels[16..], ramp_down);
+        let levels = envelope_levels(PsgModel::Ay38910, 0x0F, 20);
+        assert_eq!(levels[16..], [0; 4]);
+
+        // The YM has 32 steps in the same time
+        let levels = envelope_levels(PsgModel::Ym2149, 0x0C, 64);
+        assert_eq!(levels[..32], (0..32).collect::<Vec<_>>());
+        assert_eq!(levels[32..], (0..32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_vgm_export() {
+        let mut psg = new_psg(PsgModel::Ym2149);
+        psg.set_register(0, 0x42);
+        psg.start_vgm_log();
+        psg.tick(CLOCK_HZ / 100);
+        psg.set_register(AMPLITUDE_A, 0x0F);
+        psg.tick(CLOCK_HZ / 100);
+
+        let mut vgm = Vec::new();
+        psg.write_vgm(&mut vgm).unwrap();
+        let word = |offset: usize| u32::from_le_bytes(vgm[offset..offset + 4].try_into().unwrap());
+        assert_eq!(&vgm[..4], b"Vgm ");
+        assert_eq!(word(0x04) as usize, vgm.len() - 4);
+        assert_eq!(word(0x18), 882);
+        assert_eq!(word(0x74), CLOCK_HZ);
+        assert_eq!(vgm[0x78], 0x10);
+
+        let commands = &vgm[VGM_HEADER_SIZE..];
+        // Fifteen registers of initial state, the first being tone A
+        assert_eq!(commands[..3], [VGM_AY8910_WRITE, 0, 0x42]);
+        let rest = &commands[15 * 3..];
+        assert_eq!(
+            rest,
+            [
+                VGM_WAIT,
+                0xB9,
+                0x01, // 441 samples
+                VGM_AY8910_WRITE,
+                AMPLITUDE_A as u8,
+                0x0F,
+                VGM_WAIT,
+                0xB9,
+                0x01,
+                VGM_END,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_oric_via_wiring() {
+        // The Oric drives the PSG data bus from VIA port A, BC1 from CA2 and BDIR from CB2
+        #[rustfmt::skip]
+        let program = [
+            0xA9, 0xFF,       // LDA #$FF
+            0x8D, 0x03, 0x03, // STA DDRA
+            0xA9, 0x08,       // LDA #$08 (amplitude A)
+            0x8D, 0x01, 0x03, // STA ORA
+            0xA9, 0xFF,       // LDA #$FF (CA2 high, CB2 high: latch address)
+            0x8D, 0x0C, 0x03, // STA PCR
+            0xA9, 0xDD,       // LDA #$DD (both low: inactive)
+            0x8D, 0x0C, 0x03, // STA PCR
+            0xA9, 0x0C,       // LDA #$0C
+            0x8D, 0x01, 0x03, // STA ORA
+            0xA9, 0xFD,       // LDA #$FD (CA2 low, CB2 high: write)
+            0x8D, 0x0C, 0x03, // STA PCR
+            0xA9, 0xDD,       // LDA #$DD
+            0x8D, 0x0C, 0x03, // STA PCR
+            0x4C, 0x23, 0xF0, // JMP *
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+        let mut machine = Machine::builder()
+            .device(0x0300..=0x030F, Via6522::new())
+            .device(0x0400..=0x0401, new_psg(PsgModel::Ay38910))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+
+        for _ in 0..20 {
+            machine.step().unwrap();
+            let via = machine.device::<Via6522>().unwrap();
+            let (data, bc1, bdir) = (via.port_a(), via.ca2(), via.cb2());
+            machine
+                .device_mut::<Ay38910>()
+                .unwrap()
+                .bus_control(bdir, bc1, data);
+        }
+        assert_eq!(
+            machine.device::<Ay38910>().unwrap().register(AMPLITUDE_A),
+            0x0C
+        );
+    }
+}
@@ -0,0 +1,819 @@
+use serde::{Deserialize, Serialize};
+
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Register offsets
+const PRA: u16 = 0x0;
+const PRB: u16 = 0x1;
+const DDRA: u16 = 0x2;
+const DDRB: u16 = 0x3;
+const TA_LO: u16 = 0x4;
+const TA_HI: u16 = 0x5;
+const TB_LO: u16 = 0x6;
+const TB_HI: u16 = 0x7;
+const TOD_10THS: u16 = 0x8;
+const TOD_SEC: u16 = 0x9;
+const TOD_MIN: u16 = 0xA;
+const TOD_HR: u16 = 0xB;
+const SDR: u16 = 0xC;
+const ICR: u16 = 0xD;
+const CRA: u16 = 0xE;
+const CRB: u16 = 0xF;
+
+// Interrupt control register bits
+const ICR_TA: u8 = 1 << 0;
+const ICR_TB: u8 = 1 << 1;
+const ICR_ALARM: u8 = 1 << 2;
+const ICR_SP: u8 = 1 << 3;
+const ICR_FLAG: u8 = 1 << 4;
+const ICR_IR: u8 = 1 << 7;
+
+// Control register bits shared by CRA and CRB
+const CR_START: u8 = 1 << 0;
+const CR_PB_ON: u8 = 1 << 1;
+const CR_TOGGLE: u8 = 1 << 2;
+const CR_ONE_SHOT: u8 = 1 << 3;
+const CR_LOAD: u8 = 1 << 4;
+// CRA only
+const CRA_COUNT_CNT: u8 = 1 << 5;
+const CRA_SP_OUTPUT: u8 = 1 << 6;
+const CRA_TOD_50HZ: u8 = 1 << 7;
+// CRB only
+const CRB_INPUT_MODE: u8 = 0b11 << 5;
+const CRB_ALARM: u8 = 1 << 7;
+
+const PM: u8 = 1 << 7;
+
+/// Chip revision, which decides the TOD format and interrupt timing
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CiaModel {
+    /// Original 6526: the IRQ output follows an interrupt flag one cycle late
+    #[default]
+    Mos6526,
+    /// 6526A / 8521R4 style timing: the IRQ output follows in the same cycle
+    Mos6526A,
+    /// 8520/8521 as used by the Amiga: the TOD is a 24-bit binary event counter
+    Mos8521,
+}
+
+/// One of the two interval timers
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct CiaTimer {
+    counter: u16,
+    latch: u16,
+    control: u8,
+    toggle: bool,
+    pulse: bool,
+}
+
+impl CiaTimer {
+    /// Count one input pulse, returning true on underflow
+    fn count(&mut self) -> bool {
+        if self.control & CR_START == 0 {
+            return false;
+        }
+        if self.counter != 0 {
+            self.counter -= 1;
+            return false;
+        }
+        self.counter = self.latch;
+        self.toggle = !self.toggle;
+        self.pulse = true;
+        if self.control & CR_ONE_SHOT != 0 {
+            self.control &= !CR_START;
+        }
+        true
+    }
+
+    fn write_latch(&mut self, value: u16) {
+        self.latch = value;
+        if self.control & CR_START == 0 {
+            self.counter = self.latch;
+        }
+    }
+
+    fn write_control(&mut self, value: u8) {
+        if value & CR_LOAD != 0 {
+            self.counter = self.latch;
+        }
+        if self.control & CR_START == 0 && value & CR_START != 0 {
+            self.toggle = true;
+        }
+        self.control = value & !CR_LOAD;
+    }
+
+    /// Level driven on PB6/PB7 when the timer output is enabled
+    #[inline]
+    fn output(&self) -> bool {
+        if self.control & CR_TOGGLE != 0 {
+            self.toggle
+        } else {
+            self.pulse
+        }
+    }
+}
+
+/// MOS 6526 Complex Interface Adapter, and its 6526A and 8520/8521 relatives.
+///
+/// Port pins are open-collector with pull-ups, so they read as the AND of what the CIA
+/// drives and what the outside world pulls low. A keyboard matrix given to
+/// [`Cia6526::set_key_matrix`] connects port A columns to port B rows the way the C64
+/// keyboard does, in both scan directions.
+///
+/// Timers count N, N-1, ... 0 and then underflow and reload, for a period of N + 1
+/// cycles. The TOD clock counts pulses of its 50/60 Hz pin, either driven with
+/// [`Cia6526::tod_pulse`] or derived from the CPU clock by
+/// [`Cia6526::set_tod_input`].
+///
+/// The interrupt output goes to IRQ, or to NMI when built with [`Cia6526::with_nmi`]
+/// like the C64's second CIA.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Cia6526 {
+    model: CiaModel,
+    nmi_output: bool,
+    pra: u8,
+    prb: u8,
+    ddra: u8,
+    ddrb: u8,
+    port_a_pins: u8,
+    port_b_pins: u8,
+    key_matrix: [u8; 8],
+    timer_a: CiaTimer,
+    timer_b: CiaTimer,
+    tod: [u8; 4],
+    alarm: [u8; 4],
+    tod_latch: Option<[u8; 4]>,
+    tod_stopped: bool,
+    tod_pulses: u8,
+    tod_input: Option<(u32, u32)>,
+    tod_phase: u64,
+    sdr: u8,
+    shift_register: u8,
+    shift_bits: u8,
+    sdr_loaded: bool,
+    sp: bool,
+    cnt: bool,
+    cnt_edges: u32,
+    flag_pin: bool,
+    icr_flags: u8,
+    icr_mask: u8,
+    irq_line: bool,
+    irq_delayed: bool,
+}
+
+impl Default for Cia6526 {
+    fn default() -> Self {
+        Self::new(CiaModel::default())
+    }
+}
+
+impl Cia6526 {
+    pub fn new(model: CiaModel) -> Self {
+        Self {
+            model,
+            nmi_output: false,
+            pra: 0,
+            prb: 0,
+            ddra: 0,
+            ddrb: 0,
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            key_matrix: [0; 8],
+            timer_a: CiaTimer::default(),
+            timer_b: CiaTimer::default(),
+            tod: match model {
+                CiaModel::Mos8521 => [0; 4],
+                _ => [0, 0, 0, 0x01],
+            },
+            alarm: [0; 4],
+            tod_latch: None,
+            tod_stopped: false,
+            tod_pulses: 0,
+            tod_input: None,
+            tod_phase: 0,
+            sdr: 0,
+            shift_register: 0,
+            shift_bits: 0,
+            sdr_loaded: false,
+            sp: true,
+            cnt: true,
+            cnt_edges: 0,
+            flag_pin: true,
+            icr_flags: 0,
+            icr_mask: 0,
+            irq_line: false,
+            irq_delayed: false,
+        }
+    }
+
+    /// Route the interrupt output to NMI instead of IRQ
+    pub fn with_nmi(mut self) -> Self {
+        self.nmi_output = true;
+        self
+    }
+
+    #[inline]
+    pub fn model(&self) -> CiaModel {
+        self.model
+    }
+
+    /// Levels on the port A pins
+    pub fn port_a(&self) -> u8 {
+        let (driven_a, driven_b) = self.driven_levels();
+        let mut pulled_low = 0;
+        for (column, rows) in self.key_matrix.iter().enumerate() {
+            if rows & !driven_b != 0 {
+                pulled_low |= 1 << column;
+            }
+        }
+        driven_a & !pulled_low
+    }
+
+    /// Levels on the port B pins, including timer outputs on PB6 and PB7
+    pub fn port_b(&self) -> u8 {
+        let (driven_a, driven_b) = self.driven_levels();
+        let mut pulled_low = 0;
+        for (column, rows) in self.key_matrix.iter().enumerate() {
+            if driven_a & (1 << column) == 0 {
+                pulled_low |= rows;
+            }
+        }
+        driven_b & !pulled_low
+    }
+
+    /// Pull port A input pins low where `value` has zeros, e.g. from a joystick
+    #[inline]
+    pub fn set_port_a(&mut self, value: u8) {
+        self.port_a_pins = value;
+    }
+
+    /// Pull port B input pins low where `value` has zeros
+    #[inline]
+    pub fn set_port_b(&mut self, value: u8) {
+        self.port_b_pins = value;
+    }
+
+    /// Pressed keys, as a bitmask of port B rows for each port A column
+    #[inline]
+    pub fn set_key_matrix(&mut self, matrix: [u8; 8]) {
+        self.key_matrix = matrix;
+    }
+
+    /// Drive the FLAG input. A falling edge raises the FLAG interrupt.
+    pub fn set_flag(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.flag_pin, level);
+        if previous && !level {
+            self.raise(ICR_FLAG);
+        }
+    }
+
+    /// Drive the CNT input. Rising edges clock the timers in CNT mode and, with the
+    /// serial port in input mode, shift in the level on SP.
+    pub fn set_cnt(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.cnt, level);
+        if previous || !level {
+            return;
+        }
+        self.cnt_edges += 1;
+        if self.timer_a.control & CRA_SP_OUTPUT == 0 {
+            self.shift_register = self.shift_register << 1 | self.sp as u8;
+            self.shift_bits += 1;
+            if self.shift_bits == 8 {
+                self.shift_bits = 0;
+                self.sdr = self.shift_register;
+                self.raise(ICR_SP);
+            }
+        }
+    }
+
+    /// Drive the SP input
+    #[inline]
+    pub fn set_sp(&mut self, level: bool) {
+        self.sp = level;
+    }
+
+    /// Level on CNT, which is the shift clock output in serial output mode
+    #[inline]
+    pub fn cnt(&self) -> bool {
+        self.cnt
+    }
+
+    /// Level on SP, which is the shift data output in serial output mode
+    #[inline]
+    pub fn sp(&self) -> bool {
+        self.sp
+    }
+
+    /// Pulse the TOD input once
+    pub fn tod_pulse(&mut self) {
+        if self.model == CiaModel::Mos8521 {
+            if !self.tod_stopped {
+                self.increment_counter();
+            }
+            return;
+        }
+        self.tod_pulses += 1;
+        let divider = if self.timer_a.control & CRA_TOD_50HZ != 0 {
+            5
+        } else {
+            6
+        };
+        if self.tod_pulses >= divider {
+            self.tod_pulses = 0;
+            if !self.tod_stopped {
+                self.increment_tod();
+            }
+        }
+    }
+
+    /// Derive the TOD input from the CPU clock: `tod_hz` pulses per second on a CPU
+    /// running at `clock_hz`. `None` leaves the input to [`Cia6526::tod_pulse`].
+    pub fn set_tod_input(&mut self, input: Option<(u32, u32)>) {
+        self.tod_input = input;
+        self.tod_phase = 0;
+    }
+
+    fn raise(&mut self, flags: u8) {
+        self.icr_flags |= flags;
+        self.update_irq();
+    }
+
+    fn update_irq(&mut self) {
+        if self.icr_flags & self.icr_mask != 0 && !self.irq_line {
+            match self.model {
+                CiaModel::Mos6526 => self.irq_delayed = true,
+                _ => self.irq_line = true,
+            }
+        }
+    }
+
+    fn driven_levels(&self) -> (u8, u8) {
+        let a = (self.pra | !self.ddra) & self.port_a_pins;
+        let mut b = (self.prb | !self.ddrb) & self.port_b_pins;
+        for (timer, bit) in [(&self.timer_a, 6), (&self.timer_b, 7)] {
+            if timer.control & CR_PB_ON != 0 {
+                b = (b & !(1 << bit)) | (timer.output() as u8) << bit;
+            }
+        }
+        (a, b)
+    }
+
+    fn increment_tod(&mut self) {
+        let [tenths, seconds, minutes, hours] = &mut self.tod;
+        *tenths = (*tenths + 1) % 10;
+        if *tenths == 0 && bcd_increment(seconds, 0x59) && bcd_increment(minutes, 0x59) {
+            let pm = *hours & PM;
+            *hours = match *hours & !PM {
+                0x11 => 0x12 | (pm ^ PM),
+                0x12 => 0x01 | pm,
+                hour => {
+                    let mut hour = hour;
+                    bcd_increment(&mut hour, 0x12);
+                    hour | pm
+                }
+            };
+        }
+        self.check_alarm();
+    }
+
+    fn increment_counter(&mut self) {
+        let value = u32::from_le_bytes(self.tod).wrapping_add(1) & 0xFF_FFFF;
+        self.tod = value.to_le_bytes();
+        self.check_alarm();
+    }
+
+    fn check_alarm(&mut self) {
+        if self.tod == self.alarm {
+            self.raise(ICR_ALARM);
+        }
+    }
+
+    /// Advance the serial output on a timer A underflow: CNT toggles, data goes out on
+    /// the falling edge and each rising edge completes a bit
+    fn clock_serial_output(&mut self) {
+        if self.shift_bits == 0 {
+            if !self.sdr_loaded {
+                return;
+            }
+            self.sdr_loaded = false;
+            self.shift_register = self.sdr;
+            self.shift_bits = 8;
+        }
+        self.cnt = !self.cnt;
+        if !self.cnt {
+            self.sp = self.shift_register & 0x80 != 0;
+            self.shift_register <<= 1;
+            return;
+        }
+        self.shift_bits -= 1;
+        if self.shift_bits == 0 {
+            self.raise(ICR_SP);
+        }
+    }
+
+    fn cycle(&mut self) {
+        if std::mem::take(&mut self.irq_delayed) {
+            self.irq_line = true;
+        }
+        self.timer_a.pulse = false;
+        self.timer_b.pulse = false;
+
+        let cnt_edges = std::mem::take(&mut self.cnt_edges);
+        let a_underflow = if self.timer_a.control & CRA_COUNT_CNT != 0 {
+            (0..cnt_edges).fold(false, |underflow, _| self.timer_a.count() || underflow)
+        } else {
+            self.timer_a.count()
+        };
+        if a_underflow {
+            self.raise(ICR_TA);
+            if self.timer_a.control & CRA_SP_OUTPUT != 0 {
+                self.clock_serial_output();
+            }
+        }
+
+        let b_underflow = match (self.timer_b.control & CRB_INPUT_MODE) >> 5 {
+            0 => self.timer_b.count(),
+            1 => (0..cnt_edges).fold(false, |underflow, _| self.timer_b.count() || underflow),
+            2 => a_underflow && self.timer_b.count(),
+            _ => a_underflow && self.cnt && self.timer_b.count(),
+        };
+        if b_underflow {
+            self.raise(ICR_TB);
+        }
+
+        if let Some((clock_hz, tod_hz)) = self.tod_input {
+            self.tod_phase += tod_hz as u64;
+            if self.tod_phase >= clock_hz as u64 {
+                self.tod_phase -= clock_hz as u64;
+                self.tod_pulse();
+            }
+        }
+    }
+
+    fn read_tod(&mut self, index: usize) -> u8 {
+        let latch_index = match self.model {
+            CiaModel::Mos8521 => 2,
+            _ => 3,
+        };
+        if index == latch_index && self.tod_latch.is_none() {
+            self.tod_latch = Some(self.tod);
+        }
+        let value = self.tod_latch.unwrap_or(self.tod)[index];
+        if index == 0 {
+            self.tod_latch = None;
+        }
+        value
+    }
+
+    fn write_tod(&mut self, index: usize, value: u8) {
+        let stop_index = match self.model {
+            CiaModel::Mos8521 => 2,
+            _ => 3,
+        };
+        let value = match (self.model, index) {
+            (CiaModel::Mos8521, 3) => return,
+            (CiaModel::Mos8521, _) => value,
+            (_, 0) => value & 0x0F,
+            (_, 3) => value & 0x9F,
+            _ => value & 0x7F,
+        };
+        if self.timer_b.control & CRB_ALARM != 0 {
+            self.alarm[index] = value;
+            return;
+        }
+        self.tod[index] = value;
+        if index == stop_index {
+            self.tod_stopped = true;
+        } else if index == 0 {
+            self.tod_stopped = false;
+            self.tod_pulses = 0;
+        }
+    }
+}
+
+/// Increment a BCD byte, wrapping to zero past `max`. Returns true on wrap-around.
+fn bcd_increment(value: &mut u8, max: u8) -> bool {
+    if *value >= max {
+        *value = 0;
+        return true;
+    }
+    *value = if *value & 0x0F == 9 {
+        (*value & 0xF0) + 0x10
+    } else {
+        *value + 1
+    };
+    false
+}
+
+impl Device for Cia6526 {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        Ok(match offset & 0x0F {
+            PRA => self.port_a(),
+            PRB => self.port_b(),
+            DDRA => self.ddra,
+            DDRB => self.ddrb,
+            TA_LO => self.timer_a.counter as u8,
+            TA_HI => (self.timer_a.counter >> 8) as u8,
+            TB_LO => self.timer_b.counter as u8,
+            TB_HI => (self.timer_b.counter >> 8) as u8,
+            TOD_10THS | TOD_SEC | TOD_MIN | TOD_HR => {
+                self.read_tod(((offset & 0x0F) - TOD_10THS) as usize)
+            }
+            SDR => self.sdr,
+            ICR => {
+                let ir = self.irq_line || self.irq_delayed;
+                let value = self.icr_flags | if ir { ICR_IR } else { 0 };
+                self.icr_flags = 0;
+                self.irq_line = false;
+                self.irq_delayed = false;
+                value
+            }
+            CRA => self.timer_a.control,
+            CRB => self.timer_b.control,
+            _ => unreachable!("offset is masked to four bits"),
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        match offset & 0x0F {
+            PRA => self.pra = value,
+            PRB => self.prb = value,
+            DDRA => self.ddra = value,
+            DDRB => self.ddrb = value,
+            TA_LO => self.timer_a.latch = (self.timer_a.latch & 0xFF00) | value as u16,
+            TA_HI => self
+                .timer_a
+                .write_latch((self.timer_a.latch & 0x00FF) | (value as u16) << 8),
+            TB_LO => self.timer_b.latch = (self.timer_b.latch & 0xFF00) | value as u16,
+            TB_HI => self
+                .timer_b
+                .write_latch((self.timer_b.latch & 0x00FF) | (value as u16) << 8),
+            TOD_10THS | TOD_SEC | TOD_MIN | TOD_HR => {
+                self.write_tod(((offset & 0x0F) - TOD_10THS) as usize, value)
+            }
+            SDR => {
+                self.sdr = value;
+                if self.timer_a.control & CRA_SP_OUTPUT != 0 {
+                    self.sdr_loaded = true;
+                }
+            }
+            ICR => {
+                if value & ICR_IR != 0 {
+                    self.icr_mask |= value & !ICR_IR;
+                } else {
+                    self.icr_mask &= !value;
+                }
+                self.update_irq();
+            }
+            CRA => {
+                if (self.timer_a.control ^ value) & CRA_SP_OUTPUT != 0 {
+                    self.shift_bits = 0;
+                    self.cnt = true;
+                }
+                self.timer_a.write_control(value);
+            }
+            CRB => self.timer_b.write_control(value),
+            _ => unreachable!("offset is masked to four bits"),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_line && !self.nmi_output
+    }
+
+    fn nmi(&self) -> bool {
+        self.irq_line && self.nmi_output
+    }
+
+    /// Reset clears the ports, timers and interrupts. The TOD keeps its time but the
+    /// divider is set back to 60 Hz.
+    fn reset(&mut self) {
+        self.pra = 0;
+        self.prb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.timer_a = CiaTimer::default();
+        self.timer_b = CiaTimer::default();
+        self.timer_a.latch = 0xFFFF;
+        self.timer_b.latch = 0xFFFF;
+        self.tod_latch = None;
+        self.shift_bits = 0;
+        self.sdr_loaded = false;
+        self.icr_flags = 0;
+        self.icr_mask = 0;
+        self.irq_line = false;
+        self.irq_delayed = false;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        *self = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    #[test]
+    fn test_timer_period_and_cascade() {
+        let mut cia = Cia6526::new(CiaModel::Mos6526A);
+        cia.write(TA_LO, 3).unwrap();
+        cia.write(TA_HI, 0).unwrap();
+        cia.write(CRA, CR_START).unwrap();
+        cia.tick(3);
+        assert_eq!(cia.read(TA_LO).unwrap(), 0);
+        assert_eq!(cia.read(ICR).unwrap(), 0);
+        cia.tick(1);
+        assert_eq!(cia.read(TA_LO).unwrap(), 3);
+        assert_eq!(cia.read(ICR).unwrap(), ICR_TA);
+
+        // Timer B counts timer A underflows, which now come every other cycle
+        cia.write(TA_LO, 1).unwrap();
+        cia.write(CRA, CR_START | CR_LOAD).unwrap();
+        cia.write(TB_LO, 2).unwrap();
+        cia.write(TB_HI, 0).unwrap();
+        cia.write(CRB, CR_START | CR_ONE_SHOT | 0b10 << 5).unwrap();
+        cia.tick(5);
+        assert_eq!(cia.read(ICR).unwrap(), ICR_TA);
+        cia.tick(1);
+        assert_eq!(cia.read(ICR).unwrap(), ICR_TA | ICR_TB);
+        assert_eq!(cia.read(CRB).unwrap() & CR_START, 0, "one-shot stops");
+    }
+
+    #[test]
+    fn test_irq_timing_by_model() {
+        for (model, delay) in [(CiaModel::Mos6526, 1), (CiaModel::Mos6526A, 0)] {
+            let mut cia = Cia6526::new(model);
+            cia.write(ICR, ICR_IR | ICR_TA).unwrap();
+            cia.write(TA_LO, 1).unwrap();
+            cia.write(TA_HI, 0).unwrap();
+            cia.write(CRA, CR_START | CR_ONE_SHOT).unwrap();
+            cia.tick(2);
+            assert_eq!(cia.irq(), delay == 0, "{model:?}");
+            cia.tick(delay);
+            assert!(cia.irq(), "{model:?}");
+            assert_eq!(cia.read(ICR).unwrap(), ICR_IR | ICR_TA);
+            assert!(!cia.irq());
+        }
+
+        // The second CIA of a C64 drives NMI instead
+        let mut cia = Cia6526::new(CiaModel::Mos6526A).with_nmi();
+        cia.write(ICR, ICR_IR | ICR_FLAG).unwrap();
+        cia.set_flag(false);
+        assert!(cia.nmi());
+        assert!(!cia.irq());
+    }
+
+    #[test]
+    fn test_tod_rollover_latch_and_alarm() {
+        let mut cia = Cia6526::new(CiaModel::Mos6526);
+        cia.write(TOD_HR, 0x11).unwrap();
+        cia.write(TOD_MIN, 0x59).unwrap();
+        cia.write(TOD_SEC, 0x59).unwrap();
+        cia.write(TOD_10THS, 0x09).unwrap();
+        cia.write(CRB, CRB_ALARM).unwrap();
+        cia.write(TOD_HR, 0x92).unwrap();
+        cia.write(TOD_MIN, 0).unwrap();
+        cia.write(TOD_SEC, 0).unwrap();
+        cia.write(TOD_10THS, 0).unwrap();
+        cia.write(CRB, 0).unwrap();
+
+        // Reading the hours freezes the registers until the tenths are read
+        assert_eq!(cia.read(TOD_HR).unwrap(), 0x11);
+        for _ in 0..6 {
+            cia.tod_pulse();
+        }
+        assert_eq!(cia.read(TOD_MIN).unwrap(), 0x59);
+        assert_eq!(cia.read(TOD_10THS).unwrap(), 0x09);
+        assert_eq!(cia.read(TOD_HR).unwrap(), 0x92, "11 AM rolls over to 12 PM");
+        assert_eq!(cia.read(TOD_SEC).unwrap(), 0x00);
+        assert_eq!(cia.read(TOD_10THS).unwrap(), 0x00);
+        assert_eq!(cia.read(ICR).unwrap(), ICR_ALARM);
+
+        // 50 Hz input divides by five, and writing the hours stops the clock
+        cia.write(CRA, CRA_TOD_50HZ).unwrap();
+        for _ in 0..5 {
+            cia.tod_pulse();
+        }
+        assert_eq!(cia.read(TOD_10THS).unwrap(), 0x01);
+        cia.write(TOD_HR, 0x12).unwrap();
+        for _ in 0..5 {
+            cia.tod_pulse();
+        }
+        assert_eq!(cia.read(TOD_10THS).unwrap(), 0x01);
+
+        // The 8521 counts every pulse in binary
+        let mut cia = Cia6526::new(CiaModel::Mos8521);
+        cia.write(TOD_MIN, 0x00).unwrap();
+        cia.write(TOD_SEC, 0xFF).unwrap();
+        cia.write(TOD_10THS, 0xFF).unwrap();
+        cia.tod_pulse();
+        assert_eq!(cia.read(TOD_MIN).unwrap(), 0x01);
+        assert_eq!(cia.read(TOD_SEC).unwrap(), 0x00);
+        assert_eq!(cia.read(TOD_10THS).unwrap(), 0x00);
+    }
+
+    #[test]
+    fn test_keyboard_matrix_and_serial_output() {
+        let mut cia = Cia6526::new(CiaModel::Mos6526);
+        let mut matrix = [0; 8];
+        matrix[2] = 1 << 5;
+        cia.set_key_matrix(matrix);
+
+        // Scan with port A driving columns and port B reading rows
+        cia.write(DDRA, 0xFF).unwrap();
+        cia.write(PRA, !(1 << 1)).unwrap();
+        assert_eq!(cia.read(PRB).unwrap(), 0xFF);
+        cia.write(PRA, !(1 << 2)).unwrap();
+        assert_eq!(cia.read(PRB).unwrap(), !(1 << 5));
+
+        // And the other way round
+        cia.write(DDRA, 0x00).unwrap();
+        cia.write(DDRB, 0xFF).unwrap();
+        cia.write(PRB, !(1 << 5)).unwrap();
+        assert_eq!(cia.read(PRA).unwrap(), !(1 << 2));
+        cia.set_port_a(0x7F);
+        assert_eq!(cia.read(PRA).unwrap(), 0x7F & !(1 << 2));
+
+        // Serial output shifts MSB first, one bit per two timer A underflows
+        cia.write(TA_LO, 0).unwrap();
+        cia.write(TA_HI, 0).unwrap();
+        cia.write(CRA, CR_START | CRA_SP_OUTPUT).unwrap();
+        cia.write(SDR, 0xA5).unwrap();
+        let mut byte = 0u8;
+        for _ in 0..8 {
+            cia.tick(1);
+            assert!(!cia.cnt());
+            cia.tick(1);
+            assert!(cia.cnt());
+            byte = byte << 1 | cia.sp() as u8;
+        }
+        assert_eq!(byte, 0xA5);
+        assert_eq!(cia.read(ICR).unwrap() & ICR_SP, ICR_SP);
+
+        // Serial input shifts on CNT rising edges
+        cia.write(CRA, 0).unwrap();
+        for bit in (0..8).rev() {
+            cia.set_sp(0x3C & (1 << bit) != 0);
+            cia.set_cnt(false);
+            cia.set_cnt(true);
+        }
+        assert_eq!(cia.read(SDR).unwrap(), 0x3C);
+        assert_eq!(cia.read(ICR).unwrap(), ICR_SP);
+    }
+
+    #[test]
+    fn test_timer_irq_program() {
+        #[rustfmt::skip]
+        let program = [
+            0x78,             // SEI
+            0xA9, 0x63,       // LDA #$63
+            0x8D, 0x04, 0xDC, // STA TA_LO
+            0xA9, 0x00,       // LDA #$00
+            0x8D, 0x05, 0xDC, // STA TA_HI
+            0xA9, 0x81,       // LDA #$81
+            0x8D, 0x0D, 0xDC, // STA ICR
+            0xA9, 0x11,       // LDA #$11
+            0x8D, 0x0E, 0xDC, // STA CRA
+            0x58,             // CLI
+            0x4C, 0x16, 0xF0, // JMP *
+        ];
+        #[rustfmt::skip]
+        let handler = [
+            0xAD, 0x0D, 0xDC, // LDA ICR
+            0xE8,             // INX
+            0x40,             // RTI
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x800..0x800 + handler.len()].copy_from_slice(&handler);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF8]);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x01FF)
+            .device(0xDC00..=0xDCFF, Cia6526::new(CiaModel::Mos6526))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+
+        machine.run_cycles(1_000).unwrap();
+        // A 100 cycle period after setup, with the ICR read acknowledging each one
+        let count = machine.cpu().x_register();
+        assert!((9..=10).contains(&count), "{count} interrupts");
+        assert!(!machine.device::<Cia6526>().unwrap().irq());
+        assert_eq!(machine.bus().read(0xDC0E).unwrap(), 0x01);
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::VecDeque;
+
+use crate::device::serial::SerialBackend;
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+use crate::mos6502::Bus;
+
+// Register offsets
+const DATA: u16 = 0;
+const STATUS: u16 = 1;
+const CONTROL: u16 = 2;
+const EXIT: u16 = 3;
+/// Number of registers
+const REGISTERS: u16 = 4;
+
+// Status register bits
+const STATUS_INPUT_READY: u8 = 1 << 0;
+const STATUS_OUTPUT_READY: u8 = 1 << 1;
+
+// Control register bits
+const CONTROL_INPUT_IRQ: u8 = 1 << 0;
+
+/// Magic-address console for running programs headless.
+///
+/// Writing the data register at offset 0 sends the byte to the backend, and reading it
+/// takes the next input byte, or 0 when there is none. The status register at offset 1
+/// has bit 0 set while input is waiting and bit 1 always set, since output never
+/// blocks. Setting bit 0 of the control register at offset 2 holds IRQ asserted while
+/// input is waiting. Writing the exit register at offset 3 stops the [`Machine`] run
+/// loops with the written value as the exit code.
+///
+/// Input is polled from the backend as the console is ticked. The console is a
+/// [`Device`] for a [`Machine`], and [`ConsoleBus`] puts it in front of any other bus.
+///
+/// [`Machine`]: crate::machine::Machine
+pub struct Console<B: SerialBackend> {
+    backend: B,
+    input: VecDeque<u8>,
+    control: u8,
+    exit_code: Option<u8>,
+}
+
+impl<B: SerialBackend> Console<B> {
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            input: VecDeque::new(),
+            control: 0,
+            exit_code: None,
+        }
+    }
+
+    #[inline]
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    #[inline]
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Queue bytes for the program to read, ahead of anything from the backend
+    pub fn push_input(&mut self, bytes: &[u8]) {
+        for &byte in bytes.iter().rev() {
+            self.input.push_front(byte);
+        }
+    }
+
+    fn poll(&mut self) {
+        while let Some(byte) = self.backend.receive() {
+            self.input.push_back(byte);
+        }
+    }
+}
+
+impl<B: SerialBackend> Device for Console<B> {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        Ok(match offset % REGISTERS {
+            DATA => self.input.pop_front().unwrap_or(0),
+            STATUS => {
+                let input = if self.input.is_empty() {
+                    0
+                } else {
+                    STATUS_INPUT_READY
+                };
+                input | STATUS_OUTPUT_READY
+            }
+            CONTROL => self.control,
+            EXIT => self.exit_code.unwrap_or(0),
+            _ => unreachable!(),
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        match offset % REGISTERS {
+            DATA => self.backend.transmit(value),
+            STATUS => {}
+            CONTROL => self.control = value,
+            EXIT => self.exit_code = Some(value),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, _cycles: u32) {
+        self.poll();
+    }
+
+    fn irq(&self) -> bool {
+        self.control & CONTROL_INPUT_IRQ != 0 && !self.input.is_empty()
+    }
+
+    fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+
+    fn reset(&mut self) {
+        self.control = 0;
+        self.exit_code = None;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(&(
+            &self.input,
+            self.control,
+            self.exit_code,
+        ))?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        (self.input, self.control, self.exit_code) = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+/// Any bus with a [`Console`] mapped over four addresses from `base`, for running a bare
+/// CPU core. The caller ticks it and checks [`ConsoleBus::exit_code`] between steps.
+pub struct ConsoleBus<T: Bus, B: SerialBackend> {
+    bus: T,
+    console: Console<B>,
+    base: u16,
+}
+
+impl<T: Bus, B: SerialBackend> ConsoleBus<T, B> {
+    pub fn new(bus: T, console: Console<B>, base: u16) -> Self {
+        Self { bus, console, base }
+    }
+
+    #[inline]
+    pub fn bus(&self) -> &T {
+        &self.bus
+    }
+
+    #[inline]
+    pub fn bus_mut(&mut self) -> &mut T {
+        &mut self.bus
+    }
+
+    #[inline]
+    pub fn console(&self) -> &Console<B> {
+        &self.console
+    }
+
+    #[inline]
+    pub fn console_mut(&mut self) -> &mut Console<B> {
+        &mut self.console
+    }
+
+    /// Advance the console, polling the backend for input
+    pub fn tick(&mut self, cycles: u32) {
+        self.console.tick(cycles);
+    }
+
+    #[inline]
+    pub fn irq(&self) -> bool {
+        self.console.irq()
+    }
+
+    #[inline]
+    pub fn exit_code(&self) -> Option<u8> {
+        self.console.exit_code
+    }
+
+    fn console_offset(&self, address: u16) -> Option<u16> {
+        address
+            .checked_sub(self.base)
+            .filter(|&offset| offset < REGISTERS)
+    }
+}
+
+impl<T: Bus, B: SerialBackend> Bus for ConsoleBus<T, B> {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        match self.console_offset(address) {
+            Some(offset) => self.console.read(offset),
+            None => self.bus.read(address),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) -> Result<(), BusError> {
+        match self.console_offset(address) {
+            Some(offset) => self.console.write(offset, value),
+            None => self.bus.write(address, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::serial::BufferBackend;
+    use crate::machine::Machine;
+    use crate::mos6502::MOS6502;
+
+    struct Ram(Vec<u8>);
+
+    impl Bus for Ram {
+        fn read(&mut self, address: u16) -> Result<u8, BusError> {
+            Ok(self.0[address as usize])
+        }
+
+        fn write(&mut self, address: u16, value: u8) -> Result<(), BusError> {
+            self.0[address as usize] = value;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_hello_world() {
+        #[rustfmt::skip]
+        let program = [
+            0xA2, 0x00,       // LDX #0
+            0xBD, 0x12, 0x02, // loop: LDA message,X
+            0xF0, 0x06,       // BEQ done
+            0x8D, 0x00, 0xF0, // STA DATA
+            0xE8,             // INX
+            0xD0, 0xF5,       // BNE loop
+            0xA9, 0x2A,       // done: LDA #42
+            0x8D, 0x03, 0xF0, // STA EXIT
+        ];
+        let mut memory = vec![0; 0x10000];
+        memory[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        memory[0x0212..0x0221].copy_from_slice(b"Hello, world!\n\0");
+        let console = Console::new(BufferBackend::new());
+        let mut bus = ConsoleBus::new(Ram(memory), console, 0xF000);
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(0x0200);
+
+        while bus.exit_code().is_none() {
+            let cycles = cpu.step(&mut bus).unwrap();
+            bus.tick(cycles);
+        }
+        assert_eq!(bus.exit_code(), Some(42));
+        assert_eq!(bus.console().backend().output(), b"Hello, world!\n");
+        assert_eq!(bus.bus().0[0xF000], 0);
+    }
+
+    #[test]
+    fn test_input_irq_and_exit() {
+        // Echo each input byte in upper case from the IRQ handler, and exit on '.'
+        #[rustfmt::skip]
+        let program = [
+            0xA9, 0x01,       // LDA #CONTROL_INPUT_IRQ
+            0x8D, 0x02, 0x80, // STA CONTROL
+            0x58,             // CLI
+            0x4C, 0x06, 0xF0, // JMP *
+        ];
+        #[rustfmt::skip]
+        let handler = [
+            0xAD, 0x00, 0x80, // LDA DATA
+            0xC9, 0x2E,       // CMP #'.'
+            0xD0, 0x04,       // BNE echo
+            0x8D, 0x03, 0x80, // STA EXIT
+            0x40,             // RTI
+            0x29, 0xDF,       // echo: AND #$DF
+            0x8D, 0x00, 0x80, // STA DATA
+            0x40,             // RTI
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x800..0x800 + handler.len()].copy_from_slice(&handler);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF8]);
+        let mut backend = BufferBackend::new();
+        backend.push_input(b"ok.");
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x3FFF)
+            .device(0x8000..=0x8003, Console::new(backend))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+
+        let console = machine.device_mut::<Console<BufferBackend>>().unwrap();
+        // The backend has been polled during the reset sequence
+        assert_eq!(
+            console.read(STATUS).unwrap(),
+            STATUS_INPUT_READY | STATUS_OUTPUT_READY
+        );
+        let cycles = machine.run_cycles(1_000_000).unwrap();
+        assert!(cycles < 1000);
+        assert_eq!(machine.exit_code(), Some(b'.'));
+        let console = machine.device::<Console<BufferBackend>>().unwrap();
+        assert_eq!(console.backend().output(), b"OK");
+
+        machine.reset().unwrap();
+        assert_eq!(machine.exit_code(), None);
+    }
+
+    #[test]
+    fn test_pushed_input_comes_first() {
+        let mut backend = BufferBackend::new();
+        backend.push_input(b"backend");
+        let mut console = Console::new(backend);
+        console.tick(1);
+        console.push_input(b"pushed ");
+
+        let input: Vec<u8> = std::iter::from_fn(|| match console.read(DATA).unwrap() {
+            0 => None,
+            byte => Some(byte),
+        })
+        .collect();
+        assert_eq!(input, b"pushed backend");
+    }
+}
@@ -0,0 +1,661 @@
+use serde::{Deserialize, Serialize};
+
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+const DDRAM_SIZE: usize = 80;
+const CGRAM_SIZE: usize = 64;
+/// Characters per line of DDRAM in two-line mode
+const LINE_LENGTH: u8 = 40;
+const SECOND_LINE: u8 = 0x40;
+
+// Instructions, identified by their highest set bit
+const CLEAR_DISPLAY: u8 = 0x01;
+const RETURN_HOME: u8 = 0x02;
+const ENTRY_MODE_SET: u8 = 0x04;
+const DISPLAY_CONTROL: u8 = 0x08;
+const CURSOR_SHIFT: u8 = 0x10;
+const FUNCTION_SET: u8 = 0x20;
+const SET_CGRAM_ADDRESS: u8 = 0x40;
+const SET_DDRAM_ADDRESS: u8 = 0x80;
+
+// Instruction argument bits
+const ENTRY_INCREMENT: u8 = 1 << 1;
+const ENTRY_SHIFT: u8 = 1 << 0;
+const DISPLAY_ON: u8 = 1 << 2;
+const CURSOR_ON: u8 = 1 << 1;
+const BLINK_ON: u8 = 1 << 0;
+const SHIFT_DISPLAY: u8 = 1 << 3;
+const SHIFT_RIGHT: u8 = 1 << 2;
+const FUNCTION_8_BIT: u8 = 1 << 4;
+const FUNCTION_2_LINES: u8 = 1 << 3;
+
+const BUSY_FLAG: u8 = 1 << 7;
+
+/// Execution times in microseconds at the nominal 270 kHz oscillator
+const CLEAR_US: u64 = 1520;
+const INSTRUCTION_US: u64 = 37;
+/// Data writes and reads also update the address counter afterwards
+const DATA_US: u64 = 41;
+/// The cursor blinks with a 409.6 ms half period
+const BLINK_US: u64 = 409_600;
+
+/// Hitachi HD44780 character LCD controller with its display.
+///
+/// The controller can sit on the CPU bus, with the instruction register at offset 0
+/// and the data register at offset 1, or on port pins driven through
+/// [`Hd44780::set_pins`], as in the Ben Eater kit where a 6522's port B carries the data
+/// lines and PA5-PA7 drive RS, R/W and E. In 4-bit mode only D4-D7 are used and each
+/// byte takes two transfers, high nibble first.
+///
+/// Instructions and data take their datasheet execution times, counted in CPU cycles,
+/// during which the busy flag is set. Anything written while busy is ignored, so
+/// programs have to poll the flag or wait long enough, as on the real chip. The chip
+/// comes out of its internal reset in 8-bit, one-line mode with the display off.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Hd44780 {
+    cpu_clock_hz: u32,
+    columns: u8,
+    rows: u8,
+    ddram: Vec<u8>,
+    cgram: Vec<u8>,
+    address: u8,
+    cgram_selected: bool,
+    increment: bool,
+    shift_on_write: bool,
+    display_on: bool,
+    cursor_on: bool,
+    blink_on: bool,
+    eight_bit: bool,
+    two_lines: bool,
+    display_shift: u8,
+    busy_cycles: u64,
+    blink_cycles: u64,
+    enable: bool,
+    /// High nibble of a 4-bit write waiting for its low nibble
+    pending_nibble: Option<u8>,
+    /// Byte being read in 4-bit mode, whose low nibble comes next
+    read_byte: Option<u8>,
+    /// Data driven during a read
+    output: u8,
+}
+
+impl Hd44780 {
+    /// Controller for a display of `columns` by `rows` characters, such as 16x2 or 20x4,
+    /// on a CPU at `cpu_clock_hz`
+    pub fn new(cpu_clock_hz: u32, columns: u8, rows: u8) -> Self {
+        Self {
+            cpu_clock_hz,
+            columns,
+            rows,
+            ddram: vec![b' '; DDRAM_SIZE],
+            cgram: vec![0; CGRAM_SIZE],
+            address: 0,
+            cgram_selected: false,
+            increment: true,
+            shift_on_write: false,
+            display_on: false,
+            cursor_on: false,
+            blink_on: false,
+            eight_bit: true,
+            two_lines: false,
+            display_shift: 0,
+            busy_cycles: 0,
+            blink_cycles: 0,
+            enable: false,
+            pending_nibble: None,
+            read_byte: None,
+            output: 0,
+        }
+    }
+
+    #[inline]
+    pub fn busy(&self) -> bool {
+        self.busy_cycles > 0
+    }
+
+    #[inline]
+    pub fn ddram(&self) -> &[u8] {
+        &self.ddram
+    }
+
+    #[inline]
+    pub fn cgram(&self) -> &[u8] {
+        &self.cgram
+    }
+
+    /// Drive the RS, R/W and E pins and the data lines. Writes are latched on the falling
+    /// edge of E; while E is high during a read the chip drives the data lines, which is
+    /// returned.
+    pub fn set_pins(&mut self, rs: bool, rw: bool, enable: bool, data: u8) -> Option<u8> {
+        let rising = !self.enable && enable;
+        let falling = self.enable && !enable;
+        self.enable = enable;
+        if !rw {
+            if falling {
+                self.read_byte = None;
+                self.receive(rs, data);
+            }
+            return None;
+        }
+        if rising {
+            self.output = if self.eight_bit {
+                self.read_register(rs)
+            } else if let Some(value) = self.read_byte.take() {
+                value << 4
+            } else {
+                // In 4-bit mode the whole byte is read on the first transfer
+                let value = self.read_register(rs);
+                self.read_byte = Some(value);
+                value & 0xF0
+            };
+        }
+        enable.then_some(self.output)
+    }
+
+    /// Visible characters on each line of the display, with custom characters shown
+    /// as `?`
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.rows)
+            .map(|row| {
+                (0..self.columns)
+                    .map(|column| {
+                        if self.display_on {
+                            character(self.ddram[self.ddram_index(row, column)])
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The display's lines joined by newlines
+    pub fn text(&self) -> String {
+        self.lines().join("\n")
+    }
+
+    /// Row and column of the cursor when it is on and on screen, and whether the blinking
+    /// block is currently shown there
+    pub fn cursor(&self) -> Option<(u8, u8, bool)> {
+        if !self.display_on || !(self.cursor_on || self.blink_on) || self.cgram_selected {
+            return None;
+        }
+        let blink =
+            self.blink_on && (self.blink_cycles / self.us_to_cycles(BLINK_US)).is_multiple_of(2);
+        (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (row, column)))
+            .find(|&(row, column)| self.ddram_address(row, column) == self.address)
+            .map(|(row, column)| (row, column, blink))
+    }
+
+    #[inline]
+    fn us_to_cycles(&self, us: u64) -> u64 {
+        (us * self.cpu_clock_hz as u64).div_ceil(1_000_000)
+    }
+
+    /// DDRAM address shown at `row` and `column`, after the display shift. Rows 2 and 3
+    /// of four-line displays continue rows 0 and 1.
+    fn ddram_address(&self, row: u8, column: u8) -> u8 {
+        if !self.two_lines {
+            return (column + self.display_shift) % (LINE_LENGTH * 2);
+        }
+        let (line, offset) = match row {
+            0 => (0, 0),
+            1 => (SECOND_LINE, 0),
+            2 => (0, self.columns),
+            _ => (SECOND_LINE, self.columns),
+        };
+        line + (offset + column + self.display_shift) % LINE_LENGTH
+    }
+
+    fn ddram_index(&self, row: u8, column: u8) -> usize {
+        let address = self.ddram_address(row, column);
+        if address >= SECOND_LINE {
+            (address - SECOND_LINE + LINE_LENGTH) as usize
+        } else {
+            address as usize
+        }
+    }
+
+    fn address_index(&self) -> usize {
+        if self.cgram_selected {
+            self.address as usize & (CGRAM_SIZE - 1)
+        } else if self.two_lines && self.address >= SECOND_LINE {
+            (self.address - SECOND_LINE + LINE_LENGTH) as usize
+        } else {
+            self.address as usize % DDRAM_SIZE
+        }
+    }
+
+    /// Step the address counter in the entry mode's direction, wrapping within the RAM
+    fn step_address(&mut self) {
+        let forward = self.increment;
+        self.address = if self.cgram_selected {
+            (if forward {
+                self.address + 1
+            } else {
+                self.address.wrapping_sub(1)
+            }) & 0x3F
+        } else if self.two_lines {
+            match (forward, self.address) {
+                (true, 0x27) => SECOND_LINE,
+                (true, 0x67) => 0,
+                (false, 0) => 0x67,
+                (false, SECOND_LINE) => 0x27,
+                (true, address) => address + 1,
+                (false, address) => address - 1,
+            }
+        } else {
+            match (forward, self.address) {
+                (true, 0x4F) => 0,
+                (false, 0) => 0x4F,
+                (true, address) => address + 1,
+                (false, address) => address - 1,
+            }
+        };
+    }
+
+    fn shift_display(&mut self, right: bool) {
+        let length = if self.two_lines {
+            LINE_LENGTH
+        } else {
+            LINE_LENGTH * 2
+        };
+        // Shifting the display right shows earlier characters
+        self.display_shift = if right {
+            (self.display_shift + length - 1) % length
+        } else {
+            (self.display_shift + 1) % length
+        };
+    }
+
+    /// Read the busy flag and address, or data from RAM, as one complete transfer
+    fn read_register(&mut self, rs: bool) -> u8 {
+        if !rs {
+            let busy = if self.busy() { BUSY_FLAG } else { 0 };
+            return busy | (self.address & 0x7F);
+        }
+        let value = if self.cgram_selected {
+            self.cgram[self.address_index()]
+        } else {
+            self.ddram[self.address_index()]
+        };
+        self.step_address();
+        self.busy_cycles = self.us_to_cycles(DATA_US);
+        value
+    }
+
+    /// Take one transfer from the data lines, assembling bytes in 4-bit mode
+    fn receive(&mut self, rs: bool, data: u8) {
+        if self.eight_bit {
+            self.transfer(rs, data);
+            return;
+        }
+        match self.pending_nibble.take() {
+            Some(high) => self.transfer(rs, high | data >> 4),
+            None => self.pending_nibble = Some(data & 0xF0),
+        }
+    }
+
+    fn transfer(&mut self, rs: bool, value: u8) {
+        if self.busy() {
+            return;
+        }
+        if rs {
+            self.write_data(value);
+        } else {
+            self.execute(value);
+        }
+    }
+
+    fn write_data(&mut self, value: u8) {
+        let index = self.address_index();
+        if self.cgram_selected {
+            self.cgram[index] = value & 0x1F;
+        } else {
+            self.ddram[index] = value;
+            if self.shift_on_write {
+                self.shift_display(!self.increment);
+            }
+        }
+        self.step_address();
+        self.busy_cycles = self.us_to_cycles(DATA_US);
+    }
+
+    fn execute(&mut self, instruction: u8) {
+        // No bits set: no operation
+        if instruction == 0 {
+            return;
+        }
+        let mut duration = INSTRUCTION_US;
+        match 0x80 >> instruction.leading_zeros() {
+            SET_DDRAM_ADDRESS => {
+                self.cgram_selected = false;
+                self.address = instruction & 0x7F;
+            }
+            SET_CGRAM_ADDRESS => {
+                self.cgram_selected = true;
+                self.address = instruction & 0x3F;
+            }
+            FUNCTION_SET => {
+                self.eight_bit = instruction & FUNCTION_8_BIT != 0;
+                self.two_lines = instruction & FUNCTION_2_LINES != 0;
+                self.pending_nibble = None;
+                self.read_byte = None;
+            }
+            CURSOR_SHIFT => {
+                let right = instruction & SHIFT_RIGHT != 0;
+                if instruction & SHIFT_DISPLAY != 0 {
+                    self.shift_display(right);
+                } else {
+                    let increment = std::mem::replace(&mut self.increment, right);
+                    self.step_address();
+                    self.increment = increment;
+                }
+            }
+            DISPLAY_CONTROL => {
+                self.display_on = instruction & DISPLAY_ON != 0;
+                self.cursor_on = instruction & CURSOR_ON != 0;
+                self.blink_on = instruction & BLINK_ON != 0;
+            }
+            ENTRY_MODE_SET => {
+                self.increment = instruction & ENTRY_INCREMENT != 0;
+                self.shift_on_write = instruction & ENTRY_SHIFT != 0;
+            }
+            RETURN_HOME => {
+                self.cgram_selected = false;
+                self.address = 0;
+                self.display_shift = 0;
+                duration = CLEAR_US;
+            }
+            CLEAR_DISPLAY => {
+                self.ddram.fill(b' ');
+                self.cgram_selected = false;
+                self.address = 0;
+                self.display_shift = 0;
+                self.increment = true;
+                duration = CLEAR_US;
+            }
+            _ => unreachable!(),
+        }
+        self.busy_cycles = self.us_to_cycles(duration);
+    }
+}
+
+/// Character for `code` in the A00 (Japanese) character ROM
+fn character(code: u8) -> char {
+    match code {
+        0x00..=0x0F => '?',
+        b'\\' => '¥',
+        0x7E => '→',
+        0x7F => '←',
+        0x20..=0x7D => code as char,
+        0xA1..=0xDF => char::from_u32(0xFF61 + (code - 0xA1) as u32).unwrap_or(' '),
+        0xE0 => 'α',
+        0xE2 => 'β',
+        0xE3 => 'ε',
+        0xE4 => 'μ',
+        0xE5 => 'σ',
+        0xE6 => 'ρ',
+        0xF2 => 'θ',
+        0xF3 => '∞',
+        0xF4 => 'Ω',
+        0xF6 => 'Σ',
+        0xF7 => 'π',
+        0xFF => '█',
+        _ => ' ',
+    }
+}
+
+impl Device for Hd44780 {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        Ok(self.read_register(offset & 1 != 0))
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        self.receive(offset & 1 != 0, value);
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.busy_cycles = self.busy_cycles.saturating_sub(cycles as u64);
+        self.blink_cycles += cycles as u64;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        *self = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::Via6522;
+    use crate::machine::Machine;
+
+    const CLOCK_HZ: u32 = 1_000_000;
+
+    /// Write a byte through the bus interface and wait for it to finish
+    fn write(lcd: &mut Hd44780, offset: u16, value: u8) {
+        lcd.write(offset, value).unwrap();
+        lcd.tick(CLEAR_US as u32);
+    }
+
+    /// One E pulse with the data lines held
+    fn pulse(lcd: &mut Hd44780, rs: bool, data: u8) {
+        assert_eq!(lcd.set_pins(rs, false, true, data), None);
+        assert_eq!(lcd.set_pins(rs, false, false, data), None);
+    }
+
+    #[test]
+    fn test_busy_flag() {
+        let mut lcd = Hd44780::new(CLOCK_HZ, 16, 2);
+        lcd.write(0, FUNCTION_SET | FUNCTION_8_BIT | FUNCTION_2_LINES)
+            .unwrap();
+        assert_eq!(lcd.read(0).unwrap(), BUSY_FLAG);
+        lcd.tick(36);
+        assert!(lcd.busy());
+        // Ignored while busy
+        lcd.write(1, b'A').unwrap();
+        lcd.tick(1);
+        assert!(!lcd.busy());
+        assert_eq!(lcd.ddram()[0], b' ');
+
+        lcd.write(0, CLEAR_DISPLAY).unwrap();
+        lcd.tick(1519);
+        assert!(lcd.busy());
+        lcd.tick(1);
+        assert!(!lcd.busy());
+        lcd.write(1, b'A').unwrap();
+        lcd.tick(40);
+        assert!(lcd.busy());
+        lcd.tick(1);
+        assert_eq!(lcd.read(0).unwrap(), 0x01);
+        assert_eq!(lcd.ddram()[0], b'A');
+    }
+
+    #[test]
+    fn test_addressing_and_shift() {
+        let mut lcd = Hd44780::new(CLOCK_HZ, 16, 2);
+        write(
+            &mut lcd,
+            0,
+            FUNCTION_SET | FUNCTION_8_BIT | FUNCTION_2_LINES,
+        );
+        write(&mut lcd, 0, DISPLAY_CONTROL | DISPLAY_ON);
+        write(&mut lcd, 0, ENTRY_MODE_SET | ENTRY_INCREMENT);
+        write(&mut lcd, 1, b'A');
+        // The end of the first line continues on the second
+        write(&mut lcd, 0, SET_DDRAM_ADDRESS | 0x27);
+        write(&mut lcd, 1, b'X');
+        assert_eq!(lcd.read(0).unwrap(), SECOND_LINE);
+        write(&mut lcd, 1, b'B');
+        assert_eq!(lcd.text(), "A               \nB               ");
+
+        write(&mut lcd, 0, CURSOR_SHIFT | SHIFT_DISPLAY);
+        assert_eq!(lcd.lines()[0], "                ");
+        write(&mut lcd, 0, CURSOR_SHIFT | SHIFT_DISPLAY | SHIFT_RIGHT);
+        write(&mut lcd, 0, CURSOR_SHIFT | SHIFT_DISPLAY | SHIFT_RIGHT);
+        assert_eq!(lcd.lines()[0], "XA              ");
+        assert_eq!(lcd.lines()[1], " B              ");
+        write(&mut lcd, 0, RETURN_HOME);
+        assert_eq!(lcd.lines()[0], "A               ");
+        assert_eq!(lcd.read(0).unwrap(), 0x00);
+
+        // Custom characters
+        write(&mut lcd, 0, SET_CGRAM_ADDRESS | 0x08);
+        write(&mut lcd, 1, 0x1F);
+        write(&mut lcd, 1, 0x11);
+        assert_eq!(&lcd.cgram()[0x08..0x0A], &[0x1F, 0x11]);
+        write(&mut lcd, 0, SET_CGRAM_ADDRESS | 0x08);
+        assert_eq!(lcd.read(1).unwrap(), 0x1F);
+        lcd.tick(41);
+        write(&mut lcd, 0, SET_DDRAM_ADDRESS | 0x01);
+        write(&mut lcd, 1, 0x01);
+        assert_eq!(lcd.lines()[0], "A?              ");
+    }
+
+    #[test]
+    fn test_4_bit_pins() {
+        let mut lcd = Hd44780::new(CLOCK_HZ, 16, 2);
+        // Only D4-D7 are wired, so switching to 4-bit mode takes one transfer
+        pulse(&mut lcd, false, FUNCTION_SET);
+        lcd.tick(37);
+        for instruction in [
+            FUNCTION_SET | FUNCTION_2_LINES,
+            DISPLAY_CONTROL | DISPLAY_ON | CURSOR_ON | BLINK_ON,
+        ] {
+            pulse(&mut lcd, false, instruction & 0xF0);
+            pulse(&mut lcd, false, instruction << 4);
+            lcd.tick(37);
+        }
+        for &c in b"Hi" {
+            pulse(&mut lcd, true, c & 0xF0);
+            pulse(&mut lcd, true, c << 4);
+            lcd.tick(41);
+        }
+        assert_eq!(lcd.lines()[0], "Hi              ");
+        assert_eq!(lcd.cursor(), Some((0, 2, true)));
+        lcd.tick(409_600);
+        assert_eq!(lcd.cursor(), Some((0, 2, false)));
+
+        // Busy flag and address, high nibble first
+        pulse(&mut lcd, true, b'!' & 0xF0);
+        pulse(&mut lcd, true, b'!' << 4);
+        assert_eq!(lcd.set_pins(false, true, true, 0), Some(BUSY_FLAG));
+        assert_eq!(lcd.set_pins(false, true, true, 0), Some(BUSY_FLAG));
+        assert_eq!(lcd.set_pins(false, true, false, 0), None);
+        assert_eq!(lcd.set_pins(false, true, true, 0), Some(0x30));
+        assert_eq!(lcd.set_pins(false, true, false, 0), None);
+        lcd.tick(41);
+
+        pulse(&mut lcd, false, SET_DDRAM_ADDRESS);
+        pulse(&mut lcd, false, 0x00);
+        lcd.tick(37);
+        assert_eq!(lcd.set_pins(true, true, true, 0), Some(b'H' & 0xF0));
+        assert_eq!(lcd.set_pins(true, true, false, 0), None);
+        assert_eq!(lcd.set_pins(true, true, true, 0), Some(b'H' << 4));
+        assert_eq!(lcd.set_pins(true, true, false, 0), None);
+        assert_eq!(lcd.read(0).unwrap(), BUSY_FLAG | 0x01);
+    }
+
+    #[test]
+    fn test_ben_eater_hello_world() {
+        // Port B carries D0-D7 and PA5-PA7 drive RS, R/W and E
+        #[rustfmt::skip]
+        let program = [
+            0xA2, 0xFF,       // LDX #$FF
+            0x9A,             // TXS
+            0xA9, 0xFF,       // LDA #$FF
+            0x8D, 0x02, 0x60, // STA DDRB
+            0xA9, 0xE0,       // LDA #$E0
+            0x8D, 0x03, 0x60, // STA DDRA
+            0xA9, 0x38,       // LDA #$38 (8-bit, two lines)
+            0x20, 0x4F, 0xF0, // JSR lcd_instruction
+            0xA9, 0x0E,       // LDA #$0E (display and cursor on)
+            0x20, 0x4F, 0xF0, // JSR lcd_instruction
+            0xA9, 0x06,       // LDA #$06 (increment)
+            0x20, 0x4F, 0xF0, // JSR lcd_instruction
+            0xA2, 0x00,       // LDX #0
+            0xBD, 0x7B, 0xF0, // print: LDA message,X
+            0xF0, 0x06,       // BEQ done
+            0x20, 0x65, 0xF0, // JSR print_char
+            0xE8,             // INX
+            0xD0, 0xF5,       // BNE print
+            0x4C, 0x29, 0xF0, // done: JMP done
+            0x48,             // lcd_wait: PHA
+            0xA9, 0x00,       // LDA #0
+            0x8D, 0x02, 0x60, // STA DDRB
+            0xA9, 0x40,       // lcd_busy: LDA #RW
+            0x8D, 0x01, 0x60, // STA PORTA
+            0xA9, 0xC0,       // LDA #(RW | E)
+            0x8D, 0x01, 0x60, // STA PORTA
+            0xAD, 0x00, 0x60, // LDA PORTB
+            0x29, 0x80,       // AND #$80
+            0xD0, 0xEF,       // BNE lcd_busy
+            0xA9, 0x40,       // LDA #RW
+            0x8D, 0x01, 0x60, // STA PORTA
+            0xA9, 0xFF,       // LDA #$FF
+            0x8D, 0x02, 0x60, // STA DDRB
+            0x68,             // PLA
+            0x60,             // RTS
+            0x20, 0x2C, 0xF0, // lcd_instruction: JSR lcd_wait
+            0x8D, 0x00, 0x60, // STA PORTB
+            0xA9, 0x00,       // LDA #0
+            0x8D, 0x01, 0x60, // STA PORTA
+            0xA9, 0x80,       // LDA #E
+            0x8D, 0x01, 0x60, // STA PORTA
+            0xA9, 0x00,       // LDA #0
+            0x8D, 0x01, 0x60, // STA PORTA
+            0x60,             // RTS
+            0x20, 0x2C, 0xF0, // print_char: JSR lcd_wait
+            0x8D, 0x00, 0x60, // STA PORTB
+            0xA9, 0x20,       // LDA #RS
+            0x8D, 0x01, 0x60, // STA PORTA
+            0xA9, 0xA0,       // LDA #(RS | E)
+            0x8D, 0x01, 0x60, // STA PORTA
+            0xA9, 0x20,       // LDA #RS
+            0x8D, 0x01, 0x60, // STA PORTA
+            0x60,             // RTS
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[program.len()..program.len() + 14].copy_from_slice(b"Hello, world!\0");
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+        // Pull-downs keep E low until port A is configured as outputs
+        let mut via = Via6522::new();
+        via.set_port_a(0x00);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x3FFF)
+            .device(0x6000..=0x600F, via)
+            .device(0x7000..=0x7001, Hd44780::new(CLOCK_HZ, 16, 2))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+
+        for _ in 0..5000 {
+            machine.step().unwrap();
+            let via = machine.device::<Via6522>().unwrap();
+            let (port_a, port_b) = (via.port_a(), via.port_b());
+            let output = machine.device_mut::<Hd44780>().unwrap().set_pins(
+                port_a & 0x20 != 0,
+                port_a & 0x40 != 0,
+                port_a & 0x80 != 0,
+                port_b,
+            );
+            if let Some(value) = output {
+                machine.device_mut::<Via6522>().unwrap().set_port_b(value);
+            }
+        }
+        let lcd = machine.device::<Hd44780>().unwrap();
+        assert_eq!(lcd.text(), "Hello, world!   \n                ");
+        assert_eq!(lcd.cursor(), Some((0, 13, false)));
+    }
+}
@@ -0,0 +1,110 @@
+use std::any::Any;
+
+use crate::error::{BusError, StateError};
+
+mod acia6551;
+mod acia6850;
+mod apu2a03;
+mod ay38910;
+mod cia6526;
+mod console;
+mod hd44780;
+mod pia6821;
+mod pokey;
+mod riot6532;
+mod sd_card;
+mod serial;
+mod sid6581;
+mod spi;
+mod tms9918;
+mod via6522;
+
+pub use acia6551::Acia6551;
+pub use acia6850::Acia6850;
+pub use apu2a03::Apu2A03;
+pub use ay38910::{Ay38910, PsgModel};
+pub use cia6526::{Cia6526, CiaModel};
+pub use console::{Console, ConsoleBus};
+pub use hd44780::Hd44780;
+pub use pia6821::Pia6821;
+pub use pokey::Pokey;
+pub use riot6532::Riot6532;
+pub use sd_card::SdCard;
+#[cfg(target_os = "linux")]
+pub use serial::PtyBackend;
+pub use serial::{BufferBackend, SerialBackend, StdioBackend};
+pub use sid6581::{Sid6581, SidModel};
+pub use spi::{SpiBus, SpiDevice, SpiPins, SpiPort};
+pub use tms9918::Tms9918;
+pub use via6522::{ShiftMode, Via6522};
+
+/// A peripheral chip mapped into the CPU address space.
+///
+/// Addresses passed to [`Device::read`] and [`Device::write`] are offsets from the start
+/// of the range the device was mapped at, so the same device can be placed anywhere.
+/// Everything except the register accesses has a default that suits a passive device.
+pub trait Device: Any {
+    /// Read the register at `offset`. Reads may have side effects, such as clearing
+    /// interrupt flags.
+    fn read(&mut self, offset: u16) -> Result<u8, BusError>;
+
+    /// Write the register at `offset`
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError>;
+
+    /// Advance the device by `cycles` CPU cycles
+    fn tick(&mut self, _cycles: u32) {}
+
+    /// Level of the device's IRQ output, true when asserted
+    fn irq(&self) -> bool {
+        false
+    }
+
+    /// Level of the device's NMI output, true when asserted
+    fn nmi(&self) -> bool {
+        false
+    }
+
+    /// Address the device wants to read by DMA, if any. The [`Machine`] performs the
+    /// read ahead of the CPU's next read cycle and hands the byte to
+    /// [`Device::dma_complete`].
+    ///
+    /// [`Machine`]: crate::machine::Machine
+    fn dma_request(&self) -> Option<u16> {
+        None
+    }
+
+    /// Take the byte read for [`Device::dma_request`], returning the CPU cycles the
+    /// transfer halted the CPU for
+    fn dma_complete(&mut self, _value: u8) -> u32 {
+        0
+    }
+
+    /// Exit code the device has asked the machine to stop with, if any. The [`Machine`]
+    /// run loops stop on the next instruction boundary.
+    ///
+    /// [`Machine`]: crate::machine::Machine
+    fn exit_code(&self) -> Option<u8> {
+        None
+    }
+
+    /// Handle an event queued for the device with [`Machine::schedule_at`], returning the
+    /// cycles after which the same event should fire again, if it repeats
+    ///
+    /// [`Machine::schedule_at`]: crate::machine::Machine::schedule_at
+    fn event(&mut self, _code: u32) -> Option<u64> {
+        None
+    }
+
+    /// Respond to the system reset line
+    fn reset(&mut self) {}
+
+    /// Encode the device state for a save state
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(Vec::new())
+    }
+
+    /// Restore a state produced by [`Device::save_state`]
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), StateError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,418 @@
+use serde::{Deserialize, Serialize};
+
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Register select lines
+const RS0: u16 = 1 << 0;
+const RS1: u16 = 1 << 1;
+
+// Control register bits
+const CR_C1_ENABLE: u8 = 1 << 0;
+const CR_C1_POSITIVE: u8 = 1 << 1;
+const CR_PORT_SELECT: u8 = 1 << 2;
+const CR_C2_ENABLE: u8 = 1 << 3;
+const CR_C2_POSITIVE: u8 = 1 << 4;
+const CR_C2_OUTPUT: u8 = 1 << 5;
+const CR_IRQ2: u8 = 1 << 6;
+const CR_IRQ1: u8 = 1 << 7;
+// With C2 as an output, bit 4 picks manual mode and bit 3 is then the level driven.
+// Otherwise bit 3 picks pulse over handshake.
+const CR_C2_MANUAL: u8 = CR_C2_POSITIVE;
+const CR_C2_PULSE: u8 = CR_C2_ENABLE;
+
+/// One side of the PIA: a port with its control register and two control lines
+#[derive(Clone, Serialize, Deserialize)]
+struct PiaPort {
+    output: u8,
+    ddr: u8,
+    control: u8,
+    pins: u8,
+    c1: bool,
+    c2: bool,
+    c2_output: bool,
+    pulse: bool,
+}
+
+impl PiaPort {
+    fn new() -> Self {
+        Self {
+            output: 0,
+            ddr: 0,
+            control: 0,
+            pins: 0xFF,
+            c1: true,
+            c2: true,
+            c2_output: true,
+            pulse: false,
+        }
+    }
+
+    fn write_control(&mut self, value: u8) {
+        self.control = (self.control & (CR_IRQ1 | CR_IRQ2)) | (value & !(CR_IRQ1 | CR_IRQ2));
+        if value & CR_C2_OUTPUT != 0 {
+            // Output modes have no C2 flag
+            self.control &= !CR_IRQ2;
+            self.c2_output = value & CR_C2_MANUAL == 0 || value & CR_C2_ENABLE != 0;
+            self.pulse = false;
+        }
+    }
+
+    fn set_c1(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.c1, level);
+        if previous == level || level != (self.control & CR_C1_POSITIVE != 0) {
+            return;
+        }
+        self.control |= CR_IRQ1;
+        // The active edge ends a handshake
+        if self.control & (CR_C2_OUTPUT | CR_C2_MANUAL | CR_C2_PULSE) == CR_C2_OUTPUT {
+            self.c2_output = true;
+        }
+    }
+
+    fn set_c2(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.c2, level);
+        if self.control & CR_C2_OUTPUT == 0
+            && previous != level
+            && level == (self.control & CR_C2_POSITIVE != 0)
+        {
+            self.control |= CR_IRQ2;
+        }
+    }
+
+    /// Level on C2, whether driven from outside or by the PIA
+    fn c2(&self) -> bool {
+        if self.control & CR_C2_OUTPUT == 0 {
+            self.c2
+        } else {
+            self.c2_output
+        }
+    }
+
+    /// Start a handshake or pulse on C2 after the data register is accessed
+    fn strobe(&mut self) {
+        if self.control & (CR_C2_OUTPUT | CR_C2_MANUAL) == CR_C2_OUTPUT {
+            self.c2_output = false;
+            self.pulse = self.control & CR_C2_PULSE != 0;
+        }
+    }
+
+    fn irq(&self) -> bool {
+        (self.control & CR_IRQ1 != 0 && self.control & CR_C1_ENABLE != 0)
+            || (self.control & CR_IRQ2 != 0
+                && self.control & (CR_C2_OUTPUT | CR_C2_ENABLE) == CR_C2_ENABLE)
+    }
+
+    fn clear_flags(&mut self) {
+        self.control &= !(CR_IRQ1 | CR_IRQ2);
+    }
+}
+
+/// MOS 6520 / Motorola 6821 Peripheral Interface Adapter.
+///
+/// Four registers: port A data or DDR, control A, port B data or DDR, control B. Bit 2 of
+/// each control register picks whether the first register of the pair is the data
+/// register or the DDR.
+///
+/// Reading a data register clears that side's interrupt flags. In handshake mode CA2
+/// goes low when port A is read and CB2 goes low when port B is written; both go high
+/// again on the active C1 edge. In pulse mode they go low for one cycle instead.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Pia6821 {
+    a: PiaPort,
+    b: PiaPort,
+}
+
+impl Default for Pia6821 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pia6821 {
+    pub fn new() -> Self {
+        Self {
+            a: PiaPort::new(),
+            b: PiaPort::new(),
+        }
+    }
+
+    /// Levels on the port A pins. Outputs are resistive pull-ups, so a pin driven high
+    /// can still be pulled low from outside.
+    #[inline]
+    pub fn port_a(&self) -> u8 {
+        (self.a.output | !self.a.ddr) & self.a.pins
+    }
+
+    /// Levels on the port B pins: output bits from the output register, input bits
+    /// from outside
+    #[inline]
+    pub fn port_b(&self) -> u8 {
+        (self.b.output & self.b.ddr) | (self.b.pins & !self.b.ddr)
+    }
+
+    /// Drive the port A input pins
+    #[inline]
+    pub fn set_port_a(&mut self, value: u8) {
+        self.a.pins = value;
+    }
+
+    /// Drive the port B input pins
+    #[inline]
+    pub fn set_port_b(&mut self, value: u8) {
+        self.b.pins = value;
+    }
+
+    #[inline]
+    pub fn set_ca1(&mut self, level: bool) {
+        self.a.set_c1(level);
+    }
+
+    /// Drive CA2, which only has an effect while it is configured as an input
+    #[inline]
+    pub fn set_ca2(&mut self, level: bool) {
+        self.a.set_c2(level);
+    }
+
+    #[inline]
+    pub fn set_cb1(&mut self, level: bool) {
+        self.b.set_c1(level);
+    }
+
+    /// Drive CB2, which only has an effect while it is configured as an input
+    #[inline]
+    pub fn set_cb2(&mut self, level: bool) {
+        self.b.set_c2(level);
+    }
+
+    #[inline]
+    pub fn ca2(&self) -> bool {
+        self.a.c2()
+    }
+
+    #[inline]
+    pub fn cb2(&self) -> bool {
+        self.b.c2()
+    }
+
+    /// Level of the IRQA output, true when asserted
+    #[inline]
+    pub fn irq_a(&self) -> bool {
+        self.a.irq()
+    }
+
+    /// Level of the IRQB output, true when asserted
+    #[inline]
+    pub fn irq_b(&self) -> bool {
+        self.b.irq()
+    }
+}
+
+impl Device for Pia6821 {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        Ok(match offset & (RS1 | RS0) {
+            0 if self.a.control & CR_PORT_SELECT == 0 => self.a.ddr,
+            0 => {
+                self.a.clear_flags();
+                self.a.strobe();
+                self.port_a()
+            }
+            1 => self.a.control,
+            2 if self.b.control & CR_PORT_SELECT == 0 => self.b.ddr,
+            2 => {
+                self.b.clear_flags();
+                self.port_b()
+            }
+            _ => self.b.control,
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        match offset & (RS1 | RS0) {
+            0 if self.a.control & CR_PORT_SELECT == 0 => self.a.ddr = value,
+            0 => self.a.output = value,
+            1 => self.a.write_control(value),
+            2 if self.b.control & CR_PORT_SELECT == 0 => self.b.ddr = value,
+            2 => {
+                self.b.output = value;
+                self.b.strobe();
+            }
+            _ => self.b.write_control(value),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        if cycles == 0 {
+            return;
+        }
+        for port in [&mut self.a, &mut self.b] {
+            if std::mem::take(&mut port.pulse) {
+                port.c2_output = true;
+            }
+        }
+    }
+
+    /// IRQA and IRQB wired together, as most boards do
+    fn irq(&self) -> bool {
+        self.irq_a() || self.irq_b()
+    }
+
+    /// Reset clears the registers. The pins are driven from outside, so they keep their
+    /// levels.
+    fn reset(&mut self) {
+        for port in [&mut self.a, &mut self.b] {
+            *port = PiaPort {
+                pins: port.pins,
+                c1: port.c1,
+                c2: port.c2,
+                ..PiaPort::new()
+            };
+        }
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        *self = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    const PRA: u16 = 0;
+    const CRA: u16 = 1;
+    const PRB: u16 = 2;
+    const CRB: u16 = 3;
+
+    #[test]
+    fn test_port_select_and_interrupts() {
+        let mut pia = Pia6821::new();
+        pia.write(PRA, 0x0F).unwrap();
+        pia.write(CRA, CR_PORT_SELECT).unwrap();
+        pia.write(PRA, 0x5A).unwrap();
+        pia.set_port_a(0xF7);
+        assert_eq!(pia.read(PRA).unwrap(), 0xF2);
+        pia.write(CRA, 0).unwrap();
+        assert_eq!(pia.read(PRA).unwrap(), 0x0F, "DDR selected again");
+
+        // CA1 on the rising edge with interrupt, CA2 input on the falling edge without
+        pia.write(CRA, CR_PORT_SELECT | CR_C1_POSITIVE | CR_C1_ENABLE)
+            .unwrap();
+        pia.set_ca1(false);
+        assert!(!pia.irq_a());
+        pia.set_ca1(true);
+        assert!(pia.irq_a());
+        pia.set_ca2(false);
+        assert_eq!(
+            pia.read(CRA).unwrap() & (CR_IRQ1 | CR_IRQ2),
+            CR_IRQ1 | CR_IRQ2
+        );
+        pia.read(PRA).unwrap();
+        assert!(!pia.irq_a());
+        assert_eq!(pia.read(CRA).unwrap() & (CR_IRQ1 | CR_IRQ2), 0);
+
+        // Writing the flags does nothing
+        pia.write(CRA, 0xFF).unwrap();
+        assert_eq!(pia.read(CRA).unwrap(), 0x3F);
+        assert!(!pia.irq());
+    }
+
+    #[test]
+    fn test_c2_output_modes() {
+        let mut pia = Pia6821::new();
+        // CA2 handshake: low after a port A read until the active CA1 edge
+        pia.write(CRA, CR_PORT_SELECT | CR_C2_OUTPUT).unwrap();
+        assert!(pia.ca2());
+        pia.read(PRA).unwrap();
+        assert!(!pia.ca2());
+        pia.tick(10);
+        assert!(!pia.ca2());
+        pia.set_ca1(false);
+        assert!(pia.ca2());
+
+        // CB2 pulse: low for one cycle after a port B write
+        pia.write(CRB, CR_PORT_SELECT | CR_C2_OUTPUT | CR_C2_PULSE)
+            .unwrap();
+        pia.write(PRB, 0x12).unwrap();
+        assert!(!pia.cb2());
+        pia.tick(1);
+        assert!(pia.cb2());
+        // Reading port B does not strobe CB2
+        pia.read(PRB).unwrap();
+        assert!(pia.cb2());
+
+        // Manual output follows bit 3
+        pia.write(CRB, CR_C2_OUTPUT | CR_C2_MANUAL).unwrap();
+        assert!(!pia.cb2());
+        pia.write(CRB, CR_C2_OUTPUT | CR_C2_MANUAL | CR_C2_ENABLE)
+            .unwrap();
+        assert!(pia.cb2());
+    }
+
+    #[test]
+    fn test_apple1_echo() {
+        // The keyboard and display code from the Apple-1 monitor, PIA at $D010
+        #[rustfmt::skip]
+        let program = [
+            0x78,             // SEI (IRQ is not wired on the Apple-1)
+            0xA0, 0x7F,       // LDY #$7F
+            0x8C, 0x12, 0xD0, // STY DSP (DDR: PB7 is the display's ready input)
+            0xA9, 0xA7,       // LDA #$A7
+            0x8D, 0x11, 0xD0, // STA KBDCR
+            0x8D, 0x13, 0xD0, // STA DSPCR
+            0xAD, 0x11, 0xD0, // LDA KBDCR (loop)
+            0x10, 0xFB,       // BPL loop
+            0xAD, 0x10, 0xD0, // LDA KBD
+            0x2C, 0x12, 0xD0, // BIT DSP (echo)
+            0x30, 0xFB,       // BMI echo
+            0x8D, 0x12, 0xD0, // STA DSP
+            0x4C, 0x0E, 0xF0, // JMP loop
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+        let mut machine = Machine::builder()
+            .device(0xD010..=0xD013, Pia6821::new())
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+
+        let mut typed = b"HELLO\r".iter();
+        let mut screen = Vec::new();
+        let mut busy = 0;
+        machine.device_mut::<Pia6821>().unwrap().set_port_b(0x7F);
+        for _ in 0..2_000 {
+            machine.step().unwrap();
+            let pia = machine.device_mut::<Pia6821>().unwrap();
+            // The display raises PB7 while it draws a character, then acknowledges on CB1
+            if busy > 0 {
+                busy -= 1;
+                if busy == 0 {
+                    pia.set_port_b(0x7F);
+                    pia.set_cb1(true);
+                }
+            } else if !pia.cb2() {
+                screen.push(pia.port_b() & 0x7F);
+                pia.set_port_b(0xFF);
+                pia.set_cb1(false);
+                busy = 5;
+            }
+            // Type the next key once the last one has been read
+            if pia.a.control & CR_IRQ1 == 0 && busy == 0 {
+                if let Some(&key) = typed.next() {
+                    pia.set_port_a(key | 0x80);
+                    pia.set_ca1(false);
+                    pia.set_ca1(true);
+                }
+            }
+        }
+        assert_eq!(screen, b"HELLO\r");
+    }
+}
@@ -0,0 +1,863 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Downsampler;
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Write registers
+const AUDF1: u16 = 0x00;
+const AUDCTL: u16 = 0x08;
+const STIMER: u16 = 0x09;
+const SKRES: u16 = 0x0A;
+const POTGO: u16 = 0x0B;
+const SEROUT: u16 = 0x0D;
+const IRQEN: u16 = 0x0E;
+const SKCTL: u16 = 0x0F;
+
+// Read registers
+const ALLPOT: u16 = 0x08;
+const KBCODE: u16 = 0x09;
+const RANDOM: u16 = 0x0A;
+const SERIN: u16 = 0x0D;
+const IRQST: u16 = 0x0E;
+const SKSTAT: u16 = 0x0F;
+
+// AUDC bits
+const AUDC_VOLUME: u8 = 0x0F;
+const AUDC_VOLUME_ONLY: u8 = 1 << 4;
+const AUDC_PURE: u8 = 1 << 5;
+const AUDC_POLY4: u8 = 1 << 6;
+const AUDC_NO_POLY5: u8 = 1 << 7;
+
+// AUDCTL bits
+const AUDCTL_15KHZ: u8 = 1 << 0;
+const AUDCTL_HIPASS_2: u8 = 1 << 1;
+const AUDCTL_HIPASS_1: u8 = 1 << 2;
+const AUDCTL_LINK_34: u8 = 1 << 3;
+const AUDCTL_LINK_12: u8 = 1 << 4;
+const AUDCTL_FAST_3: u8 = 1 << 5;
+const AUDCTL_FAST_1: u8 = 1 << 6;
+const AUDCTL_POLY9: u8 = 1 << 7;
+
+// IRQEN / IRQST bits
+const IRQ_TIMER_1: u8 = 1 << 0;
+const IRQ_TIMER_2: u8 = 1 << 1;
+const IRQ_TIMER_4: u8 = 1 << 2;
+const IRQ_SERIAL_OUT_DONE: u8 = 1 << 3;
+const IRQ_SERIAL_OUT_NEEDED: u8 = 1 << 4;
+const IRQ_SERIAL_IN: u8 = 1 << 5;
+const IRQ_KEY: u8 = 1 << 6;
+const IRQ_BREAK: u8 = 1 << 7;
+
+// SKCTL bits
+const SKCTL_DEBOUNCE: u8 = 1 << 0;
+const SKCTL_SCAN: u8 = 1 << 1;
+const SKCTL_FAST_POTS: u8 = 1 << 2;
+const SKCTL_TWO_TONE: u8 = 1 << 3;
+const SKCTL_SERIAL_MODE: u8 = 0x70;
+const SKCTL_BREAK: u8 = 1 << 7;
+
+// SKSTAT bits, all active low
+const SKSTAT_KEY_DOWN: u8 = 1 << 2;
+const SKSTAT_SHIFT: u8 = 1 << 3;
+const SKSTAT_SERIAL_OVERRUN: u8 = 1 << 5;
+const SKSTAT_KEY_OVERRUN: u8 = 1 << 6;
+const SKSTAT_FRAME_ERROR: u8 = 1 << 7;
+
+/// CPU cycles per tick of the 64 kHz and 15 kHz base clocks
+const DIVIDE_64KHZ: u8 = 28;
+const DIVIDE_15KHZ: u8 = 114;
+
+/// Keyboard scan and slow pot count rate, one step per scanline
+const SCANLINE_CYCLES: u8 = 114;
+const POT_MAX: u8 = 228;
+
+/// Atari 400/800, 600XL/800XL and 130XE POKEY.
+///
+/// All timing is in CPU cycles, so the device must be ticked at the 1.79 MHz (NTSC)
+/// or 1.77 MHz (PAL) machine clock. The four audio channels are timers that reload
+/// from AUDF and clock their output through the polynomial counters selected by AUDC;
+/// channel 1 and 2 can be high-pass filtered by channels 3 and 4, and either pair can be
+/// linked into a 16-bit timer, whose low channel then also underflows every time its
+/// byte wraps.
+///
+/// The keyboard is a key code given to [`Pokey::set_key`] that the scanner finds on one of
+/// its 64 scanline steps. Serial output shifts start, eight data and stop bits at half
+/// the rate of channel 4 (or channel 2) underflows, collecting the frames for
+/// [`Pokey::take_serial_output`]. Serial input arrives a whole byte at a time through
+/// [`Pokey::receive_serial`]; an external serial clock is not modelled, so bytes written
+/// to SEROUT in those modes wait.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Pokey {
+    audf: [u8; 4],
+    audc: [u8; 4],
+    audctl: u8,
+    counters: [u16; 4],
+    outputs: [bool; 4],
+    highpass: [bool; 2],
+    base_divider: u8,
+    poly4: u8,
+    poly5: u8,
+    poly9: u16,
+    poly17: u32,
+    irq_enable: u8,
+    irq_status: u8,
+    skctl: u8,
+    skstat: u8,
+    key: Option<u8>,
+    key_reported: bool,
+    kbcode: u8,
+    scan_divider: u8,
+    scan_counter: u8,
+    pots: [u8; 8],
+    pot_latches: [u8; 8],
+    pot_counter: u8,
+    allpot: u8,
+    serin: u8,
+    serout: Option<u8>,
+    shift_out: u16,
+    shift_value: u8,
+    shift_bits: u8,
+    serial_clock: bool,
+    downsampler: Downsampler,
+    #[serde(skip)]
+    samples: Vec<i16>,
+    #[serde(skip)]
+    serial_output: Vec<u8>,
+}
+
+impl Pokey {
+    /// POKEY on a CPU at `clock_hz`, producing `sample_rate` samples per second
+    pub fn new(clock_hz: u32, sample_rate: u32) -> Self {
+        Self {
+            audf: [0; 4],
+            audc: [0; 4],
+            audctl: 0,
+            counters: [0; 4],
+            outputs: [false; 4],
+            highpass: [false; 2],
+            base_divider: 0,
+            poly4: 0x0F,
+            poly5: 0x1F,
+            poly9: 0x1FF,
+            poly17: 0x1FFFF,
+            irq_enable: 0,
+            irq_status: 0,
+            skctl: 0,
+            skstat: 0xFF,
+            key: None,
+            key_reported: false,
+            kbcode: 0xFF,
+            scan_divider: 0,
+            scan_counter: 0,
+            pots: [POT_MAX; 8],
+            pot_latches: [0; 8],
+            pot_counter: 0,
+            allpot: 0,
+            serin: 0xFF,
+            serout: None,
+            shift_out: 0,
+            shift_value: 0,
+            shift_bits: 0,
+            serial_clock: false,
+            downsampler: Downsampler::new(clock_hz, sample_rate),
+            samples: Vec::new(),
+            serial_output: Vec::new(),
+        }
+    }
+
+    /// Samples rendered so far
+    #[inline]
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Take the samples rendered so far
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+
+    /// Press a key, given as its KBCODE with shift in bit 6 and control in bit 7, or
+    /// release it with `None`
+    pub fn set_key(&mut self, key: Option<u8>) {
+        if key.map(|code| code & 0x3F) != self.key.map(|code| code & 0x3F) {
+            self.key_reported = false;
+        }
+        self.key = key;
+    }
+
+    /// Hold the shift key, seen in SKSTAT
+    pub fn set_shift(&mut self, pressed: bool) {
+        if pressed {
+            self.skstat &= !SKSTAT_SHIFT;
+        } else {
+            self.skstat |= SKSTAT_SHIFT;
+        }
+    }
+
+    /// Press the break key, which has its own interrupt
+    pub fn press_break(&mut self) {
+        self.raise(IRQ_BREAK);
+    }
+
+    /// Set the resistance of paddle `pot`, as the scanline count (0-228) at which it
+    /// charges
+    pub fn set_pot(&mut self, pot: usize, value: u8) {
+        self.pots[pot] = value.min(POT_MAX);
+    }
+
+    /// Receive a byte on the serial input
+    pub fn receive_serial(&mut self, value: u8) {
+        if self.irq_status & IRQ_SERIAL_IN != 0 {
+            self.skstat &= !SKSTAT_SERIAL_OVERRUN;
+        }
+        self.serin = value;
+        self.raise(IRQ_SERIAL_IN);
+    }
+
+    /// Bytes sent on the serial output so far
+    #[inline]
+    pub fn serial_output(&self) -> &[u8] {
+        &self.serial_output
+    }
+
+    /// Take the bytes sent on the serial output so far
+    pub fn take_serial_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.serial_output)
+    }
+
+    /// Level of the serial output line, which idles high
+    pub fn serial_output_line(&self) -> bool {
+        self.skctl & SKCTL_BREAK == 0 && (self.shift_bits == 0 || self.shift_out & 1 != 0)
+    }
+
+    /// Set the interrupt bits in `mask` that are enabled
+    #[inline]
+    fn raise(&mut self, mask: u8) {
+        self.irq_status |= mask & self.irq_enable;
+    }
+
+    #[inline]
+    fn initializing(&self) -> bool {
+        self.skctl & (SKCTL_DEBOUNCE | SKCTL_SCAN) == 0
+    }
+
+    fn read_random(&self) -> u8 {
+        if self.initializing() {
+            return 0xFF;
+        }
+        if self.audctl & AUDCTL_POLY9 != 0 {
+            !(self.poly9 >> 1) as u8
+        } else {
+            !(self.poly17 >> 9) as u8
+        }
+    }
+
+    fn irq_status(&self) -> u8 {
+        let mut status = self.irq_status;
+        if self.serout.is_none() && self.shift_bits == 0 {
+            status |= IRQ_SERIAL_OUT_DONE & self.irq_enable;
+        }
+        status
+    }
+
+    /// Reload value for channel `channel`, which underflows `reload + 1` clocks later.
+    /// 1.79 MHz channels count three more cycles, or six more when linked.
+    fn reload(&self, channel: usize) -> u16 {
+        let (linked, fast) = match channel {
+            0 => (false, self.audctl & AUDCTL_FAST_1 != 0),
+            1 => (
+                self.audctl & AUDCTL_LINK_12 != 0,
+                self.audctl & AUDCTL_FAST_1 != 0,
+            ),
+            2 => (false, self.audctl & AUDCTL_FAST_3 != 0),
+            _ => (
+                self.audctl & AUDCTL_LINK_34 != 0,
+                self.audctl & AUDCTL_FAST_3 != 0,
+            ),
+        };
+        match (linked, fast) {
+            (false, false) => self.audf[channel] as u16,
+            (false, true) => self.audf[channel] as u16 + 3,
+            (true, false) => u16::from_le_bytes([self.audf[channel - 1], self.audf[channel]]),
+            (true, true) => {
+                u16::from_le_bytes([self.audf[channel - 1], self.audf[channel]]).wrapping_add(6)
+            }
+        }
+    }
+
+    /// Reload every timer and reset the channel outputs
+    fn start_timers(&mut self) {
+        for channel in 0..4 {
+            self.counters[channel] = self.reload(channel);
+        }
+        self.outputs = [false; 4];
+    }
+
+    /// Clock `channel`'s timer, returning whether it underflowed
+    fn clock_timer(&mut self, channel: usize) -> bool {
+        if self.counters[channel] == 0 {
+            self.counters[channel] = self.reload(channel);
+            true
+        } else {
+            self.counters[channel] -= 1;
+            false
+        }
+    }
+
+    /// Clock a linked pair, whose counter is kept in the high channel, returning
+    /// whether the low and high channels underflowed
+    fn clock_pair(&mut self, high: usize) -> (bool, bool) {
+        let low = self.counters[high] & 0xFF == 0;
+        (low, self.clock_timer(high))
+    }
+
+    fn channel_underflow(&mut self, channel: usize) {
+        let audc = self.audc[channel];
+        if audc & AUDC_NO_POLY5 == 0 && self.poly5 & 1 == 0 {
+            return;
+        }
+        self.outputs[channel] = if audc & AUDC_PURE != 0 {
+            !self.outputs[channel]
+        } else if audc & AUDC_POLY4 != 0 {
+            self.poly4 & 1 != 0
+        } else if self.audctl & AUDCTL_POLY9 != 0 {
+            self.poly9 & 1 != 0
+        } else {
+            self.poly17 & 1 != 0
+        };
+    }
+
+    fn clock_polys(&mut self) {
+        let bit = (self.poly4 >> 3 ^ self.poly4 >> 2) & 1;
+        self.poly4 = (self.poly4 << 1 | bit) & 0x0F;
+        let bit = (self.poly5 >> 4 ^ self.poly5 >> 2) & 1;
+        self.poly5 = (self.poly5 << 1 | bit) & 0x1F;
+        let bit = (self.poly9 >> 8 ^ self.poly9 >> 4) & 1;
+        self.poly9 = (self.poly9 << 1 | bit) & 0x1FF;
+        let bit = (self.poly17 >> 16 ^ self.poly17 >> 13) & 1;
+        self.poly17 = (self.poly17 << 1 | bit) & 0x1FFFF;
+    }
+
+    /// Advance the four timers by one cycle, returning which channels underflowed
+    fn clock_timers(&mut self) -> [bool; 4] {
+        self.base_divider += 1;
+        let divide = if self.audctl & AUDCTL_15KHZ != 0 {
+            DIVIDE_15KHZ
+        } else {
+            DIVIDE_64KHZ
+        };
+        let base = self.base_divider >= divide;
+        if base {
+            self.base_divider = 0;
+        }
+
+        let mut underflows = [false; 4];
+        for (low, fast) in [(0, AUDCTL_FAST_1), (2, AUDCTL_FAST_3)] {
+            let link = if low == 0 {
+                AUDCTL_LINK_12
+            } else {
+                AUDCTL_LINK_34
+            };
+            let low_clocked = base || self.audctl & fast != 0;
+            if self.audctl & link != 0 {
+                if low_clocked {
+                    (underflows[low], underflows[low + 1]) = self.clock_pair(low + 1);
+                }
+            } else {
+                underflows[low] = low_clocked && self.clock_timer(low);
+                underflows[low + 1] = base && self.clock_timer(low + 1);
+            }
+        }
+        underflows
+    }
+
+    fn clock_serial_output(&mut self, underflows: [bool; 4]) {
+        let clocked = match self.skctl & SKCTL_SERIAL_MODE {
+            0x00 | 0x10 => false,
+            0x60 | 0x70 => underflows[1],
+            _ => underflows[3],
+        };
+        if !clocked {
+            return;
+        }
+        self.serial_clock = !self.serial_clock;
+        if self.serial_clock {
+            return;
+        }
+        if self.shift_bits > 0 {
+            self.shift_out >>= 1;
+            self.shift_bits -= 1;
+            if self.shift_bits == 0 {
+                self.serial_output.push(self.shift_value);
+            }
+        }
+        if self.shift_bits == 0 {
+            self.load_serial_output();
+        }
+    }
+
+    fn load_serial_output(&mut self) {
+        if let Some(value) = self.serout.take() {
+            // Start bit, eight data bits and the stop bit, least significant first
+            self.shift_out = (value as u16) << 1 | 0x200;
+            self.shift_value = value;
+            self.shift_bits = 10;
+            self.raise(IRQ_SERIAL_OUT_NEEDED);
+        }
+    }
+
+    fn clock_keyboard(&mut self) {
+        if self.skctl & SKCTL_SCAN == 0 {
+            self.skstat |= SKSTAT_KEY_DOWN;
+            return;
+        }
+        self.scan_counter = (self.scan_counter + 1) & 0x3F;
+        let Some(key) = self.key else {
+            self.skstat |= SKSTAT_KEY_DOWN;
+            return;
+        };
+        if key & 0x3F != self.scan_counter {
+            return;
+        }
+        self.skstat &= !SKSTAT_KEY_DOWN;
+        if self.key_reported && self.skctl & SKCTL_DEBOUNCE != 0 {
+            return;
+        }
+        self.key_reported = true;
+        if self.irq_status & IRQ_KEY != 0 {
+            self.skstat &= !SKSTAT_KEY_OVERRUN;
+        }
+        self.kbcode = key;
+        self.raise(IRQ_KEY);
+    }
+
+    fn clock_pots(&mut self) {
+        if self.allpot == 0 {
+            return;
+        }
+        self.pot_counter += 1;
+        for pot in 0..8 {
+            if self.allpot & 1 << pot != 0 && self.pot_counter >= self.pots[pot] {
+                self.pot_latches[pot] = self.pot_counter;
+                self.allpot &= !(1 << pot);
+            }
+        }
+    }
+
+    fn output(&self) -> f32 {
+        let mut output = 0.0;
+        for channel in 0..4 {
+            let audc = self.audc[channel];
+            let mut high = self.outputs[channel];
+            if channel < 2 {
+                let filter = [AUDCTL_HIPASS_1, AUDCTL_HIPASS_2][channel];
+                if self.audctl & filter != 0 {
+                    high ^= self.highpass[channel];
+                }
+            }
+            if high || audc & AUDC_VOLUME_ONLY != 0 {
+                output += (audc & AUDC_VOLUME) as f32 / 15.0;
+            }
+        }
+        output / 4.0
+    }
+
+    fn cycle(&mut self) {
+        if !self.initializing() {
+            self.clock_polys();
+            let underflows = self.clock_timers();
+            if self.skctl & SKCTL_TWO_TONE != 0 && underflows[1] {
+                self.counters[0] = self.reload(0);
+            }
+            for (channel, &underflow) in underflows.iter().enumerate() {
+                if underflow {
+                    self.channel_underflow(channel);
+                }
+            }
+            // Channels 3 and 4 latch the outputs of 1 and 2 into the high-pass filters
+            for channel in 0..2 {
+                if underflows[channel + 2] {
+                    self.highpass[channel] = self.outputs[channel];
+                }
+            }
+            for (channel, mask) in [(0, IRQ_TIMER_1), (1, IRQ_TIMER_2), (3, IRQ_TIMER_4)] {
+                if underflows[channel] {
+                    self.raise(mask);
+                }
+            }
+            self.clock_serial_output(underflows);
+        }
+
+        self.scan_divider += 1;
+        let scanline = self.scan_divider == SCANLINE_CYCLES;
+        if scanline {
+            self.scan_divider = 0;
+            if !self.initializing() {
+                self.clock_keyboard();
+            }
+        }
+        if scanline || self.skctl & SKCTL_FAST_POTS != 0 {
+            self.clock_pots();
+        }
+
+        if let Some(sample) = self.downsampler.push(self.output()) {
+            self.samples.push(sample);
+        }
+    }
+
+    fn write_skctl(&mut self, value: u8) {
+        self.skctl = value;
+        if self.initializing() {
+            self.poly4 = 0x0F;
+            self.poly5 = 0x1F;
+            self.poly9 = 0x1FF;
+            self.poly17 = 0x1FFFF;
+            self.base_divider = 0;
+            self.serial_clock = false;
+            self.shift_bits = 0;
+        }
+    }
+}
+
+impl Device for Pokey {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        let offset = offset & 0x0F;
+        Ok(match offset {
+            0x00..=0x07 => self.pot_latches[offset as usize],
+            ALLPOT => self.allpot,
+            KBCODE => self.kbcode,
+            RANDOM => self.read_random(),
+            SERIN => self.serin,
+            IRQST => !self.irq_status(),
+            SKSTAT => self.skstat,
+            _ => 0xFF,
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        let offset = offset & 0x0F;
+        match offset {
+            AUDF1..=0x07 => {
+                let channel = (offset >> 1) as usize;
+                if offset & 1 == 0 {
+                    self.audf[channel] = value;
+                } else {
+                    self.audc[channel] = value;
+                }
+            }
+            AUDCTL => self.audctl = value,
+            STIMER => self.start_timers(),
+            SKRES => self.skstat |= SKSTAT_FRAME_ERROR | SKSTAT_KEY_OVERRUN | SKSTAT_SERIAL_OVERRUN,
+            POTGO => {
+                self.pot_counter = 0;
+                self.pot_latches = [0; 8];
+                self.allpot = 0xFF;
+            }
+            SEROUT => {
+                self.serout = Some(value);
+                if self.shift_bits == 0 {
+                    self.load_serial_output();
+                }
+            }
+            IRQEN => {
+                self.irq_enable = value;
+                self.irq_status &= value;
+            }
+            SKCTL => self.write_skctl(value),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_status() != 0
+    }
+
+    /// Reset disables interrupts and puts the counters and serial port in their
+    /// initialization state, as the Atari's reset code does by writing SKCTL
+    fn reset(&mut self) {
+        self.irq_enable = 0;
+        self.irq_status = 0;
+        self.audctl = 0;
+        self.audc = [0; 4];
+        self.serout = None;
+        self.write_skctl(0);
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let samples = std::mem::take(&mut self.samples);
+        let serial_output = std::mem::take(&mut self.serial_output);
+        *self = bincode::deserialize(state)?;
+        self.samples = samples;
+        self.serial_output = serial_output;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    const CLOCK_HZ: u32 = 1_789_773;
+
+    fn new_pokey() -> Pokey {
+        let mut pokey = Pokey::new(CLOCK_HZ, 44_100);
+        pokey.write(SKCTL, SKCTL_DEBOUNCE | SKCTL_SCAN).unwrap();
+        pokey
+    }
+
+    /// Cycles until the next timer 1 interrupt, acknowledging it
+    fn cycles_to_timer_1(pokey: &mut Pokey) -> u32 {
+        let mut cycles = 0;
+        while !pokey.irq() {
+            pokey.tick(1);
+            cycles += 1;
+        }
+        pokey.write(IRQEN, 0).unwrap();
+        pokey.write(IRQEN, IRQ_TIMER_1).unwrap();
+        cycles
+    }
+
+    #[test]
+    fn test_polynomial_counters_and_random() {
+        let mut pokey = new_pokey();
+        let start = (pokey.poly4, pokey.poly5, pokey.poly9, pokey.poly17);
+        let mut periods = [0u32; 4];
+        for cycle in 1..=131_071 {
+            pokey.clock_polys();
+            let state = [
+                pokey.poly4 == start.0,
+                pokey.poly5 == start.1,
+                pokey.poly9 == start.2,
+                pokey.poly17 == start.3,
+            ];
+            for (period, repeated) in periods.iter_mut().zip(state) {
+                if repeated && *period == 0 {
+                    *period = cycle;
+                }
+            }
+        }
+        assert_eq!(periods, [15, 31, 511, 131_071]);
+
+        pokey.tick(100);
+        let first = pokey.read(RANDOM).unwrap();
+        pokey.tick(1);
+        assert_ne!(pokey.read(RANDOM).unwrap(), first);
+
+        // SKCTL initialization holds the counters
+        pokey.write(SKCTL, 0).unwrap();
+        pokey.tick(10);
+        assert_eq!(pokey.read(RANDOM).unwrap(), 0xFF);
+        assert_eq!(pokey.poly17, 0x1FFFF);
+    }
+
+    #[test]
+    fn test_timer_periods_and_interrupts() {
+        let mut pokey = new_pokey();
+        pokey.write(IRQEN, IRQ_TIMER_1).unwrap();
+        assert_eq!(pokey.read(IRQST).unwrap(), 0xFF);
+
+        // 64 kHz: AUDF + 1 periods of 28 cycles
+        pokey.write(AUDF1, 9).unwrap();
+        pokey.write(STIMER, 0).unwrap();
+        cycles_to_timer_1(&mut pokey);
+        assert_eq!(cycles_to_timer_1(&mut pokey), 280);
+        assert_eq!(cycles_to_timer_1(&mut pokey), 280);
+
+        // 15 kHz: 114 cycles per count
+        pokey.write(AUDCTL, AUDCTL_15KHZ).unwrap();
+        pokey.write(STIMER, 0).unwrap();
+        cycles_to_timer_1(&mut pokey);
+        assert_eq!(cycles_to_timer_1(&mut pokey), 1140);
+
+        // 1.79 MHz: AUDF + 4 cycles
+        pokey.write(AUDCTL, AUDCTL_FAST_1).unwrap();
+        pokey.write(STIMER, 0).unwrap();
+        cycles_to_timer_1(&mut pokey);
+        assert_eq!(cycles_to_timer_1(&mut pokey), 13);
+
+        // Linked 16-bit at 1.79 MHz: AUDF + 7 cycles, reported as timer 2
+        pokey.write(AUDCTL, AUDCTL_FAST_1 | AUDCTL_LINK_12).unwrap();
+        pokey.write(AUDF1, 0x34).unwrap();
+        pokey.write(AUDF1 + 2, 0x12).unwrap();
+        pokey.write(IRQEN, IRQ_TIMER_2).unwrap();
+        pokey.write(STIMER, 0).unwrap();
+        let mut cycles = 0;
+        let mut underflows = Vec::new();
+        while underflows.len() < 2 {
+            pokey.tick(1);
+            cycles += 1;
+            if pokey.irq() {
+                assert_eq!(pokey.read(IRQST).unwrap(), !IRQ_TIMER_2);
+                underflows.push(cycles);
+                pokey.write(IRQEN, 0).unwrap();
+                pokey.write(IRQEN, IRQ_TIMER_2).unwrap();
+            }
+        }
+        assert_eq!(underflows[1] - underflows[0], 0x1234 + 7);
+    }
+
+    #[test]
+    fn test_audio_channels() {
+        let mut pokey = new_pokey();
+        // Pure tone on channel 1 at full volume
+        pokey.write(AUDCTL, AUDCTL_FAST_1).unwrap();
+        pokey.write(AUDF1, 0).unwrap();
+        pokey
+            .write(AUDF1 + 1, AUDC_NO_POLY5 | AUDC_PURE | 0x0F)
+            .unwrap();
+        pokey.write(STIMER, 0).unwrap();
+        let levels: Vec<bool> = (0..16)
+            .map(|_| {
+                pokey.tick(1);
+                pokey.output() > 0.0
+            })
+            .collect();
+        assert_eq!(levels.iter().filter(|&&high| high).count(), 8);
+        assert_eq!(
+            levels[..8],
+            [false, false, false, true, true, true, true, false]
+        );
+
+        // Volume-only output is constant
+        pokey.write(AUDF1 + 1, AUDC_VOLUME_ONLY | 0x0F).unwrap();
+        pokey.tick(7);
+        assert_eq!(pokey.output(), 0.25);
+
+        // A high-pass filter clocked by channel 3 at the same rate cancels channel 1
+        pokey
+            .write(AUDCTL, AUDCTL_FAST_1 | AUDCTL_FAST_3 | AUDCTL_HIPASS_1)
+            .unwrap();
+        pokey
+            .write(AUDF1 + 1, AUDC_NO_POLY5 | AUDC_PURE | 0x0F)
+            .unwrap();
+        pokey.write(AUDF1 + 4, 0).unwrap();
+        pokey.write(STIMER, 0).unwrap();
+        pokey.take_samples();
+        pokey.tick(CLOCK_HZ / 100);
+        assert_eq!(pokey.samples().len(), 441);
+        // The first sample still averages in the volume-only output
+        assert!(pokey.samples()[1..].iter().all(|&sample| sample == 0));
+    }
+
+    #[test]
+    fn test_keyboard_pots_and_serial() {
+        let mut pokey = new_pokey();
+        pokey.write(IRQEN, IRQ_KEY).unwrap();
+        pokey.set_key(Some(0x3F | 0x40));
+        pokey.tick(SCANLINE_CYCLES as u32 * 64);
+        assert!(pokey.irq());
+        assert_eq!(pokey.read(KBCODE).unwrap(), 0x7F);
+        assert_eq!(pokey.read(SKSTAT).unwrap() & SKSTAT_KEY_DOWN, 0);
+
+        // Debounced: the held key is not reported again
+        pokey.write(IRQEN, 0).unwrap();
+        pokey.write(IRQEN, IRQ_KEY).unwrap();
+        pokey.tick(SCANLINE_CYCLES as u32 * 64);
+        assert!(!pokey.irq());
+        pokey.set_key(None);
+        pokey.tick(SCANLINE_CYCLES as u32);
+        assert_ne!(pokey.read(SKSTAT).unwrap() & SKSTAT_KEY_DOWN, 0);
+
+        // Pots count scanlines until they charge
+        pokey.set_pot(0, 10);
+        pokey.set_pot(1, 100);
+        pokey.write(POTGO, 0).unwrap();
+        pokey.tick(SCANLINE_CYCLES as u32 * 20);
+        assert_eq!(pokey.read(ALLPOT).unwrap(), 0xFE);
+        assert_eq!(pokey.read(0).unwrap(), 10);
+        pokey.tick(SCANLINE_CYCLES as u32 * 228);
+        assert_eq!(pokey.read(ALLPOT).unwrap(), 0);
+        assert_eq!(pokey.read(1).unwrap(), 100);
+
+        // Serial output at channel 4's rate, two underflows per bit
+        pokey
+            .write(SKCTL, SKCTL_DEBOUNCE | SKCTL_SCAN | 0x20)
+            .unwrap();
+        pokey.write(AUDCTL, AUDCTL_FAST_3 | AUDCTL_LINK_34).unwrap();
+        pokey.write(AUDF1 + 4, 0x28).unwrap();
+        pokey.write(AUDF1 + 6, 0x00).unwrap();
+        pokey.write(STIMER, 0).unwrap();
+        pokey
+            .write(IRQEN, IRQ_SERIAL_OUT_NEEDED | IRQ_SERIAL_OUT_DONE)
+            .unwrap();
+        assert_eq!(pokey.read(IRQST).unwrap(), !IRQ_SERIAL_OUT_DONE);
+        pokey.write(SEROUT, 0x55).unwrap();
+        assert_eq!(pokey.read(IRQST).unwrap(), !IRQ_SERIAL_OUT_NEEDED);
+        pokey.write(IRQEN, IRQ_SERIAL_OUT_DONE).unwrap();
+        pokey
+            .write(IRQEN, IRQ_SERIAL_OUT_NEEDED | IRQ_SERIAL_OUT_DONE)
+            .unwrap();
+        pokey.write(SEROUT, 0xA0).unwrap();
+        pokey.tick((0x28 + 7) * 2 * 19);
+        assert_eq!(pokey.serial_output(), [0x55]);
+        assert_eq!(pokey.read(IRQST).unwrap(), !IRQ_SERIAL_OUT_NEEDED);
+        pokey.tick((0x28 + 7) * 2 * 10);
+        assert_eq!(pokey.take_serial_output(), [0x55, 0xA0]);
+        assert_eq!(
+            pokey.read(IRQST).unwrap(),
+            !(IRQ_SERIAL_OUT_NEEDED | IRQ_SERIAL_OUT_DONE)
+        );
+
+        // Serial input, with an overrun when the last byte is not acknowledged
+        pokey.write(IRQEN, IRQ_SERIAL_IN).unwrap();
+        pokey.receive_serial(0x31);
+        assert_eq!(pokey.read(SERIN).unwrap(), 0x31);
+        pokey.receive_serial(0x32);
+        assert_eq!(pokey.read(SKSTAT).unwrap() & SKSTAT_SERIAL_OVERRUN, 0);
+        pokey.write(SKRES, 0).unwrap();
+        assert_ne!(pokey.read(SKSTAT).unwrap() & SKSTAT_SERIAL_OVERRUN, 0);
+    }
+
+    #[test]
+    fn test_timer_interrupt_handler() {
+        // Timer 4 interrupts counted by a handler, as on the Atari with POKEY at $D200
+        #[rustfmt::skip]
+        let program = [
+            0x78,             // SEI
+            0xA9, 0x03,       // LDA #$03
+            0x8D, 0x0F, 0xD2, // STA SKCTL
+            0xA9, 0x63,       // LDA #99
+            0x8D, 0x06, 0xD2, // STA AUDF4
+            0xA9, IRQ_TIMER_4,// LDA #IRQ_TIMER_4
+            0x8D, 0x0E, 0xD2, // STA IRQEN
+            0x8D, 0x09, 0xD2, // STA STIMER
+            0x58,             // CLI
+            0x4C, 0x14, 0xF0, // JMP *
+        ];
+        #[rustfmt::skip]
+        let handler = [
+            0xE6, 0x00,       // INC $00
+            0xA9, 0x00,       // LDA #$00
+            0x8D, 0x0E, 0xD2, // STA IRQEN
+            0xA9, IRQ_TIMER_4,// LDA #IRQ_TIMER_4
+            0x8D, 0x0E, 0xD2, // STA IRQEN
+            0x40,             // RTI
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x800..0x800 + handler.len()].copy_from_slice(&handler);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF8]);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x3FFF)
+            .device(0xD200..=0xD2FF, Pokey::new(CLOCK_HZ, 44_100))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+        // 100 counts of 28 cycles per interrupt
+        machine.run_cycles(28_000 + 1_000).unwrap();
+        assert_eq!(machine.bus().read(0x0000).unwrap(), 10);
+    }
+}
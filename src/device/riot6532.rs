@@ -0,0 +1,368 @@
+use serde::{Deserialize, Serialize};
+
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+const RAM_SIZE: usize = 128;
+
+// Register select lines within the I/O half
+const A0: u16 = 1 << 0;
+const A1: u16 = 1 << 1;
+const A2: u16 = 1 << 2;
+const A3: u16 = 1 << 3;
+const A4: u16 = 1 << 4;
+
+// Interrupt flag register bits
+const FLAG_TIMER: u8 = 1 << 7;
+const FLAG_PA7: u8 = 1 << 6;
+
+const PA7: u8 = 1 << 7;
+
+/// Prescaler shift for the timer write addresses, selected by A0-A1: 1, 8, 64 and 1024
+const PRESCALER_SHIFTS: [u8; 4] = [0, 3, 6, 10];
+
+/// MOS 6532 RAM-I/O-Timer.
+///
+/// The chip only decodes A0-A6 and its RS (RAM select) pin, so the device is mirrored
+/// throughout whatever range it is mapped over. RS is the offset bit given to
+/// [`Riot6532::with_rs_mask`]: offsets with it clear reach the 128 bytes of RAM, offsets
+/// with it set reach the ports and timer. All other offset bits are ignored.
+///
+/// The timer decrements on the cycle after it is written and then once per prescaler
+/// period of 1, 8, 64 or 1024 cycles. When it counts through zero it raises its flag and from then on decrements
+/// every cycle until it is written again.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Riot6532 {
+    rs_mask: u16,
+    ram: Vec<u8>,
+    ora: u8,
+    orb: u8,
+    ddra: u8,
+    ddrb: u8,
+    port_a_pins: u8,
+    port_b_pins: u8,
+    timer: u8,
+    prescaler_shift: u8,
+    divider: u16,
+    underflowed: bool,
+    timer_interrupt_enabled: bool,
+    pa7_positive_edge: bool,
+    pa7_interrupt_enabled: bool,
+    flags: u8,
+}
+
+impl Default for Riot6532 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Riot6532 {
+    /// RIOT with RS on offset bit 7, filling a 256-byte window with RAM then I/O
+    pub fn new() -> Self {
+        Self::with_rs_mask(0x80)
+    }
+
+    /// RIOT with RS on the offset bit in `rs_mask`. The Atari 2600, which has RS on A9,
+    /// maps the chip at $0080 with an RS mask of $0200.
+    pub fn with_rs_mask(rs_mask: u16) -> Self {
+        Self {
+            rs_mask,
+            ram: vec![0; RAM_SIZE],
+            ora: 0,
+            orb: 0,
+            ddra: 0,
+            ddrb: 0,
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            timer: 0,
+            prescaler_shift: PRESCALER_SHIFTS[3],
+            divider: 0,
+            underflowed: false,
+            timer_interrupt_enabled: false,
+            pa7_positive_edge: false,
+            pa7_interrupt_enabled: false,
+            flags: 0,
+        }
+    }
+
+    /// Contents of the 128 bytes of RAM
+    #[inline]
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Levels on the port A pins: output bits from ORA, input bits from outside
+    #[inline]
+    pub fn port_a(&self) -> u8 {
+        (self.ora & self.ddra) | (self.port_a_pins & !self.ddra)
+    }
+
+    /// Levels on the port B pins
+    #[inline]
+    pub fn port_b(&self) -> u8 {
+        (self.orb & self.ddrb) | (self.port_b_pins & !self.ddrb)
+    }
+
+    /// Drive the port A input pins, watching PA7 for the edge detector
+    pub fn set_port_a(&mut self, value: u8) {
+        let previous = self.port_a();
+        self.port_a_pins = value;
+        self.detect_pa7_edge(previous);
+    }
+
+    /// Drive the port B input pins
+    #[inline]
+    pub fn set_port_b(&mut self, value: u8) {
+        self.port_b_pins = value;
+    }
+
+    /// Current timer value
+    #[inline]
+    pub fn timer(&self) -> u8 {
+        self.timer
+    }
+
+    fn detect_pa7_edge(&mut self, previous: u8) {
+        let (was_high, is_high) = (previous & PA7 != 0, self.port_a() & PA7 != 0);
+        if was_high != is_high && is_high == self.pa7_positive_edge {
+            self.flags |= FLAG_PA7;
+        }
+    }
+
+    fn write_timer(&mut self, offset: u16, value: u8) {
+        self.timer = value;
+        self.prescaler_shift = PRESCALER_SHIFTS[(offset & (A1 | A0)) as usize];
+        // The next cycle decrements, then every prescaler period after it
+        self.divider = 0;
+        self.underflowed = false;
+        self.timer_interrupt_enabled = offset & A3 != 0;
+        self.flags &= !FLAG_TIMER;
+    }
+
+    fn cycle(&mut self) {
+        if !self.underflowed && self.divider != 0 {
+            self.divider -= 1;
+            return;
+        }
+        self.divider = (1 << self.prescaler_shift) - 1;
+        self.timer = self.timer.wrapping_sub(1);
+        if self.timer == 0xFF && !self.underflowed {
+            self.underflowed = true;
+            self.flags |= FLAG_TIMER;
+        }
+    }
+}
+
+impl Device for Riot6532 {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        if offset & self.rs_mask == 0 {
+            return Ok(self.ram[offset as usize % RAM_SIZE]);
+        }
+        Ok(match (offset & A2 != 0, offset & (A1 | A0)) {
+            (false, 0) => self.port_a(),
+            (false, 1) => self.ddra,
+            // Output bits of port B read back from ORB
+            (false, 2) => (self.orb & self.ddrb) | (self.port_b_pins & !self.ddrb),
+            (false, _) => self.ddrb,
+            (true, address) if address & A0 == 0 => {
+                self.timer_interrupt_enabled = offset & A3 != 0;
+                self.flags &= !FLAG_TIMER;
+                self.timer
+            }
+            (true, _) => {
+                let flags = self.flags;
+                self.flags &= !FLAG_PA7;
+                flags
+            }
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        if offset & self.rs_mask == 0 {
+            self.ram[offset as usize % RAM_SIZE] = value;
+            return Ok(());
+        }
+        if offset & A2 == 0 {
+            let previous = self.port_a();
+            match offset & (A1 | A0) {
+                0 => self.ora = value,
+                1 => self.ddra = value,
+                2 => self.orb = value,
+                _ => self.ddrb = value,
+            }
+            self.detect_pa7_edge(previous);
+        } else if offset & A4 != 0 {
+            self.write_timer(offset, value);
+        } else {
+            self.pa7_positive_edge = offset & A0 != 0;
+            self.pa7_interrupt_enabled = offset & A1 != 0;
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        (self.timer_interrupt_enabled && self.flags & FLAG_TIMER != 0)
+            || (self.pa7_interrupt_enabled && self.flags & FLAG_PA7 != 0)
+    }
+
+    /// Reset clears the ports and interrupt enables; RAM and the timer keep running
+    fn reset(&mut self) {
+        self.ora = 0;
+        self.orb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.timer_interrupt_enabled = false;
+        self.pa7_positive_edge = false;
+        self.pa7_interrupt_enabled = false;
+        self.flags &= !FLAG_PA7;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        *self = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    // I/O offsets with RS on bit 7
+    const DRA: u16 = 0x80;
+    const DDRA: u16 = 0x81;
+    const DRB: u16 = 0x82;
+    const DDRB: u16 = 0x83;
+    const INTIM: u16 = 0x84;
+    const INSTAT: u16 = 0x85;
+    const TIM8T: u16 = 0x95;
+    const TIM1024T_IRQ: u16 = 0x9F;
+
+    #[test]
+    fn test_ram_and_aliasing() {
+        let mut riot = Riot6532::new();
+        riot.write(0x05, 0x42).unwrap();
+        assert_eq!(riot.read(0x105).unwrap(), 0x42);
+        riot.write(0x17F, 0x24).unwrap();
+        assert_eq!(riot.ram()[0x7F], 0x24);
+
+        // Registers repeat wherever RS is set: A3 is ignored by the port registers
+        riot.write(DDRA + 8, 0x0F).unwrap();
+        assert_eq!(riot.read(DDRA).unwrap(), 0x0F);
+        assert_eq!(riot.read(0x181 + 0x10).unwrap(), 0x0F);
+    }
+
+    #[test]
+    fn test_ports_and_pa7_edge() {
+        let mut riot = Riot6532::new();
+        riot.write(DDRA, 0xF0).unwrap();
+        riot.write(DRA, 0x5A).unwrap();
+        riot.set_port_a(0x03);
+        assert_eq!(riot.read(DRA).unwrap(), 0x53);
+        riot.write(DDRB, 0x0F).unwrap();
+        riot.write(DRB, 0xA5).unwrap();
+        riot.set_port_b(0x30);
+        assert_eq!(riot.read(DRB).unwrap(), 0x35);
+        assert_eq!(riot.port_b(), 0x35);
+
+        // Positive edge with interrupt: A2, A1 and A0 set, A4 clear
+        riot.write(DDRA, 0x00).unwrap();
+        riot.set_port_a(0x00);
+        riot.write(0x87, 0).unwrap();
+        // Discard the negative edge seen while setting up the port
+        riot.read(INSTAT).unwrap();
+        riot.set_port_a(0x7F);
+        assert!(!riot.irq());
+        riot.set_port_a(0x80);
+        assert!(riot.irq());
+        assert_eq!(riot.read(INSTAT).unwrap(), FLAG_PA7);
+        assert!(!riot.irq(), "reading the flags clears PA7");
+
+        // Negative edge, driven from the output register
+        riot.write(0x86, 0).unwrap();
+        riot.write(DDRA, 0x80).unwrap();
+        riot.write(DRA, 0x00).unwrap();
+        assert!(riot.irq());
+    }
+
+    #[test]
+    fn test_timer_prescaler_and_underflow() {
+        let mut riot = Riot6532::new();
+        riot.write(TIM8T, 2).unwrap();
+        riot.tick(1);
+        assert_eq!(riot.timer(), 1);
+        riot.tick(7);
+        assert_eq!(riot.timer(), 1);
+        riot.tick(1);
+        assert_eq!(riot.timer(), 0);
+        riot.tick(7);
+        assert_eq!(riot.read(INSTAT).unwrap(), 0);
+        riot.tick(1);
+        assert_eq!(riot.timer(), 0xFF);
+        assert_eq!(riot.read(INSTAT).unwrap(), FLAG_TIMER);
+        assert!(!riot.irq(), "timer interrupt not enabled");
+
+        // After underflow the timer counts every cycle
+        riot.tick(3);
+        assert_eq!(riot.timer(), 0xFC);
+        // Reading clears the flag but not the one-cycle rate
+        assert_eq!(riot.read(INTIM).unwrap(), 0xFC);
+        assert_eq!(riot.read(INSTAT).unwrap(), 0);
+        riot.tick(1);
+        assert_eq!(riot.timer(), 0xFB);
+
+        riot.write(TIM1024T_IRQ, 1).unwrap();
+        riot.tick(1 + 1024 - 1);
+        assert!(!riot.irq());
+        riot.tick(1);
+        assert!(riot.irq());
+    }
+
+    #[test]
+    fn test_atari_2600_layout() {
+        #[rustfmt::skip]
+        let program = [
+            0xA2, 0xFF,       // LDX #$FF
+            0x9A,             // TXS
+            0xA9, 0x02,       // LDA #$02
+            0x8D, 0x96, 0x02, // STA TIM64T
+            0xAD, 0x84, 0x02, // LDA INTIM (loop)
+            0xD0, 0xFB,       // BNE loop
+            0x20, 0x20, 0xF0, // JSR $F020
+            0x4C, 0x10, 0xF0, // JMP *
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x20..0x23].copy_from_slice(&[0x86, 0x80, 0x60]); // STX $80, RTS
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+        let mut machine = Machine::builder()
+            .device(0x0080..=0x02FF, Riot6532::with_rs_mask(0x200))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+
+        while machine.cpu().program_counter() != 0xF010 {
+            machine.step().unwrap();
+            assert!(machine.cycles() < 300, "INTIM never reached zero");
+        }
+        // Two 64-cycle periods after the first decrement, polled every 7 cycles
+        assert!((64 + 1 + 13..64 * 2 + 1 + 13).contains(&machine.cycles()));
+
+        // The stack at $01xx and zero page at $80 share the RIOT's RAM
+        let bus = machine.bus();
+        assert_eq!(bus.read(0x0080).unwrap(), 0xFF);
+        assert_eq!(bus.read(0x01FF).unwrap(), 0xF0);
+        assert_eq!(bus.read(0x00FE).unwrap(), 0x0F);
+    }
+}
@@ -0,0 +1,623 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::device::spi::SpiDevice;
+use crate::error::StateError;
+
+const BLOCK_SIZE: usize = 512;
+/// Cards larger than this are SDHC, addressed in blocks rather than bytes
+const SDSC_MAX_CAPACITY: u64 = 2 << 30;
+
+// Commands
+const GO_IDLE_STATE: u8 = 0;
+const SEND_IF_COND: u8 = 8;
+const SET_BLOCKLEN: u8 = 16;
+const READ_SINGLE_BLOCK: u8 = 17;
+const WRITE_BLOCK: u8 = 24;
+const SD_SEND_OP_COND: u8 = 41;
+const APP_CMD: u8 = 55;
+const READ_OCR: u8 = 58;
+const CRC_ON_OFF: u8 = 59;
+
+// R1 response bits
+const R1_IDLE: u8 = 1 << 0;
+const R1_ILLEGAL_COMMAND: u8 = 1 << 2;
+const R1_CRC_ERROR: u8 = 1 << 3;
+const R1_ADDRESS_ERROR: u8 = 1 << 5;
+const R1_PARAMETER_ERROR: u8 = 1 << 6;
+
+// Tokens
+const START_BLOCK: u8 = 0xFE;
+const DATA_ERROR_TOKEN: u8 = 0x01;
+const DATA_OUT_OF_RANGE_TOKEN: u8 = 0x08;
+const DATA_ACCEPTED: u8 = 0x05;
+const DATA_WRITE_ERROR: u8 = 0x0D;
+
+// OCR bits
+const OCR_POWER_UP: u32 = 1 << 31;
+const OCR_CCS: u32 = 1 << 30;
+/// 2.7-3.6 V
+const OCR_VOLTAGE_WINDOW: u32 = 0x00FF_8000;
+/// Host capacity support bit of the ACMD41 argument
+const HCS: u32 = 1 << 30;
+
+/// Bytes the card holds MISO low for after accepting a block, standing in for the
+/// programming time
+const WRITE_BUSY_BYTES: usize = 8;
+
+/// Where the card is in receiving from the host
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Command,
+    /// Waiting for the start token of the block to write at the byte address
+    WriteToken(u64),
+    WriteData(u64),
+}
+
+/// SD card in SPI mode, backed by a disk image.
+///
+/// The card powers up in SD mode and only listens for CMD0 until that switches it to
+/// SPI mode. It then takes CMD8, ACMD41 (which has to be repeated once before the card
+/// leaves the idle state, as real cards need a few tries), CMD58, CMD16 for 512-byte
+/// blocks, CMD59, and single-block reads and writes with CMD17 and CMD24. CRCs are
+/// only checked on CMD0 and CMD8, as in SPI mode with CRCs off.
+///
+/// Images up to 2 GiB are standard capacity cards addressed in bytes; bigger ones, and
+/// cards built with [`SdCard::sdhc`], are addressed in blocks. Failed reads and writes
+/// of the image are reported to the host with error tokens.
+pub struct SdCard<S: Read + Write + Seek> {
+    image: S,
+    blocks: u64,
+    high_capacity: bool,
+    spi_mode: bool,
+    idle: bool,
+    init_attempts: u8,
+    app_command: bool,
+    phase: Phase,
+    command: Vec<u8>,
+    data: Vec<u8>,
+    output: VecDeque<u8>,
+}
+
+impl SdCard<File> {
+    /// Card backed by the disk image at `path`, which is written through
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Self::new(file)
+    }
+}
+
+impl<S: Read + Write + Seek> SdCard<S> {
+    pub fn new(mut image: S) -> io::Result<Self> {
+        let length = image.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            image,
+            blocks: length / BLOCK_SIZE as u64,
+            high_capacity: length > SDSC_MAX_CAPACITY,
+            spi_mode: false,
+            idle: true,
+            init_attempts: 0,
+            app_command: false,
+            phase: Phase::Command,
+            command: Vec::with_capacity(6),
+            data: Vec::with_capacity(BLOCK_SIZE + 2),
+            output: VecDeque::new(),
+        })
+    }
+
+    /// High capacity card, addressed in blocks whatever the image size
+    pub fn sdhc(image: S) -> io::Result<Self> {
+        Ok(Self {
+            high_capacity: true,
+            ..Self::new(image)?
+        })
+    }
+
+    #[inline]
+    pub fn image(&self) -> &S {
+        &self.image
+    }
+
+    #[inline]
+    pub fn image_mut(&mut self) -> &mut S {
+        &mut self.image
+    }
+
+    /// Whether initialization with ACMD41 has finished
+    #[inline]
+    pub fn ready(&self) -> bool {
+        !self.idle
+    }
+
+    fn r1(&self, errors: u8) -> u8 {
+        errors | if self.idle { R1_IDLE } else { 0 }
+    }
+
+    /// Queue a response after the one byte of response time
+    fn respond(&mut self, response: &[u8]) {
+        self.output.push_back(0xFF);
+        self.output.extend(response);
+    }
+
+    /// Byte address of a block command's argument, if it is a whole block on the card
+    fn block_address(&self, argument: u32) -> Option<u64> {
+        let address = if self.high_capacity {
+            argument as u64 * BLOCK_SIZE as u64
+        } else {
+            argument as u64
+        };
+        (address.is_multiple_of(BLOCK_SIZE as u64) && address / (BLOCK_SIZE as u64) < self.blocks)
+            .then_some(address)
+    }
+
+    fn execute(&mut self) {
+        let index = self.command[0] & 0x3F;
+        let argument = u32::from_be_bytes([
+            self.command[1],
+            self.command[2],
+            self.command[3],
+            self.command[4],
+        ]);
+        let crc_valid = crc7(&self.command[..5]) << 1 | 1 == self.command[5];
+        let app_command = std::mem::take(&mut self.app_command);
+
+        if !self.spi_mode {
+            // Cards start in SD mode, which is left by CMD0 with chip select asserted
+            if index == GO_IDLE_STATE && crc_valid {
+                self.spi_mode = true;
+                self.respond(&[R1_IDLE]);
+            }
+            return;
+        }
+        if matches!(index, GO_IDLE_STATE | SEND_IF_COND) && !crc_valid {
+            self.respond(&[self.r1(R1_CRC_ERROR)]);
+            return;
+        }
+
+        match (app_command, index) {
+            (_, GO_IDLE_STATE) => {
+                self.idle = true;
+                self.init_attempts = 0;
+                self.respond(&[R1_IDLE]);
+            }
+            (_, SEND_IF_COND) => {
+                let echo = (argument & 0xFFF).to_be_bytes();
+                self.respond(&[self.r1(0), 0x00, 0x00, echo[2], echo[3]]);
+            }
+            (true, SD_SEND_OP_COND) => {
+                // High capacity cards never leave idle for hosts that don't support them
+                if argument & HCS != 0 || !self.high_capacity {
+                    self.init_attempts = self.init_attempts.saturating_add(1);
+                    self.idle = self.init_attempts < 2;
+                }
+                self.respond(&[self.r1(0)]);
+            }
+            (_, APP_CMD) => {
+                self.app_command = true;
+                self.respond(&[self.r1(0)]);
+            }
+            (_, READ_OCR) => {
+                let mut ocr = OCR_VOLTAGE_WINDOW;
+                if !self.idle {
+                    ocr |= OCR_POWER_UP;
+                    if self.high_capacity {
+                        ocr |= OCR_CCS;
+                    }
+                }
+                let ocr = ocr.to_be_bytes();
+                self.respond(&[self.r1(0), ocr[0], ocr[1], ocr[2], ocr[3]]);
+            }
+            (_, SET_BLOCKLEN) => {
+                let error = if argument as usize == BLOCK_SIZE {
+                    0
+                } else {
+                    R1_PARAMETER_ERROR
+                };
+                self.respond(&[self.r1(error)]);
+            }
+            (_, CRC_ON_OFF) => self.respond(&[self.r1(0)]),
+            (_, READ_SINGLE_BLOCK | WRITE_BLOCK) if self.idle => {
+                self.respond(&[self.r1(R1_ILLEGAL_COMMAND)]);
+            }
+            (_, READ_SINGLE_BLOCK) => self.read_block(argument),
+            (_, WRITE_BLOCK) => match self.block_address(argument) {
+                Some(address) => {
+                    self.phase = Phase::WriteToken(address);
+                    self.respond(&[self.r1(0)]);
+                }
+                None => self.respond(&[self.r1(R1_ADDRESS_ERROR)]),
+            },
+            _ => self.respond(&[self.r1(R1_ILLEGAL_COMMAND)]),
+        }
+    }
+
+    fn read_block(&mut self, argument: u32) {
+        let Some(address) = self.block_address(argument) else {
+            if !self.high_capacity && !(argument as usize).is_multiple_of(BLOCK_SIZE) {
+                self.respond(&[self.r1(R1_ADDRESS_ERROR)]);
+            } else {
+                self.respond(&[self.r1(0), 0xFF, DATA_OUT_OF_RANGE_TOKEN]);
+            }
+            return;
+        };
+        let mut block = [0; BLOCK_SIZE];
+        let result = self
+            .image
+            .seek(SeekFrom::Start(address))
+            .and_then(|_| self.image.read_exact(&mut block));
+        if result.is_err() {
+            self.respond(&[self.r1(0), 0xFF, DATA_ERROR_TOKEN]);
+            return;
+        }
+        self.respond(&[self.r1(0), 0xFF, START_BLOCK]);
+        self.output.extend(block);
+        self.output.extend(crc16(&block).to_be_bytes());
+    }
+
+    fn write_block(&mut self, address: u64) {
+        // The two CRC bytes follow the data and are not checked
+        let block = &self.data[..BLOCK_SIZE];
+        let result = self
+            .image
+            .seek(SeekFrom::Start(address))
+            .and_then(|_| self.image.write_all(block))
+            .and_then(|_| self.image.flush());
+        let response = if result.is_ok() {
+            DATA_ACCEPTED
+        } else {
+            DATA_WRITE_ERROR
+        };
+        self.output.push_back(response);
+        self.output.extend([0x00; WRITE_BUSY_BYTES]);
+    }
+}
+
+impl<S: Read + Write + Seek + 'static> SpiDevice for SdCard<S> {
+    fn select(&mut self, _selected: bool) {
+        // A partly sent command is dropped
+        self.command.clear();
+    }
+
+    fn shift_out(&mut self) -> u8 {
+        self.output.pop_front().unwrap_or(0xFF)
+    }
+
+    fn shift_in(&mut self, value: u8) {
+        match self.phase {
+            Phase::Command => {
+                if self.command.is_empty() {
+                    // Commands start with a 0 start bit and a 1 transmission bit
+                    if value & 0xC0 != 0x40 {
+                        return;
+                    }
+                    self.output.clear();
+                }
+                self.command.push(value);
+                if self.command.len() == 6 {
+                    self.execute();
+                    self.command.clear();
+                }
+            }
+            Phase::WriteToken(address) => {
+                if value == START_BLOCK {
+                    self.data.clear();
+                    self.phase = Phase::WriteData(address);
+                }
+            }
+            Phase::WriteData(address) => {
+                self.data.push(value);
+                if self.data.len() == BLOCK_SIZE + 2 {
+                    self.phase = Phase::Command;
+                    self.write_block(address);
+                }
+            }
+        }
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        let phase = match self.phase {
+            Phase::Command => (0, 0),
+            Phase::WriteToken(address) => (1, address),
+            Phase::WriteData(address) => (2, address),
+        };
+        Ok(bincode::serialize(&(
+            self.spi_mode,
+            self.idle,
+            self.init_attempts,
+            self.app_command,
+            phase,
+            &self.command,
+            &self.data,
+            &self.output,
+        ))?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let phase: (u8, u64);
+        (
+            self.spi_mode,
+            self.idle,
+            self.init_attempts,
+            self.app_command,
+            phase,
+            self.command,
+            self.data,
+            self.output,
+        ) = bincode::deserialize(state)?;
+        self.phase = match phase {
+            (1, address) => Phase::WriteToken(address),
+            (2, address) => Phase::WriteData(address),
+            _ => Phase::Command,
+        };
+        Ok(())
+    }
+}
+
+/// CRC7 of a command, polynomial x^7 + x^3 + 1
+fn crc7(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in bytes {
+        for bit in (0..8).rev() {
+            let feedback = (crc >> 6 ^ byte >> bit) & 1;
+            crc = (crc << 1) & 0x7F;
+            if feedback != 0 {
+                crc ^= 0x09;
+            }
+        }
+    }
+    crc
+}
+
+/// CRC16-CCITT of a data block, polynomial x^16 + x^12 + x^5 + 1
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::device::{SpiPins, SpiPort, Via6522};
+    use crate::machine::Machine;
+
+    const BLOCKS: usize = 8;
+
+    type Card = SdCard<Cursor<Vec<u8>>>;
+
+    fn image() -> Vec<u8> {
+        (0..BLOCKS * BLOCK_SIZE)
+            .map(|i| (i / BLOCK_SIZE * 7 + i) as u8)
+            .collect()
+    }
+
+    /// Send a command and return its R1 response, if the card answered
+    fn send(card: &mut Card, index: u8, argument: u32) -> Option<u8> {
+        let mut command = vec![0x40 | index];
+        command.extend(argument.to_be_bytes());
+        command.push(crc7(&command) << 1 | 1);
+        for byte in command {
+            card.exchange(byte);
+        }
+        (0..8)
+            .map(|_| card.exchange(0xFF))
+            .find(|&byte| byte != 0xFF)
+    }
+
+    fn receive(card: &mut Card, length: usize) -> Vec<u8> {
+        (0..length).map(|_| card.exchange(0xFF)).collect()
+    }
+
+    #[test]
+    fn test_standard_capacity() {
+        let mut card = SdCard::new(Cursor::new(image())).unwrap();
+        // Ignored until CMD0 switches to SPI mode, which needs a valid CRC
+        assert_eq!(send(&mut card, SEND_IF_COND, 0x1AA), None);
+        card.exchange(0x40);
+        assert_eq!(receive(&mut card, 5), [0xFF; 5]);
+        assert_eq!(send(&mut card, GO_IDLE_STATE, 0), Some(R1_IDLE));
+
+        assert_eq!(send(&mut card, SEND_IF_COND, 0x1AA), Some(R1_IDLE));
+        assert_eq!(receive(&mut card, 4), [0x00, 0x00, 0x01, 0xAA]);
+        assert_eq!(
+            send(&mut card, READ_SINGLE_BLOCK, 0),
+            Some(R1_IDLE | R1_ILLEGAL_COMMAND)
+        );
+        for expected in [R1_IDLE, 0] {
+            assert_eq!(send(&mut card, APP_CMD, 0), Some(R1_IDLE));
+            assert_eq!(send(&mut card, SD_SEND_OP_COND, 0), Some(expected));
+        }
+        assert!(card.ready());
+        assert_eq!(send(&mut card, READ_OCR, 0), Some(0));
+        assert_eq!(receive(&mut card, 4), [0x80, 0xFF, 0x80, 0x00]);
+        assert_eq!(send(&mut card, SET_BLOCKLEN, 512), Some(0));
+        assert_eq!(
+            send(&mut card, SET_BLOCKLEN, 1024),
+            Some(R1_PARAMETER_ERROR)
+        );
+        assert_eq!(send(&mut card, 2, 0), Some(R1_ILLEGAL_COMMAND));
+
+        // Byte addresses
+        assert_eq!(send(&mut card, READ_SINGLE_BLOCK, 0x200), Some(0));
+        assert_eq!(receive(&mut card, 2), [0xFF, START_BLOCK]);
+        let block = receive(&mut card, BLOCK_SIZE + 2);
+        assert_eq!(block[..BLOCK_SIZE], image()[BLOCK_SIZE..2 * BLOCK_SIZE]);
+        assert_eq!(
+            block[BLOCK_SIZE..],
+            crc16(&block[..BLOCK_SIZE]).to_be_bytes()
+        );
+        assert_eq!(
+            send(&mut card, READ_SINGLE_BLOCK, 0x201),
+            Some(R1_ADDRESS_ERROR)
+        );
+        assert_eq!(send(&mut card, READ_SINGLE_BLOCK, 0x1000), Some(0));
+        assert_eq!(receive(&mut card, 2), [0xFF, DATA_OUT_OF_RANGE_TOKEN]);
+
+        assert_eq!(send(&mut card, WRITE_BLOCK, 0x400), Some(0));
+        card.exchange(0xFF);
+        card.exchange(START_BLOCK);
+        for i in 0..BLOCK_SIZE + 2 {
+            card.exchange(!i as u8);
+        }
+        assert_eq!(card.exchange(0xFF) & 0x1F, DATA_ACCEPTED);
+        assert_eq!(
+            receive(&mut card, WRITE_BUSY_BYTES),
+            [0x00; WRITE_BUSY_BYTES]
+        );
+        assert_eq!(card.exchange(0xFF), 0xFF);
+        let written = &card.image().get_ref()[2 * BLOCK_SIZE..3 * BLOCK_SIZE];
+        assert!(written.iter().enumerate().all(|(i, &b)| b == !i as u8));
+
+        // Back to idle
+        assert_eq!(send(&mut card, GO_IDLE_STATE, 0), Some(R1_IDLE));
+        assert!(!card.ready());
+    }
+
+    #[test]
+    fn test_high_capacity() {
+        let mut card = SdCard::sdhc(Cursor::new(image())).unwrap();
+        assert_eq!(send(&mut card, GO_IDLE_STATE, 0), Some(R1_IDLE));
+        // Without host support for high capacity the card stays idle
+        for _ in 0..3 {
+            assert_eq!(send(&mut card, APP_CMD, 0), Some(R1_IDLE));
+            assert_eq!(send(&mut card, SD_SEND_OP_COND, 0), Some(R1_IDLE));
+        }
+        for _ in 0..2 {
+            send(&mut card, APP_CMD, 0);
+            send(&mut card, SD_SEND_OP_COND, HCS);
+        }
+        assert!(card.ready());
+        assert_eq!(send(&mut card, READ_OCR, 0), Some(0));
+        assert_eq!(receive(&mut card, 4), [0xC0, 0xFF, 0x80, 0x00]);
+
+        // Block addresses
+        assert_eq!(send(&mut card, READ_SINGLE_BLOCK, 3), Some(0));
+        assert_eq!(receive(&mut card, 2), [0xFF, START_BLOCK]);
+        assert_eq!(
+            receive(&mut card, BLOCK_SIZE),
+            image()[3 * BLOCK_SIZE..4 * BLOCK_SIZE]
+        );
+
+        // A corrupted command is rejected
+        for byte in [0x48, 0x00, 0x00, 0x01, 0xAA, 0x00] {
+            card.exchange(byte);
+        }
+        assert_eq!(receive(&mut card, 2), [0xFF, R1_CRC_ERROR]);
+    }
+
+    #[test]
+    fn test_via_bit_bang() {
+        // PB0 is SCK, PB1 MOSI, PB2 chip select and PB7 MISO
+        #[rustfmt::skip]
+        let program = [
+            0xA2, 0xFF,       // LDX #$FF
+            0x9A,             // TXS
+            0xA9, 0x04,       // LDA #CS
+            0x8D, 0x00, 0x60, // STA ORB
+            0xA9, 0x07,       // LDA #$07
+            0x8D, 0x02, 0x60, // STA DDRB
+            0xA9, 0x00,       // LDA #0
+            0x8D, 0x00, 0x60, // STA ORB
+            0xA2, 0x00,       // LDX #0 (CMD0)
+            0x20, 0x7B, 0xF0, // JSR command
+            0x8D, 0x00, 0x02, // STA $0200
+            0xA2, 0x06,       // init: LDX #6 (CMD55)
+            0x20, 0x7B, 0xF0, // JSR command
+            0xA2, 0x0C,       // LDX #12 (ACMD41)
+            0x20, 0x7B, 0xF0, // JSR command
+            0xC9, 0x00,       // CMP #0
+            0xD0, 0xF2,       // BNE init
+            0xA2, 0x12,       // LDX #18 (CMD17)
+            0x20, 0x7B, 0xF0, // JSR command
+            0xA9, 0xFF,       // token: LDA #$FF
+            0x20, 0x51, 0xF0, // JSR spi_byte
+            0xC9, 0xFE,       // CMP #START_BLOCK
+            0xD0, 0xF7,       // BNE token
+            0xA0, 0x00,       // LDY #0
+            0xA9, 0xFF,       // low: LDA #$FF
+            0x20, 0x51, 0xF0, // JSR spi_byte
+            0x99, 0x00, 0x03, // STA $0300,Y
+            0xC8,             // INY
+            0xD0, 0xF5,       // BNE low
+            0xA9, 0xFF,       // high: LDA #$FF
+            0x20, 0x51, 0xF0, // JSR spi_byte
+            0x99, 0x00, 0x04, // STA $0400,Y
+            0xC8,             // INY
+            0xD0, 0xF5,       // BNE high
+            0x4C, 0x4E, 0xF0, // JMP *
+            0x85, 0x00,       // spi_byte: STA $00
+            0xA9, 0x08,       // LDA #8
+            0x85, 0x01,       // STA $01
+            0x06, 0x00,       // bit: ASL $00
+            0xA9, 0x00,       // LDA #0
+            0x90, 0x02,       // BCC clock
+            0xA9, 0x02,       // LDA #MOSI
+            0x8D, 0x00, 0x60, // clock: STA ORB
+            0x09, 0x01,       // ORA #SCK
+            0x8D, 0x00, 0x60, // STA ORB
+            0xAD, 0x00, 0x60, // LDA ORB
+            0x0A,             // ASL A
+            0x90, 0x02,       // BCC zero
+            0xE6, 0x00,       // INC $00
+            0xA9, 0x00,       // zero: LDA #0
+            0x8D, 0x00, 0x60, // STA ORB
+            0xC6, 0x01,       // DEC $01
+            0xD0, 0xDF,       // BNE bit
+            0xA5, 0x00,       // LDA $00
+            0x60,             // RTS
+            0xA9, 0x06,       // command: LDA #6
+            0x85, 0x02,       // STA $02
+            0xBD, 0x94, 0xF0, // send: LDA commands,X
+            0x20, 0x51, 0xF0, // JSR spi_byte
+            0xE8,             // INX
+            0xC6, 0x02,       // DEC $02
+            0xD0, 0xF5,       // BNE send
+            0xA9, 0xFF,       // poll: LDA #$FF
+            0x20, 0x51, 0xF0, // JSR spi_byte
+            0xC9, 0xFF,       // CMP #$FF
+            0xF0, 0xF7,       // BEQ poll
+            0x60,             // RTS
+            0x40, 0x00, 0x00, 0x00, 0x00, 0x95, // commands: CMD0
+            0x77, 0x00, 0x00, 0x00, 0x00, 0x01, // CMD55
+            0x69, 0x40, 0x00, 0x00, 0x00, 0x01, // ACMD41
+            0x51, 0x00, 0x00, 0x02, 0x00, 0x01, // CMD17 $200
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+        let pins = SpiPins {
+            register: 0,
+            sck: 1 << 0,
+            mosi: 1 << 1,
+            miso: 1 << 7,
+            cs: 1 << 2,
+        };
+        let card = SdCard::new(Cursor::new(image())).unwrap();
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x3FFF)
+            .device(0x6000..=0x600F, SpiPort::new(Via6522::new(), card, pins))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+        machine.run_cycles(400_000).unwrap();
+
+        assert_eq!(machine.cpu().program_counter(), 0xF04E);
+        let bus = machine.bus();
+        assert_eq!(bus.read(0x0200).unwrap(), R1_IDLE);
+        let block: Vec<u8> = (0x0300..0x0500).map(|a| bus.read(a).unwrap()).collect();
+        assert_eq!(block, image()[BLOCK_SIZE..2 * BLOCK_SIZE]);
+    }
+}
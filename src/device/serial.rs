@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Host side of an emulated serial line.
+///
+/// Both calls must return immediately: the UART polls [`SerialBackend::receive`] once per
+/// character time and drops the byte if nothing is there yet.
+pub trait SerialBackend: 'static {
+    /// Next byte sent by the host, if one is waiting
+    fn receive(&mut self) -> Option<u8>;
+
+    /// Send a byte to the host
+    fn transmit(&mut self, byte: u8);
+}
+
+/// In-memory serial line, mostly for tests
+#[derive(Default)]
+pub struct BufferBackend {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+impl BufferBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue bytes for the emulated machine to receive
+    pub fn push_input(&mut self, bytes: &[u8]) {
+        self.input.extend(bytes);
+    }
+
+    /// Bytes transmitted by the emulated machine so far
+    #[inline]
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Take the bytes transmitted so far
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+}
+
+impl SerialBackend for BufferBackend {
+    fn receive(&mut self) -> Option<u8> {
+        self.input.pop_front()
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        self.output.push(byte);
+    }
+}
+
+/// Serial line connected to the process's standard input and output.
+///
+/// Standard input is read on a background thread so receiving never blocks. Line feeds
+/// from the terminal are turned into carriage returns, which is what monitor ROMs wait
+/// for at the end of a line.
+pub struct StdioBackend {
+    input: Receiver<u8>,
+}
+
+impl Default for StdioBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StdioBackend {
+    pub fn new() -> Self {
+        let (sender, input) = mpsc::channel();
+        thread::spawn(move || {
+            for byte in io::stdin().lock().bytes() {
+                let Ok(byte) = byte else { break };
+                let byte = if byte == b'\n' { b'\r' } else { byte };
+                if sender.send(byte).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { input }
+    }
+}
+
+impl SerialBackend for StdioBackend {
+    fn receive(&mut self) -> Option<u8> {
+        self.input.try_recv().ok()
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        let mut stdout = io::stdout().lock();
+        // A closed stdout has nobody left to read the output
+        let _ = stdout.write_all(&[byte]).and_then(|_| stdout.flush());
+    }
+}
+
+/// Serial line exposed as a Linux pseudo-terminal, so a terminal program such as
+/// `screen` or `minicom` can connect to [`PtyBackend::path`].
+#[cfg(target_os = "linux")]
+pub struct PtyBackend {
+    master: std::fs::File,
+    // Held open so the master does not report EIO before a terminal program attaches
+    _slave: std::fs::File,
+    path: std::path::PathBuf,
+    input: VecDeque<u8>,
+}
+
+#[cfg(target_os = "linux")]
+impl PtyBackend {
+    /// Allocate a new pseudo-terminal in raw mode
+    pub fn open() -> io::Result<Self> {
+        use std::ffi::CStr;
+        use std::os::fd::FromRawFd;
+
+        // SAFETY: plain libc calls on a descriptor we own; each result is checked, and
+        // the descriptor is handed to a File right away so it is closed on every path
+        let master = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_NONBLOCK);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let master = std::fs::File::from_raw_fd(fd);
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            master
+        };
+
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: the buffer outlives the call and its length is passed along
+        let result = unsafe {
+            use std::os::fd::AsRawFd;
+            libc::ptsname_r(master.as_raw_fd(), name.as_mut_ptr(), name.len())
+        };
+        if result != 0 {
+            return Err(io::Error::from_raw_os_error(result));
+        }
+        // SAFETY: ptsname_r succeeded, so the buffer holds a NUL-terminated string
+        let path = unsafe { CStr::from_ptr(name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+            .into();
+
+        let slave = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)?;
+        Self::make_raw(&slave)?;
+
+        Ok(Self {
+            master,
+            _slave: slave,
+            path,
+            input: VecDeque::new(),
+        })
+    }
+
+    /// Path of the terminal device to connect to
+    #[inline]
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Turn off echo and line editing, so bytes pass through unchanged
+    fn make_raw(terminal: &std::fs::File) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let fd = terminal.as_raw_fd();
+        // SAFETY: termios is plain data, filled in by tcgetattr before it is used
+        unsafe {
+            let mut termios = std::mem::zeroed::<libc::termios>();
+            if libc::tcgetattr(fd, &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            libc::cfmakeraw(&mut termios);
+            if libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl SerialBackend for PtyBackend {
+    fn receive(&mut self) -> Option<u8> {
+        if self.input.is_empty() {
+            let mut buffer = [0; 256];
+            if let Ok(length) = self.master.read(&mut buffer) {
+                self.input.extend(&buffer[..length]);
+            }
+        }
+        self.input.pop_front()
+    }
+
+    fn transmit(&mut self, byte: u8) {
+        // Bytes are dropped when the terminal program does not keep up, like on a wire
+        // without flow control
+        let _ = self.master.write(&[byte]);
+    }
+}
@@ -0,0 +1,657 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Downsampler;
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Register offsets, voice registers relative to the voice base at 7 * voice
+const FREQ_LO: usize = 0x00;
+const FREQ_HI: usize = 0x01;
+const PW_LO: usize = 0x02;
+const PW_HI: usize = 0x03;
+const CONTROL: usize = 0x04;
+const ATTACK_DECAY: usize = 0x05;
+const SUSTAIN_RELEASE: usize = 0x06;
+const FC_LO: u16 = 0x15;
+const FC_HI: u16 = 0x16;
+const RES_FILT: u16 = 0x17;
+const MODE_VOL: u16 = 0x18;
+const POT_X: u16 = 0x19;
+const POT_Y: u16 = 0x1A;
+const OSC3: u16 = 0x1B;
+const ENV3: u16 = 0x1C;
+
+// Voice control register bits
+const CONTROL_GATE: u8 = 1 << 0;
+const CONTROL_SYNC: u8 = 1 << 1;
+const CONTROL_RING: u8 = 1 << 2;
+const CONTROL_TEST: u8 = 1 << 3;
+const CONTROL_TRIANGLE: u8 = 1 << 4;
+const CONTROL_SAWTOOTH: u8 = 1 << 5;
+const CONTROL_PULSE: u8 = 1 << 6;
+const CONTROL_NOISE: u8 = 1 << 7;
+
+// Mode/volume register bits
+const MODE_LOW_PASS: u8 = 1 << 4;
+const MODE_BAND_PASS: u8 = 1 << 5;
+const MODE_HIGH_PASS: u8 = 1 << 6;
+const MODE_VOICE3_OFF: u8 = 1 << 7;
+
+const ACCUMULATOR_MSB: u32 = 1 << 23;
+const NOISE_CLOCK: u32 = 1 << 19;
+const NOISE_SEED: u32 = 0x7F_FFF8;
+
+/// Envelope rate counter periods in cycles for the 16 ADSR rate settings
+const RATE_PERIODS: [u16; 16] = [
+    9, 32, 63, 95, 149, 220, 267, 313, 392, 977, 1954, 3126, 3907, 11720, 19532, 31251,
+];
+
+/// DC level the 6581 mixer adds in proportion to the master volume, which is what
+/// makes sample playback through the volume register audible
+const MIXER_DC_6581: f32 = 0.3;
+
+/// Chip revision, which decides the filter curve and the mixer DC offset
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SidModel {
+    #[default]
+    Mos6581,
+    Mos8580,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum EnvelopeState {
+    Attack,
+    DecaySustain,
+    Release,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Voice {
+    frequency: u16,
+    pulse_width: u16,
+    control: u8,
+    attack_decay: u8,
+    sustain_release: u8,
+    accumulator: u32,
+    msb_rising: bool,
+    shift_register: u32,
+    envelope: u8,
+    state: EnvelopeState,
+    rate_counter: u16,
+    exponential_counter: u8,
+}
+
+impl Voice {
+    fn new() -> Self {
+        Self {
+            frequency: 0,
+            pulse_width: 0,
+            control: 0,
+            attack_decay: 0,
+            sustain_release: 0,
+            accumulator: 0,
+            msb_rising: false,
+            shift_register: NOISE_SEED,
+            envelope: 0,
+            state: EnvelopeState::Release,
+            rate_counter: 0,
+            exponential_counter: 0,
+        }
+    }
+
+    fn write_control(&mut self, value: u8) {
+        let gate_changed = (self.control ^ value) & CONTROL_GATE != 0;
+        if gate_changed {
+            self.state = if value & CONTROL_GATE != 0 {
+                EnvelopeState::Attack
+            } else {
+                EnvelopeState::Release
+            };
+        }
+        if value & CONTROL_TEST != 0 {
+            self.accumulator = 0;
+            self.shift_register = NOISE_SEED;
+        }
+        self.control = value;
+    }
+
+    fn clock_oscillator(&mut self) {
+        if self.control & CONTROL_TEST != 0 {
+            self.msb_rising = false;
+            return;
+        }
+        let previous = self.accumulator;
+        self.accumulator = (self.accumulator + self.frequency as u32) & 0xFF_FFFF;
+        self.msb_rising =
+            previous & ACCUMULATOR_MSB == 0 && self.accumulator & ACCUMULATOR_MSB != 0;
+        if previous & NOISE_CLOCK == 0 && self.accumulator & NOISE_CLOCK != 0 {
+            let bit = (self.shift_register >> 22 ^ self.shift_register >> 17) & 1;
+            self.shift_register = (self.shift_register << 1 | bit) & 0x7F_FFFF;
+        }
+    }
+
+    /// 12-bit waveform output, ring modulated by the accumulator of `source`
+    fn waveform(&self, source_accumulator: u32) -> u16 {
+        let waveforms = self.control & 0xF0;
+        if waveforms == 0 {
+            return 0;
+        }
+        let mut output = 0xFFF;
+        if waveforms & CONTROL_TRIANGLE != 0 {
+            let mut msb = self.accumulator & ACCUMULATOR_MSB;
+            if self.control & CONTROL_RING != 0 {
+                msb ^= source_accumulator & ACCUMULATOR_MSB;
+            }
+            let folded = if msb != 0 {
+                !self.accumulator
+            } else {
+                self.accumulator
+            };
+            output &= (folded >> 11) as u16 & 0xFFF;
+        }
+        if waveforms & CONTROL_SAWTOOTH != 0 {
+            output &= (self.accumulator >> 12) as u16;
+        }
+        if waveforms & CONTROL_PULSE != 0
+            && self.control & CONTROL_TEST == 0
+            && (self.accumulator >> 12) < self.pulse_width as u32
+        {
+            output = 0;
+        }
+        if waveforms & CONTROL_NOISE != 0 {
+            output &= self.noise();
+        }
+        output
+    }
+
+    fn noise(&self) -> u16 {
+        let register = self.shift_register;
+        (((register & 0x40_0000) >> 11)
+            | ((register & 0x10_0000) >> 10)
+            | ((register & 0x01_0000) >> 7)
+            | ((register & 0x00_2000) >> 5)
+            | ((register & 0x00_0800) >> 4)
+            | ((register & 0x00_0080) >> 1)
+            | ((register & 0x00_0010) << 1)
+            | ((register & 0x00_0004) << 2)) as u16
+    }
+
+    fn clock_envelope(&mut self) {
+        let rate = match self.state {
+            EnvelopeState::Attack => self.attack_decay >> 4,
+            EnvelopeState::DecaySustain => self.attack_decay & 0x0F,
+            EnvelopeState::Release => self.sustain_release & 0x0F,
+        };
+        // The counter is only compared for equality, so lowering the rate below the
+        // current count makes it wrap through 15 bits first: the ADSR delay bug
+        self.rate_counter = (self.rate_counter + 1) & 0x7FFF;
+        if self.rate_counter != RATE_PERIODS[rate as usize] {
+            return;
+        }
+        self.rate_counter = 0;
+
+        if self.state == EnvelopeState::Attack {
+            self.exponential_counter = 0;
+            self.envelope = self.envelope.saturating_add(1);
+            if self.envelope == 0xFF {
+                self.state = EnvelopeState::DecaySustain;
+            }
+            return;
+        }
+        self.exponential_counter += 1;
+        if self.exponential_counter < exponential_period(self.envelope) {
+            return;
+        }
+        self.exponential_counter = 0;
+        let sustain = (self.sustain_release >> 4) * 0x11;
+        if self.state == EnvelopeState::DecaySustain && self.envelope == sustain {
+            return;
+        }
+        self.envelope = self.envelope.saturating_sub(1);
+    }
+}
+
+/// Extra divider applied to decay and release, approximating an exponential curve
+fn exponential_period(envelope: u8) -> u8 {
+    match envelope {
+        0x5E..=0xFF => 1,
+        0x37..=0x5D => 2,
+        0x1B..=0x36 => 4,
+        0x0F..=0x1A => 8,
+        0x07..=0x0E => 16,
+        0x01..=0x06 => 30,
+        0x00 => 1,
+    }
+}
+
+/// MOS 6581 / 8580 Sound Interface Device.
+///
+/// Each voice has a 24-bit phase accumulator and a 23-bit noise LFSR, and produces the
+/// triangle, sawtooth, pulse and noise waveforms with sync and ring modulation.
+/// Combined waveforms are the AND of the selected waveforms, which is close to the
+/// 8580 and somewhat brighter than the 6581. The envelope generators use the chip's
+/// rate counter periods, exponential decay steps and 15-bit rate counter, so the ADSR
+/// delay bug happens as on the real chip.
+///
+/// The filter is a state-variable filter run every cycle. Its cutoff follows an
+/// approximation of each model's curve: linear up to about 12.5 kHz on the 8580, and a
+/// flat start followed by a steep rise on the 6581. The 6581 also has the mixer DC
+/// offset that makes volume register samples audible.
+///
+/// The output is averaged down to PCM at the sample rate given to [`Sid6581::new`],
+/// and the samples collect until [`Sid6581::take_samples`] is called.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Sid6581 {
+    model: SidModel,
+    clock_hz: u32,
+    voices: [Voice; 3],
+    filter_cutoff: u16,
+    resonance_filter: u8,
+    mode_volume: u8,
+    cutoff_coefficient: f32,
+    damping: f32,
+    low_pass: f32,
+    band_pass: f32,
+    pot_x: u8,
+    pot_y: u8,
+    bus_value: u8,
+    downsampler: Downsampler,
+    #[serde(skip)]
+    samples: Vec<i16>,
+}
+
+impl Sid6581 {
+    /// Create a SID clocked at `clock_hz`, producing `sample_rate` samples per second
+    pub fn new(model: SidModel, clock_hz: u32, sample_rate: u32) -> Self {
+        let mut sid = Self {
+            model,
+            clock_hz,
+            voices: [Voice::new(), Voice::new(), Voice::new()],
+            filter_cutoff: 0,
+            resonance_filter: 0,
+            mode_volume: 0,
+            cutoff_coefficient: 0.0,
+            damping: 0.0,
+            low_pass: 0.0,
+            band_pass: 0.0,
+            pot_x: 0xFF,
+            pot_y: 0xFF,
+            bus_value: 0,
+            downsampler: Downsampler::new(clock_hz, sample_rate),
+            samples: Vec::new(),
+        };
+        sid.update_filter();
+        sid
+    }
+
+    #[inline]
+    pub fn model(&self) -> SidModel {
+        self.model
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.downsampler.sample_rate()
+    }
+
+    /// Samples rendered so far
+    #[inline]
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Take the samples rendered so far
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+
+    /// Set the values read from the paddle registers
+    pub fn set_pots(&mut self, x: u8, y: u8) {
+        self.pot_x = x;
+        self.pot_y = y;
+    }
+
+    /// Cutoff frequency in Hz for the current filter register value
+    pub fn cutoff_hz(&self) -> f32 {
+        let x = self.filter_cutoff as f32 / 2047.0;
+        match self.model {
+            SidModel::Mos6581 => 220.0 + 17_780.0 * x.powf(2.5),
+            SidModel::Mos8580 => 30.0 + 12_470.0 * x,
+        }
+    }
+
+    fn update_filter(&mut self) {
+        let cutoff = self.cutoff_hz().min(self.clock_hz as f32 / 8.0);
+        self.cutoff_coefficient =
+            2.0 * (std::f32::consts::PI * cutoff / self.clock_hz as f32).sin();
+        let resonance = (self.resonance_filter >> 4) as f32 / 15.0;
+        let q = match self.model {
+            SidModel::Mos6581 => 0.707 + resonance,
+            SidModel::Mos8580 => 0.707 + 1.6 * resonance,
+        };
+        self.damping = 1.0 / q;
+    }
+
+    fn cycle(&mut self) {
+        for voice in &mut self.voices {
+            voice.clock_oscillator();
+            voice.clock_envelope();
+        }
+        // Each voice is synced by the one before it
+        for index in 0..3 {
+            let source = (index + 2) % 3;
+            if self.voices[source].msb_rising && self.voices[index].control & CONTROL_SYNC != 0 {
+                self.voices[index].accumulator = 0;
+            }
+        }
+
+        let mut direct = 0.0;
+        let mut filtered = 0.0;
+        for index in 0..3 {
+            let voice = &self.voices[index];
+            let source = self.voices[(index + 2) % 3].accumulator;
+            let level =
+                (voice.waveform(source) as f32 - 2048.0) / 2048.0 * (voice.envelope as f32 / 255.0);
+            if self.resonance_filter & (1 << index) != 0 {
+                filtered += level;
+            } else if index != 2 || self.mode_volume & MODE_VOICE3_OFF == 0 {
+                direct += level;
+            }
+        }
+
+        let high_pass = filtered - self.damping * self.band_pass - self.low_pass;
+        self.band_pass += self.cutoff_coefficient * high_pass;
+        self.low_pass += self.cutoff_coefficient * self.band_pass;
+        let mut output = direct;
+        if self.mode_volume & MODE_LOW_PASS != 0 {
+            output += self.low_pass;
+        }
+        if self.mode_volume & MODE_BAND_PASS != 0 {
+            output += self.band_pass;
+        }
+        if self.mode_volume & MODE_HIGH_PASS != 0 {
+            output += high_pass;
+        }
+        if self.model == SidModel::Mos6581 {
+            output += MIXER_DC_6581;
+        }
+        let volume = (self.mode_volume & 0x0F) as f32 / 15.0;
+        // Three full-scale voices and some resonance headroom
+        if let Some(sample) = self.downsampler.push(output * volume / 4.0) {
+            self.samples.push(sample);
+        }
+    }
+}
+
+impl Device for Sid6581 {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        let voice3 = &self.voices[2];
+        Ok(match offset & 0x1F {
+            POT_X => self.pot_x,
+            POT_Y => self.pot_y,
+            OSC3 => (voice3.waveform(self.voices[1].accumulator) >> 4) as u8,
+            ENV3 => voice3.envelope,
+            // Write-only registers read back whatever was last on the data bus
+            _ => self.bus_value,
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        self.bus_value = value;
+        let register = offset & 0x1F;
+        match register {
+            0x00..=0x14 => {
+                let voice = &mut self.voices[register as usize / 7];
+                match register as usize % 7 {
+                    FREQ_LO => voice.frequency = (voice.frequency & 0xFF00) | value as u16,
+                    FREQ_HI => voice.frequency = (voice.frequency & 0x00FF) | (value as u16) << 8,
+                    PW_LO => voice.pulse_width = (voice.pulse_width & 0x0F00) | value as u16,
+                    PW_HI => {
+                        voice.pulse_width =
+                            (voice.pulse_width & 0x00FF) | (value as u16 & 0x0F) << 8
+                    }
+                    CONTROL => voice.write_control(value),
+                    ATTACK_DECAY => voice.attack_decay = value,
+                    SUSTAIN_RELEASE => voice.sustain_release = value,
+                    _ => unreachable!("voice registers are seven apart"),
+                }
+            }
+            FC_LO => {
+                self.filter_cutoff = (self.filter_cutoff & 0x7F8) | (value as u16 & 0x07);
+                self.update_filter();
+            }
+            FC_HI => {
+                self.filter_cutoff = (self.filter_cutoff & 0x007) | (value as u16) << 3;
+                self.update_filter();
+            }
+            RES_FILT => {
+                self.resonance_filter = value;
+                self.update_filter();
+            }
+            MODE_VOL => self.mode_volume = value,
+            // The read-only registers ignore writes
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn reset(&mut self) {
+        let samples = std::mem::take(&mut self.samples);
+        *self = Self {
+            samples,
+            pot_x: self.pot_x,
+            pot_y: self.pot_y,
+            ..Self::new(self.model, self.clock_hz, self.sample_rate())
+        };
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let samples = std::mem::take(&mut self.samples);
+        *self = bincode::deserialize(state)?;
+        self.samples = samples;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK_HZ: u32 = 985_248;
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn voice_register(voice: u16, register: usize) -> u16 {
+        voice * 7 + register as u16
+    }
+
+    #[test]
+    fn test_oscillator_readback_and_waveforms() {
+        let mut sid = Sid6581::new(SidModel::Mos8580, CLOCK_HZ, SAMPLE_RATE);
+        sid.write(voice_register(2, FREQ_HI), 0x10).unwrap();
+        sid.write(voice_register(2, CONTROL), CONTROL_SAWTOOTH)
+            .unwrap();
+        sid.tick(0x100);
+        // 0x1000 per cycle: the accumulator is at 0x100000 after 256 cycles
+        assert_eq!(sid.read(OSC3).unwrap(), 0x10);
+
+        sid.write(voice_register(2, PW_HI), 0x08).unwrap();
+        sid.write(voice_register(2, CONTROL), CONTROL_PULSE)
+            .unwrap();
+        assert_eq!(sid.read(OSC3).unwrap(), 0x00);
+        sid.tick(0x700);
+        assert_eq!(sid.read(OSC3).unwrap(), 0xFF);
+
+        // Triangle peaks at the MSB, and ring modulation flips it with voice 2's MSB
+        sid.write(voice_register(2, CONTROL), CONTROL_TRIANGLE)
+            .unwrap();
+        assert_eq!(sid.read(OSC3).unwrap(), 0xFF);
+        sid.write(voice_register(2, CONTROL), CONTROL_TEST).unwrap();
+        sid.write(voice_register(1, FREQ_HI), 0x80).unwrap();
+        sid.tick(0x100);
+        sid.write(voice_register(2, CONTROL), CONTROL_TRIANGLE)
+            .unwrap();
+        assert_eq!(sid.read(OSC3).unwrap(), 0x00);
+        sid.write(voice_register(2, CONTROL), CONTROL_TRIANGLE | CONTROL_RING)
+            .unwrap();
+        assert_eq!(sid.read(OSC3).unwrap(), 0xFF);
+
+        // Combined sawtooth and triangle, and test bit holding everything at zero
+        sid.write(voice_register(2, CONTROL), CONTROL_TEST).unwrap();
+        sid.write(
+            voice_register(2, CONTROL),
+            CONTROL_SAWTOOTH | CONTROL_TRIANGLE,
+        )
+        .unwrap();
+        sid.tick(0x600);
+        // $600 AND $C00
+        assert_eq!(sid.read(OSC3).unwrap(), 0x40);
+        sid.write(voice_register(2, CONTROL), CONTROL_TEST | CONTROL_SAWTOOTH)
+            .unwrap();
+        sid.tick(100);
+        assert_eq!(sid.read(OSC3).unwrap(), 0x00);
+
+        // Noise reads back the LFSR, which only moves as bit 19 rises
+        sid.write(voice_register(2, CONTROL), CONTROL_NOISE)
+            .unwrap();
+        let first = sid.read(OSC3).unwrap();
+        assert_eq!(first, 0xFE);
+        sid.tick(0x180);
+        assert_eq!(sid.read(OSC3).unwrap(), 0xFC);
+    }
+
+    #[test]
+    fn test_envelope_and_delay_bug() {
+        let mut sid = Sid6581::new(SidModel::Mos6581, CLOCK_HZ, SAMPLE_RATE);
+        sid.write(voice_register(2, ATTACK_DECAY), 0x00).unwrap();
+        sid.write(voice_register(2, SUSTAIN_RELEASE), 0x80).unwrap();
+        sid.write(voice_register(2, CONTROL), CONTROL_GATE).unwrap();
+        sid.tick(9 * 0xFF - 1);
+        assert_eq!(sid.read(ENV3).unwrap(), 0xFE);
+        sid.tick(1);
+        assert_eq!(sid.read(ENV3).unwrap(), 0xFF);
+        // Decay at rate 0 to the sustain level $88: 9 cycles a step at first
+        sid.tick(9 * 0x22);
+        assert_eq!(sid.read(ENV3).unwrap(), 0xDD);
+        sid.tick(100_000);
+        assert_eq!(sid.read(ENV3).unwrap(), 0x88);
+
+        // Release at the slowest rate, then switch to the fastest mid-count
+        sid.write(voice_register(2, SUSTAIN_RELEASE), 0x0F).unwrap();
+        sid.write(voice_register(2, CONTROL), 0).unwrap();
+        sid.tick(20_000);
+        assert_eq!(sid.read(ENV3).unwrap(), 0x88);
+        sid.write(voice_register(2, SUSTAIN_RELEASE), 0x00).unwrap();
+        // The counter has to wrap past $7FFF before the rate 0 period can match
+        sid.tick(32_768 - 20_000 - 9);
+        assert_eq!(sid.read(ENV3).unwrap(), 0x88);
+        sid.tick(9 * 2 + 1);
+        assert_eq!(sid.read(ENV3).unwrap(), 0x87);
+    }
+
+    #[test]
+    fn test_filter_and_sync() {
+        // A high voice through the low-pass filter comes out much quieter than direct
+        let rms = |filter: u8| {
+            let mut sid = Sid6581::new(SidModel::Mos8580, CLOCK_HZ, SAMPLE_RATE);
+            sid.write(voice_register(0, FREQ_HI), 0xC0).unwrap();
+            sid.write(voice_register(0, ATTACK_DECAY), 0x00).unwrap();
+            sid.write(voice_register(0, SUSTAIN_RELEASE), 0xF0).unwrap();
+            sid.write(voice_register(0, CONTROL), CONTROL_SAWTOOTH | CONTROL_GATE)
+                .unwrap();
+            sid.write(FC_HI, 0x10).unwrap();
+            sid.write(RES_FILT, filter).unwrap();
+            sid.write(MODE_VOL, MODE_LOW_PASS | 0x0F).unwrap();
+            sid.tick(CLOCK_HZ / 10);
+            let samples = &sid.samples()[2_000..];
+            let sum: f64 = samples.iter().map(|&s| (s as f64).powi(2)).sum();
+            (sum / samples.len() as f64).sqrt()
+        };
+        assert!(rms(0x01) * 4.0 < rms(0x00));
+
+        // Hard sync resets voice 3 whenever voice 2 wraps
+        let mut sid = Sid6581::new(SidModel::Mos8580, CLOCK_HZ, SAMPLE_RATE);
+        sid.write(voice_register(1, FREQ_HI), 0x40).unwrap();
+        sid.write(voice_register(2, FREQ_HI), 0x01).unwrap();
+        sid.write(voice_register(2, CONTROL), CONTROL_SAWTOOTH | CONTROL_SYNC)
+            .unwrap();
+        sid.tick(0x200);
+        assert_eq!(sid.read(OSC3).unwrap(), 0x00);
+        sid.tick(0x100);
+        assert_eq!(sid.read(OSC3).unwrap(), 0x01);
+        assert_eq!(sid.cutoff_hz(), 30.0);
+    }
+
+    #[test]
+    fn test_waveform_values() {
+        let waveform = |control: u8, accumulator: u32| {
+            let mut voice = Voice::new();
+            voice.control = control;
+            voice.pulse_width = 0x800;
+            voice.accumulator = accumulator;
+            voice.waveform(0)
+        };
+        assert_eq!(waveform(CONTROL_SAWTOOTH, 0x12_3456), 0x123);
+        assert_eq!(waveform(CONTROL_SAWTOOTH, 0xFF_FFFF), 0xFFF);
+        // The triangle rises over the lower half and falls over the upper half
+        assert_eq!(waveform(CONTROL_TRIANGLE, 0x00_0000), 0x000);
+        assert_eq!(waveform(CONTROL_TRIANGLE, 0x40_0000), 0x800);
+        assert_eq!(waveform(CONTROL_TRIANGLE, 0x7F_FFFF), 0xFFF);
+        assert_eq!(waveform(CONTROL_TRIANGLE, 0xC0_0000), 0x7FF);
+        // The pulse is high once the top 12 bits reach the pulse width
+        assert_eq!(waveform(CONTROL_PULSE, 0x7F_F000), 0x000);
+        assert_eq!(waveform(CONTROL_PULSE, 0x80_0000), 0xFFF);
+        assert_eq!(waveform(CONTROL_PULSE | CONTROL_TEST, 0), 0xFFF);
+        // Bits 22, 20, 16, 13, 11, 7, 4 and 2 of the seed, of which only bit 2 is clear
+        assert_eq!(waveform(CONTROL_NOISE, 0), 0xFE0);
+        assert_eq!(
+            waveform(CONTROL_SAWTOOTH | CONTROL_TRIANGLE, 0x60_0000),
+            0x400
+        );
+        assert_eq!(waveform(0, 0x12_3456), 0x000);
+
+        // Ring modulation flips the triangle with the source's MSB
+        let mut voice = Voice::new();
+        voice.control = CONTROL_TRIANGLE | CONTROL_RING;
+        voice.accumulator = 0x40_0000;
+        assert_eq!(voice.waveform(0), 0x800);
+        assert_eq!(voice.waveform(ACCUMULATOR_MSB), 0x7FF);
+    }
+
+    #[test]
+    fn test_envelope_rate_periods() {
+        for (rate, &period) in RATE_PERIODS.iter().enumerate() {
+            let mut sid = Sid6581::new(SidModel::Mos6581, CLOCK_HZ, SAMPLE_RATE);
+            sid.write(
+                voice_register(2, ATTACK_DECAY),
+                (rate as u8) << 4 | rate as u8,
+            )
+            .unwrap();
+            sid.write(voice_register(2, CONTROL), CONTROL_GATE).unwrap();
+            // Attack steps once per period
+            sid.tick(period as u32 - 1);
+            assert_eq!(sid.read(ENV3).unwrap(), 0x00, "attack rate {rate}");
+            sid.tick(1);
+            assert_eq!(sid.read(ENV3).unwrap(), 0x01, "attack rate {rate}");
+            sid.tick(period as u32);
+            assert_eq!(sid.read(ENV3).unwrap(), 0x02, "attack rate {rate}");
+
+            // Decay from the peak steps at the same rate while the envelope is high
+            sid.tick(period as u32 * 0xFD);
+            assert_eq!(sid.read(ENV3).unwrap(), 0xFF, "attack rate {rate}");
+            sid.tick(period as u32 - 1);
+            assert_eq!(sid.read(ENV3).unwrap(), 0xFF, "decay rate {rate}");
+            sid.tick(1);
+            assert_eq!(sid.read(ENV3).unwrap(), 0xFE, "decay rate {rate}");
+        }
+    }
+}
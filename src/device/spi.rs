@@ -0,0 +1,354 @@
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+/// A peripheral on an SPI bus, seen a byte at a time.
+///
+/// Bytes are exchanged in full duplex, so the byte the device sends has to be known
+/// before the byte it receives: [`SpiDevice::shift_out`] is asked for it when the
+/// controller starts clocking, and [`SpiDevice::shift_in`] gets the received byte once
+/// all eight bits are in.
+pub trait SpiDevice: 'static {
+    /// Chip select asserted or released
+    fn select(&mut self, _selected: bool) {}
+
+    /// Byte to send during the next transfer
+    fn shift_out(&mut self) -> u8;
+
+    /// Byte received in a complete transfer
+    fn shift_in(&mut self, value: u8);
+
+    /// One complete transfer, returning the byte the device sent
+    fn exchange(&mut self, value: u8) -> u8 {
+        let output = self.shift_out();
+        self.shift_in(value);
+        output
+    }
+
+    /// Encode the device state for a save state
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(Vec::new())
+    }
+
+    /// Restore a state produced by [`SpiDevice::save_state`]
+    fn load_state(&mut self, _state: &[u8]) -> Result<(), StateError> {
+        Ok(())
+    }
+}
+
+/// Pin-level SPI bus in mode 0 (clock idle low, data sampled on the rising edge), most
+/// significant bit first, with an active-low chip select.
+///
+/// MOSI is sampled on each rising edge of SCK and MISO changes after each falling edge.
+/// While chip select is high the device leaves MISO floating.
+pub struct SpiBus<S: SpiDevice> {
+    device: S,
+    selected: bool,
+    sck: bool,
+    bits: u8,
+    received: u8,
+    /// Byte being sent, fetched from the device when it is first needed
+    sending: Option<u8>,
+}
+
+impl<S: SpiDevice> SpiBus<S> {
+    pub fn new(device: S) -> Self {
+        Self {
+            device,
+            selected: false,
+            sck: false,
+            bits: 0,
+            received: 0,
+            sending: None,
+        }
+    }
+
+    #[inline]
+    pub fn device(&self) -> &S {
+        &self.device
+    }
+
+    #[inline]
+    pub fn device_mut(&mut self) -> &mut S {
+        &mut self.device
+    }
+
+    #[inline]
+    pub fn selected(&self) -> bool {
+        self.selected
+    }
+
+    /// Drive the chip select, clock and MOSI lines
+    pub fn set_pins(&mut self, cs: bool, sck: bool, mosi: bool) {
+        let rising = !self.sck && sck;
+        let falling = self.sck && !sck;
+        self.sck = sck;
+
+        if self.selected == cs {
+            self.selected = !cs;
+            self.bits = 0;
+            self.sending = None;
+            self.device.select(self.selected);
+        }
+        if !self.selected {
+            return;
+        }
+        if rising {
+            self.fetch();
+            self.received = self.received << 1 | mosi as u8;
+            self.bits += 1;
+            if self.bits == 8 {
+                self.device.shift_in(self.received);
+            }
+        } else if falling {
+            if self.bits == 8 {
+                self.bits = 0;
+                self.sending = None;
+            } else if let Some(sending) = &mut self.sending {
+                *sending <<= 1;
+            }
+        }
+    }
+
+    /// Level on MISO, or `None` while the device is not selected
+    pub fn miso(&mut self) -> Option<bool> {
+        if !self.selected {
+            return None;
+        }
+        Some(self.fetch() & 0x80 != 0)
+    }
+
+    fn fetch(&mut self) -> u8 {
+        *self.sending.get_or_insert_with(|| self.device.shift_out())
+    }
+}
+
+/// SPI lines wired to bits of a port register, each given as a one-bit mask
+#[derive(Clone, Copy, Debug)]
+pub struct SpiPins {
+    /// Offset of the port register within the wrapped device
+    pub register: u16,
+    pub sck: u8,
+    pub mosi: u8,
+    pub miso: u8,
+    /// Active-low chip select
+    pub cs: u8,
+}
+
+/// A bus-mapped device, typically a VIA or PIA, with an SPI device bit-banged through
+/// one of its port registers.
+///
+/// Every write to the port register drives CS, SCK and MOSI from the written bits, and
+/// reads of it return the MISO level in the MISO bit while the SPI device is selected.
+/// The register is taken to hold the pin levels, so the bits used have to be set up as
+/// outputs (MISO as an input) before they are driven. Everything else goes to the
+/// wrapped device, and wrappers can be nested to put several SPI devices on one port.
+pub struct SpiPort<D: Device, S: SpiDevice> {
+    inner: D,
+    bus: SpiBus<S>,
+    pins: SpiPins,
+}
+
+impl<D: Device, S: SpiDevice> SpiPort<D, S> {
+    pub fn new(inner: D, device: S, pins: SpiPins) -> Self {
+        Self {
+            inner,
+            bus: SpiBus::new(device),
+            pins,
+        }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn bus(&self) -> &SpiBus<S> {
+        &self.bus
+    }
+
+    #[inline]
+    pub fn bus_mut(&mut self) -> &mut SpiBus<S> {
+        &mut self.bus
+    }
+}
+
+impl<D: Device, S: SpiDevice> Device for SpiPort<D, S> {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        let value = self.inner.read(offset)?;
+        if offset != self.pins.register {
+            return Ok(value);
+        }
+        Ok(match self.bus.miso() {
+            Some(true) => value | self.pins.miso,
+            Some(false) => value & !self.pins.miso,
+            None => value,
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        self.inner.write(offset, value)?;
+        if offset == self.pins.register {
+            self.bus.set_pins(
+                value & self.pins.cs != 0,
+                value & self.pins.sck != 0,
+                value & self.pins.mosi != 0,
+            );
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.inner.tick(cycles);
+    }
+
+    fn irq(&self) -> bool {
+        self.inner.irq()
+    }
+
+    fn nmi(&self) -> bool {
+        self.inner.nmi()
+    }
+
+    fn dma_request(&self) -> Option<u16> {
+        self.inner.dma_request()
+    }
+
+    fn dma_complete(&mut self, value: u8) -> u32 {
+        self.inner.dma_complete(value)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        let bus = &self.bus;
+        Ok(bincode::serialize(&(
+            self.inner.save_state()?,
+            bus.device.save_state()?,
+            bus.selected,
+            bus.sck,
+            bus.bits,
+            bus.received,
+            bus.sending,
+        ))?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        type State = (Vec<u8>, Vec<u8>, bool, bool, u8, u8, Option<u8>);
+        let (inner, device, selected, sck, bits, received, sending): State =
+            bincode::deserialize(state)?;
+        self.inner.load_state(&inner)?;
+        let bus = &mut self.bus;
+        bus.device.load_state(&device)?;
+        (bus.selected, bus.sck, bus.bits, bus.received, bus.sending) =
+            (selected, sck, bits, received, sending);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends back each byte it received, starting with 0xA5
+    struct Echo {
+        last: u8,
+        selects: u32,
+    }
+
+    impl SpiDevice for Echo {
+        fn select(&mut self, selected: bool) {
+            self.selects += selected as u32;
+        }
+
+        fn shift_out(&mut self) -> u8 {
+            self.last
+        }
+
+        fn shift_in(&mut self, value: u8) {
+            self.last = value;
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>, StateError> {
+            Ok(vec![self.last])
+        }
+
+        fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+            self.last = state[0];
+            Ok(())
+        }
+    }
+
+    /// Output latch with nothing else on it
+    struct Latch(u8);
+
+    impl Device for Latch {
+        fn read(&mut self, _: u16) -> Result<u8, BusError> {
+            Ok(self.0)
+        }
+
+        fn write(&mut self, _: u16, value: u8) -> Result<(), BusError> {
+            self.0 = value;
+            Ok(())
+        }
+    }
+
+    const SCK: u8 = 1 << 0;
+    const MOSI: u8 = 1 << 1;
+    const CS: u8 = 1 << 2;
+    const MISO: u8 = 1 << 7;
+
+    /// Bit-bang one byte the way a 6502 driver does
+    fn exchange(port: &mut SpiPort<Latch, Echo>, value: u8) -> u8 {
+        let mut received = 0;
+        for bit in (0..8).rev() {
+            let mosi = if value >> bit & 1 != 0 { MOSI } else { 0 };
+            port.write(0, mosi).unwrap();
+            port.write(0, mosi | SCK).unwrap();
+            received = received << 1 | (port.read(0).unwrap() & MISO != 0) as u8;
+        }
+        port.write(0, 0).unwrap();
+        received
+    }
+
+    #[test]
+    fn test_port_bit_bang() {
+        let pins = SpiPins {
+            register: 0,
+            sck: SCK,
+            mosi: MOSI,
+            miso: MISO,
+            cs: CS,
+        };
+        let echo = Echo {
+            last: 0xA5,
+            selects: 0,
+        };
+        let mut port = SpiPort::new(Latch(0), echo, pins);
+        port.write(0, CS).unwrap();
+        // MISO floats while deselected
+        assert_eq!(port.read(0).unwrap(), CS);
+        assert_eq!(exchange(&mut port, 0x3C), 0xA5);
+        assert_eq!(exchange(&mut port, 0x81), 0x3C);
+        assert_eq!(port.bus().device().last, 0x81);
+
+        // Deselecting mid-byte drops the partial byte
+        port.write(0, MOSI | SCK).unwrap();
+        port.write(0, CS).unwrap();
+        assert!(!port.bus().selected());
+        assert_eq!(exchange(&mut port, 0x42), 0x81);
+        assert_eq!(port.bus().device().selects, 2);
+
+        let state = port.save_state().unwrap();
+        exchange(&mut port, 0x00);
+        port.load_state(&state).unwrap();
+        assert_eq!(exchange(&mut port, 0x00), 0x42);
+    }
+}
@@ -0,0 +1,685 @@
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+use crate::video;
+
+const VRAM_SIZE: usize = 0x4000;
+
+// Port offsets, selected by the MODE pin on A0
+const DATA_PORT: u16 = 0;
+
+// Register 0 and 1 bits
+const R0_M3: u8 = 1 << 1;
+const R1_ENABLE: u8 = 1 << 6;
+const R1_IE: u8 = 1 << 5;
+const R1_M1: u8 = 1 << 4;
+const R1_M2: u8 = 1 << 3;
+const R1_SIZE: u8 = 1 << 1;
+const R1_MAG: u8 = 1 << 0;
+
+// Status register bits
+const STATUS_INT: u8 = 1 << 7;
+const STATUS_FIFTH: u8 = 1 << 6;
+const STATUS_COLLISION: u8 = 1 << 5;
+
+/// NTSC timing: a 5.37 MHz dot clock, 342 dots per line and 262 lines per frame
+const DOT_CLOCK_HZ: u64 = 5_369_318;
+const DOTS_PER_LINE: u16 = 342;
+const LINES_PER_FRAME: u16 = 262;
+
+/// Sprite Y that ends the attribute table
+const SPRITE_TERMINATOR: u8 = 0xD0;
+const SPRITES_PER_LINE: usize = 4;
+
+/// RGB values of the 16 colors; color 0 is transparent and shows as black
+const PALETTE: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00],
+    [0x21, 0xC8, 0x42],
+    [0x5E, 0xDC, 0x78],
+    [0x54, 0x55, 0xED],
+    [0x7D, 0x76, 0xFC],
+    [0xD4, 0x52, 0x4D],
+    [0x42, 0xEB, 0xF5],
+    [0xFC, 0x55, 0x54],
+    [0xFF, 0x79, 0x78],
+    [0xD4, 0xC1, 0x54],
+    [0xE6, 0xCE, 0x80],
+    [0x21, 0xB0, 0x3B],
+    [0xC9, 0x5B, 0xBA],
+    [0xCC, 0xCC, 0xCC],
+    [0xFF, 0xFF, 0xFF],
+];
+
+/// Screen mode selected by M1-M3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Graphics1,
+    Graphics2,
+    Multicolor,
+    Text,
+}
+
+/// Texas Instruments TMS9918A video display processor with 16K of VRAM, NTSC timing.
+///
+/// Map it over two addresses: the data port at even offsets and the control port at
+/// odd ones. The frame is rendered a scanline at a time as the dot clock passes the
+/// end of each active line, so register changes between lines show up where they
+/// happen. Only the 256x192 active area is rendered, as RGB triples in
+/// [`Tms9918::frame`]; the border is left out. VRAM is always addressed linearly, as
+/// with the 4K/16K bit set, and CPU accesses are never delayed by the display.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tms9918 {
+    cpu_clock_hz: u32,
+    vram: Vec<u8>,
+    registers: [u8; 8],
+    status: u8,
+    address: u16,
+    read_buffer: u8,
+    latch: Option<u8>,
+    phase: u64,
+    dot: u16,
+    line: u16,
+    frames: u64,
+    #[serde(skip)]
+    framebuffer: Vec<u8>,
+}
+
+impl Tms9918 {
+    pub const WIDTH: usize = 256;
+    pub const HEIGHT: usize = 192;
+
+    /// VDP next to a CPU at `cpu_clock_hz`
+    pub fn new(cpu_clock_hz: u32) -> Self {
+        Self {
+            cpu_clock_hz,
+            vram: vec![0; VRAM_SIZE],
+            registers: [0; 8],
+            status: 0,
+            address: 0,
+            read_buffer: 0,
+            latch: None,
+            phase: 0,
+            dot: 0,
+            line: 0,
+            frames: 0,
+            framebuffer: vec![0; Self::WIDTH * Self::HEIGHT * 3],
+        }
+    }
+
+    #[inline]
+    pub fn vram(&self) -> &[u8] {
+        &self.vram
+    }
+
+    #[inline]
+    pub fn vram_mut(&mut self) -> &mut [u8] {
+        &mut self.vram
+    }
+
+    #[inline]
+    pub fn register(&self, index: usize) -> u8 {
+        self.registers[index]
+    }
+
+    /// Frames completed since creation
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// The rendered picture, 256x192 RGB triples
+    #[inline]
+    pub fn frame(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    /// Save the rendered picture as a binary PPM
+    pub fn write_ppm<W: Write>(&self, writer: W) -> io::Result<()> {
+        video::write_ppm(writer, Self::WIDTH, Self::HEIGHT, &self.framebuffer)
+    }
+
+    /// Save the rendered picture as a PNG
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        video::write_png(writer, Self::WIDTH, Self::HEIGHT, &self.framebuffer)
+    }
+
+    fn mode(&self) -> Mode {
+        if self.registers[1] & R1_M1 != 0 {
+            Mode::Text
+        } else if self.registers[1] & R1_M2 != 0 {
+            Mode::Multicolor
+        } else if self.registers[0] & R0_M3 != 0 {
+            Mode::Graphics2
+        } else {
+            Mode::Graphics1
+        }
+    }
+
+    #[inline]
+    fn name_table(&self) -> usize {
+        (self.registers[2] as usize & 0x0F) << 10
+    }
+
+    #[inline]
+    fn color_table(&self) -> usize {
+        (self.registers[3] as usize) << 6
+    }
+
+    #[inline]
+    fn pattern_table(&self) -> usize {
+        (self.registers[4] as usize & 0x07) << 11
+    }
+
+    #[inline]
+    fn sprite_attributes(&self) -> usize {
+        (self.registers[5] as usize & 0x7F) << 7
+    }
+
+    #[inline]
+    fn sprite_patterns(&self) -> usize {
+        (self.registers[6] as usize & 0x07) << 11
+    }
+
+    #[inline]
+    fn backdrop(&self) -> u8 {
+        self.registers[7] & 0x0F
+    }
+
+    fn write_control(&mut self, value: u8) {
+        let Some(first) = self.latch.take() else {
+            self.latch = Some(value);
+            self.address = self.address & 0x3F00 | value as u16;
+            return;
+        };
+        if value & 0x80 != 0 {
+            self.registers[value as usize & 0x07] = first;
+            return;
+        }
+        self.address = (value as u16 & 0x3F) << 8 | first as u16;
+        if value & 0x40 == 0 {
+            // Read setup: fetch ahead into the read buffer
+            self.read_buffer = self.vram[self.address as usize];
+            self.increment_address();
+        }
+    }
+
+    #[inline]
+    fn increment_address(&mut self) {
+        self.address = (self.address + 1) & 0x3FFF;
+    }
+
+    /// Colors of the background pixels on `line`, 0 meaning transparent
+    fn render_background(&self, line: usize, pixels: &mut [u8; Tms9918::WIDTH]) {
+        let row = line & 7;
+        let names = &self.vram[self.name_table()..];
+        match self.mode() {
+            Mode::Graphics1 => {
+                for column in 0..32 {
+                    let name = names[line / 8 * 32 + column] as usize;
+                    let pattern = self.vram[self.pattern_table() + name * 8 + row];
+                    let colors = self.vram[self.color_table() + name / 8];
+                    draw_pattern(&mut pixels[column * 8..], 8, pattern, colors);
+                }
+            }
+            Mode::Graphics2 => {
+                // Each third of the screen has its own patterns and colors, unless the
+                // table masks in registers 3 and 4 fold them together
+                let color_base = (self.registers[3] as usize & 0x80) << 6;
+                let color_mask = (self.registers[3] as usize & 0x7F) << 6 | 0x3F;
+                let pattern_base = (self.registers[4] as usize & 0x04) << 11;
+                let pattern_mask = (self.registers[4] as usize & 0x03) << 11 | 0x7FF;
+                let third = line / 64;
+                for column in 0..32 {
+                    let name = names[line / 8 * 32 + column] as usize;
+                    let offset = third << 11 | name << 3 | row;
+                    let pattern = self.vram[pattern_base | offset & pattern_mask];
+                    let colors = self.vram[color_base | offset & color_mask];
+                    draw_pattern(&mut pixels[column * 8..], 8, pattern, colors);
+                }
+            }
+            Mode::Multicolor => {
+                for column in 0..32 {
+                    let name = names[line / 8 * 32 + column] as usize;
+                    let block = ((line / 8) & 3) * 2 + row / 4;
+                    let colors = self.vram[self.pattern_table() + name * 8 + block];
+                    pixels[column * 8..column * 8 + 4].fill(colors >> 4);
+                    pixels[column * 8 + 4..column * 8 + 8].fill(colors & 0x0F);
+                }
+            }
+            Mode::Text => {
+                let colors = self.registers[7];
+                pixels.fill(0);
+                for column in 0..40 {
+                    let name = names[line / 8 * 40 + column] as usize;
+                    let pattern = self.vram[self.pattern_table() + name * 8 + row];
+                    draw_pattern(&mut pixels[8 + column * 6..], 6, pattern, colors);
+                }
+            }
+        }
+    }
+
+    /// Draw the sprites on `line` over `pixels`, updating the status flags
+    fn render_sprites(&mut self, line: usize, pixels: &mut [u8; Tms9918::WIDTH]) {
+        let size = if self.registers[1] & R1_SIZE != 0 {
+            16
+        } else {
+            8
+        };
+        let magnify = self.registers[1] & R1_MAG != 0;
+        let height = if magnify { size * 2 } else { size };
+        let attributes = self.sprite_attributes();
+
+        let mut coverage = [false; Tms9918::WIDTH];
+        let mut shown = 0;
+        let mut last = 31;
+        for sprite in 0..32 {
+            let attribute = &self.vram[attributes + sprite * 4..attributes + sprite * 4 + 4];
+            let [y, x, name, flags] = [attribute[0], attribute[1], attribute[2], attribute[3]];
+            if y == SPRITE_TERMINATOR {
+                last = sprite;
+                break;
+            }
+            // Sprites start on the line after their Y, and Y above 224 is above the top
+            let top = if y > 0xE0 {
+                y as i32 - 255
+            } else {
+                y as i32 + 1
+            };
+            let sprite_row = line as i32 - top;
+            if !(0..height).contains(&sprite_row) {
+                continue;
+            }
+            if shown == SPRITES_PER_LINE {
+                if self.status & STATUS_FIFTH == 0 {
+                    self.status = self.status & 0xE0 | STATUS_FIFTH | sprite as u8;
+                }
+                return;
+            }
+            shown += 1;
+
+            let row = if magnify { sprite_row / 2 } else { sprite_row } as usize;
+            let name = if size == 16 { name & 0xFC } else { name } as usize;
+            let pattern_address = self.sprite_patterns() + name * 8 + row;
+            let pattern = if size == 16 {
+                u16::from_be_bytes([self.vram[pattern_address], self.vram[pattern_address + 16]])
+            } else {
+                (self.vram[pattern_address] as u16) << 8
+            };
+            // The early clock bit moves the sprite 32 pixels left
+            let left = x as i32 - if flags & 0x80 != 0 { 32 } else { 0 };
+            let color = flags & 0x0F;
+            for pixel in 0..height {
+                let bit = if magnify { pixel / 2 } else { pixel };
+                if pattern & 0x8000 >> bit == 0 {
+                    continue;
+                }
+                let Ok(column) = usize::try_from(left + pixel) else {
+                    continue;
+                };
+                if column >= Tms9918::WIDTH {
+                    break;
+                }
+                if coverage[column] {
+                    self.status |= STATUS_COLLISION;
+                } else {
+                    coverage[column] = true;
+                    if color != 0 {
+                        pixels[column] = color;
+                    }
+                }
+            }
+        }
+        if self.status & STATUS_FIFTH == 0 {
+            self.status = self.status & 0xE0 | last as u8;
+        }
+    }
+
+    fn render_line(&mut self, line: usize) {
+        let mut pixels = [0; Tms9918::WIDTH];
+        if self.registers[1] & R1_ENABLE != 0 {
+            self.render_background(line, &mut pixels);
+            if self.mode() != Mode::Text {
+                self.render_sprites(line, &mut pixels);
+            }
+        }
+        let backdrop = self.backdrop();
+        let out = &mut self.framebuffer[line * Tms9918::WIDTH * 3..][..Tms9918::WIDTH * 3];
+        for (rgb, &color) in out.chunks_mut(3).zip(&pixels) {
+            let color = if color == 0 { backdrop } else { color };
+            rgb.copy_from_slice(&PALETTE[color as usize]);
+        }
+    }
+
+    fn end_line(&mut self) {
+        if (self.line as usize) < Tms9918::HEIGHT {
+            self.render_line(self.line as usize);
+        }
+        self.line += 1;
+        if self.line as usize == Tms9918::HEIGHT {
+            self.status |= STATUS_INT;
+            self.frames += 1;
+        } else if self.line == LINES_PER_FRAME {
+            self.line = 0;
+        }
+    }
+
+    fn cycle(&mut self) {
+        self.phase += DOT_CLOCK_HZ;
+        while self.phase >= self.cpu_clock_hz as u64 {
+            self.phase -= self.cpu_clock_hz as u64;
+            self.dot += 1;
+            if self.dot == DOTS_PER_LINE {
+                self.dot = 0;
+                self.end_line();
+            }
+        }
+    }
+}
+
+/// Expand a pattern byte into `width` pixels, set bits taking the high nibble of
+/// `colors` and clear bits the low one
+fn draw_pattern(pixels: &mut [u8], width: usize, pattern: u8, colors: u8) {
+    for (bit, pixel) in pixels[..width].iter_mut().enumerate() {
+        *pixel = if pattern & 0x80 >> bit != 0 {
+            colors >> 4
+        } else {
+            colors & 0x0F
+        };
+    }
+}
+
+impl Device for Tms9918 {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        self.latch = None;
+        if offset & 1 == DATA_PORT {
+            let value = self.read_buffer;
+            self.read_buffer = self.vram[self.address as usize];
+            self.increment_address();
+            Ok(value)
+        } else {
+            let status = self.status;
+            self.status &= !(STATUS_INT | STATUS_FIFTH | STATUS_COLLISION);
+            Ok(status)
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        if offset & 1 == DATA_PORT {
+            self.latch = None;
+            self.vram[self.address as usize] = value;
+            self.read_buffer = value;
+            self.increment_address();
+        } else {
+            self.write_control(value);
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.status & STATUS_INT != 0 && self.registers[1] & R1_IE != 0
+    }
+
+    /// Reset clears the registers, blanking the display and disabling the interrupt
+    fn reset(&mut self) {
+        self.registers = [0; 8];
+        self.latch = None;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let framebuffer = std::mem::take(&mut self.framebuffer);
+        *self = bincode::deserialize(state)?;
+        self.framebuffer = framebuffer;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    const CLOCK_HZ: u32 = 3_579_545;
+    /// CPU cycles per frame: 342 * 262 dots at the dot clock
+    const FRAME_CYCLES: u32 = 59_736;
+
+    fn set_register(vdp: &mut Tms9918, register: u8, value: u8) {
+        vdp.write(1, value).unwrap();
+        vdp.write(1, 0x80 | register).unwrap();
+    }
+
+    fn write_vram(vdp: &mut Tms9918, address: u16, data: &[u8]) {
+        vdp.write(1, address as u8).unwrap();
+        vdp.write(1, 0x40 | (address >> 8) as u8).unwrap();
+        for &byte in data {
+            vdp.write(0, byte).unwrap();
+        }
+    }
+
+    fn pixel(vdp: &Tms9918, x: usize, y: usize) -> [u8; 3] {
+        let offset = (y * Tms9918::WIDTH + x) * 3;
+        vdp.frame()[offset..offset + 3].try_into().unwrap()
+    }
+
+    /// Graphics I with names at $1800, patterns at $0000 and colors at $2000
+    fn graphics_1() -> Tms9918 {
+        let mut vdp = Tms9918::new(CLOCK_HZ);
+        set_register(&mut vdp, 1, R1_ENABLE);
+        set_register(&mut vdp, 2, 0x06);
+        set_register(&mut vdp, 3, 0x80);
+        set_register(&mut vdp, 4, 0x00);
+        set_register(&mut vdp, 5, 0x36);
+        set_register(&mut vdp, 6, 0x07);
+        set_register(&mut vdp, 7, 0x04);
+        write_vram(&mut vdp, 0x1B00, &[SPRITE_TERMINATOR]);
+        vdp
+    }
+
+    #[test]
+    fn test_ports_and_read_ahead() {
+        let mut vdp = Tms9918::new(CLOCK_HZ);
+        write_vram(&mut vdp, 0x3FFF, &[1, 2, 3]);
+        assert_eq!(vdp.vram()[0x3FFF], 1);
+        assert_eq!(vdp.vram()[..2], [2, 3], "the address wraps at 16K");
+
+        // A read setup fetches the first byte ahead
+        vdp.write(1, 0xFF).unwrap();
+        vdp.write(1, 0x3F).unwrap();
+        vdp.vram_mut()[0x3FFF] = 9;
+        assert_eq!(vdp.read(0).unwrap(), 1);
+        assert_eq!(vdp.read(0).unwrap(), 2);
+        // A write goes to the next address and through the read buffer
+        vdp.write(0, 7).unwrap();
+        assert_eq!(vdp.vram()[2], 7);
+        assert_eq!(vdp.read(0).unwrap(), 7);
+
+        set_register(&mut vdp, 7, 0x1F);
+        assert_eq!(vdp.register(7), 0x1F);
+        // Reading the status resets the control port's byte latch
+        vdp.write(1, 0x55).unwrap();
+        vdp.read(1).unwrap();
+        set_register(&mut vdp, 7, 0xF4);
+        assert_eq!(vdp.register(7), 0xF4);
+    }
+
+    #[test]
+    fn test_graphics_1_frame() {
+        let mut vdp = graphics_1();
+        // Pattern 1 is a checkerboard, pattern 2 a box outline
+        write_vram(
+            &mut vdp,
+            0x0008,
+            &[0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55],
+        );
+        write_vram(
+            &mut vdp,
+            0x0010,
+            &[0xFF, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xFF],
+        );
+        let names: Vec<u8> = (0..768)
+            .map(|cell| ((cell / 32 + cell) % 3) as u8)
+            .collect();
+        write_vram(&mut vdp, 0x1800, &names);
+        // Names 0-7 in white on transparent; 8-15 would be the next color byte
+        write_vram(&mut vdp, 0x2000, &[0xF0]);
+        vdp.tick(FRAME_CYCLES);
+
+        assert_eq!(vdp.frames(), 1);
+        assert_eq!(pixel(&vdp, 0, 0), PALETTE[4]);
+        assert_eq!(pixel(&vdp, 8, 0), PALETTE[15]);
+        assert_eq!(pixel(&vdp, 9, 0), PALETTE[4]);
+        assert_eq!(pixel(&vdp, 9, 1), PALETTE[15]);
+
+        // Every pixel of the frame against the pattern it was drawn from
+        let patterns = [
+            [0x00u8; 8],
+            [0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55, 0xAA, 0x55],
+            [0xFF, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xFF],
+        ];
+        for y in 0..Tms9918::HEIGHT {
+            for x in 0..Tms9918::WIDTH {
+                let cell = y / 8 * 32 + x / 8;
+                let pattern = patterns[(cell / 32 + cell) % 3][y % 8];
+                let expected = if pattern << (x % 8) & 0x80 != 0 {
+                    PALETTE[15]
+                } else {
+                    PALETTE[4]
+                };
+                assert_eq!(pixel(&vdp, x, y), expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_graphics_2_text_and_multicolor() {
+        let mut vdp = graphics_1();
+        // Graphics II with full pattern and color tables at $0000 and $2000
+        set_register(&mut vdp, 0, R0_M3);
+        set_register(&mut vdp, 3, 0xFF);
+        set_register(&mut vdp, 4, 0x03);
+        write_vram(&mut vdp, 0x1800, &[0; 768]);
+        // Name 0 is solid in the top third and empty in the middle one
+        write_vram(&mut vdp, 0x0000, &[0xFF; 8]);
+        write_vram(&mut vdp, 0x2000, &[0x60; 8]);
+        write_vram(&mut vdp, 0x2800, &[0x0C; 8]);
+        vdp.tick(FRAME_CYCLES);
+        assert_eq!(pixel(&vdp, 0, 0), PALETTE[6]);
+        assert_eq!(pixel(&vdp, 0, 64), PALETTE[12]);
+        assert_eq!(pixel(&vdp, 0, 128), PALETTE[4]);
+
+        // Text mode: 40 columns of 6 pixels between 8-pixel borders
+        set_register(&mut vdp, 0, 0);
+        set_register(&mut vdp, 4, 0);
+        set_register(&mut vdp, 1, R1_ENABLE | R1_M1);
+        set_register(&mut vdp, 7, 0xF1);
+        write_vram(&mut vdp, 0x0008, &[0xA8; 8]);
+        write_vram(&mut vdp, 0x1800, &[1]);
+        vdp.tick(FRAME_CYCLES);
+        assert_eq!(pixel(&vdp, 7, 0), PALETTE[1]);
+        assert_eq!(pixel(&vdp, 8, 0), PALETTE[15]);
+        assert_eq!(pixel(&vdp, 9, 0), PALETTE[1]);
+        assert_eq!(pixel(&vdp, 13, 0), PALETTE[1]);
+        // The next character is name 0, solid from the Graphics II test
+        assert_eq!(pixel(&vdp, 14, 0), PALETTE[15]);
+
+        // Multicolor: each name picks 2x2 blocks of 4x4 pixels, by row within the pattern
+        set_register(&mut vdp, 1, R1_ENABLE | R1_M2);
+        write_vram(&mut vdp, 0x1800, &[0; 64]);
+        write_vram(&mut vdp, 0x0000, &[0x23, 0x45, 0x67, 0x89]);
+        vdp.tick(FRAME_CYCLES);
+        assert_eq!(pixel(&vdp, 0, 0), PALETTE[2]);
+        assert_eq!(pixel(&vdp, 4, 0), PALETTE[3]);
+        assert_eq!(pixel(&vdp, 0, 4), PALETTE[4]);
+        assert_eq!(pixel(&vdp, 4, 8), PALETTE[7]);
+        assert_eq!(pixel(&vdp, 4, 12), PALETTE[9]);
+
+        // Blanking shows only the backdrop
+        set_register(&mut vdp, 1, 0);
+        vdp.tick(FRAME_CYCLES);
+        assert!(vdp.frame().chunks(3).all(|rgb| rgb == PALETTE[1]));
+    }
+
+    #[test]
+    fn test_sprites() {
+        let mut vdp = graphics_1();
+        write_vram(&mut vdp, 0x3800, &[0xFF; 8]);
+        // Five sprites on lines 10-17, the first two overlapping, then the terminator
+        let mut attributes = Vec::new();
+        for (sprite, x) in [0u8, 4, 40, 60, 80].into_iter().enumerate() {
+            attributes.extend([9, x, 0, 2 + sprite as u8]);
+        }
+        attributes.push(SPRITE_TERMINATOR);
+        write_vram(&mut vdp, 0x1B00, &attributes);
+        vdp.tick(FRAME_CYCLES);
+
+        assert_eq!(pixel(&vdp, 0, 9), PALETTE[4]);
+        assert_eq!(pixel(&vdp, 0, 10), PALETTE[2]);
+        // The earlier sprite wins where they overlap
+        assert_eq!(pixel(&vdp, 5, 10), PALETTE[2]);
+        assert_eq!(pixel(&vdp, 9, 10), PALETTE[3]);
+        assert_eq!(pixel(&vdp, 60, 17), PALETTE[5]);
+        // Only four sprites per line are shown
+        assert_eq!(pixel(&vdp, 80, 10), PALETTE[4]);
+        let status = vdp.read(1).unwrap();
+        assert_eq!(status, STATUS_INT | STATUS_FIFTH | STATUS_COLLISION | 4);
+        assert_eq!(vdp.read(1).unwrap() & 0xE0, 0);
+
+        // Without a fifth sprite the status holds the terminator's number. Magnified
+        // sprites are twice the size, and a transparent sprite still collides.
+        write_vram(&mut vdp, 0x1B05, &[100]);
+        write_vram(&mut vdp, 0x1B0D, &[8, 0, 0]);
+        write_vram(&mut vdp, 0x1B10, &[SPRITE_TERMINATOR]);
+        set_register(&mut vdp, 1, R1_ENABLE | R1_MAG);
+        vdp.tick(FRAME_CYCLES);
+        assert_eq!(vdp.read(1).unwrap(), STATUS_INT | STATUS_COLLISION | 4);
+        assert_eq!(pixel(&vdp, 15, 25), PALETTE[2]);
+        assert_eq!(pixel(&vdp, 16, 10), PALETTE[4]);
+        assert_eq!(pixel(&vdp, 115, 10), PALETTE[3]);
+    }
+
+    #[test]
+    fn test_vblank_interrupt() {
+        #[rustfmt::skip]
+        let program = [
+            0x58,             // CLI
+            0xA9, 0xE0,       // LDA #$E0 (16K, display, interrupt enable)
+            0x8D, 0x01, 0x80, // STA $8001
+            0xA9, 0x81,       // LDA #$81
+            0x8D, 0x01, 0x80, // STA $8001
+            0x4C, 0x0B, 0xF0, // JMP *
+        ];
+        #[rustfmt::skip]
+        let handler = [
+            0xAD, 0x01, 0x80, // LDA $8001
+            0xE6, 0x00,       // INC $00
+            0x40,             // RTI
+        ];
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(&program);
+        rom[0x800..0x800 + handler.len()].copy_from_slice(&handler);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF8]);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x3FFF)
+            .device(0x8000..=0x8001, Tms9918::new(CLOCK_HZ))
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().unwrap();
+        machine.run_cycles(CLOCK_HZ as u64).unwrap();
+        let frames = machine.device::<Tms9918>().unwrap().frames();
+        assert_eq!(frames, 60);
+        assert_eq!(machine.bus().read(0x0000).unwrap() as u64, frames);
+    }
+}
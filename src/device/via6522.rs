@@ -0,0 +1,1030 @@
+use serde::{Deserialize, Serialize};
+
+use crate::device::Device;
+use crate::error::{BusError, StateError};
+
+// Register offsets
+const ORB: u16 = 0x0;
+const ORA: u16 = 0x1;
+const DDRB: u16 = 0x2;
+const DDRA: u16 = 0x3;
+const T1C_L: u16 = 0x4;
+const T1C_H: u16 = 0x5;
+const T1L_L: u16 = 0x6;
+const T1L_H: u16 = 0x7;
+const T2C_L: u16 = 0x8;
+const T2C_H: u16 = 0x9;
+const SR: u16 = 0xA;
+const ACR: u16 = 0xB;
+const PCR: u16 = 0xC;
+const IFR: u16 = 0xD;
+const IER: u16 = 0xE;
+const ORA_NO_HANDSHAKE: u16 = 0xF;
+
+// Interrupt flag bits
+const IRQ_CA2: u8 = 1 << 0;
+const IRQ_CA1: u8 = 1 << 1;
+const IRQ_SR: u8 = 1 << 2;
+const IRQ_CB2: u8 = 1 << 3;
+const IRQ_CB1: u8 = 1 << 4;
+const IRQ_T2: u8 = 1 << 5;
+const IRQ_T1: u8 = 1 << 6;
+const IRQ_ANY: u8 = 1 << 7;
+
+// Auxiliary control register bits
+const ACR_PA_LATCH: u8 = 1 << 0;
+const ACR_PB_LATCH: u8 = 1 << 1;
+const ACR_T2_PULSE_COUNT: u8 = 1 << 5;
+const ACR_T1_FREE_RUN: u8 = 1 << 6;
+const ACR_T1_PB7: u8 = 1 << 7;
+
+const PB6: u8 = 1 << 6;
+const PB7: u8 = 1 << 7;
+
+/// Behaviour of CA2 or CB2, from the three PCR bits controlling it
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ControlMode {
+    /// Active edge input, flag cleared by port access unless `independent`
+    Input {
+        positive: bool,
+        independent: bool,
+    },
+    /// Goes low on port access, back high on the active edge of CA1/CB1
+    Handshake,
+    /// Goes low for one cycle after port access
+    Pulse,
+    Manual(bool),
+}
+
+impl ControlMode {
+    fn from_pcr(bits: u8) -> Self {
+        match bits & 0b111 {
+            0b100 => ControlMode::Handshake,
+            0b101 => ControlMode::Pulse,
+            0b110 => ControlMode::Manual(false),
+            0b111 => ControlMode::Manual(true),
+            input => ControlMode::Input {
+                positive: input & 0b010 != 0,
+                independent: input & 0b001 != 0,
+            },
+        }
+    }
+}
+
+/// Shift register mode, ACR bits 2-4
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShiftMode {
+    Disabled,
+    InUnderTimer2,
+    InUnderPhi2,
+    InUnderCb1,
+    OutFreeRunning,
+    OutUnderTimer2,
+    OutUnderPhi2,
+    OutUnderCb1,
+}
+
+impl ShiftMode {
+    fn from_acr(acr: u8) -> Self {
+        match (acr >> 2) & 0b111 {
+            0 => ShiftMode::Disabled,
+            1 => ShiftMode::InUnderTimer2,
+            2 => ShiftMode::InUnderPhi2,
+            3 => ShiftMode::InUnderCb1,
+            4 => ShiftMode::OutFreeRunning,
+            5 => ShiftMode::OutUnderTimer2,
+            6 => ShiftMode::OutUnderPhi2,
+            _ => ShiftMode::OutUnderCb1,
+        }
+    }
+
+    #[inline]
+    fn shifts_out(self) -> bool {
+        matches!(
+            self,
+            ShiftMode::OutFreeRunning
+                | ShiftMode::OutUnderTimer2
+                | ShiftMode::OutUnderPhi2
+                | ShiftMode::OutUnderCb1
+        )
+    }
+}
+
+/// MOS 6522 Versatile Interface Adapter.
+///
+/// Both timers are emulated, T1 one-shot or free-running with its PB7 output and T2
+/// one-shot or counting PB6 pulses, as are the shift register in all eight modes, the
+/// ports with their DDRs and input latching, and the CA1/CA2/CB1/CB2 handshakes.
+///
+/// The 16 registers repeat every 16 bytes, so the device can be mapped over any range.
+/// Timing is modelled per cycle: writing T1C-H or T2C-H loads the counter, which then
+/// reads N, N-1, ... 0, $FFFF on the following cycles. The interrupt flag rises N + 1.5
+/// cycles after the write, which reads first see on cycle N + 2, and for T1 in
+/// free-run mode every N + 2 cycles after that.
+/// The [`Machine`](crate::machine::Machine) ticks devices on every bus cycle, so the VIA
+/// sees register accesses on the cycle they happen. A host that ticks it after each
+/// instruction moves them to the start of the instruction instead.
+///
+/// Pins default to high, as the port and control lines are pulled up on most boards.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Via6522 {
+    ora: u8,
+    orb: u8,
+    ddra: u8,
+    ddrb: u8,
+    port_a_pins: u8,
+    port_b_pins: u8,
+    port_a_latch: u8,
+    port_b_latch: u8,
+    ca1: bool,
+    ca2: bool,
+    cb1: bool,
+    cb2: bool,
+    ca2_output: bool,
+    cb2_output: bool,
+    ca2_pulse: bool,
+    cb2_pulse: bool,
+    t1_counter: u16,
+    t1_latch: u16,
+    t1_reload: bool,
+    t1_armed: bool,
+    pb7: bool,
+    t2_counter: u16,
+    t2_latch_low: u8,
+    t2_loaded: bool,
+    t2_armed: bool,
+    shift_register: u8,
+    shift_count: u8,
+    shift_timer: u16,
+    shift_clock: bool,
+    acr: u8,
+    pcr: u8,
+    ifr: u8,
+    ier: u8,
+}
+
+impl Default for Via6522 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Via6522 {
+    pub fn new() -> Self {
+        Self {
+            ora: 0,
+            orb: 0,
+            ddra: 0,
+            ddrb: 0,
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            port_a_latch: 0,
+            port_b_latch: 0,
+            ca1: true,
+            ca2: true,
+            cb1: true,
+            cb2: true,
+            ca2_output: true,
+            cb2_output: true,
+            ca2_pulse: false,
+            cb2_pulse: false,
+            t1_counter: 0,
+            t1_latch: 0,
+            t1_reload: false,
+            t1_armed: false,
+            pb7: true,
+            t2_counter: 0,
+            t2_latch_low: 0,
+            t2_loaded: false,
+            t2_armed: false,
+            shift_register: 0,
+            shift_count: 0,
+            shift_timer: 0,
+            shift_clock: true,
+            acr: 0,
+            pcr: 0,
+            ifr: 0,
+            ier: 0,
+        }
+    }
+
+    /// Levels on the port A pins: output bits from ORA, input bits from outside
+    #[inline]
+    pub fn port_a(&self) -> u8 {
+        (self.ora & self.ddra) | (self.port_a_pins & !self.ddra)
+    }
+
+    /// Levels on the port B pins. PB7 follows timer 1 when ACR bit 7 is set.
+    #[inline]
+    pub fn port_b(&self) -> u8 {
+        let value = (self.orb & self.ddrb) | (self.port_b_pins & !self.ddrb);
+        if self.acr & ACR_T1_PB7 != 0 {
+            (value & !PB7) | if self.pb7 { PB7 } else { 0 }
+        } else {
+            value
+        }
+    }
+
+    /// Drive the port A input pins
+    #[inline]
+    pub fn set_port_a(&mut self, value: u8) {
+        self.port_a_pins = value;
+    }
+
+    /// Drive the port B input pins. A falling edge on PB6 counts a pulse for timer 2.
+    pub fn set_port_b(&mut self, value: u8) {
+        let falling = self.port_b_pins & !value & PB6 != 0;
+        self.port_b_pins = value;
+        if falling && self.acr & ACR_T2_PULSE_COUNT != 0 {
+            self.t2_counter = self.t2_counter.wrapping_sub(1);
+            if self.t2_counter == 0 && self.t2_armed {
+                self.t2_armed = false;
+                self.set_flags(IRQ_T2);
+            }
+        }
+    }
+
+    /// Level on CA2 when it is an output
+    #[inline]
+    pub fn ca2(&self) -> bool {
+        match ControlMode::from_pcr(self.pcr >> 1) {
+            ControlMode::Input { .. } => self.ca2,
+            _ => self.ca2_output,
+        }
+    }
+
+    /// Level on CB1, which is the shift clock output in the internally clocked modes
+    #[inline]
+    pub fn cb1(&self) -> bool {
+        match self.shift_mode() {
+            ShiftMode::Disabled | ShiftMode::InUnderCb1 | ShiftMode::OutUnderCb1 => self.cb1,
+            _ => self.shift_clock,
+        }
+    }
+
+    /// Level on CB2, which is the shift data output in the shift-out modes
+    #[inline]
+    pub fn cb2(&self) -> bool {
+        if self.shift_mode().shifts_out() {
+            return self.cb2_output;
+        }
+        match ControlMode::from_pcr(self.pcr >> 5) {
+            ControlMode::Input { .. } => self.cb2,
+            _ => self.cb2_output,
+        }
+    }
+
+    pub fn set_ca1(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.ca1, level);
+        if previous == level || level != (self.pcr & 0x01 != 0) {
+            return;
+        }
+        self.set_flags(IRQ_CA1);
+        if self.acr & ACR_PA_LATCH != 0 {
+            self.port_a_latch = self.port_a();
+        }
+        if ControlMode::from_pcr(self.pcr >> 1) == ControlMode::Handshake {
+            self.ca2_output = true;
+        }
+    }
+
+    pub fn set_ca2(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.ca2, level);
+        if let ControlMode::Input { positive, .. } = ControlMode::from_pcr(self.pcr >> 1) {
+            if previous != level && level == positive {
+                self.set_flags(IRQ_CA2);
+            }
+        }
+    }
+
+    pub fn set_cb1(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.cb1, level);
+        if previous == level {
+            return;
+        }
+        match self.shift_mode() {
+            ShiftMode::InUnderCb1 | ShiftMode::OutUnderCb1 => self.clock_shift_register(level),
+            _ => {}
+        }
+        if level != (self.pcr & 0x10 != 0) {
+            return;
+        }
+        self.set_flags(IRQ_CB1);
+        if self.acr & ACR_PB_LATCH != 0 {
+            self.port_b_latch = self.port_b();
+        }
+        if ControlMode::from_pcr(self.pcr >> 5) == ControlMode::Handshake {
+            self.cb2_output = true;
+        }
+    }
+
+    /// Drive CB2. While the shift register is enabled CB2 is its data line and raises no
+    /// interrupt.
+    pub fn set_cb2(&mut self, level: bool) {
+        let previous = std::mem::replace(&mut self.cb2, level);
+        if self.shift_mode() != ShiftMode::Disabled {
+            return;
+        }
+        if let ControlMode::Input { positive, .. } = ControlMode::from_pcr(self.pcr >> 5) {
+            if previous != level && level == positive {
+                self.set_flags(IRQ_CB2);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn shift_mode(&self) -> ShiftMode {
+        ShiftMode::from_acr(self.acr)
+    }
+
+    #[inline]
+    fn set_flags(&mut self, flags: u8) {
+        self.ifr |= flags;
+    }
+
+    #[inline]
+    fn clear_flags(&mut self, flags: u8) {
+        self.ifr &= !flags;
+    }
+
+    /// Clear the port A flags and run the CA2 handshake after an ORA access
+    fn access_port_a(&mut self) {
+        self.clear_flags(IRQ_CA1);
+        match ControlMode::from_pcr(self.pcr >> 1) {
+            ControlMode::Input {
+                independent: false, ..
+            } => self.clear_flags(IRQ_CA2),
+            ControlMode::Handshake => self.ca2_output = false,
+            ControlMode::Pulse => {
+                self.ca2_output = false;
+                self.ca2_pulse = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Clear the port B flags and, on writes, run the CB2 handshake after an ORB access
+    fn access_port_b(&mut self, write: bool) {
+        self.clear_flags(IRQ_CB1);
+        match ControlMode::from_pcr(self.pcr >> 5) {
+            ControlMode::Input {
+                independent: false, ..
+            } => self.clear_flags(IRQ_CB2),
+            ControlMode::Handshake if write => self.cb2_output = false,
+            ControlMode::Pulse if write => {
+                self.cb2_output = false;
+                self.cb2_pulse = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Restart the 8-bit shift count after an SR access
+    fn access_shift_register(&mut self) {
+        self.clear_flags(IRQ_SR);
+        if self.shift_mode() != ShiftMode::Disabled {
+            self.shift_count = 8;
+            self.shift_timer = self.t2_latch_low as u16 + 2;
+        }
+    }
+
+    /// Handle an edge of the shift clock: data goes out on falling edges and is sampled
+    /// on rising edges, which also count the bits
+    fn clock_shift_register(&mut self, rising: bool) {
+        let mode = self.shift_mode();
+        if self.shift_count == 0 && mode != ShiftMode::OutFreeRunning {
+            return;
+        }
+        if !rising {
+            if mode.shifts_out() {
+                self.cb2_output = self.shift_register & 0x80 != 0;
+                self.shift_register = self.shift_register.rotate_left(1);
+            }
+            return;
+        }
+        if !mode.shifts_out() {
+            self.shift_register = self.shift_register << 1 | self.cb2 as u8;
+        }
+        if mode != ShiftMode::OutFreeRunning {
+            self.shift_count -= 1;
+            if self.shift_count == 0 {
+                self.set_flags(IRQ_SR);
+            }
+        }
+    }
+
+    fn tick_timer1(&mut self) {
+        if std::mem::take(&mut self.t1_reload) {
+            self.t1_counter = self.t1_latch;
+            return;
+        }
+        self.t1_counter = self.t1_counter.wrapping_sub(1);
+        if self.t1_counter != 0xFFFF {
+            return;
+        }
+        // The counter always reloads from the latches; only the interrupt is one-shot
+        self.t1_reload = true;
+        if self.acr & ACR_T1_FREE_RUN != 0 {
+            self.set_flags(IRQ_T1);
+            self.pb7 = !self.pb7;
+        } else if std::mem::take(&mut self.t1_armed) {
+            self.set_flags(IRQ_T1);
+            self.pb7 = true;
+        }
+    }
+
+    fn tick_timer2(&mut self) {
+        if self.acr & ACR_T2_PULSE_COUNT != 0 || std::mem::take(&mut self.t2_loaded) {
+            return;
+        }
+        self.t2_counter = self.t2_counter.wrapping_sub(1);
+        if self.t2_counter == 0xFFFF && std::mem::take(&mut self.t2_armed) {
+            self.set_flags(IRQ_T2);
+        }
+    }
+
+    fn tick_shift_register(&mut self) {
+        let active = self.shift_count != 0;
+        match self.shift_mode() {
+            ShiftMode::InUnderPhi2 | ShiftMode::OutUnderPhi2 if active => {}
+            ShiftMode::InUnderTimer2 | ShiftMode::OutUnderTimer2 if active => {
+                self.shift_timer -= 1;
+                if self.shift_timer != 0 {
+                    return;
+                }
+                self.shift_timer = self.t2_latch_low as u16 + 2;
+            }
+            ShiftMode::OutFreeRunning => {
+                self.shift_timer = self.shift_timer.saturating_sub(1);
+                if self.shift_timer != 0 {
+                    return;
+                }
+                self.shift_timer = self.t2_latch_low as u16 + 2;
+            }
+            _ => return,
+        }
+        self.shift_clock = !self.shift_clock;
+        self.clock_shift_register(self.shift_clock);
+    }
+
+    fn cycle(&mut self) {
+        if std::mem::take(&mut self.ca2_pulse) {
+            self.ca2_output = true;
+        }
+        if std::mem::take(&mut self.cb2_pulse) {
+            self.cb2_output = true;
+        }
+        self.tick_timer1();
+        self.tick_timer2();
+        self.tick_shift_register();
+    }
+}
+
+impl Device for Via6522 {
+    fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+        Ok(match offset & 0x0F {
+            ORB => {
+                self.access_port_b(false);
+                let pins = if self.acr & ACR_PB_LATCH != 0 {
+                    self.port_b_latch
+                } else {
+                    self.port_b()
+                };
+                // Output bits read back from ORB rather than the pins, except for PB7
+                // while timer 1 drives it
+                let mut outputs = self.ddrb;
+                if self.acr & ACR_T1_PB7 != 0 {
+                    outputs &= !PB7;
+                }
+                (self.orb & outputs) | (pins & !outputs)
+            }
+            ORA | ORA_NO_HANDSHAKE => {
+                if offset & 0x0F == ORA {
+                    self.access_port_a();
+                }
+                if self.acr & ACR_PA_LATCH != 0 {
+                    self.port_a_latch
+                } else {
+                    self.port_a()
+                }
+            }
+            DDRB => self.ddrb,
+            DDRA => self.ddra,
+            T1C_L => {
+                self.clear_flags(IRQ_T1);
+                self.t1_counter as u8
+            }
+            T1C_H => (self.t1_counter >> 8) as u8,
+            T1L_L => self.t1_latch as u8,
+            T1L_H => (self.t1_latch >> 8) as u8,
+            T2C_L => {
+                self.clear_flags(IRQ_T2);
+                self.t2_counter as u8
+            }
+            T2C_H => (self.t2_counter >> 8) as u8,
+            SR => {
+                self.access_shift_register();
+                self.shift_register
+            }
+            ACR => self.acr,
+            PCR => self.pcr,
+            IFR => {
+                let pending = self.ifr & self.ier & !IRQ_ANY != 0;
+                self.ifr | if pending { IRQ_ANY } else { 0 }
+            }
+            IER => self.ier | IRQ_ANY,
+            _ => unreachable!("offset is masked to four bits"),
+        })
+    }
+
+    fn write(&mut self, offset: u16, value: u8) -> Result<(), BusError> {
+        match offset & 0x0F {
+            ORB => {
+                self.access_port_b(true);
+                self.orb = value;
+            }
+            ORA => {
+                self.access_port_a();
+                self.ora = value;
+            }
+            ORA_NO_HANDSHAKE => self.ora = value,
+            DDRB => self.ddrb = value,
+            DDRA => self.ddra = value,
+            T1C_L | T1L_L => self.t1_latch = (self.t1_latch & 0xFF00) | value as u16,
+            T1C_H => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (value as u16) << 8;
+                self.t1_counter = self.t1_latch;
+                self.t1_reload = true;
+                self.t1_armed = true;
+                self.clear_flags(IRQ_T1);
+                if self.acr & ACR_T1_PB7 != 0 {
+                    self.pb7 = false;
+                }
+            }
+            T1L_H => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (value as u16) << 8;
+                self.clear_flags(IRQ_T1);
+            }
+            T2C_L => self.t2_latch_low = value,
+            T2C_H => {
+                self.t2_counter = u16::from_le_bytes([self.t2_latch_low, value]);
+                self.t2_loaded = true;
+                self.t2_armed = true;
+                self.clear_flags(IRQ_T2);
+            }
+            SR => {
+                self.access_shift_register();
+                self.shift_register = value;
+            }
+            ACR => {
+                let previous = self.shift_mode();
+                self.acr = value;
+                if self.shift_mode() != previous {
+                    self.shift_count = 0;
+                    self.shift_clock = true;
+                    self.shift_timer = self.t2_latch_low as u16 + 2;
+                }
+            }
+            PCR => {
+                self.pcr = value;
+                if let ControlMode::Manual(level) = ControlMode::from_pcr(value >> 1) {
+                    self.ca2_output = level;
+                }
+                if let ControlMode::Manual(level) = ControlMode::from_pcr(value >> 5) {
+                    self.cb2_output = level;
+                }
+            }
+            IFR => self.clear_flags(value & !IRQ_ANY),
+            IER => {
+                if value & IRQ_ANY != 0 {
+                    self.ier |= value & !IRQ_ANY;
+                } else {
+                    self.ier &= !value;
+                }
+            }
+            _ => unreachable!("offset is masked to four bits"),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.cycle();
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.ifr & self.ier & !IRQ_ANY != 0
+    }
+
+    /// Reset clears the registers, but not the timer counters, latches or shift register
+    fn reset(&mut self) {
+        self.ora = 0;
+        self.orb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.acr = 0;
+        self.pcr = 0;
+        self.ifr = 0;
+        self.ier = 0;
+        self.t1_armed = false;
+        self.t2_armed = false;
+        self.shift_count = 0;
+        self.shift_clock = true;
+        self.ca2_output = true;
+        self.cb2_output = true;
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        *self = bincode::deserialize(state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    const VIA: u16 = 0x6000;
+
+    /// Machine with the VIA at $6000, `program` at $F000 and `irq_handler` at $F800
+    fn machine(program: &[u8], irq_handler: &[u8]) -> Machine {
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..program.len()].copy_from_slice(program);
+        rom[0x800..0x800 + irq_handler.len()].copy_from_slice(irq_handler);
+        rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF8]);
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x07FF)
+            .device(VIA..=VIA + 0x0F, Via6522::new())
+            .rom(0xF000, rom)
+            .build();
+        machine.reset().expect("Failed to reset machine");
+        machine
+    }
+
+    fn via(machine: &mut Machine) -> &mut Via6522 {
+        machine.device_mut().expect("VIA is mapped")
+    }
+
+    #[test]
+    fn test_timer1_one_shot_counter_reads() {
+        #[rustfmt::skip]
+        let program = [
+            0xA9, 0x10,       // LDA #$10
+            0x8D, 0x04, 0x60, // STA T1C-L
+            0xA9, 0x00,       // LDA #$00
+            0x8D, 0x05, 0x60, // STA T1C-H
+            0xAD, 0x04, 0x60, // LDA T1C-L
+            0x85, 0x00,       // STA $00
+            0xAD, 0x04, 0x60, // LDA T1C-L
+            0x85, 0x01,       // STA $01
+            0x2C, 0x0D, 0x60, // BIT IFR (loop)
+            0x50, 0xFB,       // BVC loop
+            0xAD, 0x05, 0x60, // LDA T1C-H
+            0x85, 0x02,       // STA $02
+            0x4C, 0x1E, 0xF0, // JMP *
+        ];
+        let mut machine = machine(&program, &[]);
+        machine.run_cycles(80).unwrap();
+
+        // The first read lands four cycles after the write, the second eleven
+        assert_eq!(machine.bus().read(0x0000).unwrap(), 0x10 - 3);
+        assert_eq!(machine.bus().read(0x0001).unwrap(), 0x10 - 10);
+        // The counter reloaded from the latch after timing out
+        assert_eq!(machine.bus().read(0x0002).unwrap(), 0x00);
+        assert_eq!(machine.cpu().program_counter(), 0xF01E);
+
+        // One-shot: the counter keeps running but does not interrupt again
+        via(&mut machine).write(IFR, IRQ_T1).unwrap();
+        machine.run_cycles(200).unwrap();
+        assert_eq!(via(&mut machine).read(IFR).unwrap() & IRQ_T1, 0);
+    }
+
+    #[test]
+    fn test_timer1_exact_timeout() {
+        let mut via = Via6522::new();
+        via.write(T1C_L, 5).unwrap();
+        via.write(T1C_H, 0).unwrap();
+        for expected in [5, 4, 3, 2, 1, 0] {
+            via.tick(1);
+            assert_eq!(via.t1_counter, expected);
+            assert_eq!(via.ifr, 0);
+        }
+        // The flag rises N + 1.5 cycles after the write, so a read sees it on cycle N + 2
+        via.tick(1);
+        assert_eq!(via.t1_counter, 0xFFFF);
+        assert_eq!(via.ifr, IRQ_T1);
+        via.tick(1);
+        assert_eq!(via.t1_counter, 5);
+
+        // One-shot: the counter carries on through the latch, without interrupting
+        via.read(T1C_L).unwrap();
+        via.tick(6);
+        assert_eq!(via.t1_counter, 0xFFFF);
+        assert_eq!(via.ifr, 0);
+    }
+
+    #[test]
+    fn test_timer1_free_run_exact_reload() {
+        let mut via = Via6522::new();
+        via.write(ACR, ACR_T1_FREE_RUN | ACR_T1_PB7).unwrap();
+        via.write(T1C_L, 5).unwrap();
+        via.write(T1C_H, 0).unwrap();
+        assert_eq!(via.port_b() & PB7, 0);
+        for period in 1..=3 {
+            // N, N-1, ... 0, $FFFF and back to N: every N + 2 cycles
+            for expected in [5, 4, 3, 2, 1, 0] {
+                via.tick(1);
+                assert_eq!(via.t1_counter, expected, "period {period}");
+                assert_eq!(via.ifr, 0, "period {period}");
+            }
+            via.tick(1);
+            assert_eq!(via.t1_counter, 0xFFFF);
+            assert_eq!(via.ifr, IRQ_T1, "period {period}");
+            assert_eq!(via.port_b() & PB7 != 0, period % 2 == 1);
+            via.read(T1C_L).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_timer1_free_run_irq_and_pb7() {
+        #[rustfmt::skip]
+        let program = [
+            0xA9, 0xC0,       // LDA #$C0
+            0x8D, 0x0B, 0x60, // STA ACR (free-run, PB7 output)
+            0xA9, 0x62,       // LDA #98
+            0x8D, 0x04, 0x60, // STA T1C-L
+            0xA9, 0x00,       // LDA #$00
+            0x8D, 0x05, 0x60, // STA T1C-H
+            0xA9, 0xC0,       // LDA #$C0
+            0x8D, 0x0E, 0x60, // STA IER (enable T1)
+            0x58,             // CLI
+            0x4C, 0x15, 0xF0, // JMP *
+        ];
+        #[rustfmt::skip]
+        let handler = [
+            0xE6, 0x10,       // INC $10
+            0xAD, 0x04, 0x60, // LDA T1C-L (acknowledge)
+            0x40,             // RTI
+        ];
+        let mut machine = machine(&program, &handler);
+        while machine.cpu().program_counter() != 0xF00F {
+            machine.step().unwrap();
+        }
+        // STA T1C-H writes on its last cycle
+        let written = machine.cycles() - 1;
+        assert_eq!(via(&mut machine).port_b() & PB7, 0);
+
+        let mut entries = Vec::new();
+        while entries.len() < 10 {
+            assert!(machine.cycles() < 2000, "T1 stopped interrupting");
+            machine.step().unwrap();
+            if machine.cpu().program_counter() == 0xF800 {
+                entries.push(machine.cycles() - written);
+            }
+        }
+        // T1 times out every N + 2 = 100 cycles, two cycles before the JMP * in progress
+        // ends, and the IRQ sequence takes seven more
+        let expected: Vec<u64> = (1..=10).map(|period| 100 * period + 2 + 7).collect();
+        assert_eq!(entries, expected);
+        // Ten toggles of PB7 leave it low
+        assert_eq!(via(&mut machine).port_b() & PB7, 0);
+        assert_eq!(machine.bus().read(0x0010).unwrap(), 9);
+    }
+
+    #[test]
+    fn test_timer2_one_shot_and_pulse_counting() {
+        let mut via = Via6522::new();
+        via.write(T2C_L, 5).unwrap();
+        via.write(T2C_H, 0).unwrap();
+        via.tick(6);
+        assert_eq!(via.read(T2C_L).unwrap(), 0);
+        assert_eq!(via.ifr, 0);
+        via.tick(1);
+        assert_eq!(via.ifr, IRQ_T2);
+        assert_eq!(via.read(T2C_H).unwrap(), 0xFF);
+
+        // Reading T2C-L acknowledges it, and the timer does not interrupt again
+        via.read(T2C_L).unwrap();
+        assert_eq!(via.ifr, 0);
+        via.tick(0x10000);
+        assert_eq!(via.ifr, 0);
+
+        via.write(ACR, ACR_T2_PULSE_COUNT).unwrap();
+        via.write(T2C_L, 3).unwrap();
+        via.write(T2C_H, 0).unwrap();
+        via.tick(100);
+        for pulse in 1..=3 {
+            assert_eq!(via.ifr, 0);
+            via.set_port_b(!PB6);
+            via.set_port_b(0xFF);
+            assert_eq!(via.read(T2C_L).unwrap(), 3 - pulse);
+        }
+        assert_eq!(via.ifr, 0, "reading T2C-L acknowledges the interrupt");
+        via.write(T2C_H, 0).unwrap();
+        via.set_port_b(!PB6);
+        via.set_port_b(!PB6);
+        assert_eq!(via.read(T2C_L).unwrap(), 2);
+    }
+
+    /// Cycles until the shift register interrupt, collecting CB2 on each rising CB1 edge
+    fn shift_out(via: &mut Via6522) -> (u32, u8) {
+        let (mut cycles, mut bits) = (0, 0u8);
+        while via.ifr & IRQ_SR == 0 {
+            let clock = via.cb1();
+            via.tick(1);
+            cycles += 1;
+            if !clock && via.cb1() {
+                bits = bits << 1 | via.cb2() as u8;
+            }
+            assert!(cycles < 1000, "shift register never finished");
+        }
+        (cycles, bits)
+    }
+
+    #[test]
+    fn test_shift_register_modes() {
+        let mut via = Via6522::new();
+
+        // Mode 0: disabled
+        via.write(SR, 0x5A).unwrap();
+        via.tick(100);
+        assert_eq!(via.read(SR).unwrap(), 0x5A);
+        assert_eq!(via.ifr, 0);
+
+        // Mode 6: out under phi2, one bit every two cycles
+        via.write(ACR, 6 << 2).unwrap();
+        via.write(SR, 0xA5).unwrap();
+        assert_eq!(shift_out(&mut via), (16, 0xA5));
+        assert_eq!(via.read(SR).unwrap(), 0xA5, "shifting out recirculates");
+
+        // Mode 5: out under T2, one edge every N + 2 cycles
+        via.write(T2C_L, 2).unwrap();
+        via.write(ACR, 5 << 2).unwrap();
+        via.write(SR, 0x3C).unwrap();
+        assert_eq!(shift_out(&mut via), (16 * 4, 0x3C));
+
+        // Mode 4: free-running out under T2, never stops or interrupts
+        via.write(ACR, 4 << 2).unwrap();
+        via.write(SR, 0x81).unwrap();
+        via.tick(16 * 4 * 3 + 4);
+        assert_eq!(via.ifr, 0);
+        assert!(!via.cb1());
+        assert_eq!(via.shift_register, 0x03);
+
+        // Mode 7: out under CB1, data changes on falling edges
+        via.write(ACR, 7 << 2).unwrap();
+        via.write(SR, 0x96).unwrap();
+        let mut bits = 0u8;
+        for _ in 0..8 {
+            via.set_cb1(false);
+            bits = bits << 1 | via.cb2() as u8;
+            via.set_cb1(true);
+        }
+        assert_eq!(bits, 0x96);
+        // The clock edges also raise the CB1 interrupt
+        assert_eq!(via.ifr, IRQ_SR | IRQ_CB1);
+        via.write(IFR, IRQ_CB1).unwrap();
+
+        // Mode 3: in under CB1, data sampled on rising edges
+        via.write(ACR, 3 << 2).unwrap();
+        via.write(SR, 0).unwrap();
+        for bit in [0, 1, 1, 0, 1, 0, 0, 1] {
+            via.set_cb2(bit != 0);
+            via.set_cb1(false);
+            via.set_cb1(true);
+        }
+        assert_eq!(via.ifr, IRQ_SR | IRQ_CB1);
+        assert_eq!(via.read(SR).unwrap(), 0x69);
+        via.write(IFR, IRQ_CB1).unwrap();
+
+        // Mode 2: in under phi2
+        via.write(ACR, 2 << 2).unwrap();
+        via.set_cb2(true);
+        via.read(SR).unwrap();
+        via.tick(15);
+        assert_eq!(via.ifr, 0);
+        via.tick(1);
+        assert_eq!(via.ifr, IRQ_SR);
+        assert_eq!(via.read(SR).unwrap(), 0xFF);
+        assert!(via.cb1(), "the clock idles high");
+
+        // Mode 1: in under T2
+        via.write(T2C_L, 0).unwrap();
+        via.write(ACR, 1 << 2).unwrap();
+        via.set_cb2(false);
+        via.read(SR).unwrap();
+        via.tick(16 * 2 - 1);
+        assert_eq!(via.ifr, 0);
+        via.tick(1);
+        assert_eq!(via.ifr, IRQ_SR);
+        assert_eq!(via.read(SR).unwrap(), 0x00);
+    }
+
+    #[test]
+    fn test_ports_and_latching() {
+        let mut via = Via6522::new();
+        via.write(DDRA, 0xF0).unwrap();
+        via.write(ORA, 0xA5).unwrap();
+        via.set_port_a(0x3C);
+        assert_eq!(via.port_a(), 0xAC);
+        assert_eq!(via.read(ORA).unwrap(), 0xAC);
+
+        // Output bits of port B read back from ORB even if the pin is pulled low
+        via.write(DDRB, 0x0F).unwrap();
+        via.write(ORB, 0x5A).unwrap();
+        via.set_port_b(0x30);
+        assert_eq!(via.read(ORB).unwrap(), 0x3A);
+
+        // Latch port A on a rising CA1 edge
+        via.write(ACR, ACR_PA_LATCH).unwrap();
+        via.write(PCR, 0x01).unwrap();
+        via.set_ca1(false);
+        assert_eq!(via.ifr, 0);
+        via.set_ca1(true);
+        assert_eq!(via.ifr, IRQ_CA1);
+        via.set_port_a(0x00);
+        via.write(ORA_NO_HANDSHAKE, 0x00).unwrap();
+        assert_eq!(via.read(ORA_NO_HANDSHAKE).unwrap(), 0xAC);
+        assert_eq!(via.ifr, IRQ_CA1, "register F does not touch the flags");
+        assert_eq!(via.read(ORA).unwrap(), 0xAC);
+        assert_eq!(via.ifr, 0);
+    }
+
+    #[test]
+    fn test_handshake_lines() {
+        let mut via = Via6522::new();
+
+        // CA2 handshake: low after an ORA access, high on the active CA1 edge
+        via.write(PCR, 0b1000).unwrap();
+        via.read(ORA).unwrap();
+        assert!(!via.ca2());
+        via.set_ca1(false);
+        assert!(via.ca2());
+        assert_eq!(via.ifr, IRQ_CA1);
+
+        // CA2 pulse: low for one cycle
+        via.write(PCR, 0b1010).unwrap();
+        via.write(ORA, 0).unwrap();
+        assert!(!via.ca2());
+        via.tick(1);
+        assert!(via.ca2());
+
+        // CA2 manual output
+        via.write(PCR, 0b1100).unwrap();
+        assert!(!via.ca2());
+        via.write(PCR, 0b1110).unwrap();
+        assert!(via.ca2());
+
+        // CB2 handshake only reacts to writes
+        via.write(PCR, 0b1001_0000).unwrap();
+        via.read(ORB).unwrap();
+        assert!(via.cb2());
+        via.write(ORB, 0).unwrap();
+        assert!(!via.cb2());
+        via.set_cb1(false);
+        assert!(!via.cb2(), "CB1 is configured for rising edges");
+        via.set_cb1(true);
+        assert!(via.cb2());
+        assert_eq!(via.ifr, IRQ_CB1);
+
+        // CB2 inputs, cleared by ORB access unless independent
+        via.write(PCR, 0b0100_0000).unwrap();
+        via.set_cb2(false);
+        via.set_cb2(true);
+        assert_eq!(via.ifr, IRQ_CB1 | IRQ_CB2);
+        via.read(ORB).unwrap();
+        assert_eq!(via.ifr, 0);
+        via.write(PCR, 0b0010_0000).unwrap();
+        via.set_cb2(false);
+        via.read(ORB).unwrap();
+        assert_eq!(via.ifr, IRQ_CB2);
+    }
+
+    #[test]
+    fn test_interrupt_registers() {
+        let mut via = Via6522::new();
+        via.set_ca1(false);
+        assert_eq!(via.read(IFR).unwrap(), IRQ_CA1);
+        assert!(!via.irq());
+
+        via.write(IER, IRQ_ANY | IRQ_CA1 | IRQ_T1).unwrap();
+        assert_eq!(via.read(IER).unwrap(), IRQ_ANY | IRQ_CA1 | IRQ_T1);
+        assert_eq!(via.read(IFR).unwrap(), IRQ_ANY | IRQ_CA1);
+        assert!(via.irq());
+
+        via.write(IER, IRQ_CA1).unwrap();
+        assert_eq!(via.read(IER).unwrap(), IRQ_ANY | IRQ_T1);
+        assert!(!via.irq());
+
+        via.write(IER, IRQ_ANY | IRQ_CA1).unwrap();
+        via.write(IFR, IRQ_ANY | IRQ_CA1).unwrap();
+        assert_eq!(via.read(IFR).unwrap(), 0);
+        assert!(!via.irq());
+    }
+}
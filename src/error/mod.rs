@@ -1,11 +1,14 @@
 use thiserror::Error;
 
 use crate::mos6502::AddressingMode;
+use crate::wdc65c816;
 
 #[derive(Error, Debug)]
 pub enum CpuError {
     #[error("invalid addressing mode")]
     InvalidAddressingMode(AddressingMode),
+    #[error("invalid addressing mode")]
+    Invalid65816AddressingMode(wdc65c816::AddressingMode),
     #[error("opcode not implemented")]
     OpcodeNotImplemented,
     #[error("invalid bus operation")]
@@ -20,4 +23,52 @@ pub enum BusError {
     InvalidWrite(u16),
     #[error("attempted write on read-only address {0}")]
     ReadOnlyAddress(u16),
+    #[error("attempted invalid read at long address {0}")]
+    InvalidLongRead(u32),
+    #[error("attempted invalid write at long address {0}")]
+    InvalidLongWrite(u32),
+}
+
+#[derive(Error, Debug)]
+pub enum StateError {
+    #[error("failed to encode or decode save state")]
+    Encoding(#[from] bincode::Error),
+    #[error("save state does not match the machine layout")]
+    LayoutMismatch,
+    #[error("unsupported save state version {0}")]
+    UnsupportedVersion(u32),
+}
+
+#[derive(Error, Debug)]
+pub enum SidError {
+    #[error("not a PSID or RSID file")]
+    InvalidMagic,
+    #[error("unsupported SID file version {0}")]
+    UnsupportedVersion(u16),
+    #[error("SID file is truncated")]
+    Truncated,
+    #[error("subtune {0} does not exist")]
+    InvalidSubtune(u16),
+    #[error("no free memory for the player driver")]
+    NoDriverSpace,
+    #[error("CPU error while playing")]
+    Cpu(#[from] CpuError),
+}
+
+#[derive(Error, Debug)]
+pub enum SimError {
+    #[error("not a sim65 program")]
+    InvalidMagic,
+    #[error("unsupported sim65 header version {0}")]
+    UnsupportedVersion(u8),
+    #[error("unsupported CPU type {0}")]
+    UnsupportedCpu(u8),
+    #[error("sim65 program is truncated")]
+    Truncated,
+    #[error("program does not fit in memory")]
+    TooLarge,
+    #[error("maximum number of cycles reached")]
+    CycleLimit,
+    #[error("CPU error while running")]
+    Cpu(#[from] CpuError),
 }
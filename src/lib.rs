@@ -1,14 +1,23 @@
 #![doc = include_str!("../README.md")]
 
+pub mod audio;
+pub mod device;
 pub mod error;
+pub mod machine;
 pub mod mos6502;
+pub mod scheduler;
+pub mod sid;
+pub mod sim65;
+pub mod video;
+pub mod wdc65c816;
 
 #[cfg(test)]
 mod tests {
-    use crate::error::BusError;
+    use crate::error::{BusError, StateError};
     use crate::mos6502;
     use mos6502::*;
 
+    #[derive(Default)]
     struct TestBus(Vec<u8>);
 
     impl Bus for TestBus {
@@ -83,4 +92,467 @@ mod tests {
         }
         assert_eq!(last_pc, 0x06e5);
     }
+
+    struct RecordingBus {
+        memory: Vec<u8>,
+        accesses: Vec<(u16, AccessKind, bool)>,
+    }
+
+    impl AccessBus for RecordingBus {
+        fn read_access(&mut self, address: u16, kind: AccessKind) -> Result<u8, BusError> {
+            self.accesses.push((address, kind, false));
+            Ok(self.memory[address as usize])
+        }
+
+        fn write_access(
+            &mut self,
+            address: u16,
+            value: u8,
+            kind: AccessKind,
+        ) -> Result<(), BusError> {
+            self.accesses.push((address, kind, true));
+            self.memory[address as usize] = value;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_access_kinds() {
+        let mut bus = RecordingBus {
+            memory: vec![0; 0x10000],
+            accesses: Vec::new(),
+        };
+        // INC $10; PHA; BRK
+        bus.memory[0x0200..0x0205].copy_from_slice(&[0xE6, 0x10, 0x48, 0x00, 0x00]);
+        bus.memory[0xFFFE..].copy_from_slice(&[0x00, 0x03]);
+
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(0x0200);
+        for _ in 0..3 {
+            cpu.step(&mut bus).expect("Failed to step CPU");
+        }
+
+        use AccessKind::*;
+        assert_eq!(
+            bus.accesses,
+            vec![
+                (0x0200, OpcodeFetch, false),
+                (0x0201, Operand, false),
+                (0x0010, ReadModifyWrite, false),
+                (0x0010, Dummy, true),
+                (0x0010, ReadModifyWrite, true),
+                (0x0202, OpcodeFetch, false),
+                (0x0203, Dummy, false),
+                (0x01FF, Stack, true),
+                (0x0203, OpcodeFetch, false),
+                (0x0204, Dummy, false),
+                (0x01FE, Stack, true),
+                (0x01FD, Stack, true),
+                (0x01FC, Stack, true),
+                (0xFFFE, VectorPull, false),
+                (0xFFFF, VectorPull, false),
+            ]
+        );
+        assert_eq!(bus.memory[0x10], 1);
+        assert_eq!(cpu.program_counter(), 0x0300);
+    }
+
+    #[test]
+    fn test_nmos_dummy_cycles() {
+        let mut bus = RecordingBus {
+            memory: vec![0; 0x10000],
+            accesses: Vec::new(),
+        };
+        #[rustfmt::skip]
+        let program = [
+            0x58,             // CLI
+            0xA2, 0xFF,       // LDX #$FF
+            0xBD, 0x01, 0x12, // LDA $1201,X
+            0x9D, 0x00, 0x12, // STA $1200,X
+            0x48,             // PHA
+            0x68,             // PLA
+            0x20, 0x00, 0x03, // JSR $0300
+            0xF0, 0x80,       // BEQ $0190
+        ];
+        bus.memory[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+        // RTS at $0300 and RTI at $0400, the IRQ handler
+        bus.memory[0x0300] = 0x60;
+        bus.memory[0x0400] = 0x40;
+        bus.memory[0xFFFE..].copy_from_slice(&[0x00, 0x04]);
+
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(0x0200);
+        let mut cycles = Vec::new();
+        for _ in 0..9 {
+            cycles.push(cpu.step(&mut bus).expect("Failed to step CPU"));
+        }
+        cycles.push(cpu.irq(&mut bus).expect("Failed to perform IRQ"));
+        cycles.push(cpu.step(&mut bus).expect("Failed to step CPU"));
+        assert_eq!(cycles, [2, 2, 5, 5, 3, 4, 6, 6, 4, 7, 6]);
+        assert_eq!(cpu.program_counter(), 0x0190);
+
+        use AccessKind::*;
+        let expected = vec![
+            // CLI and LDX #$FF
+            (0x0200, OpcodeFetch, false),
+            (0x0201, Dummy, false),
+            (0x0201, OpcodeFetch, false),
+            (0x0202, Operand, false),
+            // LDA $1201,X reads $1200 before the carry reaches the high byte
+            (0x0203, OpcodeFetch, false),
+            (0x0204, Operand, false),
+            (0x0205, Operand, false),
+            (0x1200, Dummy, false),
+            (0x1300, Data, false),
+            // STA $1200,X always takes the extra cycle
+            (0x0206, OpcodeFetch, false),
+            (0x0207, Operand, false),
+            (0x0208, Operand, false),
+            (0x12FF, Dummy, false),
+            (0x12FF, Data, true),
+            // PHA and PLA, which reads the stack before incrementing the pointer
+            (0x0209, OpcodeFetch, false),
+            (0x020A, Dummy, false),
+            (0x01FF, Stack, true),
+            (0x020A, OpcodeFetch, false),
+            (0x020B, Dummy, false),
+            (0x01FE, Dummy, false),
+            (0x01FF, Stack, false),
+            // JSR fetches the high byte of the target after pushing
+            (0x020B, OpcodeFetch, false),
+            (0x020C, Operand, false),
+            (0x01FF, Dummy, false),
+            (0x01FF, Stack, true),
+            (0x01FE, Stack, true),
+            (0x020D, Operand, false),
+            // RTS reads the return address before moving past it
+            (0x0300, OpcodeFetch, false),
+            (0x0301, Dummy, false),
+            (0x01FD, Dummy, false),
+            (0x01FE, Stack, false),
+            (0x01FF, Stack, false),
+            (0x020D, Dummy, false),
+            // A taken BEQ across a page reads the next opcode and the unfixed target
+            (0x020E, OpcodeFetch, false),
+            (0x020F, Operand, false),
+            (0x0210, Dummy, false),
+            (0x0290, Dummy, false),
+            // The IRQ reads the opcode twice instead of fetching it
+            (0x0190, Dummy, false),
+            (0x0190, Dummy, false),
+            (0x01FF, Stack, true),
+            (0x01FE, Stack, true),
+            (0x01FD, Stack, true),
+            (0xFFFE, VectorPull, false),
+            (0xFFFF, VectorPull, false),
+            // RTI reads the stack before pulling
+            (0x0400, OpcodeFetch, false),
+            (0x0401, Dummy, false),
+            (0x01FC, Dummy, false),
+            (0x01FD, Stack, false),
+            (0x01FE, Stack, false),
+            (0x01FF, Stack, false),
+        ];
+        assert_eq!(bus.accesses, expected);
+    }
+
+    struct CountingBus {
+        ram: TestBus,
+        reads: usize,
+        writes: usize,
+    }
+
+    impl Bus for CountingBus {
+        fn read(&mut self, address: u16) -> Result<u8, BusError> {
+            self.reads += 1;
+            self.ram.read(address)
+        }
+
+        fn write(&mut self, address: u16, value: u8) -> Result<(), BusError> {
+            self.writes += 1;
+            self.ram.write(address, value)
+        }
+    }
+
+    #[test]
+    fn test_plain_bus_skips_dummy_cycles() {
+        let mut bus = CountingBus {
+            ram: TestBus(vec![0; 0x10000]),
+            reads: 0,
+            writes: 0,
+        };
+        // INC $10; NOP
+        bus.ram.0[0x0200..0x0203].copy_from_slice(&[0xE6, 0x10, 0xEA]);
+
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(0x0200);
+        assert_eq!(cpu.step(&mut bus).expect("Failed to step CPU"), 5);
+        assert_eq!(cpu.step(&mut bus).expect("Failed to step CPU"), 2);
+        assert_eq!((bus.reads, bus.writes), (4, 1));
+        assert_eq!(bus.ram.0[0x10], 1);
+    }
+
+    #[test]
+    fn test_rdy_and_so() {
+        let mut ram = TestBus(vec![0xEA; 0x10000]);
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(0x0200);
+
+        cpu.set_rdy(false);
+        let trace = cpu.trace(|address| ram.0[address as usize]);
+        assert!(trace.starts_with("0200            RDY stall"));
+        assert_eq!(cpu.step(&mut ram).expect("Failed to step CPU"), 1);
+        assert_eq!(cpu.program_counter(), 0x0200);
+
+        cpu.set_rdy(true);
+        let trace = cpu.trace(|address| ram.0[address as usize]);
+        assert!(trace.starts_with("0200  EA        NOP"));
+        assert_eq!(cpu.step(&mut ram).expect("Failed to step CPU"), 2);
+        assert_eq!(cpu.program_counter(), 0x0201);
+
+        // Interrupts wait for RDY; the NMI edge is kept until then
+        ram.0[0xFFFA..0xFFFC].copy_from_slice(&[0x00, 0x03]);
+        cpu.set_rdy(false);
+        assert_eq!(cpu.irq(&mut ram).expect("Failed to perform IRQ"), 0);
+        assert_eq!(cpu.nmi(&mut ram).expect("Failed to perform NMI"), 0);
+        assert_eq!(cpu.step(&mut ram).expect("Failed to step CPU"), 1);
+        assert_eq!(cpu.program_counter(), 0x0201);
+        cpu.set_rdy(true);
+        assert_eq!(cpu.step(&mut ram).expect("Failed to step CPU"), 7);
+        assert_eq!(cpu.program_counter(), 0x0300);
+        assert_eq!(cpu.step(&mut ram).expect("Failed to step CPU"), 2);
+
+        assert!(!cpu.flag_check(CpuFlags::Overflow));
+        cpu.set_so(true);
+        assert!(!cpu.flag_check(CpuFlags::Overflow));
+        cpu.set_so(false);
+        assert!(cpu.flag_check(CpuFlags::Overflow));
+    }
+
+    /// Pulls RDY low for `stall` cycles before the bus cycle numbered `stall_at`, and
+    /// records the kind of access that was held
+    struct StallingBus {
+        memory: Vec<u8>,
+        cycles: usize,
+        stall_at: usize,
+        stall: u32,
+        stalled: Option<AccessKind>,
+    }
+
+    impl AccessBus for StallingBus {
+        fn read_access(&mut self, address: u16, kind: AccessKind) -> Result<u8, BusError> {
+            if self.cycles == self.stall_at {
+                self.stalled = Some(kind);
+            }
+            Ok(self.memory[address as usize])
+        }
+
+        fn write_access(&mut self, address: u16, value: u8, _: AccessKind) -> Result<(), BusError> {
+            self.memory[address as usize] = value;
+            Ok(())
+        }
+
+        fn rdy_wait(&mut self) -> Result<u32, BusError> {
+            self.cycles += 1;
+            if self.cycles == self.stall_at {
+                Ok(self.stall)
+            } else {
+                Ok(0)
+            }
+        }
+    }
+
+    #[test]
+    fn test_rdy_mid_instruction() {
+        let mut bus = StallingBus {
+            memory: vec![0; 0x10000],
+            cycles: 0,
+            stall_at: 4,
+            stall: 3,
+            stalled: None,
+        };
+        // LDA $1234; STA $1235
+        bus.memory[0x0200..0x0206].copy_from_slice(&[0xAD, 0x34, 0x12, 0x8D, 0x35, 0x12]);
+        bus.memory[0x1234] = 0x42;
+
+        // The halt lands on the fourth read, that of $1234, after the opcode and operand
+        // fetches
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(0x0200);
+        assert_eq!(cpu.step(&mut bus).expect("Failed to step CPU"), 7);
+        assert_eq!(cpu.accumulator(), 0x42);
+        assert_eq!(bus.cycles, 4);
+        assert_eq!(bus.stalled, Some(AccessKind::Data));
+
+        // NMOS parts only halt on reads, so the write cycle is not held
+        bus.stall_at = 8;
+        assert_eq!(cpu.step(&mut bus).expect("Failed to step CPU"), 4);
+        assert_eq!(bus.cycles, 7);
+        assert_eq!(bus.memory[0x1235], 0x42);
+
+        // The 65C02 halts on the write as well
+        let mut cpu = MOS6502::with_variant(Variant::Wdc65c02);
+        cpu.set_program_counter(0x0203);
+        bus.cycles = 0;
+        bus.stall_at = 4;
+        assert_eq!(cpu.step(&mut bus).expect("Failed to step CPU"), 7);
+        assert_eq!(bus.cycles, 4);
+    }
+
+    #[test]
+    fn test_load_state_without_pins() {
+        // A state from before the RDY/SO pins and the other variants existed
+        let state = r#"{
+            "accumulator": 1, "x_register": 2, "y_register": 3, "stack_pointer": 253,
+            "status_register": 48, "program_counter": 512
+        }"#;
+        let mut cpu: MOS6502<TestBus> =
+            serde_json::from_str(state).expect("Failed to load old state");
+        assert_eq!(cpu.variant(), Variant::Nmos6502);
+        assert!(cpu.rdy() && cpu.so());
+        assert_eq!(cpu.stack_pointer_high(), 0x01);
+        assert_eq!(cpu.accumulator(), 1);
+
+        // PHA still lands on page one
+        let mut ram = TestBus(vec![0; 0x10000]);
+        ram.0[0x0200] = 0x48;
+        cpu.step(&mut ram).expect("Failed to step CPU");
+        assert_eq!(ram.0[0x01FD], 1);
+    }
+
+    #[test]
+    fn test_load_unversioned_bincode_state() {
+        // 0.1.0 encoded the registers alone: A, X, Y, SP, P, PC
+        let state = bincode::serialize(&(1u8, 2u8, 3u8, 253u8, 0x30u8, 0x0200u16)).unwrap();
+        let mut cpu: MOS6502<TestBus> = MOS6502::with_variant(Variant::Mos6510);
+        cpu.load_state(&state).expect("Failed to load old state");
+        assert_eq!(cpu.variant(), Variant::Nmos6502);
+        assert!(cpu.rdy() && cpu.so());
+        assert_eq!(cpu.accumulator(), 1);
+        assert_eq!(cpu.stack_pointer(), 253);
+        assert_eq!(cpu.program_counter(), 0x0200);
+
+        let state = cpu.save_state().unwrap();
+        assert_eq!(bincode::deserialize::<u32>(&state).unwrap(), STATE_VERSION);
+        let mut restored: MOS6502<TestBus> = MOS6502::new();
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.program_counter(), 0x0200);
+
+        let future = bincode::serialize(&(STATE_VERSION + 1, &cpu)).unwrap();
+        assert!(matches!(
+            restored.load_state(&future),
+            Err(StateError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_6510_io_port() {
+        // LDA #$FF; STA $00; LDA #$C7; STA $01; LDA #$07; STA $00; LDA $01
+        let program = [
+            0xA9, 0xFF, 0x85, 0x00, 0xA9, 0xC7, 0x85, 0x01, 0xA9, 0x07, 0x85, 0x00, 0xA5, 0x01,
+        ];
+        let mut ram = TestBus(vec![0; 0x10000]);
+        ram.0[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+        let mut cpu = MOS6502::with_variant(Variant::Mos6510);
+        cpu.set_program_counter(0x0200);
+        cpu.io_port_mut()
+            .expect("6510 has an I/O port")
+            .set_input(0b0001_0000, 0b0001_0000);
+        for _ in 0..7 {
+            cpu.step(&mut ram).expect("Failed to step CPU");
+        }
+
+        assert_eq!(ram.0[0x0000], 0);
+        assert_eq!(ram.0[0x0001], 0);
+        // Bits 6/7 still hold their charge, bit 4 is driven high by the host, and bits 3
+        // and 5 float at the 0 last written to them
+        assert_eq!(cpu.accumulator(), 0b1101_0111);
+        let port = cpu.io_port().expect("6510 has an I/O port");
+        assert_eq!(port.output_pins(), 0b0001_0111);
+
+        // Wait for the floating bits to fade
+        for _ in 0..200_000 {
+            cpu.set_program_counter(0x020C);
+            cpu.step(&mut ram).expect("Failed to step CPU");
+        }
+        assert_eq!(cpu.accumulator(), 0b0001_0111);
+        let port = cpu.io_port().expect("6510 has an I/O port");
+        assert_eq!(port.output_pins(), cpu.accumulator());
+    }
+
+    #[test]
+    fn test_6510_power_on_banking() {
+        // LDA $01
+        let mut ram = TestBus(vec![0; 0x10000]);
+        ram.0[0x0200..0x0202].copy_from_slice(&[0xA5, 0x01]);
+        ram.0[0xFFFC..0xFFFE].copy_from_slice(&[0x00, 0x02]);
+
+        // Without pull-ups the undriven pins read low, banking out every ROM
+        let mut cpu = MOS6502::with_variant(Variant::Mos6510);
+        cpu.reset(&mut ram).expect("Failed to reset CPU");
+        let port = cpu.io_port().expect("6510 has an I/O port");
+        assert_eq!(port.output_pins() & 0b0000_0111, 0);
+
+        // The C64 board pulls up LORAM, HIRAM and CHAREN
+        let mut cpu = MOS6502::with_variant(Variant::Mos6510);
+        cpu.io_port_mut()
+            .expect("6510 has an I/O port")
+            .set_pull_ups(0b0000_0111);
+        cpu.reset(&mut ram).expect("Failed to reset CPU");
+        let port = cpu.io_port().expect("6510 has an I/O port");
+        assert_eq!(port.direction(), 0);
+        assert_eq!(port.output_pins(), 0b0000_0111);
+        cpu.step(&mut ram).expect("Failed to step CPU");
+        assert_eq!(cpu.accumulator(), 0b0000_0111);
+    }
+
+    #[test]
+    fn test_6507_address_masking() {
+        // LDA #$42; STA $2080; LDA $E080
+        let program = [0xA9, 0x42, 0x8D, 0x80, 0x20, 0xAD, 0x80, 0xE0];
+        let mut ram = TestBus(vec![0; 0x2000]);
+        ram.0[0x1000..0x1000 + program.len()].copy_from_slice(&program);
+
+        let mut cpu = MOS6502::with_variant(Variant::Mos6507);
+        cpu.set_program_counter(0xF000);
+        cpu.step(&mut ram).expect("Failed to step CPU");
+        let trace = cpu.trace(|address| ram.0[address as usize]);
+        assert_eq!(
+            trace,
+            "F002 [$1002]  8D 80 20  STA $2080 [$0080]         A:42 X:00 Y:00 P:30 SP:FF"
+        );
+        for _ in 0..2 {
+            cpu.step(&mut ram).expect("Failed to step CPU");
+        }
+        assert_eq!(ram.0[0x0080], 0x42);
+        assert_eq!(cpu.accumulator(), 0x42);
+
+        // No interrupt pins are bonded out
+        assert_eq!(cpu.irq(&mut ram).expect("Failed to perform IRQ"), 0);
+        assert_eq!(cpu.nmi(&mut ram).expect("Failed to perform NMI"), 0);
+        assert_eq!(cpu.program_counter(), 0xF008);
+    }
+
+    #[test]
+    fn test_6503_and_6504_pins() {
+        let mut ram = TestBus(vec![0xEA; 0x2000]);
+        ram.0[0x1FFA..].copy_from_slice(&[0x00, 0x03, 0x00, 0x00, 0x00, 0x04]);
+
+        let mut cpu = MOS6502::with_variant(Variant::Mos6504);
+        cpu.set_program_counter(0x1200);
+        assert_eq!(cpu.nmi(&mut ram).expect("Failed to perform NMI"), 0);
+        assert_eq!(cpu.irq(&mut ram).expect("Failed to perform IRQ"), 7);
+        assert_eq!(cpu.program_counter(), 0x0400);
+
+        let mut cpu = MOS6502::with_variant(Variant::Mos6503);
+        cpu.set_program_counter(0x1200);
+        assert_eq!(cpu.nmi(&mut ram).expect("Failed to perform NMI"), 7);
+        // $FFFA reads as $0FFA on 12 address lines
+        assert_eq!(
+            cpu.program_counter(),
+            u16::from_le_bytes([ram.0[0x0FFA], ram.0[0x0FFB]])
+        );
+        assert_eq!(Variant::Mos6503.address_mask(), 0x0FFF);
+    }
 }
@@ -0,0 +1,897 @@
+use std::any::Any;
+use std::num::NonZeroU64;
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::device::Device;
+use crate::error::{BusError, CpuError, StateError};
+use crate::mos6502::{AccessBus, AccessKind, Variant, MOS6502};
+use crate::scheduler::{EventId, Scheduler};
+
+/// Cycles in a 60 Hz frame at 1 MHz
+const DEFAULT_FRAME_CYCLES: NonZeroU64 = match NonZeroU64::new(1_000_000 / 60) {
+    Some(cycles) => cycles,
+    None => panic!("frame cycles must be non-zero"),
+};
+
+struct Region {
+    start: u16,
+    data: Vec<u8>,
+    writable: bool,
+}
+
+impl Region {
+    #[inline]
+    fn offset(&self, address: u16) -> Option<usize> {
+        let offset = address.checked_sub(self.start)? as usize;
+        (offset < self.data.len()).then_some(offset)
+    }
+}
+
+/// Event on a [`Machine`]'s scheduler, handed to [`Device::event`] when due
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineEvent {
+    /// Index of the device, in the order the devices were added
+    pub device: usize,
+    /// Code the device interprets
+    pub code: u32,
+}
+
+struct MappedDevice {
+    range: RangeInclusive<u16>,
+    device: Box<dyn Device>,
+}
+
+/// Address space of a [`Machine`]: RAM and ROM regions with devices mapped on top.
+///
+/// Devices take priority over memory, so a device can punch a hole into a RAM region.
+/// Accesses that hit neither fail with [`BusError::InvalidRead`] or
+/// [`BusError::InvalidWrite`].
+///
+/// Every CPU bus cycle, dummy cycles included, ticks the devices by one cycle once the
+/// access is made, so devices see accesses in cycle order. [`MachineBus::read`] and
+/// [`MachineBus::write`] access the bus without spending a cycle.
+#[derive(Default)]
+pub struct MachineBus {
+    regions: Vec<Region>,
+    devices: Vec<MappedDevice>,
+    /// Cycles the devices have been ticked through during the current CPU operation
+    ticked: u32,
+}
+
+impl MachineBus {
+    #[inline]
+    fn device_at(&mut self, address: u16) -> Option<(&mut dyn Device, u16)> {
+        self.devices
+            .iter_mut()
+            .find(|mapped| mapped.range.contains(&address))
+            .map(|mapped| {
+                let offset = address - mapped.range.start();
+                (mapped.device.as_mut(), offset)
+            })
+    }
+
+    #[inline]
+    fn region_at(&mut self, address: u16) -> Option<(&mut Region, usize)> {
+        self.regions
+            .iter_mut()
+            .find_map(|region| region.offset(address).map(|offset| (region, offset)))
+    }
+
+    /// Read `address` without spending a cycle
+    pub fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        if let Some((device, offset)) = self.device_at(address) {
+            return device.read(offset);
+        }
+        self.region_at(address)
+            .map(|(region, offset)| region.data[offset])
+            .ok_or(BusError::InvalidRead(address))
+    }
+
+    /// Write `address` without spending a cycle
+    pub fn write(&mut self, address: u16, value: u8) -> Result<(), BusError> {
+        if let Some((device, offset)) = self.device_at(address) {
+            return device.write(offset, value);
+        }
+        match self.region_at(address) {
+            Some((region, offset)) if region.writable => {
+                region.data[offset] = value;
+                Ok(())
+            }
+            Some(_) => Err(BusError::ReadOnlyAddress(address)),
+            None => Err(BusError::InvalidWrite(address)),
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        for mapped in &mut self.devices {
+            mapped.device.tick(cycles);
+        }
+        self.ticked += cycles;
+    }
+
+    /// Perform the DMA reads devices request, ticking the devices through the cycles
+    /// each transfer halts the CPU for, and return the total
+    fn service_dma(&mut self) -> Result<u32, BusError> {
+        let mut stall = 0;
+        for index in 0..self.devices.len() {
+            while let Some(address) = self.devices[index].device.dma_request() {
+                let value = self.read(address)?;
+                let cycles = self.devices[index].device.dma_complete(value);
+                self.tick(cycles);
+                stall += cycles;
+            }
+        }
+        Ok(stall)
+    }
+
+    /// Combined IRQ and NMI lines of all devices, as wired-OR open-collector outputs
+    fn interrupt_lines(&self) -> (bool, bool) {
+        self.devices
+            .iter()
+            .fold((false, false), |(irq, nmi), mapped| {
+                (irq || mapped.device.irq(), nmi || mapped.device.nmi())
+            })
+    }
+}
+
+impl AccessBus for MachineBus {
+    fn read_access(&mut self, address: u16, kind: AccessKind) -> Result<u8, BusError> {
+        // Dummy reads of unmapped addresses see an open bus, and their value is unused
+        let value = match self.read(address) {
+            Err(BusError::InvalidRead(_)) if kind == AccessKind::Dummy => Ok(0),
+            result => result,
+        };
+        self.tick(1);
+        value
+    }
+
+    fn write_access(&mut self, address: u16, value: u8, _: AccessKind) -> Result<(), BusError> {
+        let result = self.write(address, value);
+        self.tick(1);
+        result
+    }
+
+    /// DMA transfers take the bus while RDY holds the CPU ahead of its next read
+    fn rdy_wait(&mut self) -> Result<u32, BusError> {
+        self.service_dma()
+    }
+}
+
+/// Builder for a [`Machine`]
+pub struct MachineBuilder {
+    variant: Variant,
+    frame_cycles: NonZeroU64,
+    bus: MachineBus,
+}
+
+impl Default for MachineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MachineBuilder {
+    pub fn new() -> Self {
+        Self {
+            variant: Variant::default(),
+            frame_cycles: DEFAULT_FRAME_CYCLES,
+            bus: MachineBus::default(),
+        }
+    }
+
+    /// CPU variant to build the machine around
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Number of cycles run by [`Machine::run_frame`]
+    pub fn frame_cycles(mut self, cycles: NonZeroU64) -> Self {
+        self.frame_cycles = cycles;
+        self
+    }
+
+    /// Map zero-filled RAM over `range`
+    pub fn ram(mut self, range: RangeInclusive<u16>) -> Self {
+        self.bus.regions.push(Region {
+            start: *range.start(),
+            data: vec![0; range.len()],
+            writable: true,
+        });
+        self
+    }
+
+    /// Map `data` as ROM starting at `start`
+    ///
+    /// # Panics
+    /// If `data` runs past the end of the address space.
+    pub fn rom(mut self, start: u16, data: impl Into<Vec<u8>>) -> Self {
+        let data = data.into();
+        assert!(
+            start as usize + data.len() <= 0x10000,
+            "ROM at {start:#06X} does not fit in the address space"
+        );
+        self.bus.regions.push(Region {
+            start,
+            data,
+            writable: false,
+        });
+        self
+    }
+
+    /// Map `device` over `range`. Devices are ticked in the order they were added.
+    pub fn device(mut self, range: RangeInclusive<u16>, device: impl Device) -> Self {
+        self.bus.devices.push(MappedDevice {
+            range,
+            device: Box::new(device),
+        });
+        self
+    }
+
+    pub fn build(self) -> Machine {
+        Machine {
+            cpu: MOS6502::with_variant(self.variant),
+            bus: self.bus,
+            scheduler: Scheduler::new(),
+            frame_cycles: self.frame_cycles,
+            nmi_line: false,
+        }
+    }
+}
+
+/// A CPU, its memory map and the devices on its bus, stepped together.
+///
+/// The devices are ticked on every bus cycle of an instruction, and by any cycles the
+/// CPU spends without a bus access at its end. DMA requests are served on the CPU's
+/// next read cycle, with the CPU halted as by RDY. After every instruction the devices'
+/// interrupt outputs are combined: NMI is taken on a rising edge of any device's output,
+/// IRQ while any device holds its output asserted.
+///
+/// Devices that only need to act at known cycles, such as a timer running out, can
+/// instead queue events on the machine's [`Scheduler`] with [`Machine::schedule_at`]. Due
+/// events are handed to [`Device::event`] at the end of the instruction that reaches
+/// their cycle, before the interrupt lines are sampled.
+pub struct Machine {
+    cpu: MOS6502<MachineBus>,
+    bus: MachineBus,
+    scheduler: Scheduler<MachineEvent>,
+    frame_cycles: NonZeroU64,
+    nmi_line: bool,
+}
+
+impl Machine {
+    pub fn builder() -> MachineBuilder {
+        MachineBuilder::new()
+    }
+
+    #[inline]
+    pub fn cpu(&self) -> &MOS6502<MachineBus> {
+        &self.cpu
+    }
+
+    #[inline]
+    pub fn cpu_mut(&mut self) -> &mut MOS6502<MachineBus> {
+        &mut self.cpu
+    }
+
+    #[inline]
+    pub fn bus(&mut self) -> &mut MachineBus {
+        &mut self.bus
+    }
+
+    /// Cycles elapsed since the machine was built
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.scheduler.now()
+    }
+
+    /// Queue `code` for the device at `device`, in the order the devices were added, to
+    /// fire at the absolute `cycle`
+    ///
+    /// # Panics
+    /// If there is no device at that index.
+    pub fn schedule_at(&mut self, cycle: u64, device: usize, code: u32) -> EventId {
+        assert!(
+            device < self.bus.devices.len(),
+            "no device at index {device}"
+        );
+        self.scheduler
+            .schedule_at(cycle, MachineEvent { device, code })
+    }
+
+    /// Move a pending event to another cycle. Returns false if it already fired or was
+    /// cancelled.
+    pub fn reschedule(&mut self, id: EventId, cycle: u64) -> bool {
+        self.scheduler.reschedule(id, cycle)
+    }
+
+    /// Remove a pending event, returning it if it had not fired yet
+    pub fn cancel(&mut self, id: EventId) -> Option<MachineEvent> {
+        self.scheduler.cancel(id)
+    }
+
+    /// Cycle of the earliest pending event
+    #[inline]
+    pub fn next_event_cycle(&self) -> Option<u64> {
+        self.scheduler.next_event_cycle()
+    }
+
+    /// First device of type `D`
+    pub fn device<D: Device>(&self) -> Option<&D> {
+        self.bus
+            .devices
+            .iter()
+            .find_map(|mapped| (mapped.device.as_ref() as &dyn Any).downcast_ref())
+    }
+
+    /// First device of type `D`
+    pub fn device_mut<D: Device>(&mut self) -> Option<&mut D> {
+        self.bus
+            .devices
+            .iter_mut()
+            .find_map(|mapped| (mapped.device.as_mut() as &mut dyn Any).downcast_mut())
+    }
+
+    /// Device of type `D` mapped at `address`, for machines with several chips of a kind
+    pub fn device_at<D: Device>(&mut self, address: u16) -> Option<&mut D> {
+        let (device, _) = self.bus.device_at(address)?;
+        (device as &mut dyn Any).downcast_mut()
+    }
+
+    /// Exit code requested by the first device that asked to stop, see
+    /// [`Device::exit_code`]
+    pub fn exit_code(&self) -> Option<u8> {
+        self.bus
+            .devices
+            .iter()
+            .find_map(|mapped| mapped.device.exit_code())
+    }
+
+    /// Reset all devices and the CPU, which runs its reset sequence and loads the
+    /// program counter from the reset vector
+    pub fn reset(&mut self) -> Result<(), CpuError> {
+        for mapped in &mut self.bus.devices {
+            mapped.device.reset();
+        }
+        self.nmi_line = false;
+        let cycles = self.timed(|cpu, bus| cpu.reset(bus))?;
+        self.scheduler.advance(cycles);
+        Ok(())
+    }
+
+    /// Step one instruction, fire the events that have come due, then take any
+    /// interrupt the devices raise. Returns the cycles spent, including DMA stalls and
+    /// the interrupt sequence.
+    pub fn step(&mut self) -> Result<u32, CpuError> {
+        let mut cycles = self.timed(|cpu, bus| cpu.step(bus))?;
+        let instruction_cycles = cycles;
+        self.scheduler.advance(cycles);
+        self.fire_due_events();
+
+        let (irq, nmi) = self.bus.interrupt_lines();
+        let nmi_edge = nmi && !self.nmi_line;
+        self.nmi_line = nmi;
+        cycles += if nmi_edge {
+            self.timed(|cpu, bus| cpu.nmi(bus))?
+        } else if irq {
+            self.timed(|cpu, bus| cpu.irq(bus))?
+        } else {
+            0
+        };
+        self.scheduler.advance(cycles - instruction_cycles);
+        Ok(cycles)
+    }
+
+    fn fire_due_events(&mut self) {
+        while let Some((_, event)) = self.scheduler.pop_due() {
+            let device = &mut self.bus.devices[event.device].device;
+            if let Some(delay) = device.event(event.code) {
+                self.scheduler.schedule_in(delay, event);
+            }
+        }
+    }
+
+    /// Run `operation` on the CPU, then tick the devices through the cycles it took
+    /// beyond its bus accesses
+    fn timed(
+        &mut self,
+        operation: impl FnOnce(&mut MOS6502<MachineBus>, &mut MachineBus) -> Result<u32, CpuError>,
+    ) -> Result<u32, CpuError> {
+        self.bus.ticked = 0;
+        let cycles = operation(&mut self.cpu, &mut self.bus)?;
+        self.bus.tick(cycles.saturating_sub(self.bus.ticked));
+        Ok(cycles)
+    }
+
+    /// Run for at least `cycles` cycles, stopping on an instruction boundary, or until a
+    /// device asks to exit. Returns the cycles actually run.
+    pub fn run_cycles(&mut self, cycles: u64) -> Result<u64, CpuError> {
+        let start = self.cycles();
+        let end = start + cycles;
+        while self.cycles() < end && self.exit_code().is_none() {
+            self.step()?;
+        }
+        Ok(self.cycles() - start)
+    }
+
+    /// Run until the next frame boundary, or until a device asks to exit. Frames are
+    /// counted from the machine's creation, so the overshoot of one frame is taken out of
+    /// the next.
+    pub fn run_frame(&mut self) -> Result<u64, CpuError> {
+        let frame_cycles = self.frame_cycles.get();
+        let frame_end = (self.cycles() / frame_cycles + 1) * frame_cycles;
+        let start = self.cycles();
+        while self.cycles() < frame_end && self.exit_code().is_none() {
+            self.step()?;
+        }
+        Ok(self.cycles() - start)
+    }
+
+    /// Encode the CPU, RAM and device states
+    pub fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        let ram: Vec<&[u8]> = self
+            .bus
+            .regions
+            .iter()
+            .filter(|region| region.writable)
+            .map(|region| region.data.as_slice())
+            .collect();
+        let devices = self
+            .bus
+            .devices
+            .iter()
+            .map(|mapped| mapped.device.save_state())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bincode::serialize(&(
+            self.cpu.save_state()?,
+            &self.scheduler,
+            self.nmi_line,
+            ram,
+            devices,
+        ))?)
+    }
+
+    /// Restore a state produced by [`Machine::save_state`] on a machine built the same way
+    ///
+    /// On error the machine is left as it was: devices that already loaded their part of
+    /// the state are put back from a snapshot taken beforehand.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        type State = (
+            Vec<u8>,
+            Scheduler<MachineEvent>,
+            bool,
+            Vec<Vec<u8>>,
+            Vec<Vec<u8>>,
+        );
+        let (cpu_state, scheduler, nmi_line, ram, devices): State = bincode::deserialize(state)?;
+        let mut cpu = MOS6502::new();
+        cpu.load_state(&cpu_state)?;
+
+        let mut writable: Vec<&mut Region> = self
+            .bus
+            .regions
+            .iter_mut()
+            .filter(|region| region.writable)
+            .collect();
+        if writable.len() != ram.len()
+            || writable
+                .iter()
+                .zip(&ram)
+                .any(|(region, data)| region.data.len() != data.len())
+            || self.bus.devices.len() != devices.len()
+        {
+            return Err(StateError::LayoutMismatch);
+        }
+
+        let snapshot = self
+            .bus
+            .devices
+            .iter()
+            .map(|mapped| mapped.device.save_state())
+            .collect::<Result<Vec<_>, _>>()?;
+        for (index, device) in devices.iter().enumerate() {
+            if let Err(error) = self.bus.devices[index].device.load_state(device) {
+                for (mapped, previous) in self.bus.devices[..=index].iter_mut().zip(&snapshot) {
+                    mapped.device.load_state(previous)?;
+                }
+                return Err(error);
+            }
+        }
+        for (region, data) in writable.iter_mut().zip(ram) {
+            region.data = data;
+        }
+        self.cpu = cpu;
+        self.scheduler = scheduler;
+        self.nmi_line = nmi_line;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::mos6502::CpuFlags;
+
+    /// Counts down and holds IRQ until the counter register is read
+    #[derive(Default, Serialize, Deserialize)]
+    struct CountdownTimer {
+        counter: u32,
+        period: u32,
+        interrupt: bool,
+        resets: u32,
+    }
+
+    impl Device for CountdownTimer {
+        fn read(&mut self, offset: u16) -> Result<u8, BusError> {
+            self.interrupt = false;
+            Ok(self.counter.to_le_bytes()[offset as usize & 3])
+        }
+
+        fn write(&mut self, _: u16, value: u8) -> Result<(), BusError> {
+            self.period = value as u32;
+            self.counter = self.period;
+            Ok(())
+        }
+
+        fn tick(&mut self, cycles: u32) {
+            if self.period == 0 {
+                return;
+            }
+            if cycles >= self.counter {
+                self.interrupt = true;
+                self.counter = self.period - (cycles - self.counter) % self.period;
+            } else {
+                self.counter -= cycles;
+            }
+        }
+
+        fn irq(&self) -> bool {
+            self.interrupt
+        }
+
+        fn reset(&mut self) {
+            self.resets += 1;
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>, StateError> {
+            Ok(bincode::serialize(self)?)
+        }
+
+        fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+            *self = bincode::deserialize(state)?;
+            Ok(())
+        }
+    }
+
+    /// Holds NMI while the last value written is non-zero
+    #[derive(Default)]
+    struct NmiLatch(bool);
+
+    impl Device for NmiLatch {
+        fn read(&mut self, _: u16) -> Result<u8, BusError> {
+            Ok(self.0 as u8)
+        }
+
+        fn write(&mut self, _: u16, value: u8) -> Result<(), BusError> {
+            self.0 = value != 0;
+            Ok(())
+        }
+
+        fn nmi(&self) -> bool {
+            self.0
+        }
+    }
+
+    /// Raises IRQ on every scheduled event until read, repeating every `period` cycles
+    #[derive(Default, Serialize, Deserialize)]
+    struct EventTimer {
+        period: u64,
+        interrupt: bool,
+    }
+
+    impl Device for EventTimer {
+        fn read(&mut self, _: u16) -> Result<u8, BusError> {
+            self.interrupt = false;
+            Ok(0)
+        }
+
+        fn write(&mut self, _: u16, _: u8) -> Result<(), BusError> {
+            Ok(())
+        }
+
+        fn irq(&self) -> bool {
+            self.interrupt
+        }
+
+        fn event(&mut self, _: u32) -> Option<u64> {
+            self.interrupt = true;
+            Some(self.period)
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>, StateError> {
+            Ok(bincode::serialize(self)?)
+        }
+
+        fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+            *self = bincode::deserialize(state)?;
+            Ok(())
+        }
+    }
+
+    /// Records the cycle of every write, after which it asks for a DMA read
+    #[derive(Default)]
+    struct Probe {
+        cycle: u32,
+        writes: Vec<u32>,
+        dma: Option<u16>,
+    }
+
+    impl Device for Probe {
+        fn read(&mut self, _: u16) -> Result<u8, BusError> {
+            Ok(0)
+        }
+
+        fn write(&mut self, _: u16, _: u8) -> Result<(), BusError> {
+            self.writes.push(self.cycle);
+            self.dma = Some(0xF000);
+            Ok(())
+        }
+
+        fn tick(&mut self, cycles: u32) {
+            self.cycle += cycles;
+        }
+
+        fn dma_request(&self) -> Option<u16> {
+            self.dma
+        }
+
+        fn dma_complete(&mut self, _: u8) -> u32 {
+            self.dma = None;
+            4
+        }
+    }
+
+    fn rom() -> Vec<u8> {
+        let mut rom = vec![0xEA; 0x1000];
+        // IRQ handler at $F800: INC $10, LDA $D000 (acknowledge), RTI
+        rom[0x800..0x808].copy_from_slice(&[0xE6, 0x10, 0xAD, 0x00, 0xD0, 0x40, 0xEA, 0xEA]);
+        // NMI handler at $F900: INC $11, RTI
+        rom[0x900..0x903].copy_from_slice(&[0xE6, 0x11, 0x40]);
+        // Main program at $F000: CLI, then NOPs that loop back
+        rom[0x000] = 0x58;
+        rom[0x7FD..0x800].copy_from_slice(&[0x4C, 0x01, 0xF0]);
+        rom[0xFFA..].copy_from_slice(&[0x00, 0xF9, 0x00, 0xF0, 0x00, 0xF8]);
+        rom
+    }
+
+    fn build() -> Machine {
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x07FF)
+            .rom(0xF000, rom())
+            .device(0xD000..=0xD003, CountdownTimer::default())
+            .device(0xD010..=0xD010, NmiLatch::default())
+            .frame_cycles(NonZeroU64::new(1000).unwrap())
+            .build();
+        machine.reset().expect("Failed to reset machine");
+        machine
+    }
+
+    #[test]
+    fn test_memory_map() {
+        let mut machine = build();
+        assert_eq!(machine.cpu().program_counter(), 0xF000);
+        let bus = machine.bus();
+        bus.write(0x0200, 0x42).unwrap();
+        assert_eq!(bus.read(0x0200).unwrap(), 0x42);
+        assert!(matches!(
+            bus.write(0xF000, 0x00),
+            Err(BusError::ReadOnlyAddress(0xF000))
+        ));
+        assert!(matches!(
+            bus.read(0x0800),
+            Err(BusError::InvalidRead(0x0800))
+        ));
+        assert!(matches!(
+            bus.write(0xC000, 0x00),
+            Err(BusError::InvalidWrite(0xC000))
+        ));
+        // Offsets are relative to the start of the device's range
+        bus.write(0xD002, 100).unwrap();
+        assert_eq!(bus.read(0xD000).unwrap(), 100);
+        assert_eq!(machine.device::<CountdownTimer>().unwrap().resets, 1);
+        assert!(machine.device_at::<NmiLatch>(0xD000).is_none());
+        assert!(machine.device_at::<NmiLatch>(0xD010).is_some());
+    }
+
+    #[test]
+    fn test_reset_after_running() {
+        let mut machine = build();
+        assert_eq!(machine.cpu().stack_pointer(), 0xFC);
+        machine.run_cycles(100).unwrap();
+        assert!(!machine.cpu().flag_check(CpuFlags::NoInterrupts));
+
+        // As on hardware, the stack pointer moves down three more and IRQs are masked
+        machine.reset().unwrap();
+        assert_eq!(machine.cpu().program_counter(), 0xF000);
+        assert_eq!(machine.cpu().stack_pointer(), 0xF9);
+        assert!(machine.cpu().flag_check(CpuFlags::NoInterrupts));
+        assert_eq!(machine.device::<CountdownTimer>().unwrap().resets, 2);
+    }
+
+    #[test]
+    fn test_device_interrupts() {
+        let mut machine = build();
+        machine.bus().write(0xD000, 200).unwrap();
+
+        // The reset sequence has taken the first seven cycles
+        assert_eq!(machine.cycles(), 7);
+        let ran = machine.run_frame().unwrap();
+        assert!(machine.cycles() >= 1000 && machine.cycles() == ran + 7);
+        // IRQs at cycles 207, 407, 607 and 807 have been handled and acknowledged, and
+        // the one at cycle 1007 is still to come
+        assert_eq!(machine.bus().read(0x0010).unwrap(), 4);
+        assert!(!machine.cpu().flag_check(CpuFlags::NoInterrupts));
+
+        // NMI is edge triggered: holding the line only interrupts once
+        machine.bus().write(0xD010, 1).unwrap();
+        machine.run_cycles(500).unwrap();
+        assert_eq!(machine.bus().read(0x0011).unwrap(), 1);
+        machine.bus().write(0xD010, 0).unwrap();
+        machine.run_cycles(10).unwrap();
+        machine.bus().write(0xD010, 1).unwrap();
+        machine.run_cycles(10).unwrap();
+        assert_eq!(machine.bus().read(0x0011).unwrap(), 2);
+
+        // Frames stay aligned to multiples of the frame length
+        machine.run_frame().unwrap();
+        assert!(machine.cycles() >= 2000 && machine.cycles() < 2010);
+    }
+
+    #[test]
+    fn test_cycle_order_and_dma() {
+        // NOP; STA $D000; INC $D000; NOP
+        let mut rom = vec![0xEA; 0x1000];
+        rom[..7].copy_from_slice(&[0xEA, 0x8D, 0x00, 0xD0, 0xEE, 0x00, 0xD0]);
+        rom[0xFFC..0xFFE].copy_from_slice(&[0x00, 0xF0]);
+        let mut machine = Machine::builder()
+            .rom(0xF000, rom)
+            .device(0xD000..=0xD000, Probe::default())
+            .build();
+        machine.reset().unwrap();
+        assert_eq!(machine.cycles(), 7);
+
+        // STA writes on its fourth cycle, and the DMA this asks for waits for a read
+        assert_eq!(machine.step().unwrap(), 2);
+        assert_eq!(machine.step().unwrap(), 4);
+        assert_eq!(machine.device::<Probe>().unwrap().writes, [12]);
+        // INC is held before its opcode fetch, then writes twice in its last cycles
+        assert_eq!(machine.step().unwrap(), 4 + 6);
+        assert_eq!(machine.device::<Probe>().unwrap().writes, [12, 21, 22]);
+        assert_eq!(machine.step().unwrap(), 4 + 2);
+        assert_eq!(machine.cycles(), 29);
+        assert_eq!(machine.device::<Probe>().unwrap().cycle, 29);
+    }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut machine = build();
+        machine.bus().write(0xD000, 150).unwrap();
+        machine.run_cycles(700).unwrap();
+        let state = machine.save_state().unwrap();
+
+        machine.run_cycles(700).unwrap();
+        let expected = (
+            machine.cycles(),
+            machine.cpu().program_counter(),
+            machine.bus().read(0x0010).unwrap(),
+        );
+
+        let mut restored = build();
+        restored.load_state(&state).unwrap();
+        restored.run_cycles(700).unwrap();
+        assert_eq!(
+            (
+                restored.cycles(),
+                restored.cpu().program_counter(),
+                restored.bus().read(0x0010).unwrap(),
+            ),
+            expected
+        );
+
+        let mut smaller = Machine::builder().ram(0x0000..=0x00FF).build();
+        assert!(matches!(
+            smaller.load_state(&state),
+            Err(StateError::LayoutMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_scheduled_timer_irq_on_exact_cycle() {
+        let build = || {
+            let mut machine = Machine::builder()
+                .ram(0x0000..=0x07FF)
+                .rom(0xF000, rom())
+                .device(
+                    0xD000..=0xD003,
+                    EventTimer {
+                        period: 200,
+                        ..Default::default()
+                    },
+                )
+                .build();
+            machine.reset().expect("Failed to reset machine");
+            machine
+        };
+        let mut machine = build();
+        let cancelled = machine.schedule_at(50, 0, 0);
+        let timer = machine.schedule_at(90, 0, 0);
+        assert!(machine.cancel(cancelled).is_some());
+        assert!(machine.reschedule(timer, 101));
+
+        // Reset takes 7 cycles and CLI 2, then NOPs end on every odd cycle
+        while machine.cycles() < 99 {
+            machine.step().unwrap();
+        }
+        assert_eq!(machine.cpu().program_counter(), 0xF02E);
+        let state = machine.save_state().unwrap();
+
+        // The NOP that reaches cycle 101 fires the event and the IRQ is taken right away
+        assert_eq!(machine.step().unwrap(), 2 + 7);
+        assert_eq!(machine.cpu().program_counter(), 0xF800);
+        assert_eq!(machine.next_event_cycle(), Some(301));
+
+        // The pending event is part of the save state
+        let mut restored = build();
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.next_event_cycle(), Some(101));
+        assert_eq!(restored.step().unwrap(), 2 + 7);
+        assert_eq!(restored.cpu().program_counter(), 0xF800);
+        assert_eq!(restored.cycles(), 108);
+    }
+
+    #[test]
+    fn test_load_state_failure_leaves_machine_unchanged() {
+        let mut machine = Machine::builder()
+            .ram(0x0000..=0x00FF)
+            .device(0xD000..=0xD003, CountdownTimer::default())
+            .device(0xD004..=0xD007, CountdownTimer::default())
+            .build();
+        let bus = machine.bus();
+        bus.write(0xD000, 10).unwrap();
+        bus.write(0xD004, 20).unwrap();
+        bus.write(0x0010, 0x42).unwrap();
+        let state = machine.save_state().unwrap();
+
+        type State = (
+            Vec<u8>,
+            Scheduler<MachineEvent>,
+            bool,
+            Vec<Vec<u8>>,
+            Vec<Vec<u8>>,
+        );
+        let (cpu, scheduler, nmi_line, mut ram, mut devices): State =
+            bincode::deserialize(&state).unwrap();
+        ram[0][0x10] = 0x99;
+        devices[0] = bincode::serialize(&CountdownTimer {
+            counter: 99,
+            period: 99,
+            ..Default::default()
+        })
+        .unwrap();
+        devices[1].truncate(1);
+        let corrupt = bincode::serialize(&(&cpu, &scheduler, nmi_line, ram, devices)).unwrap();
+
+        assert!(matches!(
+            machine.load_state(&corrupt),
+            Err(StateError::Encoding(_))
+        ));
+        let bus = machine.bus();
+        assert_eq!(bus.read(0xD000).unwrap(), 10);
+        assert_eq!(bus.read(0xD004).unwrap(), 20);
+        assert_eq!(bus.read(0x0010).unwrap(), 0x42);
+    }
+}
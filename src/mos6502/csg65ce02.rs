@@ -0,0 +1,543 @@
+use serde::{Deserialize, Serialize};
+
+use crate::mos6502::*;
+
+/// Registers the 65CE02 family adds to the 6502
+#[derive(Clone, Serialize, Deserialize)]
+pub(in crate::mos6502) struct Csg65ce02Extension {
+    pub(in crate::mos6502) z_register: u8,
+    pub(in crate::mos6502) base_page: u8,
+    pub(in crate::mos6502) stack_pointer_high: u8,
+    /// 16-bit stack pointer, i.e. the E flag cleared
+    pub(in crate::mos6502) extended_stack: bool,
+    /// A, X, Y and Z as latched by the last 45GS02 MAP instruction
+    pub(in crate::mos6502) memory_map: [u8; 4],
+    /// Interrupts are inhibited from MAP until the following EOM
+    pub(in crate::mos6502) map_in_progress: bool,
+}
+
+impl Csg65ce02Extension {
+    pub(in crate::mos6502) fn new() -> Self {
+        Self {
+            z_register: 0,
+            base_page: 0x00,
+            stack_pointer_high: 0x01,
+            extended_stack: false,
+            memory_map: [0; 4],
+            map_in_progress: false,
+        }
+    }
+}
+
+impl<T: AccessBus> OpcodeFunctionArray<T> {
+    /// Opcode table shared by the 65CE02 and the 45GS02, which differ only in the
+    /// handlers of $42 (NEG, or the quad prefix) and $5C (AUG, or MAP).
+    ///
+    /// The 65CE02 drops the dead cycles of the NMOS core, so most instructions take one
+    /// cycle per byte fetched or transferred and page crossings cost nothing extra.
+    #[rustfmt::skip]
+    pub(in crate::mos6502) const CSG65CE02: Self = OpcodeFunctionArray([
+        (MOS6502::brk, AddressingMode::Implied, Cycles::Fixed(7)),              // 00
+        (MOS6502::ora, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // 01
+        (MOS6502::cle, AddressingMode::Implied, Cycles::Fixed(1)),              // 02
+        (MOS6502::see, AddressingMode::Implied, Cycles::Fixed(1)),              // 03
+        (MOS6502::tsb, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 04
+        (MOS6502::ora, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 05
+        (MOS6502::asl, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 06
+        (MOS6502::rmb0, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 07
+        (MOS6502::php, AddressingMode::Implied, Cycles::Fixed(2)),              // 08
+        (MOS6502::ora, AddressingMode::Immediate, Cycles::Fixed(2)),            // 09
+        (MOS6502::asl, AddressingMode::Accumulator, Cycles::Fixed(1)),          // 0A
+        (MOS6502::tsy, AddressingMode::Implied, Cycles::Fixed(1)),              // 0B
+        (MOS6502::tsb, AddressingMode::Absolute, Cycles::Fixed(5)),             // 0C
+        (MOS6502::ora, AddressingMode::Absolute, Cycles::Fixed(4)),             // 0D
+        (MOS6502::asl, AddressingMode::Absolute, Cycles::Fixed(5)),             // 0E
+        (MOS6502::bbr0, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 0F
+        (MOS6502::bpl, AddressingMode::Relative, Cycles::Branch(2, 1)),         // 10
+        (MOS6502::ora, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // 11
+        (MOS6502::ora, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // 12
+        (MOS6502::bpl, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // 13
+        (MOS6502::trb, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 14
+        (MOS6502::ora, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 15
+        (MOS6502::asl, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 16
+        (MOS6502::rmb1, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 17
+        (MOS6502::clc, AddressingMode::Implied, Cycles::Fixed(1)),              // 18
+        (MOS6502::ora, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // 19
+        (MOS6502::ina, AddressingMode::Accumulator, Cycles::Fixed(1)),          // 1A
+        (MOS6502::inz, AddressingMode::Implied, Cycles::Fixed(1)),              // 1B
+        (MOS6502::trb, AddressingMode::Absolute, Cycles::Fixed(5)),             // 1C
+        (MOS6502::ora, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 1D
+        (MOS6502::asl, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 1E
+        (MOS6502::bbr1, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 1F
+        (MOS6502::jsr, AddressingMode::Absolute, Cycles::Fixed(5)),             // 20
+        (MOS6502::and, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // 21
+        (MOS6502::jsr, AddressingMode::Indirect, Cycles::Fixed(7)),             // 22
+        (MOS6502::jsr, AddressingMode::AbsoluteXIndexIndirect, Cycles::Fixed(7)),// 23
+        (MOS6502::bit, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 24
+        (MOS6502::and, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 25
+        (MOS6502::rol, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 26
+        (MOS6502::rmb2, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 27
+        (MOS6502::plp, AddressingMode::Implied, Cycles::Fixed(3)),              // 28
+        (MOS6502::and, AddressingMode::Immediate, Cycles::Fixed(2)),            // 29
+        (MOS6502::rol, AddressingMode::Accumulator, Cycles::Fixed(1)),          // 2A
+        (MOS6502::tys, AddressingMode::Implied, Cycles::Fixed(1)),              // 2B
+        (MOS6502::bit, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2C
+        (MOS6502::and, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2D
+        (MOS6502::rol, AddressingMode::Absolute, Cycles::Fixed(5)),             // 2E
+        (MOS6502::bbr2, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 2F
+        (MOS6502::bmi, AddressingMode::Relative, Cycles::Branch(2, 1)),         // 30
+        (MOS6502::and, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // 31
+        (MOS6502::and, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // 32
+        (MOS6502::bmi, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // 33
+        (MOS6502::bit, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 34
+        (MOS6502::and, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 35
+        (MOS6502::rol, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 36
+        (MOS6502::rmb3, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 37
+        (MOS6502::sec, AddressingMode::Implied, Cycles::Fixed(1)),              // 38
+        (MOS6502::and, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // 39
+        (MOS6502::dea, AddressingMode::Accumulator, Cycles::Fixed(1)),          // 3A
+        (MOS6502::dez, AddressingMode::Implied, Cycles::Fixed(1)),              // 3B
+        (MOS6502::bit, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 3C
+        (MOS6502::and, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 3D
+        (MOS6502::rol, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 3E
+        (MOS6502::bbr3, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 3F
+        (MOS6502::rti, AddressingMode::Implied, Cycles::Fixed(5)),              // 40
+        (MOS6502::eor, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // 41
+        (MOS6502::neg, AddressingMode::Accumulator, Cycles::Variable(0)),       // 42
+        (MOS6502::asr, AddressingMode::Accumulator, Cycles::Fixed(1)),          // 43
+        (MOS6502::asr, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 44
+        (MOS6502::eor, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 45
+        (MOS6502::lsr, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 46
+        (MOS6502::rmb4, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 47
+        (MOS6502::pha, AddressingMode::Implied, Cycles::Fixed(2)),              // 48
+        (MOS6502::eor, AddressingMode::Immediate, Cycles::Fixed(2)),            // 49
+        (MOS6502::lsr, AddressingMode::Accumulator, Cycles::Fixed(1)),          // 4A
+        (MOS6502::taz, AddressingMode::Implied, Cycles::Fixed(1)),              // 4B
+        (MOS6502::jmp, AddressingMode::Absolute, Cycles::Fixed(3)),             // 4C
+        (MOS6502::eor, AddressingMode::Absolute, Cycles::Fixed(4)),             // 4D
+        (MOS6502::lsr, AddressingMode::Absolute, Cycles::Fixed(5)),             // 4E
+        (MOS6502::bbr4, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 4F
+        (MOS6502::bvc, AddressingMode::Relative, Cycles::Branch(2, 1)),         // 50
+        (MOS6502::eor, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // 51
+        (MOS6502::eor, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // 52
+        (MOS6502::bvc, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // 53
+        (MOS6502::asr, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 54
+        (MOS6502::eor, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 55
+        (MOS6502::lsr, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 56
+        (MOS6502::rmb5, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 57
+        (MOS6502::cli, AddressingMode::Implied, Cycles::Fixed(1)),              // 58
+        (MOS6502::eor, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // 59
+        (MOS6502::phy, AddressingMode::Implied, Cycles::Fixed(2)),              // 5A
+        (MOS6502::tab, AddressingMode::Implied, Cycles::Fixed(1)),              // 5B
+        (MOS6502::aug, AddressingMode::Implied, Cycles::Variable(0)),           // 5C
+        (MOS6502::eor, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 5D
+        (MOS6502::lsr, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 5E
+        (MOS6502::bbr5, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 5F
+        (MOS6502::rts, AddressingMode::Implied, Cycles::Fixed(4)),              // 60
+        (MOS6502::adc, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // 61
+        (MOS6502::rtn, AddressingMode::Immediate, Cycles::Fixed(6)),            // 62
+        (MOS6502::bsr, AddressingMode::RelativeLong, Cycles::Fixed(5)),         // 63
+        (MOS6502::stz, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 64
+        (MOS6502::adc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 65
+        (MOS6502::ror, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 66
+        (MOS6502::rmb6, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 67
+        (MOS6502::pla, AddressingMode::Implied, Cycles::Fixed(3)),              // 68
+        (MOS6502::adc, AddressingMode::Immediate, Cycles::Fixed(2)),            // 69
+        (MOS6502::ror, AddressingMode::Accumulator, Cycles::Fixed(1)),          // 6A
+        (MOS6502::tza, AddressingMode::Implied, Cycles::Fixed(1)),              // 6B
+        (MOS6502::jmp, AddressingMode::Indirect, Cycles::Fixed(5)),             // 6C
+        (MOS6502::adc, AddressingMode::Absolute, Cycles::Fixed(4)),             // 6D
+        (MOS6502::ror, AddressingMode::Absolute, Cycles::Fixed(5)),             // 6E
+        (MOS6502::bbr6, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 6F
+        (MOS6502::bvs, AddressingMode::Relative, Cycles::Branch(2, 1)),         // 70
+        (MOS6502::adc, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // 71
+        (MOS6502::adc, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // 72
+        (MOS6502::bvs, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // 73
+        (MOS6502::stz, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 74
+        (MOS6502::adc, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 75
+        (MOS6502::ror, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 76
+        (MOS6502::rmb7, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 77
+        (MOS6502::sei, AddressingMode::Implied, Cycles::Fixed(1)),              // 78
+        (MOS6502::adc, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // 79
+        (MOS6502::ply, AddressingMode::Implied, Cycles::Fixed(3)),              // 7A
+        (MOS6502::tba, AddressingMode::Implied, Cycles::Fixed(1)),              // 7B
+        (MOS6502::jmp, AddressingMode::AbsoluteXIndexIndirect, Cycles::Fixed(5)),// 7C
+        (MOS6502::adc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 7D
+        (MOS6502::ror, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 7E
+        (MOS6502::bbr7, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 7F
+        (MOS6502::bra, AddressingMode::Relative, Cycles::Branch(2, 1)),         // 80
+        (MOS6502::sta, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // 81
+        (MOS6502::sta, AddressingMode::StackRelativeIndirectYIndex, Cycles::Fixed(6)),// 82
+        (MOS6502::bra, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // 83
+        (MOS6502::sty, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 84
+        (MOS6502::sta, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 85
+        (MOS6502::stx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 86
+        (MOS6502::smb0, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 87
+        (MOS6502::dey, AddressingMode::Implied, Cycles::Fixed(1)),              // 88
+        (MOS6502::bit, AddressingMode::Immediate, Cycles::Fixed(2)),            // 89
+        (MOS6502::txa, AddressingMode::Implied, Cycles::Fixed(1)),              // 8A
+        (MOS6502::sty, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 8B
+        (MOS6502::sty, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8C
+        (MOS6502::sta, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8D
+        (MOS6502::stx, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8E
+        (MOS6502::bbs0, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 8F
+        (MOS6502::bcc, AddressingMode::Relative, Cycles::Branch(2, 1)),         // 90
+        (MOS6502::sta, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // 91
+        (MOS6502::sta, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // 92
+        (MOS6502::bcc, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // 93
+        (MOS6502::sty, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 94
+        (MOS6502::sta, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // 95
+        (MOS6502::stx, AddressingMode::ZeropageYIndex, Cycles::Fixed(3)),       // 96
+        (MOS6502::smb1, AddressingMode::Zeropage, Cycles::Fixed(4)),            // 97
+        (MOS6502::tya, AddressingMode::Implied, Cycles::Fixed(1)),              // 98
+        (MOS6502::sta, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // 99
+        (MOS6502::txs, AddressingMode::Implied, Cycles::Fixed(1)),              // 9A
+        (MOS6502::stx, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // 9B
+        (MOS6502::stz, AddressingMode::Absolute, Cycles::Fixed(4)),             // 9C
+        (MOS6502::sta, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 9D
+        (MOS6502::stz, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // 9E
+        (MOS6502::bbs1, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // 9F
+        (MOS6502::ldy, AddressingMode::Immediate, Cycles::Fixed(2)),            // A0
+        (MOS6502::lda, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // A1
+        (MOS6502::ldx, AddressingMode::Immediate, Cycles::Fixed(2)),            // A2
+        (MOS6502::ldz, AddressingMode::Immediate, Cycles::Fixed(2)),            // A3
+        (MOS6502::ldy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A4
+        (MOS6502::lda, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A5
+        (MOS6502::ldx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A6
+        (MOS6502::smb2, AddressingMode::Zeropage, Cycles::Fixed(4)),            // A7
+        (MOS6502::tay, AddressingMode::Implied, Cycles::Fixed(1)),              // A8
+        (MOS6502::lda, AddressingMode::Immediate, Cycles::Fixed(2)),            // A9
+        (MOS6502::tax, AddressingMode::Implied, Cycles::Fixed(1)),              // AA
+        (MOS6502::ldz, AddressingMode::Absolute, Cycles::Fixed(4)),             // AB
+        (MOS6502::ldy, AddressingMode::Absolute, Cycles::Fixed(4)),             // AC
+        (MOS6502::lda, AddressingMode::Absolute, Cycles::Fixed(4)),             // AD
+        (MOS6502::ldx, AddressingMode::Absolute, Cycles::Fixed(4)),             // AE
+        (MOS6502::bbs2, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // AF
+        (MOS6502::bcs, AddressingMode::Relative, Cycles::Branch(2, 1)),         // B0
+        (MOS6502::lda, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // B1
+        (MOS6502::lda, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // B2
+        (MOS6502::bcs, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // B3
+        (MOS6502::ldy, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // B4
+        (MOS6502::lda, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // B5
+        (MOS6502::ldx, AddressingMode::ZeropageYIndex, Cycles::Fixed(3)),       // B6
+        (MOS6502::smb3, AddressingMode::Zeropage, Cycles::Fixed(4)),            // B7
+        (MOS6502::clv, AddressingMode::Implied, Cycles::Fixed(1)),              // B8
+        (MOS6502::lda, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // B9
+        (MOS6502::tsx, AddressingMode::Implied, Cycles::Fixed(1)),              // BA
+        (MOS6502::ldz, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // BB
+        (MOS6502::ldy, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // BC
+        (MOS6502::lda, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // BD
+        (MOS6502::ldx, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // BE
+        (MOS6502::bbs3, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // BF
+        (MOS6502::cpy, AddressingMode::Immediate, Cycles::Fixed(2)),            // C0
+        (MOS6502::cmp, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // C1
+        (MOS6502::cpz, AddressingMode::Immediate, Cycles::Fixed(2)),            // C2
+        (MOS6502::dew, AddressingMode::Zeropage, Cycles::Fixed(6)),             // C3
+        (MOS6502::cpy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C4
+        (MOS6502::cmp, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C5
+        (MOS6502::dec, AddressingMode::Zeropage, Cycles::Fixed(4)),             // C6
+        (MOS6502::smb4, AddressingMode::Zeropage, Cycles::Fixed(4)),            // C7
+        (MOS6502::iny, AddressingMode::Implied, Cycles::Fixed(1)),              // C8
+        (MOS6502::cmp, AddressingMode::Immediate, Cycles::Fixed(2)),            // C9
+        (MOS6502::dex, AddressingMode::Implied, Cycles::Fixed(1)),              // CA
+        (MOS6502::asw, AddressingMode::Absolute, Cycles::Fixed(7)),             // CB
+        (MOS6502::cpy, AddressingMode::Absolute, Cycles::Fixed(4)),             // CC
+        (MOS6502::cmp, AddressingMode::Absolute, Cycles::Fixed(4)),             // CD
+        (MOS6502::dec, AddressingMode::Absolute, Cycles::Fixed(5)),             // CE
+        (MOS6502::bbs4, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // CF
+        (MOS6502::bne, AddressingMode::Relative, Cycles::Branch(2, 1)),         // D0
+        (MOS6502::cmp, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // D1
+        (MOS6502::cmp, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // D2
+        (MOS6502::bne, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // D3
+        (MOS6502::cpz, AddressingMode::Zeropage, Cycles::Fixed(3)),             // D4
+        (MOS6502::cmp, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // D5
+        (MOS6502::dec, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // D6
+        (MOS6502::smb5, AddressingMode::Zeropage, Cycles::Fixed(4)),            // D7
+        (MOS6502::cld, AddressingMode::Implied, Cycles::Fixed(1)),              // D8
+        (MOS6502::cmp, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // D9
+        (MOS6502::phx, AddressingMode::Implied, Cycles::Fixed(2)),              // DA
+        (MOS6502::phz, AddressingMode::Implied, Cycles::Fixed(2)),              // DB
+        (MOS6502::cpz, AddressingMode::Absolute, Cycles::Fixed(4)),             // DC
+        (MOS6502::cmp, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // DD
+        (MOS6502::dec, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // DE
+        (MOS6502::bbs5, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // DF
+        (MOS6502::cpx, AddressingMode::Immediate, Cycles::Fixed(2)),            // E0
+        (MOS6502::sbc, AddressingMode::XIndexIndirect, Cycles::Fixed(5)),       // E1
+        (MOS6502::lda, AddressingMode::StackRelativeIndirectYIndex, Cycles::Fixed(6)),// E2
+        (MOS6502::inw, AddressingMode::Zeropage, Cycles::Fixed(6)),             // E3
+        (MOS6502::cpx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E4
+        (MOS6502::sbc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E5
+        (MOS6502::inc, AddressingMode::Zeropage, Cycles::Fixed(4)),             // E6
+        (MOS6502::smb6, AddressingMode::Zeropage, Cycles::Fixed(4)),            // E7
+        (MOS6502::inx, AddressingMode::Implied, Cycles::Fixed(1)),              // E8
+        (MOS6502::sbc, AddressingMode::Immediate, Cycles::Fixed(2)),            // E9
+        (MOS6502::eom, AddressingMode::Implied, Cycles::Fixed(1)),              // EA
+        (MOS6502::row, AddressingMode::Absolute, Cycles::Fixed(7)),             // EB
+        (MOS6502::cpx, AddressingMode::Absolute, Cycles::Fixed(4)),             // EC
+        (MOS6502::sbc, AddressingMode::Absolute, Cycles::Fixed(4)),             // ED
+        (MOS6502::inc, AddressingMode::Absolute, Cycles::Fixed(5)),             // EE
+        (MOS6502::bbs6, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // EF
+        (MOS6502::beq, AddressingMode::Relative, Cycles::Branch(2, 1)),         // F0
+        (MOS6502::sbc, AddressingMode::IndirectYIndex, Cycles::Fixed(5)),       // F1
+        (MOS6502::sbc, AddressingMode::IndirectZIndex, Cycles::Fixed(5)),       // F2
+        (MOS6502::beq, AddressingMode::RelativeLong, Cycles::Branch(3, 1)),     // F3
+        (MOS6502::phw, AddressingMode::Immediate, Cycles::Fixed(5)),            // F4
+        (MOS6502::sbc, AddressingMode::ZeropageXIndex, Cycles::Fixed(3)),       // F5
+        (MOS6502::inc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // F6
+        (MOS6502::smb7, AddressingMode::Zeropage, Cycles::Fixed(4)),            // F7
+        (MOS6502::sed, AddressingMode::Implied, Cycles::Fixed(1)),              // F8
+        (MOS6502::sbc, AddressingMode::AbsoluteYIndex, Cycles::Fixed(4)),       // F9
+        (MOS6502::plx, AddressingMode::Implied, Cycles::Fixed(3)),              // FA
+        (MOS6502::plz, AddressingMode::Implied, Cycles::Fixed(3)),              // FB
+        (MOS6502::phw, AddressingMode::Absolute, Cycles::Fixed(7)),             // FC
+        (MOS6502::sbc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(4)),       // FD
+        (MOS6502::inc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // FE
+        (MOS6502::bbs7, AddressingMode::Zeropage, Cycles::Branch(4, 1)),        // FF
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mos6502::test_bus::{run, setup, TestBus};
+    use crate::mos6502::*;
+
+    #[test]
+    fn test_base_page_and_z_index() {
+        // LDA #$12; TAB; LDZ #$04; LDA #$34; STA $10; LDA #$13; STA $11;
+        // LDA #$AB; STA ($10),Z; LDA #$00; LDA ($10),Z; STZ $20
+        let program = [
+            0xA9, 0x12, 0x5B, 0xA3, 0x04, 0xA9, 0x34, 0x85, 0x10, 0xA9, 0x13, 0x85, 0x11, 0xA9,
+            0xAB, 0x92, 0x10, 0xA9, 0x00, 0xB2, 0x10, 0x64, 0x20,
+        ];
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &program);
+        run(&mut cpu, &mut bus, 12);
+
+        assert_eq!(cpu.base_page(), 0x12);
+        assert_eq!(cpu.z_register(), 0x04);
+        assert_eq!(bus.0[0x1210], 0x34);
+        assert_eq!(bus.0[0x1211], 0x13);
+        assert_eq!(bus.0[0x0010], 0x00);
+        assert_eq!(bus.0[0x1338], 0xAB);
+        assert_eq!(cpu.accumulator(), 0xAB);
+        assert_eq!(bus.0[0x1220], 0x04);
+    }
+
+    #[test]
+    fn test_extended_stack() {
+        // CLE; LDY #$05; TYS; LDX #$00; TXS; LDA #$77; PHA; PHW #$1234; TSY; PHP; SEE; PLA
+        let program = [
+            0x02, 0xA0, 0x05, 0x2B, 0xA2, 0x00, 0x9A, 0xA9, 0x77, 0x48, 0xF4, 0x34, 0x12, 0x0B,
+            0x08, 0x03, 0x68,
+        ];
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &program);
+        run(&mut cpu, &mut bus, 9);
+
+        assert!(cpu.extended_stack());
+        assert_eq!(bus.0[0x0500], 0x77);
+        assert_eq!(bus.0[0x04FF], 0x12);
+        assert_eq!(bus.0[0x04FE], 0x34);
+        assert_eq!(cpu.y_register(), 0x04);
+        assert_eq!(cpu.stack_pointer_high(), 0x04);
+        assert_eq!(cpu.stack_pointer(), 0xFD);
+
+        run(&mut cpu, &mut bus, 3);
+        assert!(!cpu.extended_stack());
+        // E was clear when the status was pushed
+        assert_eq!(cpu.accumulator() & 0b0011_0000, 0b0001_0000);
+        assert_eq!(cpu.stack_pointer(), 0xFD);
+    }
+
+    #[test]
+    fn test_word_instructions() {
+        // INW $40; DEW $40; INW $42; ASW $3000; ROW $3000
+        let program = [
+            0xE3, 0x40, 0xC3, 0x40, 0xE3, 0x42, 0xCB, 0x00, 0x30, 0xEB, 0x00, 0x30,
+        ];
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &program);
+        bus.0[0x40..0x44].copy_from_slice(&[0xFF, 0x00, 0xFF, 0xFF]);
+        bus.0[0x3000..0x3002].copy_from_slice(&[0x01, 0x80]);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[0x40..0x42], [0x00, 0x01]);
+        assert!(!cpu.flag_check(CpuFlags::Zero));
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[0x40..0x42], [0xFF, 0x00]);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[0x42..0x44], [0x00, 0x00]);
+        assert!(cpu.flag_check(CpuFlags::Zero));
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[0x3000..0x3002], [0x02, 0x00]);
+        assert!(cpu.flag_check(CpuFlags::Carry));
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[0x3000..0x3002], [0x05, 0x00]);
+        assert!(!cpu.flag_check(CpuFlags::Carry));
+    }
+
+    #[test]
+    fn test_branch_timing() {
+        #[rustfmt::skip]
+        let program = [
+            0xA9, 0x00,       // LDA #$00
+            0xD0, 0x02,       // BNE, not taken
+            0xF0, 0x01,       // BEQ, taken
+            0xEA,
+            0xD3, 0x00, 0x10, // BNE, word offset, not taken
+            0xF3, 0x02, 0x00, // BEQ, word offset, taken
+            0xEA,
+            0x8F, 0x50, 0x01, // BBS0 $50, taken
+        ];
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &program);
+        bus.0[0x50] = 0x01;
+
+        let cycles: Vec<u32> = (0..6).map(|_| run(&mut cpu, &mut bus, 1)).collect();
+        // A taken branch adds one cycle
+        assert_eq!(cycles, [2, 2, 3, 3, 4, 5]);
+        assert_eq!(cpu.program_counter(), 0x0212);
+    }
+
+    #[test]
+    fn test_branches_and_bit_instructions() {
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &[]);
+        let code: [(u16, &[u8]); 6] = [
+            (0x0200, &[0x63, 0xFD, 0x00]),       // BSR $02FF
+            (0x02FF, &[0x60]),                   // RTS
+            (0x0203, &[0x83, 0x10, 0x00]),       // BRA $0215
+            (0x0215, &[0x0F, 0x50, 0x02]),       // BBR0 $50, not taken
+            (0x0218, &[0x8F, 0x50, 0x02]),       // BBS0 $50, taken
+            (0x021D, &[0x97, 0x50, 0x07, 0x50]), // SMB1 $50; RMB0 $50
+        ];
+        for (address, bytes) in code {
+            let start = address as usize;
+            bus.0[start..start + bytes.len()].copy_from_slice(bytes);
+        }
+        bus.0[0x50] = 0x01;
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.program_counter(), 0x02FF);
+        assert_eq!(bus.0[0x01FE..0x0200], [0x02, 0x02]);
+
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(cpu.program_counter(), 0x0215);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.program_counter(), 0x0218);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.program_counter(), 0x021D);
+
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(bus.0[0x50], 0x02);
+    }
+
+    #[test]
+    fn test_accumulator_and_memory_bit_instructions() {
+        // LDA #$01; NEG; ASR; LDA #$30; TSB $60; TRB $60
+        let program = [0xA9, 0x01, 0x42, 0x43, 0xA9, 0x30, 0x04, 0x60, 0x14, 0x60];
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &program);
+        bus.0[0x60] = 0x0F;
+
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(cpu.accumulator(), 0xFF);
+        assert!(cpu.flag_check(CpuFlags::Negative));
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.accumulator(), 0xFF);
+        assert!(cpu.flag_check(CpuFlags::Carry));
+
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(bus.0[0x60], 0x3F);
+        assert!(cpu.flag_check(CpuFlags::Zero));
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[0x60], 0x0F);
+        assert!(!cpu.flag_check(CpuFlags::Zero));
+    }
+
+    #[test]
+    fn test_return_and_drop() {
+        // PHA; PHA; JSR $0300 ... $0300: RTN #$02
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &[0x48, 0x48, 0x20, 0x00, 0x03]);
+        bus.0[0x0300..0x0302].copy_from_slice(&[0x62, 0x02]);
+
+        run(&mut cpu, &mut bus, 4);
+        assert_eq!(cpu.program_counter(), 0x0205);
+        assert_eq!(cpu.stack_pointer(), 0xFF);
+    }
+
+    #[test]
+    fn test_quad_instructions() {
+        // LDQ $3000; CLC; ADCQ $3000; STQ $3004; INQ
+        let program = [
+            0x42, 0x42, 0xAD, 0x00, 0x30, 0x18, 0x42, 0x42, 0x6D, 0x00, 0x30, 0x42, 0x42, 0x8D,
+            0x04, 0x30, 0x42, 0x42, 0x1A,
+        ];
+        let (mut cpu, mut bus) = setup(Variant::Mega45gs02, &program);
+        bus.0[0x3000..0x3004].copy_from_slice(&[0x78, 0x56, 0x34, 0x12]);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(
+            [
+                cpu.accumulator(),
+                cpu.x_register(),
+                cpu.y_register(),
+                cpu.z_register()
+            ],
+            [0x78, 0x56, 0x34, 0x12]
+        );
+
+        run(&mut cpu, &mut bus, 3);
+        assert_eq!(bus.0[0x3004..0x3008], [0xF0, 0xAC, 0x68, 0x24]);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.accumulator(), 0xF1);
+        assert!(!cpu.flag_check(CpuFlags::Negative));
+        assert_eq!(cpu.program_counter(), 0x0200 + program.len() as u16);
+
+        // Without the quad extension the prefix is just two NEGs
+        let (mut cpu, mut bus) = setup(Variant::Csg65ce02, &[0xA9, 0x05, 0x42, 0x42]);
+        run(&mut cpu, &mut bus, 3);
+        assert_eq!(cpu.accumulator(), 0x05);
+    }
+
+    #[test]
+    fn test_map_inhibits_interrupts() {
+        // MAP; EOM
+        let (mut cpu, mut bus) = setup(Variant::Mega45gs02, &[0x5C, 0xEA]);
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.irq(&mut bus).expect("Failed to perform IRQ"), 0);
+        assert_eq!(cpu.nmi(&mut bus).expect("Failed to perform NMI"), 0);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.irq(&mut bus).expect("Failed to perform IRQ"), 7);
+    }
+
+    #[test]
+    fn test_nmi_during_map_taken_after_eom() {
+        // MAP; EOM; NOP; NOP
+        let (mut cpu, mut bus) = setup(Variant::Mega45gs02, &[0x5C, 0xEA, 0xEA, 0xEA]);
+        bus.0[0xFFFA..0xFFFC].copy_from_slice(&[0x00, 0x30]);
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.nmi(&mut bus).expect("Failed to perform NMI"), 0);
+
+        // The NMI waits for EOM and is taken in place of the next instruction
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.program_counter(), 0x0202);
+        assert_eq!(run(&mut cpu, &mut bus, 1), 7);
+        assert_eq!(cpu.program_counter(), 0x3000);
+    }
+
+    #[test]
+    fn test_deserialized_variant_keeps_opcodes() {
+        let (cpu, mut bus) = setup(Variant::Csg65ce02, &[0xA3, 0x42]);
+        let state = serde_json::to_string(&cpu).expect("Failed to serialize CPU");
+        let mut cpu: MOS6502<TestBus> =
+            serde_json::from_str(&state).expect("Failed to deserialize CPU");
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.z_register(), 0x42);
+    }
+}
@@ -0,0 +1,676 @@
+use crate::mos6502::{AddressingMode, Variant};
+
+/// NMOS 6502 mnemonics and addressing modes by opcode
+#[rustfmt::skip]
+const OPCODES: [Option<(&str, AddressingMode)>; 256] = [
+            Some(("BRK", AddressingMode::Implied)),                 // 00
+            Some(("ORA", AddressingMode::XIndexIndirect)),          // 01
+            None,                                                   // 02
+            None,                                                   // 03
+            None,                                                   // 04
+            Some(("ORA", AddressingMode::Zeropage)),                // 05
+            Some(("ASL", AddressingMode::Zeropage)),                // 06
+            None,                                                   // 07
+            Some(("PHP", AddressingMode::Implied)),                 // 08
+            Some(("ORA", AddressingMode::Immediate)),               // 09
+            Some(("ASL", AddressingMode::Accumulator)),             // 0A
+            None,                                                   // 0B
+            None,                                                   // 0C
+            Some(("ORA", AddressingMode::Absolute)),                // 0D
+            Some(("ASL", AddressingMode::Absolute)),                // 0E
+            None,                                                   // 0F
+            Some(("BPL", AddressingMode::Relative)),                // 10
+            Some(("ORA", AddressingMode::IndirectYIndex)),          // 11
+            None,                                                   // 12
+            None,                                                   // 13
+            None,                                                   // 14
+            Some(("ORA", AddressingMode::ZeropageXIndex)),          // 15
+            Some(("ASL", AddressingMode::ZeropageXIndex)),          // 16
+            None,                                                   // 17
+            Some(("CLC", AddressingMode::Implied)),                 // 18
+            Some(("ORA", AddressingMode::AbsoluteYIndex)),          // 19
+            None,                                                   // 1A
+            None,                                                   // 1B
+            None,                                                   // 1C
+            Some(("ORA", AddressingMode::AbsoluteXIndex)),          // 1D
+            Some(("ASL", AddressingMode::AbsoluteXIndex)),          // 1E
+            None,                                                   // 1F
+            Some(("JSR", AddressingMode::Absolute)),                // 20
+            Some(("AND", AddressingMode::XIndexIndirect)),          // 21
+            None,                                                   // 22
+            None,                                                   // 23
+            Some(("BIT", AddressingMode::Zeropage)),                // 24
+            Some(("AND", AddressingMode::Zeropage)),                // 25
+            Some(("ROL", AddressingMode::Zeropage)),                // 26
+            None,                                                   // 27
+            Some(("PLP", AddressingMode::Implied)),                 // 28
+            Some(("AND", AddressingMode::Immediate)),               // 29
+            Some(("ROL", AddressingMode::Accumulator)),             // 2A
+            None,                                                   // 2B
+            Some(("BIT", AddressingMode::Absolute)),                // 2C
+            Some(("AND", AddressingMode::Absolute)),                // 2D
+            Some(("ROL", AddressingMode::Absolute)),                // 2E
+            None,                                                   // 2F
+            Some(("BMI", AddressingMode::Relative)),                // 30
+            Some(("AND", AddressingMode::IndirectYIndex)),          // 31
+            None,                                                   // 32
+            None,                                                   // 33
+            None,                                                   // 34
+            Some(("AND", AddressingMode::ZeropageXIndex)),          // 35
+            Some(("ROL", AddressingMode::ZeropageXIndex)),          // 36
+            None,                                                   // 37
+            Some(("SEC", AddressingMode::Implied)),                 // 38
+            Some(("AND", AddressingMode::AbsoluteYIndex)),          // 39
+            None,                                                   // 3A
+            None,                                                   // 3B
+            None,                                                   // 3C
+            Some(("AND", AddressingMode::AbsoluteXIndex)),          // 3D
+            Some(("ROL", AddressingMode::AbsoluteXIndex)),          // 3E
+            None,                                                   // 3F
+            Some(("RTI", AddressingMode::Implied)),                 // 40
+            Some(("EOR", AddressingMode::XIndexIndirect)),          // 41
+            None,                                                   // 42
+            None,                                                   // 43
+            None,                                                   // 44
+            Some(("EOR", AddressingMode::Zeropage)),                // 45
+            Some(("LSR", AddressingMode::Zeropage)),                // 46
+            None,                                                   // 47
+            Some(("PHA", AddressingMode::Implied)),                 // 48
+            Some(("EOR", AddressingMode::Immediate)),               // 49
+            Some(("LSR", AddressingMode::Accumulator)),             // 4A
+            None,                                                   // 4B
+            Some(("JMP", AddressingMode::Absolute)),                // 4C
+            Some(("EOR", AddressingMode::Absolute)),                // 4D
+            Some(("LSR", AddressingMode::Absolute)),                // 4E
+            None,                                                   // 4F
+            Some(("BVC", AddressingMode::Relative)),                // 50
+            Some(("EOR", AddressingMode::IndirectYIndex)),          // 51
+            None,                                                   // 52
+            None,                                                   // 53
+            None,                                                   // 54
+            Some(("EOR", AddressingMode::ZeropageXIndex)),          // 55
+            Some(("LSR", AddressingMode::ZeropageXIndex)),          // 56
+            None,                                                   // 57
+            Some(("CLI", AddressingMode::Implied)),                 // 58
+            Some(("EOR", AddressingMode::AbsoluteYIndex)),          // 59
+            None,                                                   // 5A
+            None,                                                   // 5B
+            None,                                                   // 5C
+            Some(("EOR", AddressingMode::AbsoluteXIndex)),          // 5D
+            Some(("LSR", AddressingMode::AbsoluteXIndex)),          // 5E
+            None,                                                   // 5F
+            Some(("RTS", AddressingMode::Implied)),                 // 60
+            Some(("ADC", AddressingMode::XIndexIndirect)),          // 61
+            None,                                                   // 62
+            None,                                                   // 63
+            None,                                                   // 64
+            Some(("ADC", AddressingMode::Zeropage)),                // 65
+            Some(("ROR", AddressingMode::Zeropage)),                // 66
+            None,                                                   // 67
+            Some(("PLA", AddressingMode::Implied)),                 // 68
+            Some(("ADC", AddressingMode::Immediate)),               // 69
+            Some(("ROR", AddressingMode::Accumulator)),             // 6A
+            None,                                                   // 6B
+            Some(("JMP", AddressingMode::Indirect)),                // 6C
+            Some(("ADC", AddressingMode::Absolute)),                // 6D
+            Some(("ROR", AddressingMode::Absolute)),                // 6E
+            None,                                                   // 6F
+            Some(("BVS", AddressingMode::Relative)),                // 70
+            Some(("ADC", AddressingMode::IndirectYIndex)),          // 71
+            None,                                                   // 72
+            None,                                                   // 73
+            None,                                                   // 74
+            Some(("ADC", AddressingMode::ZeropageXIndex)),          // 75
+            Some(("ROR", AddressingMode::ZeropageXIndex)),          // 76
+            None,                                                   // 77
+            Some(("SEI", AddressingMode::Implied)),                 // 78
+            Some(("ADC", AddressingMode::AbsoluteYIndex)),          // 79
+            None,                                                   // 7A
+            None,                                                   // 7B
+            None,                                                   // 7C
+            Some(("ADC", AddressingMode::AbsoluteXIndex)),          // 7D
+            Some(("ROR", AddressingMode::AbsoluteXIndex)),          // 7E
+            None,                                                   // 7F
+            None,                                                   // 80
+            Some(("STA", AddressingMode::XIndexIndirect)),          // 81
+            None,                                                   // 82
+            None,                                                   // 83
+            Some(("STY", AddressingMode::Zeropage)),                // 84
+            Some(("STA", AddressingMode::Zeropage)),                // 85
+            Some(("STX", AddressingMode::Zeropage)),                // 86
+            None,                                                   // 87
+            Some(("DEY", AddressingMode::Implied)),                 // 88
+            None,                                                   // 89
+            Some(("TXA", AddressingMode::Implied)),                 // 8A
+            None,                                                   // 8B
+            Some(("STY", AddressingMode::Absolute)),                // 8C
+            Some(("STA", AddressingMode::Absolute)),                // 8D
+            Some(("STX", AddressingMode::Absolute)),                // 8E
+            None,                                                   // 8F
+            Some(("BCC", AddressingMode::Relative)),                // 90
+            Some(("STA", AddressingMode::IndirectYIndex)),          // 91
+            None,                                                   // 92
+            None,                                                   // 93
+            Some(("STY", AddressingMode::ZeropageXIndex)),          // 94
+            Some(("STA", AddressingMode::ZeropageXIndex)),          // 95
+            Some(("STX", AddressingMode::ZeropageYIndex)),          // 96
+            None,                                                   // 97
+            Some(("TYA", AddressingMode::Implied)),                 // 98
+            Some(("STA", AddressingMode::AbsoluteYIndex)),          // 99
+            Some(("TXS", AddressingMode::Implied)),                 // 9A
+            None,                                                   // 9B
+            None,                                                   // 9C
+            Some(("STA", AddressingMode::AbsoluteXIndex)),          // 9D
+            None,                                                   // 9E
+            None,                                                   // 9F
+            Some(("LDY", AddressingMode::Immediate)),               // A0
+            Some(("LDA", AddressingMode::XIndexIndirect)),          // A1
+            Some(("LDX", AddressingMode::Immediate)),               // A2
+            None,                                                   // A3
+            Some(("LDY", AddressingMode::Zeropage)),                // A4
+            Some(("LDA", AddressingMode::Zeropage)),                // A5
+            Some(("LDX", AddressingMode::Zeropage)),                // A6
+            None,                                                   // A7
+            Some(("TAY", AddressingMode::Implied)),                 // A8
+            Some(("LDA", AddressingMode::Immediate)),               // A9
+            Some(("TAX", AddressingMode::Implied)),                 // AA
+            None,                                                   // AB
+            Some(("LDY", AddressingMode::Absolute)),                // AC
+            Some(("LDA", AddressingMode::Absolute)),                // AD
+            Some(("LDX", AddressingMode::Absolute)),                // AE
+            None,                                                   // AF
+            Some(("BCS", AddressingMode::Relative)),                // B0
+            Some(("LDA", AddressingMode::IndirectYIndex)),          // B1
+            None,                                                   // B2
+            None,                                                   // B3
+            Some(("LDY", AddressingMode::ZeropageXIndex)),          // B4
+            Some(("LDA", AddressingMode::ZeropageXIndex)),          // B5
+            Some(("LDX", AddressingMode::ZeropageYIndex)),          // B6
+            None,                                                   // B7
+            Some(("CLV", AddressingMode::Implied)),                 // B8
+            Some(("LDA", AddressingMode::AbsoluteYIndex)),          // B9
+            Some(("TSX", AddressingMode::Implied)),                 // BA
+            None,                                                   // BB
+            Some(("LDY", AddressingMode::AbsoluteXIndex)),          // BC
+            Some(("LDA", AddressingMode::AbsoluteXIndex)),          // BD
+            Some(("LDX", AddressingMode::AbsoluteYIndex)),          // BE
+            None,                                                   // BF
+            Some(("CPY", AddressingMode::Immediate)),               // C0
+            Some(("CMP", AddressingMode::XIndexIndirect)),          // C1
+            None,                                                   // C2
+            None,                                                   // C3
+            Some(("CPY", AddressingMode::Zeropage)),                // C4
+            Some(("CMP", AddressingMode::Zeropage)),                // C5
+            Some(("DEC", AddressingMode::Zeropage)),                // C6
+            None,                                                   // C7
+            Some(("INY", AddressingMode::Implied)),                 // C8
+            Some(("CMP", AddressingMode::Immediate)),               // C9
+            Some(("DEX", AddressingMode::Implied)),                 // CA
+            None,                                                   // CB
+            Some(("CPY", AddressingMode::Absolute)),                // CC
+            Some(("CMP", AddressingMode::Absolute)),                // CD
+            Some(("DEC", AddressingMode::Absolute)),                // CE
+            None,                                                   // CF
+            Some(("BNE", AddressingMode::Relative)),                // D0
+            Some(("CMP", AddressingMode::IndirectYIndex)),          // D1
+            None,                                                   // D2
+            None,                                                   // D3
+            None,                                                   // D4
+            Some(("CMP", AddressingMode::ZeropageXIndex)),          // D5
+            Some(("DEC", AddressingMode::ZeropageXIndex)),          // D6
+            None,                                                   // D7
+            Some(("CLD", AddressingMode::Implied)),                 // D8
+            Some(("CMP", AddressingMode::AbsoluteYIndex)),          // D9
+            None,                                                   // DA
+            None,                                                   // DB
+            None,                                                   // DC
+            Some(("CMP", AddressingMode::AbsoluteXIndex)),          // DD
+            Some(("DEC", AddressingMode::AbsoluteXIndex)),          // DE
+            None,                                                   // DF
+            Some(("CPX", AddressingMode::Immediate)),               // E0
+            Some(("SBC", AddressingMode::XIndexIndirect)),          // E1
+            None,                                                   // E2
+            None,                                                   // E3
+            Some(("CPX", AddressingMode::Zeropage)),                // E4
+            Some(("SBC", AddressingMode::Zeropage)),                // E5
+            Some(("INC", AddressingMode::Zeropage)),                // E6
+            None,                                                   // E7
+            Some(("INX", AddressingMode::Implied)),                 // E8
+            Some(("SBC", AddressingMode::Immediate)),               // E9
+            Some(("NOP", AddressingMode::Implied)),                 // EA
+            None,                                                   // EB
+            Some(("CPX", AddressingMode::Absolute)),                // EC
+            Some(("SBC", AddressingMode::Absolute)),                // ED
+            Some(("INC", AddressingMode::Absolute)),                // EE
+            None,                                                   // EF
+            Some(("BEQ", AddressingMode::Relative)),                // F0
+            Some(("SBC", AddressingMode::IndirectYIndex)),          // F1
+            None,                                                   // F2
+            None,                                                   // F3
+            None,                                                   // F4
+            Some(("SBC", AddressingMode::ZeropageXIndex)),          // F5
+            Some(("INC", AddressingMode::ZeropageXIndex)),          // F6
+            None,                                                   // F7
+            Some(("SED", AddressingMode::Implied)),                 // F8
+            Some(("SBC", AddressingMode::AbsoluteYIndex)),          // F9
+            None,                                                   // FA
+            None,                                                   // FB
+            None,                                                   // FC
+            Some(("SBC", AddressingMode::AbsoluteXIndex)),          // FD
+            Some(("INC", AddressingMode::AbsoluteXIndex)),          // FE
+            None,                                                   // FF
+];
+
+/// Mnemonic and addressing mode of `opcode` on `variant`
+fn decode(variant: Variant, opcode: u8) -> Option<(&'static str, AddressingMode)> {
+    match variant {
+        Variant::Wdc65c02 => wdc65c02_opcode(opcode),
+        Variant::Csg65ce02 | Variant::Mega45gs02 => csg65ce02_opcode(variant, opcode),
+        Variant::Huc6280 => huc6280_opcode(opcode),
+        _ => OPCODES[opcode as usize],
+    }
+}
+
+/// 65C02 opcodes that differ from the NMOS table, the undefined ones being NOPs
+fn wdc65c02_opcode(opcode: u8) -> Option<(&'static str, AddressingMode)> {
+    match opcode {
+        0x02 | 0x22 | 0x42 | 0x62 | 0x82 | 0xC2 | 0xE2 => Some(("NOP", AddressingMode::Immediate)),
+        0x03 | 0x0B | 0x13 | 0x1B | 0x23 | 0x2B | 0x33 | 0x3B | 0x43 | 0x4B | 0x53 | 0x5B
+        | 0x63 | 0x6B | 0x73 | 0x7B | 0x83 | 0x8B | 0x93 | 0x9B | 0xA3 | 0xAB | 0xB3 | 0xBB
+        | 0xC3 | 0xD3 | 0xE3 | 0xEB | 0xF3 | 0xFB => Some(("NOP", AddressingMode::Implied)),
+        0x04 => Some(("TSB", AddressingMode::Zeropage)),
+        0x07 => Some(("RMB0", AddressingMode::Zeropage)),
+        0x0C => Some(("TSB", AddressingMode::Absolute)),
+        0x0F => Some(("BBR0", AddressingMode::Zeropage)),
+        0x12 => Some(("ORA", AddressingMode::ZeropageIndirect)),
+        0x14 => Some(("TRB", AddressingMode::Zeropage)),
+        0x17 => Some(("RMB1", AddressingMode::Zeropage)),
+        0x1A => Some(("INC", AddressingMode::Accumulator)),
+        0x1C => Some(("TRB", AddressingMode::Absolute)),
+        0x1F => Some(("BBR1", AddressingMode::Zeropage)),
+        0x27 => Some(("RMB2", AddressingMode::Zeropage)),
+        0x2F => Some(("BBR2", AddressingMode::Zeropage)),
+        0x32 => Some(("AND", AddressingMode::ZeropageIndirect)),
+        0x34 => Some(("BIT", AddressingMode::ZeropageXIndex)),
+        0x37 => Some(("RMB3", AddressingMode::Zeropage)),
+        0x3A => Some(("DEC", AddressingMode::Accumulator)),
+        0x3C => Some(("BIT", AddressingMode::AbsoluteXIndex)),
+        0x3F => Some(("BBR3", AddressingMode::Zeropage)),
+        0x44 => Some(("NOP", AddressingMode::Zeropage)),
+        0x47 => Some(("RMB4", AddressingMode::Zeropage)),
+        0x4F => Some(("BBR4", AddressingMode::Zeropage)),
+        0x52 => Some(("EOR", AddressingMode::ZeropageIndirect)),
+        0x54 | 0xD4 | 0xF4 => Some(("NOP", AddressingMode::ZeropageXIndex)),
+        0x57 => Some(("RMB5", AddressingMode::Zeropage)),
+        0x5A => Some(("PHY", AddressingMode::Implied)),
+        0x5C | 0xDC | 0xFC => Some(("NOP", AddressingMode::Absolute)),
+        0x5F => Some(("BBR5", AddressingMode::Zeropage)),
+        0x64 => Some(("STZ", AddressingMode::Zeropage)),
+        0x67 => Some(("RMB6", AddressingMode::Zeropage)),
+        0x6F => Some(("BBR6", AddressingMode::Zeropage)),
+        0x72 => Some(("ADC", AddressingMode::ZeropageIndirect)),
+        0x74 => Some(("STZ", AddressingMode::ZeropageXIndex)),
+        0x77 => Some(("RMB7", AddressingMode::Zeropage)),
+        0x7A => Some(("PLY", AddressingMode::Implied)),
+        0x7C => Some(("JMP", AddressingMode::AbsoluteXIndexIndirect)),
+        0x7F => Some(("BBR7", AddressingMode::Zeropage)),
+        0x80 => Some(("BRA", AddressingMode::Relative)),
+        0x87 => Some(("SMB0", AddressingMode::Zeropage)),
+        0x89 => Some(("BIT", AddressingMode::Immediate)),
+        0x8F => Some(("BBS0", AddressingMode::Zeropage)),
+        0x92 => Some(("STA", AddressingMode::ZeropageIndirect)),
+        0x97 => Some(("SMB1", AddressingMode::Zeropage)),
+        0x9C => Some(("STZ", AddressingMode::Absolute)),
+        0x9E => Some(("STZ", AddressingMode::AbsoluteXIndex)),
+        0x9F => Some(("BBS1", AddressingMode::Zeropage)),
+        0xA7 => Some(("SMB2", AddressingMode::Zeropage)),
+        0xAF => Some(("BBS2", AddressingMode::Zeropage)),
+        0xB2 => Some(("LDA", AddressingMode::ZeropageIndirect)),
+        0xB7 => Some(("SMB3", AddressingMode::Zeropage)),
+        0xBF => Some(("BBS3", AddressingMode::Zeropage)),
+        0xC7 => Some(("SMB4", AddressingMode::Zeropage)),
+        0xCF => Some(("BBS4", AddressingMode::Zeropage)),
+        0xD2 => Some(("CMP", AddressingMode::ZeropageIndirect)),
+        0xD7 => Some(("SMB5", AddressingMode::Zeropage)),
+        0xDA => Some(("PHX", AddressingMode::Implied)),
+        0xDF => Some(("BBS5", AddressingMode::Zeropage)),
+        0xE7 => Some(("SMB6", AddressingMode::Zeropage)),
+        0xEF => Some(("BBS6", AddressingMode::Zeropage)),
+        0xF2 => Some(("SBC", AddressingMode::ZeropageIndirect)),
+        0xF7 => Some(("SMB7", AddressingMode::Zeropage)),
+        0xFA => Some(("PLX", AddressingMode::Implied)),
+        0xFF => Some(("BBS7", AddressingMode::Zeropage)),
+        _ => OPCODES[opcode as usize],
+    }
+}
+
+/// 65CE02 opcodes that differ from the 65C02, with MAP in place of AUG on the 45GS02
+fn csg65ce02_opcode(variant: Variant, opcode: u8) -> Option<(&'static str, AddressingMode)> {
+    match opcode {
+        0x5C if variant == Variant::Mega45gs02 => Some(("MAP", AddressingMode::Implied)),
+        0x5C => Some(("AUG", AddressingMode::Implied)),
+        0x02 => Some(("CLE", AddressingMode::Implied)),
+        0x03 => Some(("SEE", AddressingMode::Implied)),
+        0x0B => Some(("TSY", AddressingMode::Implied)),
+        0x12 => Some(("ORA", AddressingMode::IndirectZIndex)),
+        0x13 => Some(("BPL", AddressingMode::RelativeLong)),
+        0x1B => Some(("INZ", AddressingMode::Implied)),
+        0x22 => Some(("JSR", AddressingMode::Indirect)),
+        0x23 => Some(("JSR", AddressingMode::AbsoluteXIndexIndirect)),
+        0x2B => Some(("TYS", AddressingMode::Implied)),
+        0x32 => Some(("AND", AddressingMode::IndirectZIndex)),
+        0x33 => Some(("BMI", AddressingMode::RelativeLong)),
+        0x3B => Some(("DEZ", AddressingMode::Implied)),
+        0x42 => Some(("NEG", AddressingMode::Accumulator)),
+        0x43 => Some(("ASR", AddressingMode::Accumulator)),
+        0x44 => Some(("ASR", AddressingMode::Zeropage)),
+        0x4B => Some(("TAZ", AddressingMode::Implied)),
+        0x52 => Some(("EOR", AddressingMode::IndirectZIndex)),
+        0x53 => Some(("BVC", AddressingMode::RelativeLong)),
+        0x54 => Some(("ASR", AddressingMode::ZeropageXIndex)),
+        0x5B => Some(("TAB", AddressingMode::Implied)),
+        0x62 => Some(("RTN", AddressingMode::Immediate)),
+        0x63 => Some(("BSR", AddressingMode::RelativeLong)),
+        0x6B => Some(("TZA", AddressingMode::Implied)),
+        0x72 => Some(("ADC", AddressingMode::IndirectZIndex)),
+        0x73 => Some(("BVS", AddressingMode::RelativeLong)),
+        0x7B => Some(("TBA", AddressingMode::Implied)),
+        0x82 => Some(("STA", AddressingMode::StackRelativeIndirectYIndex)),
+        0x83 => Some(("BRA", AddressingMode::RelativeLong)),
+        0x8B => Some(("STY", AddressingMode::AbsoluteXIndex)),
+        0x92 => Some(("STA", AddressingMode::IndirectZIndex)),
+        0x93 => Some(("BCC", AddressingMode::RelativeLong)),
+        0x9B => Some(("STX", AddressingMode::AbsoluteYIndex)),
+        0xA3 => Some(("LDZ", AddressingMode::Immediate)),
+        0xAB => Some(("LDZ", AddressingMode::Absolute)),
+        0xB2 => Some(("LDA", AddressingMode::IndirectZIndex)),
+        0xB3 => Some(("BCS", AddressingMode::RelativeLong)),
+        0xBB => Some(("LDZ", AddressingMode::AbsoluteXIndex)),
+        0xC2 => Some(("CPZ", AddressingMode::Immediate)),
+        0xC3 => Some(("DEW", AddressingMode::Zeropage)),
+        0xCB => Some(("ASW", AddressingMode::Absolute)),
+        0xD2 => Some(("CMP", AddressingMode::IndirectZIndex)),
+        0xD3 => Some(("BNE", AddressingMode::RelativeLong)),
+        0xD4 => Some(("CPZ", AddressingMode::Zeropage)),
+        0xDB => Some(("PHZ", AddressingMode::Implied)),
+        0xDC => Some(("CPZ", AddressingMode::Absolute)),
+        0xE2 => Some(("LDA", AddressingMode::StackRelativeIndirectYIndex)),
+        0xE3 => Some(("INW", AddressingMode::Zeropage)),
+        0xEA => Some(("EOM", AddressingMode::Implied)),
+        0xEB => Some(("ROW", AddressingMode::Absolute)),
+        0xF2 => Some(("SBC", AddressingMode::IndirectZIndex)),
+        0xF3 => Some(("BEQ", AddressingMode::RelativeLong)),
+        0xF4 => Some(("PHW", AddressingMode::Immediate)),
+        0xFB => Some(("PLZ", AddressingMode::Implied)),
+        0xFC => Some(("PHW", AddressingMode::Absolute)),
+        _ => wdc65c02_opcode(opcode),
+    }
+}
+
+/// HuC6280 opcodes that differ from the 65C02
+fn huc6280_opcode(opcode: u8) -> Option<(&'static str, AddressingMode)> {
+    match opcode {
+        0x02 => Some(("SXY", AddressingMode::Implied)),
+        0x03 => Some(("ST0", AddressingMode::Immediate)),
+        0x13 => Some(("ST1", AddressingMode::Immediate)),
+        0x22 => Some(("SAX", AddressingMode::Implied)),
+        0x23 => Some(("ST2", AddressingMode::Immediate)),
+        0x42 => Some(("SAY", AddressingMode::Implied)),
+        0x43 => Some(("TMA", AddressingMode::Immediate)),
+        0x44 => Some(("BSR", AddressingMode::Relative)),
+        0x53 => Some(("TAM", AddressingMode::Immediate)),
+        0x54 => Some(("CSL", AddressingMode::Implied)),
+        0x5C | 0xCB | 0xDB | 0xDC | 0xE2 | 0xFC => Some(("NOP", AddressingMode::Implied)),
+        0x62 => Some(("CLA", AddressingMode::Implied)),
+        0x73 => Some(("TII", AddressingMode::Implied)),
+        0x82 => Some(("CLX", AddressingMode::Implied)),
+        0x83 => Some(("TST", AddressingMode::Zeropage)),
+        0x93 => Some(("TST", AddressingMode::Absolute)),
+        0xA3 => Some(("TST", AddressingMode::ZeropageXIndex)),
+        0xB3 => Some(("TST", AddressingMode::AbsoluteXIndex)),
+        0xC2 => Some(("CLY", AddressingMode::Implied)),
+        0xC3 => Some(("TDD", AddressingMode::Implied)),
+        0xD3 => Some(("TIN", AddressingMode::Implied)),
+        0xD4 => Some(("CSH", AddressingMode::Implied)),
+        0xE3 => Some(("TIA", AddressingMode::Implied)),
+        0xF3 => Some(("TAI", AddressingMode::Implied)),
+        0xF4 => Some(("SET", AddressingMode::Implied)),
+        _ => wdc65c02_opcode(opcode),
+    }
+}
+
+/// An instruction decoded by [`disassemble`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disassembly {
+    /// Length of the instruction in bytes
+    pub length: u16,
+    /// Opcode and operand bytes
+    pub bytes: Vec<u8>,
+    /// Mnemonic and operand in assembler syntax
+    pub text: String,
+}
+
+/// Disassemble the instruction at `address`, reading its bytes through `peek`, which is
+/// given the addresses the CPU would put on the bus.
+///
+/// Decodes the instruction set of `variant`; opcodes it lacks show as `.BYTE`. On
+/// packages with fewer address lines, absolute operands and branch targets that alias
+/// are followed by the address the bus sees, as in `STA $F080 [$1080]`.
+pub fn disassemble(variant: Variant, address: u16, mut peek: impl FnMut(u16) -> u8) -> Disassembly {
+    let mask = variant.address_mask();
+    let opcode = peek(address & mask);
+    let Some((mnemonic, mode)) = decode(variant, opcode) else {
+        return Disassembly {
+            length: 1,
+            bytes: vec![opcode],
+            text: format!(".BYTE ${opcode:02X}"),
+        };
+    };
+    let length = match mnemonic {
+        "TII" | "TDD" | "TIN" | "TIA" | "TAI" => 7,
+        "AUG" => 4,
+        "PHW" if matches!(mode, AddressingMode::Immediate) => 3,
+        "TST" => operand_length(mode) + 2,
+        _ if mnemonic.starts_with("BB") => 3,
+        _ => operand_length(mode) + 1,
+    };
+    let bytes: Vec<u8> = (0..length)
+        .map(|offset| peek(address.wrapping_add(offset) & mask))
+        .collect();
+    let byte_at = |index: usize| bytes.get(index).copied().unwrap_or(0);
+    let word_at = |index: usize| u16::from_le_bytes([byte_at(index), byte_at(index + 1)]);
+    let end = address.wrapping_add(length);
+
+    // Operand of `mode` starting at byte `index`
+    let operand_at = |index: usize| {
+        let byte = byte_at(index);
+        let word = word_at(index);
+        match mode {
+            AddressingMode::Implied => String::new(),
+            AddressingMode::Accumulator => "A".into(),
+            AddressingMode::Immediate => format!("#${byte:02X}"),
+            AddressingMode::Zeropage => format!("${byte:02X}"),
+            AddressingMode::ZeropageXIndex => format!("${byte:02X},X"),
+            AddressingMode::ZeropageYIndex => format!("${byte:02X},Y"),
+            AddressingMode::XIndexIndirect => format!("(${byte:02X},X)"),
+            AddressingMode::IndirectYIndex => format!("(${byte:02X}),Y"),
+            AddressingMode::ZeropageIndirect => format!("(${byte:02X})"),
+            AddressingMode::IndirectZIndex => format!("(${byte:02X}),Z"),
+            AddressingMode::StackRelativeIndirectYIndex => format!("(${byte:02X},SP),Y"),
+            AddressingMode::Absolute => format_address(word, mask),
+            AddressingMode::AbsoluteXIndex => format!("${word:04X},X{}", physical(word, mask)),
+            AddressingMode::AbsoluteYIndex => format!("${word:04X},Y{}", physical(word, mask)),
+            AddressingMode::Indirect => format!("(${word:04X}){}", physical(word, mask)),
+            AddressingMode::AbsoluteXIndexIndirect => {
+                format!("(${word:04X},X){}", physical(word, mask))
+            }
+            AddressingMode::Relative => {
+                format_address(end.wrapping_add_signed(byte as i8 as i16), mask)
+            }
+            // Counted from the last byte of the instruction
+            AddressingMode::RelativeLong => {
+                format_address(end.wrapping_sub(1).wrapping_add(word), mask)
+            }
+        }
+    };
+    let operand = match mnemonic {
+        "TII" | "TDD" | "TIN" | "TIA" | "TAI" => {
+            format!(
+                "${:04X},${:04X},${:04X}",
+                word_at(1),
+                word_at(3),
+                word_at(5)
+            )
+        }
+        "AUG" => String::new(),
+        "PHW" if matches!(mode, AddressingMode::Immediate) => format!("#${:04X}", word_at(1)),
+        "TST" => format!("#${:02X},{}", byte_at(1), operand_at(2)),
+        _ if mnemonic.starts_with("BB") => {
+            let target = end.wrapping_add_signed(byte_at(2) as i8 as i16);
+            format!("${:02X},{}", byte_at(1), format_address(target, mask))
+        }
+        _ => operand_at(1),
+    };
+    let text = if operand.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{mnemonic} {operand}")
+    };
+    Disassembly {
+        length,
+        bytes,
+        text,
+    }
+}
+
+/// Bytes following the opcode
+fn operand_length(mode: AddressingMode) -> u16 {
+    match mode {
+        AddressingMode::Implied | AddressingMode::Accumulator => 0,
+        AddressingMode::Absolute
+        | AddressingMode::AbsoluteXIndex
+        | AddressingMode::AbsoluteYIndex
+        | AddressingMode::Indirect
+        | AddressingMode::AbsoluteXIndexIndirect
+        | AddressingMode::RelativeLong => 2,
+        _ => 1,
+    }
+}
+
+fn format_address(address: u16, mask: u16) -> String {
+    format!("${address:04X}{}", physical(address, mask))
+}
+
+/// Address seen on the bus in brackets, if the package's missing lines change it
+pub(in crate::mos6502) fn physical(address: u16, mask: u16) -> String {
+    if address & mask == address {
+        String::new()
+    } else {
+        format!(" [${:04X}]", address & mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disassemble_bytes(variant: Variant, address: u16, bytes: &[u8]) -> Disassembly {
+        disassemble(variant, address, |bus_address| {
+            bytes[bus_address.wrapping_sub(address & variant.address_mask()) as usize]
+        })
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let lda = disassemble_bytes(Variant::Nmos6502, 0x0200, &[0xB1, 0x10]);
+        assert_eq!(lda.text, "LDA ($10),Y");
+        assert_eq!(lda.length, 2);
+        let jmp = disassemble_bytes(Variant::Nmos6502, 0x0200, &[0x6C, 0xFC, 0xFF]);
+        assert_eq!(jmp.text, "JMP ($FFFC)");
+        assert_eq!(jmp.bytes, [0x6C, 0xFC, 0xFF]);
+        let bne = disassemble_bytes(Variant::Nmos6502, 0x0200, &[0xD0, 0xFE]);
+        assert_eq!(bne.text, "BNE $0200");
+        let unknown = disassemble_bytes(Variant::Nmos6502, 0x0200, &[0x02]);
+        assert_eq!((unknown.text.as_str(), unknown.length), (".BYTE $02", 1));
+    }
+
+    #[test]
+    fn test_6507_masked_addresses() {
+        // STA $F080 from $F000, which the cartridge sees at $1000
+        let sta = disassemble_bytes(Variant::Mos6507, 0xF000, &[0x8D, 0x80, 0xF0]);
+        assert_eq!(sta.text, "STA $F080 [$1080]");
+        let lda = disassemble_bytes(Variant::Mos6507, 0xF000, &[0xBD, 0x00, 0x20]);
+        assert_eq!(lda.text, "LDA $2000,X [$0000]");
+        let bpl = disassemble_bytes(Variant::Mos6507, 0xFFFE, &[0x10, 0x00]);
+        assert_eq!(bpl.text, "BPL $0000");
+        // Addresses below $2000 are the same on every package
+        let stx = disassemble_bytes(Variant::Mos6507, 0x1000, &[0x8E, 0x80, 0x1F]);
+        assert_eq!(stx.text, "STX $1F80");
+        let nmos = disassemble_bytes(Variant::Nmos6502, 0xF000, &[0x8D, 0x80, 0xF0]);
+        assert_eq!(nmos.text, "STA $F080");
+    }
+
+    #[test]
+    fn test_variant_opcodes() {
+        let cases: [(Variant, &[u8], &str); 14] = [
+            (Variant::Wdc65c02, &[0x80, 0xFE], "BRA $0200"),
+            (Variant::Wdc65c02, &[0x9C, 0x00, 0x30], "STZ $3000"),
+            (Variant::Wdc65c02, &[0x7C, 0x00, 0x30], "JMP ($3000,X)"),
+            (Variant::Wdc65c02, &[0x1A], "INC A"),
+            (Variant::Wdc65c02, &[0x0F, 0x12, 0xFD], "BBR0 $12,$0200"),
+            (Variant::Wdc65c02, &[0x5C, 0x00, 0x30], "NOP $3000"),
+            (Variant::Csg65ce02, &[0xB2, 0x10], "LDA ($10),Z"),
+            (Variant::Csg65ce02, &[0xE2, 0x03], "LDA ($03,SP),Y"),
+            (Variant::Csg65ce02, &[0xD3, 0xFF, 0xFF], "BNE $0201"),
+            (Variant::Csg65ce02, &[0xF4, 0x34, 0x12], "PHW #$1234"),
+            (Variant::Csg65ce02, &[0x5C, 0x00, 0x00, 0x00], "AUG"),
+            (Variant::Mega45gs02, &[0x5C], "MAP"),
+            (
+                Variant::Huc6280,
+                &[0x73, 0x00, 0x20, 0x00, 0x30, 0x10, 0x00],
+                "TII $2000,$3000,$0010",
+            ),
+            (
+                Variant::Huc6280,
+                &[0x93, 0x80, 0x00, 0x30],
+                "TST #$80,$3000",
+            ),
+        ];
+        for (variant, bytes, text) in cases {
+            let instruction = disassemble_bytes(variant, 0x0200, bytes);
+            assert_eq!(instruction.text, text);
+            assert_eq!(instruction.length as usize, bytes.len(), "{text}");
+        }
+        // The 65C02 opcodes are undefined on the NMOS core
+        let nmos = disassemble_bytes(Variant::Nmos6502, 0x0200, &[0x80, 0xFE]);
+        assert_eq!(nmos.text, ".BYTE $80");
+    }
+
+    #[test]
+    fn test_every_opcode_of_every_variant() {
+        let variants = [
+            Variant::Nmos6502,
+            Variant::Wdc65c02,
+            Variant::Csg65ce02,
+            Variant::Mega45gs02,
+            Variant::Huc6280,
+        ];
+        for variant in variants {
+            for opcode in 0..=0xFF {
+                let instruction =
+                    disassemble(
+                        variant,
+                        0x0200,
+                        |address| {
+                            if address == 0x0200 {
+                                opcode
+                            } else {
+                                0
+                            }
+                        },
+                    );
+                assert!((1..=7).contains(&instruction.length));
+            }
+        }
+    }
+}
@@ -0,0 +1,745 @@
+use serde::{Deserialize, Serialize};
+
+use crate::mos6502::*;
+
+/// Physical base of the hardware page, where MPR value $FF maps the on-die I/O
+pub(in crate::mos6502) const HARDWARE_PAGE: u32 = 0x1F_E000;
+
+/// Master clocks per timer count
+const TIMER_PRESCALER: u32 = 1024;
+
+const IRQ2_REQUEST: u8 = 1 << 0;
+const IRQ1_REQUEST: u8 = 1 << 1;
+const TIMER_REQUEST: u8 = 1 << 2;
+
+/// HuC6280 on-die 7-bit down counter, mapped at $0C00-$0FFF of the hardware page
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Timer {
+    reload: u8,
+    counter: u8,
+    running: bool,
+    prescaler: u32,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current value of the counter
+    #[inline]
+    pub fn counter(&self) -> u8 {
+        self.counter
+    }
+
+    /// Value the counter restarts from after an underflow
+    #[inline]
+    pub fn reload(&self) -> u8 {
+        self.reload
+    }
+
+    #[inline]
+    pub fn running(&self) -> bool {
+        self.running
+    }
+
+    /// Advance by `master_cycles` of the 7.16 MHz clock. Returns true on underflow.
+    pub(in crate::mos6502) fn tick(&mut self, master_cycles: u32) -> bool {
+        if !self.running {
+            return false;
+        }
+        let mut underflow = false;
+        self.prescaler += master_cycles;
+        while self.prescaler >= TIMER_PRESCALER {
+            self.prescaler -= TIMER_PRESCALER;
+            if self.counter == 0 {
+                self.counter = self.reload;
+                underflow = true;
+            } else {
+                self.counter -= 1;
+            }
+        }
+        underflow
+    }
+
+    pub(in crate::mos6502) fn read(&self) -> u8 {
+        self.counter
+    }
+
+    pub(in crate::mos6502) fn write(&mut self, offset: u32, value: u8) {
+        if offset & 1 == 0 {
+            self.reload = value & 0x7F;
+        } else {
+            let start = value & 1 != 0;
+            if start && !self.running {
+                self.counter = self.reload;
+                self.prescaler = 0;
+            }
+            self.running = start;
+        }
+    }
+}
+
+/// HuC6280 on-die interrupt controller, mapped at $1400-$17FF of the hardware page.
+///
+/// IRQ1 (the VDC on a PC Engine) and IRQ2 are level inputs driven by the host; the
+/// timer request is latched until acknowledged by a write to $1403.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct InterruptController {
+    disabled: u8,
+    irq1: bool,
+    irq2: bool,
+    timer_request: bool,
+}
+
+impl InterruptController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drive the IRQ1 input. `active` means the line is asserted.
+    #[inline]
+    pub fn set_irq1(&mut self, active: bool) {
+        self.irq1 = active;
+    }
+
+    /// Drive the IRQ2 input. `active` means the line is asserted.
+    #[inline]
+    pub fn set_irq2(&mut self, active: bool) {
+        self.irq2 = active;
+    }
+
+    /// Disable mask ($1402): bit 0 IRQ2, bit 1 IRQ1, bit 2 timer
+    #[inline]
+    pub fn disabled(&self) -> u8 {
+        self.disabled
+    }
+
+    /// Request status ($1403), in the same bit layout as the disable mask
+    pub fn requests(&self) -> u8 {
+        (self.irq2 as u8 * IRQ2_REQUEST)
+            | (self.irq1 as u8 * IRQ1_REQUEST)
+            | (self.timer_request as u8 * TIMER_REQUEST)
+    }
+
+    #[inline]
+    pub(in crate::mos6502) fn request_timer(&mut self) {
+        self.timer_request = true;
+    }
+
+    /// Highest priority request that is not disabled, with IRQ2 also asserted if `irq2`
+    pub(in crate::mos6502) fn pending(&self, irq2: bool) -> Option<InterruptKind> {
+        let requests = self.requests() | (irq2 as u8 * IRQ2_REQUEST);
+        let active = requests & !self.disabled;
+        if active & TIMER_REQUEST != 0 {
+            Some(InterruptKind::TimerIrq)
+        } else if active & IRQ1_REQUEST != 0 {
+            Some(InterruptKind::Irq1)
+        } else if active & IRQ2_REQUEST != 0 {
+            Some(InterruptKind::Irq)
+        } else {
+            None
+        }
+    }
+
+    pub(in crate::mos6502) fn read(&self, offset: u32) -> u8 {
+        match offset & 3 {
+            2 => self.disabled,
+            3 => self.requests(),
+            _ => 0,
+        }
+    }
+
+    pub(in crate::mos6502) fn write(&mut self, offset: u32, value: u8) {
+        match offset & 3 {
+            2 => self.disabled = value & 0x07,
+            3 => self.timer_request = false,
+            _ => {}
+        }
+    }
+}
+
+/// Paging registers, speed switch, T flag and on-die peripherals of the HuC6280
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(in crate::mos6502) struct Huc6280Extension {
+    pub(in crate::mos6502) mpr: [u8; 8],
+    /// T flag, set by SET for the following instruction only
+    pub(in crate::mos6502) memory_operation: bool,
+    pub(in crate::mos6502) high_speed: bool,
+    pub(in crate::mos6502) timer: Timer,
+    pub(in crate::mos6502) interrupt_controller: InterruptController,
+}
+
+impl Huc6280Extension {
+    pub(in crate::mos6502) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Translate a logical address through the MPR selected by its top three bits
+    #[inline]
+    pub(in crate::mos6502) fn physical_address(&self, address: u16) -> u32 {
+        (self.mpr[(address >> 13) as usize] as u32) << 13 | (address & 0x1FFF) as u32
+    }
+
+    /// Run the timer for `cycles` CPU cycles
+    #[inline]
+    pub(in crate::mos6502) fn tick(&mut self, cycles: u32) {
+        // The timer runs off the 7.16 MHz clock, which the slow mode divides by four
+        let master_cycles = if self.high_speed { cycles } else { cycles * 4 };
+        if self.timer.tick(master_cycles) {
+            self.interrupt_controller.request_timer();
+        }
+    }
+}
+
+/// Opcodes whose accumulator operand is replaced by zero page X while T is set:
+/// every addressing mode of ORA, AND, EOR and ADC
+pub(in crate::mos6502) fn uses_memory_operation(opcode: u8) -> bool {
+    opcode < 0x80
+        && matches!(
+            opcode & 0x1F,
+            0x01 | 0x05 | 0x09 | 0x0D | 0x11 | 0x12 | 0x15 | 0x19 | 0x1D
+        )
+}
+
+impl<T: AccessBus> OpcodeFunctionArray<T> {
+    /// HuC6280 opcode table. Undefined opcodes are one-byte NOPs, and the T flag
+    /// adds three cycles to the instruction it modifies.
+    #[rustfmt::skip]
+    pub(in crate::mos6502) const HUC6280: Self = OpcodeFunctionArray([
+        (MOS6502::brk, AddressingMode::Implied, Cycles::Fixed(8)),              // 00
+        (MOS6502::ora, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // 01
+        (MOS6502::sxy, AddressingMode::Implied, Cycles::Fixed(3)),              // 02
+        (MOS6502::st0, AddressingMode::Immediate, Cycles::Fixed(5)),            // 03
+        (MOS6502::tsb, AddressingMode::Zeropage, Cycles::Fixed(6)),             // 04
+        (MOS6502::ora, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 05
+        (MOS6502::asl, AddressingMode::Zeropage, Cycles::Fixed(6)),             // 06
+        (MOS6502::rmb0, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 07
+        (MOS6502::php, AddressingMode::Implied, Cycles::Fixed(3)),              // 08
+        (MOS6502::ora, AddressingMode::Immediate, Cycles::Fixed(2)),            // 09
+        (MOS6502::asl, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 0A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 0B
+        (MOS6502::tsb, AddressingMode::Absolute, Cycles::Fixed(7)),             // 0C
+        (MOS6502::ora, AddressingMode::Absolute, Cycles::Fixed(5)),             // 0D
+        (MOS6502::asl, AddressingMode::Absolute, Cycles::Fixed(7)),             // 0E
+        (MOS6502::bbr0, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 0F
+        (MOS6502::bpl, AddressingMode::Relative, Cycles::Branch(2, 2)),         // 10
+        (MOS6502::ora, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // 11
+        (MOS6502::ora, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // 12
+        (MOS6502::st1, AddressingMode::Immediate, Cycles::Fixed(5)),            // 13
+        (MOS6502::trb, AddressingMode::Zeropage, Cycles::Fixed(6)),             // 14
+        (MOS6502::ora, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 15
+        (MOS6502::asl, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 16
+        (MOS6502::rmb1, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 17
+        (MOS6502::clc, AddressingMode::Implied, Cycles::Fixed(2)),              // 18
+        (MOS6502::ora, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 19
+        (MOS6502::ina, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 1A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 1B
+        (MOS6502::trb, AddressingMode::Absolute, Cycles::Fixed(7)),             // 1C
+        (MOS6502::ora, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 1D
+        (MOS6502::asl, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 1E
+        (MOS6502::bbr1, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 1F
+        (MOS6502::jsr, AddressingMode::Absolute, Cycles::Fixed(7)),             // 20
+        (MOS6502::and, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // 21
+        (MOS6502::sax, AddressingMode::Implied, Cycles::Fixed(3)),              // 22
+        (MOS6502::st2, AddressingMode::Immediate, Cycles::Fixed(5)),            // 23
+        (MOS6502::bit, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 24
+        (MOS6502::and, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 25
+        (MOS6502::rol, AddressingMode::Zeropage, Cycles::Fixed(6)),             // 26
+        (MOS6502::rmb2, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 27
+        (MOS6502::plp, AddressingMode::Implied, Cycles::Fixed(4)),              // 28
+        (MOS6502::and, AddressingMode::Immediate, Cycles::Fixed(2)),            // 29
+        (MOS6502::rol, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 2A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 2B
+        (MOS6502::bit, AddressingMode::Absolute, Cycles::Fixed(5)),             // 2C
+        (MOS6502::and, AddressingMode::Absolute, Cycles::Fixed(5)),             // 2D
+        (MOS6502::rol, AddressingMode::Absolute, Cycles::Fixed(7)),             // 2E
+        (MOS6502::bbr2, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 2F
+        (MOS6502::bmi, AddressingMode::Relative, Cycles::Branch(2, 2)),         // 30
+        (MOS6502::and, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // 31
+        (MOS6502::and, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // 32
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 33
+        (MOS6502::bit, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 34
+        (MOS6502::and, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 35
+        (MOS6502::rol, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 36
+        (MOS6502::rmb3, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 37
+        (MOS6502::sec, AddressingMode::Implied, Cycles::Fixed(2)),              // 38
+        (MOS6502::and, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 39
+        (MOS6502::dea, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 3A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 3B
+        (MOS6502::bit, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 3C
+        (MOS6502::and, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 3D
+        (MOS6502::rol, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 3E
+        (MOS6502::bbr3, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 3F
+        (MOS6502::rti, AddressingMode::Implied, Cycles::Fixed(7)),              // 40
+        (MOS6502::eor, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // 41
+        (MOS6502::say, AddressingMode::Implied, Cycles::Fixed(3)),              // 42
+        (MOS6502::tma, AddressingMode::Immediate, Cycles::Fixed(4)),            // 43
+        (MOS6502::bsr, AddressingMode::Relative, Cycles::Fixed(8)),             // 44
+        (MOS6502::eor, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 45
+        (MOS6502::lsr, AddressingMode::Zeropage, Cycles::Fixed(6)),             // 46
+        (MOS6502::rmb4, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 47
+        (MOS6502::pha, AddressingMode::Implied, Cycles::Fixed(3)),              // 48
+        (MOS6502::eor, AddressingMode::Immediate, Cycles::Fixed(2)),            // 49
+        (MOS6502::lsr, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 4A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 4B
+        (MOS6502::jmp, AddressingMode::Absolute, Cycles::Fixed(4)),             // 4C
+        (MOS6502::eor, AddressingMode::Absolute, Cycles::Fixed(5)),             // 4D
+        (MOS6502::lsr, AddressingMode::Absolute, Cycles::Fixed(7)),             // 4E
+        (MOS6502::bbr4, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 4F
+        (MOS6502::bvc, AddressingMode::Relative, Cycles::Branch(2, 2)),         // 50
+        (MOS6502::eor, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // 51
+        (MOS6502::eor, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // 52
+        (MOS6502::tam, AddressingMode::Immediate, Cycles::Fixed(5)),            // 53
+        (MOS6502::csl, AddressingMode::Implied, Cycles::Fixed(3)),              // 54
+        (MOS6502::eor, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 55
+        (MOS6502::lsr, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 56
+        (MOS6502::rmb5, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 57
+        (MOS6502::cli, AddressingMode::Implied, Cycles::Fixed(2)),              // 58
+        (MOS6502::eor, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 59
+        (MOS6502::phy, AddressingMode::Implied, Cycles::Fixed(3)),              // 5A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 5B
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 5C
+        (MOS6502::eor, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 5D
+        (MOS6502::lsr, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 5E
+        (MOS6502::bbr5, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 5F
+        (MOS6502::rts, AddressingMode::Implied, Cycles::Fixed(7)),              // 60
+        (MOS6502::adc, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // 61
+        (MOS6502::cla, AddressingMode::Implied, Cycles::Fixed(2)),              // 62
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 63
+        (MOS6502::stz, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 64
+        (MOS6502::adc, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 65
+        (MOS6502::ror, AddressingMode::Zeropage, Cycles::Fixed(6)),             // 66
+        (MOS6502::rmb6, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 67
+        (MOS6502::pla, AddressingMode::Implied, Cycles::Fixed(4)),              // 68
+        (MOS6502::adc, AddressingMode::Immediate, Cycles::Fixed(2)),            // 69
+        (MOS6502::ror, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 6A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 6B
+        (MOS6502::jmp, AddressingMode::Indirect, Cycles::Fixed(7)),             // 6C
+        (MOS6502::adc, AddressingMode::Absolute, Cycles::Fixed(5)),             // 6D
+        (MOS6502::ror, AddressingMode::Absolute, Cycles::Fixed(7)),             // 6E
+        (MOS6502::bbr6, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 6F
+        (MOS6502::bvs, AddressingMode::Relative, Cycles::Branch(2, 2)),         // 70
+        (MOS6502::adc, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // 71
+        (MOS6502::adc, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // 72
+        (MOS6502::tii, AddressingMode::Implied, Cycles::Variable(17)),          // 73
+        (MOS6502::stz, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 74
+        (MOS6502::adc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 75
+        (MOS6502::ror, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 76
+        (MOS6502::rmb7, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 77
+        (MOS6502::sei, AddressingMode::Implied, Cycles::Fixed(2)),              // 78
+        (MOS6502::adc, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 79
+        (MOS6502::ply, AddressingMode::Implied, Cycles::Fixed(4)),              // 7A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 7B
+        (MOS6502::jmp, AddressingMode::AbsoluteXIndexIndirect, Cycles::Fixed(7)),// 7C
+        (MOS6502::adc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 7D
+        (MOS6502::ror, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 7E
+        (MOS6502::bbr7, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 7F
+        (MOS6502::bra, AddressingMode::Relative, Cycles::Fixed(4)),             // 80
+        (MOS6502::sta, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // 81
+        (MOS6502::clx, AddressingMode::Implied, Cycles::Fixed(2)),              // 82
+        (MOS6502::tst, AddressingMode::Zeropage, Cycles::Fixed(7)),             // 83
+        (MOS6502::sty, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 84
+        (MOS6502::sta, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 85
+        (MOS6502::stx, AddressingMode::Zeropage, Cycles::Fixed(4)),             // 86
+        (MOS6502::smb0, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 87
+        (MOS6502::dey, AddressingMode::Implied, Cycles::Fixed(2)),              // 88
+        (MOS6502::bit, AddressingMode::Immediate, Cycles::Fixed(2)),            // 89
+        (MOS6502::txa, AddressingMode::Implied, Cycles::Fixed(2)),              // 8A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 8B
+        (MOS6502::sty, AddressingMode::Absolute, Cycles::Fixed(5)),             // 8C
+        (MOS6502::sta, AddressingMode::Absolute, Cycles::Fixed(5)),             // 8D
+        (MOS6502::stx, AddressingMode::Absolute, Cycles::Fixed(5)),             // 8E
+        (MOS6502::bbs0, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 8F
+        (MOS6502::bcc, AddressingMode::Relative, Cycles::Branch(2, 2)),         // 90
+        (MOS6502::sta, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // 91
+        (MOS6502::sta, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // 92
+        (MOS6502::tst, AddressingMode::Absolute, Cycles::Fixed(8)),             // 93
+        (MOS6502::sty, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 94
+        (MOS6502::sta, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 95
+        (MOS6502::stx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // 96
+        (MOS6502::smb1, AddressingMode::Zeropage, Cycles::Fixed(7)),            // 97
+        (MOS6502::tya, AddressingMode::Implied, Cycles::Fixed(2)),              // 98
+        (MOS6502::sta, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 99
+        (MOS6502::txs, AddressingMode::Implied, Cycles::Fixed(2)),              // 9A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // 9B
+        (MOS6502::stz, AddressingMode::Absolute, Cycles::Fixed(5)),             // 9C
+        (MOS6502::sta, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 9D
+        (MOS6502::stz, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 9E
+        (MOS6502::bbs1, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // 9F
+        (MOS6502::ldy, AddressingMode::Immediate, Cycles::Fixed(2)),            // A0
+        (MOS6502::lda, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // A1
+        (MOS6502::ldx, AddressingMode::Immediate, Cycles::Fixed(2)),            // A2
+        (MOS6502::tst, AddressingMode::ZeropageXIndex, Cycles::Fixed(7)),       // A3
+        (MOS6502::ldy, AddressingMode::Zeropage, Cycles::Fixed(4)),             // A4
+        (MOS6502::lda, AddressingMode::Zeropage, Cycles::Fixed(4)),             // A5
+        (MOS6502::ldx, AddressingMode::Zeropage, Cycles::Fixed(4)),             // A6
+        (MOS6502::smb2, AddressingMode::Zeropage, Cycles::Fixed(7)),            // A7
+        (MOS6502::tay, AddressingMode::Implied, Cycles::Fixed(2)),              // A8
+        (MOS6502::lda, AddressingMode::Immediate, Cycles::Fixed(2)),            // A9
+        (MOS6502::tax, AddressingMode::Implied, Cycles::Fixed(2)),              // AA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // AB
+        (MOS6502::ldy, AddressingMode::Absolute, Cycles::Fixed(5)),             // AC
+        (MOS6502::lda, AddressingMode::Absolute, Cycles::Fixed(5)),             // AD
+        (MOS6502::ldx, AddressingMode::Absolute, Cycles::Fixed(5)),             // AE
+        (MOS6502::bbs2, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // AF
+        (MOS6502::bcs, AddressingMode::Relative, Cycles::Branch(2, 2)),         // B0
+        (MOS6502::lda, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // B1
+        (MOS6502::lda, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // B2
+        (MOS6502::tst, AddressingMode::AbsoluteXIndex, Cycles::Fixed(8)),       // B3
+        (MOS6502::ldy, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B4
+        (MOS6502::lda, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B5
+        (MOS6502::ldx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // B6
+        (MOS6502::smb3, AddressingMode::Zeropage, Cycles::Fixed(7)),            // B7
+        (MOS6502::clv, AddressingMode::Implied, Cycles::Fixed(2)),              // B8
+        (MOS6502::lda, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // B9
+        (MOS6502::tsx, AddressingMode::Implied, Cycles::Fixed(2)),              // BA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // BB
+        (MOS6502::ldy, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // BC
+        (MOS6502::lda, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // BD
+        (MOS6502::ldx, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // BE
+        (MOS6502::bbs3, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // BF
+        (MOS6502::cpy, AddressingMode::Immediate, Cycles::Fixed(2)),            // C0
+        (MOS6502::cmp, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // C1
+        (MOS6502::cly, AddressingMode::Implied, Cycles::Fixed(2)),              // C2
+        (MOS6502::tdd, AddressingMode::Implied, Cycles::Variable(17)),          // C3
+        (MOS6502::cpy, AddressingMode::Zeropage, Cycles::Fixed(4)),             // C4
+        (MOS6502::cmp, AddressingMode::Zeropage, Cycles::Fixed(4)),             // C5
+        (MOS6502::dec, AddressingMode::Zeropage, Cycles::Fixed(6)),             // C6
+        (MOS6502::smb4, AddressingMode::Zeropage, Cycles::Fixed(7)),            // C7
+        (MOS6502::iny, AddressingMode::Implied, Cycles::Fixed(2)),              // C8
+        (MOS6502::cmp, AddressingMode::Immediate, Cycles::Fixed(2)),            // C9
+        (MOS6502::dex, AddressingMode::Implied, Cycles::Fixed(2)),              // CA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // CB
+        (MOS6502::cpy, AddressingMode::Absolute, Cycles::Fixed(5)),             // CC
+        (MOS6502::cmp, AddressingMode::Absolute, Cycles::Fixed(5)),             // CD
+        (MOS6502::dec, AddressingMode::Absolute, Cycles::Fixed(7)),             // CE
+        (MOS6502::bbs4, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // CF
+        (MOS6502::bne, AddressingMode::Relative, Cycles::Branch(2, 2)),         // D0
+        (MOS6502::cmp, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // D1
+        (MOS6502::cmp, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // D2
+        (MOS6502::tin, AddressingMode::Implied, Cycles::Variable(17)),          // D3
+        (MOS6502::csh, AddressingMode::Implied, Cycles::Fixed(3)),              // D4
+        (MOS6502::cmp, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // D5
+        (MOS6502::dec, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // D6
+        (MOS6502::smb5, AddressingMode::Zeropage, Cycles::Fixed(7)),            // D7
+        (MOS6502::cld, AddressingMode::Implied, Cycles::Fixed(2)),              // D8
+        (MOS6502::cmp, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // D9
+        (MOS6502::phx, AddressingMode::Implied, Cycles::Fixed(3)),              // DA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // DB
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // DC
+        (MOS6502::cmp, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // DD
+        (MOS6502::dec, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // DE
+        (MOS6502::bbs5, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // DF
+        (MOS6502::cpx, AddressingMode::Immediate, Cycles::Fixed(2)),            // E0
+        (MOS6502::sbc, AddressingMode::XIndexIndirect, Cycles::Fixed(7)),       // E1
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // E2
+        (MOS6502::tia, AddressingMode::Implied, Cycles::Variable(17)),          // E3
+        (MOS6502::cpx, AddressingMode::Zeropage, Cycles::Fixed(4)),             // E4
+        (MOS6502::sbc, AddressingMode::Zeropage, Cycles::Fixed(4)),             // E5
+        (MOS6502::inc, AddressingMode::Zeropage, Cycles::Fixed(6)),             // E6
+        (MOS6502::smb6, AddressingMode::Zeropage, Cycles::Fixed(7)),            // E7
+        (MOS6502::inx, AddressingMode::Implied, Cycles::Fixed(2)),              // E8
+        (MOS6502::sbc, AddressingMode::Immediate, Cycles::Fixed(2)),            // E9
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // EA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // EB
+        (MOS6502::cpx, AddressingMode::Absolute, Cycles::Fixed(5)),             // EC
+        (MOS6502::sbc, AddressingMode::Absolute, Cycles::Fixed(5)),             // ED
+        (MOS6502::inc, AddressingMode::Absolute, Cycles::Fixed(7)),             // EE
+        (MOS6502::bbs6, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // EF
+        (MOS6502::beq, AddressingMode::Relative, Cycles::Branch(2, 2)),         // F0
+        (MOS6502::sbc, AddressingMode::IndirectYIndex, Cycles::Fixed(7)),       // F1
+        (MOS6502::sbc, AddressingMode::ZeropageIndirect, Cycles::Fixed(7)),     // F2
+        (MOS6502::tai, AddressingMode::Implied, Cycles::Variable(17)),          // F3
+        (MOS6502::set, AddressingMode::Implied, Cycles::Fixed(2)),              // F4
+        (MOS6502::sbc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // F5
+        (MOS6502::inc, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // F6
+        (MOS6502::smb7, AddressingMode::Zeropage, Cycles::Fixed(7)),            // F7
+        (MOS6502::sed, AddressingMode::Implied, Cycles::Fixed(2)),              // F8
+        (MOS6502::sbc, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // F9
+        (MOS6502::plx, AddressingMode::Implied, Cycles::Fixed(4)),              // FA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // FB
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // FC
+        (MOS6502::sbc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // FD
+        (MOS6502::inc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // FE
+        (MOS6502::bbs7, AddressingMode::Zeropage, Cycles::Branch(6, 2)),        // FF
+    ]);
+}
+
+impl<T: AccessBus> MOS6502<T> {
+    pub(in crate::mos6502) fn read_physical_bus(
+        &mut self,
+        bus: &mut T,
+        address: u32,
+        kind: AccessKind,
+    ) -> Result<u8, BusError> {
+        let offset = address.wrapping_sub(HARDWARE_PAGE);
+        match (offset, &self.extension) {
+            (0x0C00..=0x0FFF, Extension::Huc6280(huc6280)) => Ok(huc6280.timer.read()),
+            (0x1400..=0x17FF, Extension::Huc6280(huc6280)) => {
+                Ok(huc6280.interrupt_controller.read(offset))
+            }
+            _ => bus.read_physical(address, kind),
+        }
+    }
+
+    pub(in crate::mos6502) fn write_physical_bus(
+        &mut self,
+        bus: &mut T,
+        address: u32,
+        value: u8,
+        kind: AccessKind,
+    ) -> Result<(), BusError> {
+        let offset = address.wrapping_sub(HARDWARE_PAGE);
+        match (offset, &mut self.extension) {
+            (0x0C00..=0x0FFF, Extension::Huc6280(huc6280)) => huc6280.timer.write(offset, value),
+            (0x1400..=0x17FF, Extension::Huc6280(huc6280)) => {
+                huc6280.interrupt_controller.write(offset, value)
+            }
+            _ => return bus.write_physical(address, value, kind),
+        }
+        Ok(())
+    }
+
+    /// Run an accumulator instruction on the zero page byte at X instead, as the
+    /// HuC6280 does for the instruction following SET
+    pub(in crate::mos6502) fn with_memory_operand(
+        &mut self,
+        bus: &mut T,
+        instruction: impl FnOnce(&mut Self, &mut T) -> Result<u32, CpuError>,
+    ) -> Result<u32, CpuError> {
+        let address = self.base_page_address(self.x_register);
+        let accumulator = self.accumulator;
+        self.accumulator = self.read_bus(bus, address, AccessKind::Data)?;
+        let cycles = instruction(self, bus)?;
+        let result = std::mem::replace(&mut self.accumulator, accumulator);
+        self.write_bus(bus, address, result, AccessKind::Data)?;
+        Ok(cycles)
+    }
+
+    /// Request from the on-die interrupt controller that should be taken now, with IRQ2
+    /// also asserted if `irq2`
+    #[inline]
+    pub(in crate::mos6502) fn pending_controller_interrupt(
+        &self,
+        irq2: bool,
+    ) -> Option<InterruptKind> {
+        if self.flag_check(CpuFlags::NoInterrupts) {
+            return None;
+        }
+        self.interrupt_controller()?.pending(irq2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mos6502::test_bus::{run, TestBus};
+    use crate::mos6502::*;
+
+    const RAM: usize = 0xF8 << 13;
+
+    /// 2 MiB of physical memory, with `program` in bank 0 at logical $E000
+    fn setup(program: &[u8]) -> (MOS6502<TestBus>, TestBus) {
+        let mut bus = TestBus::new(0x20_0000);
+        bus.0[..program.len()].copy_from_slice(program);
+        let mut cpu = MOS6502::with_variant(Variant::Huc6280);
+        cpu.set_mpr(0, 0xFF);
+        cpu.set_mpr(1, 0xF8);
+        cpu.set_program_counter(0xE000);
+        (cpu, bus)
+    }
+
+    #[test]
+    fn test_mpr_zero_page_and_stack() {
+        // LDA #$F9; TAM #$04; LDA #$55; STA $10; STA $4000; TMA #$04; PHA
+        let program = [
+            0xA9, 0xF9, 0x53, 0x04, 0xA9, 0x55, 0x85, 0x10, 0x8D, 0x00, 0x40, 0x43, 0x04, 0x48,
+        ];
+        let (mut cpu, mut bus) = setup(&program);
+        run(&mut cpu, &mut bus, 7);
+
+        assert_eq!(cpu.mpr()[2], 0xF9);
+        assert_eq!(bus.0[RAM + 0x10], 0x55);
+        assert_eq!(bus.0[0xF9 << 13], 0x55);
+        assert_eq!(cpu.accumulator(), 0xF9);
+        assert_eq!(bus.0[RAM + 0x01FF], 0xF9);
+
+        // Only three bits select the register, as with TAM
+        cpu.set_mpr(10, 0x42);
+        assert_eq!(cpu.mpr()[2], 0x42);
+    }
+
+    #[test]
+    fn test_block_transfers() {
+        // TII $2000, $2100, 4; TAI $2000, $2200, 4; TDD $2003, $2303, 4
+        let program = [
+            0x73, 0x00, 0x20, 0x00, 0x21, 0x04, 0x00, 0xF3, 0x00, 0x20, 0x00, 0x22, 0x04, 0x00,
+            0xC3, 0x03, 0x20, 0x03, 0x23, 0x04, 0x00,
+        ];
+        let (mut cpu, mut bus) = setup(&program);
+        bus.0[RAM..RAM + 4].copy_from_slice(&[1, 2, 3, 4]);
+
+        assert_eq!(run(&mut cpu, &mut bus, 1), 17 + 6 * 4);
+        assert_eq!(bus.0[RAM + 0x100..RAM + 0x104], [1, 2, 3, 4]);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[RAM + 0x200..RAM + 0x204], [1, 2, 1, 2]);
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(bus.0[RAM + 0x300..RAM + 0x304], [1, 2, 3, 4]);
+        assert_eq!(cpu.stack_pointer(), 0xFF);
+    }
+
+    #[test]
+    fn test_memory_operation_flag() {
+        // LDX #$05; LDA #$AA; SET; ORA #$0F; ORA #$01
+        let program = [0xA2, 0x05, 0xA9, 0xAA, 0xF4, 0x09, 0x0F, 0x09, 0x01];
+        let (mut cpu, mut bus) = setup(&program);
+        bus.0[RAM + 0x05] = 0xF0;
+
+        run(&mut cpu, &mut bus, 3);
+        assert!(cpu.memory_operation());
+        assert_eq!(run(&mut cpu, &mut bus, 1), 2 + 3);
+        assert_eq!(bus.0[RAM + 0x05], 0xFF);
+        assert_eq!(cpu.accumulator(), 0xAA);
+        assert!(!cpu.memory_operation());
+
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(cpu.accumulator(), 0xAB);
+    }
+
+    #[test]
+    fn test_read_modify_write_stores_once() {
+        // INC $0002; ASL $0002
+        let program = [0xEE, 0x02, 0x00, 0x0E, 0x02, 0x00];
+        let (mut cpu, mut bus) = setup(&program);
+        let vdc_data = (0xFF << 13) + 0x0002;
+        bus.0[vdc_data] = 0x20;
+
+        // The VDC sees one write per instruction, not the NMOS write-back
+        assert_eq!(run(&mut cpu, &mut bus, 2), 2 * 7);
+        assert_eq!(bus.0[vdc_data], 0x42);
+        assert_eq!(bus.1, [vdc_data as u32, vdc_data as u32]);
+    }
+
+    #[test]
+    fn test_vdc_stores_and_register_instructions() {
+        // ST0 #$05; ST1 #$34; ST2 #$12; LDA #$01; LDX #$02; SAX; CLY; TST #$80, $10
+        let program = [
+            0x03, 0x05, 0x13, 0x34, 0x23, 0x12, 0xA9, 0x01, 0xA2, 0x02, 0x22, 0xC2, 0x83, 0x80,
+            0x10,
+        ];
+        let (mut cpu, mut bus) = setup(&program);
+        cpu.set_mpr(0, 0x00);
+        bus.0[RAM + 0x10] = 0x40;
+        run(&mut cpu, &mut bus, 8);
+
+        assert_eq!(bus.0[0x1F_E000..0x1F_E004], [0x05, 0x00, 0x34, 0x12]);
+        assert_eq!(cpu.accumulator(), 0x02);
+        assert_eq!(cpu.x_register(), 0x01);
+        assert_eq!(cpu.y_register(), 0x00);
+        assert!(cpu.flag_check(CpuFlags::Zero));
+        assert!(cpu.flag_check(CpuFlags::Overflow));
+        assert!(!cpu.flag_check(CpuFlags::Negative));
+    }
+
+    #[test]
+    fn test_branch_timing() {
+        // BBR0 $10, +2 (taken); LDA #$00; BNE +0 (not taken); BEQ -2 (taken)
+        let program = [
+            0x0F, 0x10, 0x02, 0x00, 0x00, 0xA9, 0x00, 0xD0, 0x00, 0xF0, 0xFE,
+        ];
+        let (mut cpu, mut bus) = setup(&program);
+
+        assert_eq!(run(&mut cpu, &mut bus, 1), 8);
+        assert_eq!(cpu.program_counter(), 0xE005);
+        run(&mut cpu, &mut bus, 1);
+        assert_eq!(run(&mut cpu, &mut bus, 1), 2);
+        assert_eq!(run(&mut cpu, &mut bus, 1), 4);
+        assert_eq!(cpu.program_counter(), 0xE009);
+    }
+
+    #[test]
+    fn test_timer_interrupt_on_exact_cycle() {
+        // STZ $0C00; LDA #$01; STA $0C01; BRA *
+        let program = [0x9C, 0x00, 0x0C, 0xA9, 0x01, 0x8D, 0x01, 0x0C, 0x80, 0xFE];
+        let (mut cpu, mut bus) = setup(&program);
+        // Timer vector points at STA $1403 in bank 0
+        bus.0[0x1FFA..0x1FFC].copy_from_slice(&[0x00, 0xE1]);
+        bus.0[0x0100..0x0103].copy_from_slice(&[0x8D, 0x03, 0x14]);
+
+        run(&mut cpu, &mut bus, 3);
+        assert!(cpu.timer().expect("HuC6280 has a timer").running());
+
+        // 20 of 1024 master clocks have passed; each BRA at 1.79 MHz adds 16
+        run(&mut cpu, &mut bus, 62);
+        assert_eq!(cpu.program_counter(), 0xE008);
+        run(&mut cpu, &mut bus, 1);
+        let controller = cpu
+            .interrupt_controller()
+            .expect("HuC6280 has a controller");
+        assert_eq!(controller.requests(), 0b100);
+
+        assert_eq!(run(&mut cpu, &mut bus, 1), 8);
+        assert_eq!(cpu.program_counter(), 0xE100);
+        assert!(cpu.flag_check(CpuFlags::NoInterrupts));
+
+        run(&mut cpu, &mut bus, 1);
+        let controller = cpu
+            .interrupt_controller()
+            .expect("HuC6280 has a controller");
+        assert_eq!(controller.requests(), 0);
+    }
+
+    #[test]
+    fn test_interrupt_controller_mask_and_priority() {
+        // LDA #$02; STA $1402
+        let program = [0xA9, 0x02, 0x8D, 0x02, 0x14];
+        for (disabled, expected_pc) in [(false, 0xE100), (true, 0xE200)] {
+            let (mut cpu, mut bus) = setup(&program);
+            // IRQ2 vector, then IRQ1 vector
+            bus.0[0x1FF6..0x1FFA].copy_from_slice(&[0x00, 0xE2, 0x00, 0xE1]);
+            if disabled {
+                run(&mut cpu, &mut bus, 2);
+            }
+            let controller = cpu
+                .interrupt_controller_mut()
+                .expect("HuC6280 has a controller");
+            controller.set_irq1(true);
+            controller.set_irq2(true);
+
+            run(&mut cpu, &mut bus, 1);
+            assert_eq!(cpu.program_counter(), expected_pc);
+        }
+    }
+
+    #[test]
+    fn test_irq_pin_goes_through_controller() {
+        // LDA #$01; STA $1402; LDA #$03; STA $1402
+        let program = [0xA9, 0x01, 0x8D, 0x02, 0x14, 0xA9, 0x03, 0x8D, 0x02, 0x14];
+        let (mut cpu, mut bus) = setup(&program);
+        // IRQ2 vector, then IRQ1 vector
+        bus.0[0x1FF6..0x1FFA].copy_from_slice(&[0x00, 0xE2, 0x00, 0xE1]);
+
+        // The IRQ pin is IRQ2, so masking IRQ2 masks it
+        run(&mut cpu, &mut bus, 2);
+        assert_eq!(cpu.irq(&mut bus).expect("Failed to perform IRQ"), 0);
+        assert_eq!(cpu.program_counter(), 0xE005);
+
+        // A pending IRQ1 has priority over it
+        cpu.interrupt_controller_mut()
+            .expect("HuC6280 has a controller")
+            .set_irq1(true);
+        assert_eq!(cpu.irq(&mut bus).expect("Failed to perform IRQ"), 8);
+        assert_eq!(cpu.program_counter(), 0xE100);
+
+        // With both IRQ1 and IRQ2 masked it is ignored
+        let (mut cpu, mut bus) = setup(&program);
+        run(&mut cpu, &mut bus, 4);
+        cpu.interrupt_controller_mut()
+            .expect("HuC6280 has a controller")
+            .set_irq1(true);
+        assert_eq!(cpu.irq(&mut bus).expect("Failed to perform IRQ"), 0);
+        assert_eq!(cpu.program_counter(), 0xE00A);
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+/// Cycles an undriven input bit keeps its last output level before reading as 0
+const FALL_OFF_CYCLES: u64 = 350_000;
+
+/// Bits 6 and 7 of the data register have no package pins on the 6510
+const PIN_MASK: u8 = 0b0011_1111;
+
+/// On-chip I/O port of the MOS 6510, mapped at $0000 (direction) and $0001 (data)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IoPort {
+    direction: u8,
+    data: u8,
+    input_levels: u8,
+    driven_inputs: u8,
+    floating_levels: u8,
+    fall_off_deadline: [u64; 8],
+    cycles: u64,
+    #[serde(default)]
+    pull_ups: u8,
+}
+
+impl Default for IoPort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoPort {
+    /// Port as after power-on, with every bit an input
+    pub fn new() -> Self {
+        Self {
+            direction: 0,
+            data: 0,
+            input_levels: 0,
+            driven_inputs: 0,
+            floating_levels: 0,
+            fall_off_deadline: [0; 8],
+            cycles: 0,
+            pull_ups: 0,
+        }
+    }
+
+    /// Value of the data direction register ($0000). Set bits are outputs.
+    #[inline]
+    pub fn direction(&self) -> u8 {
+        self.direction
+    }
+
+    /// Value of the output latch ($0001), regardless of pin direction
+    #[inline]
+    pub fn data(&self) -> u8 {
+        self.data
+    }
+
+    /// Levels on the six port pins, as read back from $0001: outputs at the latch value,
+    /// driven inputs at the level driven on them, undriven inputs with a pull-up high,
+    /// and the other undriven inputs at their last output level until it fades to 0.
+    ///
+    /// After reset every pin is an input, so a host that derives its memory banking
+    /// from these pins sets the pull-ups of its board first, e.g. bits 0-2 on the C64
+    /// to have LORAM, HIRAM and CHAREN read high at power-on.
+    #[inline]
+    pub fn output_pins(&self) -> u8 {
+        self.read(1) & PIN_MASK
+    }
+
+    /// Pins pulled up on the board, which read high whenever nothing drives them
+    #[inline]
+    pub fn pull_ups(&self) -> u8 {
+        self.pull_ups
+    }
+
+    /// Pull up the pins set in `mask`, as resistors on the board would
+    pub fn set_pull_ups(&mut self, mask: u8) {
+        self.pull_ups = mask & PIN_MASK;
+    }
+
+    /// Drive the input pins from outside. Only pins set in `driven_mask` are driven;
+    /// the rest float and hold the last level written to them for a while.
+    pub fn set_input(&mut self, levels: u8, driven_mask: u8) {
+        self.input_levels = levels;
+        self.driven_inputs = driven_mask & PIN_MASK;
+    }
+
+    /// Advance the capacitor fade clock
+    #[inline]
+    pub(in crate::mos6502) fn tick(&mut self, cycles: u32) {
+        self.cycles += cycles as u64;
+    }
+
+    pub(in crate::mos6502) fn read(&self, address: u16) -> u8 {
+        if address == 0 {
+            return self.direction;
+        }
+        let inputs = !self.direction;
+        let driven = inputs & self.driven_inputs;
+        let pulled_up = inputs & !self.driven_inputs & self.pull_ups;
+        let floating = inputs & !self.driven_inputs & !self.pull_ups;
+
+        let mut held = 0;
+        for bit in 0..8 {
+            let mask = 1 << bit;
+            if floating & mask != 0 && self.cycles < self.fall_off_deadline[bit] {
+                held |= self.floating_levels & mask;
+            }
+        }
+
+        (self.data & self.direction) | (self.input_levels & driven) | pulled_up | held
+    }
+
+    pub(in crate::mos6502) fn write(&mut self, address: u16, value: u8) {
+        if address == 0 {
+            let released = self.direction & !value;
+            self.direction = value;
+            self.charge(released);
+        } else {
+            self.data = value;
+            self.charge(self.direction);
+        }
+    }
+
+    /// Latch the current output level into the floating capacitance of `mask` bits
+    fn charge(&mut self, mask: u8) {
+        for bit in 0..8 {
+            if mask & (1 << bit) != 0 {
+                self.fall_off_deadline[bit] = self.cycles + FALL_OFF_CYCLES;
+            }
+        }
+        self.floating_levels = (self.floating_levels & !mask) | (self.data & mask);
+    }
+}
@@ -1,15 +1,120 @@
+mod csg65ce02;
+mod disassembler;
+mod huc6280;
+mod io_port;
 mod opcodes;
+#[cfg(test)]
+mod test_bus;
+mod wdc65c02;
+
+pub use disassembler::{disassemble, Disassembly};
+pub use huc6280::{InterruptController, Timer};
+pub use io_port::IoPort;
+
+use std::marker::PhantomData;
 
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
 use crate::error::*;
+use csg65ce02::Csg65ce02Extension;
+use huc6280::Huc6280Extension;
 
 pub trait Bus {
     fn read(&mut self, address: u16) -> Result<u8, BusError>;
     fn write(&mut self, address: u16, value: u8) -> Result<(), BusError>;
 }
 
+/// Reason the CPU is driving the bus on a given cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    /// First cycle of an instruction, with the SYNC pin asserted
+    OpcodeFetch,
+    /// Operand bytes following the opcode
+    Operand,
+    /// Effective address reads and writes, including indirect pointers
+    Data,
+    /// Pushes and pulls on page one
+    Stack,
+    /// Interrupt vector fetch (VPB on the 65C02)
+    VectorPull,
+    /// Cycles whose value is discarded. On NMOS parts every cycle is a bus access, so
+    /// these are the byte after a one-byte opcode, the write-back of the unmodified value
+    /// during read-modify-write, the read of a half-computed address on indexed accesses,
+    /// the reads of the unindexed zero-page address, the opcode reads of taken branches,
+    /// the stack reads ahead of pulls and in JSR, the read of the return address in RTS,
+    /// and the reads that replace the opcode fetch on interrupts and the pushes on reset.
+    /// The 65C02 and HuC6280 only make the first one and a second read instead of the
+    /// write-back; the 65CE02 makes none.
+    Dummy,
+    /// Read and final write of a read-modify-write instruction (ML pin asserted)
+    ReadModifyWrite,
+}
+
+/// Bus interface that is told why every access happens.
+///
+/// Every type implementing [`Bus`] gets this for free, ignoring the access kind and
+/// without seeing [`AccessKind::Dummy`] cycles.
+pub trait AccessBus {
+    fn read_access(&mut self, address: u16, kind: AccessKind) -> Result<u8, BusError>;
+    fn write_access(&mut self, address: u16, value: u8, kind: AccessKind) -> Result<(), BusError>;
+
+    /// Whether the CPU performs the [`AccessKind::Dummy`] cycles on this bus
+    fn dummy_accesses(&self) -> bool {
+        true
+    }
+
+    /// Cycles RDY is held low before the bus cycle about to happen, such as a DMA
+    /// controller taking the bus. It is asked before every read, and on the 65C02 before
+    /// every write too, so the CPU halts partway through an instruction as NMOS parts
+    /// do on their next read cycle. The cycles are added to what the instruction takes.
+    fn rdy_wait(&mut self) -> Result<u32, BusError> {
+        Ok(0)
+    }
+
+    /// Read from a physical address wider than 16 bits, as produced by the HuC6280 MPRs.
+    /// By default only the first 64 KiB are reachable.
+    fn read_physical(&mut self, address: u32, kind: AccessKind) -> Result<u8, BusError> {
+        match u16::try_from(address) {
+            Ok(address) => self.read_access(address, kind),
+            Err(_) => Err(BusError::InvalidLongRead(address)),
+        }
+    }
+
+    /// Write to a physical address wider than 16 bits, as produced by the HuC6280 MPRs.
+    /// By default only the first 64 KiB are reachable.
+    fn write_physical(
+        &mut self,
+        address: u32,
+        value: u8,
+        kind: AccessKind,
+    ) -> Result<(), BusError> {
+        match u16::try_from(address) {
+            Ok(address) => self.write_access(address, value, kind),
+            Err(_) => Err(BusError::InvalidLongWrite(address)),
+        }
+    }
+}
+
+impl<B: Bus> AccessBus for B {
+    #[inline]
+    fn read_access(&mut self, address: u16, _: AccessKind) -> Result<u8, BusError> {
+        self.read(address)
+    }
+
+    #[inline]
+    fn write_access(&mut self, address: u16, value: u8, _: AccessKind) -> Result<(), BusError> {
+        self.write(address, value)
+    }
+
+    /// Reads and writes with side effects, such as clearing a status register, happen
+    /// once per instruction as they always have for a plain bus
+    #[inline]
+    fn dummy_accesses(&self) -> bool {
+        false
+    }
+}
+
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct CpuFlags(u8);
 
@@ -38,24 +143,28 @@ impl From<u8> for CpuFlags {
     }
 }
 
-const STACK_BASE: u16 = 0x0100;
-
 const NEGATIVE_BIT_MASK: u8 = 0b10000000;
 
 enum InterruptKind {
     Nmi,
     Irq,
     Brk,
+    // HuC6280 interrupt controller sources; Irq is its IRQ2 input
+    Irq1,
+    TimerIrq,
 }
 
 #[derive(Clone, Copy, Debug)]
 enum Cycles {
     Fixed(u32),
     Variable(u32),
+    /// Branches of the 65CE02 and HuC6280: base cycles, and the extra cycles a taken
+    /// branch adds regardless of page crossings
+    Branch(u32, u32),
 }
 
 type OpcodeFunction<T> = fn(&mut MOS6502<T>, &mut T, AddressingMode) -> Result<u32, CpuError>;
-struct OpcodeFunctionArray<T: Bus>([(OpcodeFunction<T>, AddressingMode, Cycles); 256]);
+struct OpcodeFunctionArray<T: AccessBus>([(OpcodeFunction<T>, AddressingMode, Cycles); 256]);
 
 enum OpcodeOperand {
     Byte(u8),
@@ -79,293 +188,393 @@ pub enum AddressingMode {
     Zeropage,
     ZeropageXIndex,
     ZeropageYIndex,
+    // 65C02 and 65CE02 modes
+    AbsoluteXIndexIndirect,
+    ZeropageIndirect,
+    StackRelativeIndirectYIndex,
+    RelativeLong,
+    IndirectZIndex,
+}
+
+/// Chip variant being emulated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Variant {
+    #[default]
+    Nmos6502,
+    /// 6502 core with the on-chip I/O port at $0000/$0001, as used in the C64
+    Mos6510,
+    /// 28-pin 6502 with 13 address lines and no interrupt inputs, as used in the Atari 2600
+    Mos6507,
+    /// 28-pin 6502 with 13 address lines and IRQ but no NMI
+    Mos6504,
+    /// 28-pin 6502 with 12 address lines, IRQ and NMI
+    Mos6503,
+    /// WDC 65C02, the CMOS 6502 with the added instructions and the Rockwell bit
+    /// instructions, but without WAI and STP
+    Wdc65c02,
+    /// CSG 65CE02 with the Z and B registers, relocatable base page and 16-bit stack
+    Csg65ce02,
+    /// MEGA65 45GS02: a 65CE02 with the 4510 MAP instruction and 32-bit quad instructions
+    Mega45gs02,
+    /// Hudson HuC6280 (PC Engine): a 65C02 with MPR banking to 21-bit physical addresses,
+    /// zero page at $2000, block transfers, the T flag, and an on-die timer and interrupt
+    /// controller
+    Huc6280,
+}
+
+impl Variant {
+    /// Mask applied to every address put on the bus, as the package only bonds out
+    /// the low address lines
+    pub fn address_mask(self) -> u16 {
+        match self {
+            Variant::Mos6507 | Variant::Mos6504 => 0x1FFF,
+            Variant::Mos6503 => 0x0FFF,
+            _ => 0xFFFF,
+        }
+    }
+
+    /// Whether the package has an IRQ input
+    pub fn has_irq(self) -> bool {
+        self != Variant::Mos6507
+    }
+
+    /// Whether the package has an NMI input
+    pub fn has_nmi(self) -> bool {
+        !matches!(self, Variant::Mos6507 | Variant::Mos6504)
+    }
 }
 
+/// Version of the encoding written by [`MOS6502::save_state`]
+pub const STATE_VERSION: u32 = 1;
+
+/// Length of a 0.1.0 state: the registers bincode-encoded without a version
+const UNVERSIONED_STATE_LENGTH: usize = 7;
+
+/// 6502-family CPU core.
+///
+/// Registers and pins added since the first release are filled with their power-on
+/// values when a state saved without them is deserialized. That works for
+/// self-describing formats such as JSON. bincode relies on the field layout, so use
+/// [`MOS6502::save_state`] and [`MOS6502::load_state`] for binary states: they carry
+/// [`STATE_VERSION`] and migrate the unversioned states of 0.1.0.
 #[derive(Serialize, Deserialize)]
-pub struct MOS6502<T: Bus> {
+pub struct MOS6502<T: AccessBus> {
     accumulator: u8,
     x_register: u8,
     y_register: u8,
     stack_pointer: u8,
     status_register: CpuFlags,
     program_counter: u16,
-    #[serde(skip_serializing, skip_deserializing)]
-    opcode_array: OpcodeFunctionArray<T>,
+    #[serde(default = "default_line")]
+    rdy_line: bool,
+    #[serde(default = "default_line")]
+    so_line: bool,
+    /// NMI edge seen while RDY was low
+    #[serde(default)]
+    nmi_pending: bool,
+    /// Cycles the bus has held RDY low during the instruction in progress
+    #[serde(skip)]
+    wait_cycles: u32,
+    #[serde(default)]
+    variant: Variant,
+    #[serde(default)]
+    extension: Extension,
+    #[serde(skip)]
+    _bus: PhantomData<T>,
+}
+
+/// Registers and on-chip peripherals that only some variants have
+#[derive(Clone, Default, Serialize, Deserialize)]
+enum Extension {
+    #[default]
+    None,
+    Mos6510(IoPort),
+    Csg65ce02(Csg65ce02Extension),
+    Huc6280(Huc6280Extension),
 }
 
-impl<T: Bus> Default for OpcodeFunctionArray<T> {
+/// Input pins are pulled up
+fn default_line() -> bool {
+    true
+}
+
+impl<T: AccessBus> OpcodeFunctionArray<T> {
+    /// Opcode table of the NMOS 6502, which the 6510 and the 28-pin packages share
     #[rustfmt::skip]
-    fn default() -> Self {
-        OpcodeFunctionArray([
-            (MOS6502::brk, AddressingMode::Implied, Cycles::Fixed(7)),              // 00
-            (MOS6502::ora, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 01
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 02
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 03
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 04
-            (MOS6502::ora, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 05
-            (MOS6502::asl, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 06
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 07
-            (MOS6502::php, AddressingMode::Implied, Cycles::Fixed(3)),              // 08
-            (MOS6502::ora, AddressingMode::Immediate, Cycles::Fixed(2)),            // 09
-            (MOS6502::asl, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 0A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 0B
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 0C
-            (MOS6502::ora, AddressingMode::Absolute, Cycles::Fixed(4)),             // 0D
-            (MOS6502::asl, AddressingMode::Absolute, Cycles::Fixed(6)),             // 0E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 0F
-            (MOS6502::bpl, AddressingMode::Relative, Cycles::Variable(2)),          // 10
-            (MOS6502::ora, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 11
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 12
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 13
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 14
-            (MOS6502::ora, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 15
-            (MOS6502::asl, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 16
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 17
-            (MOS6502::clc, AddressingMode::Implied, Cycles::Fixed(2)),              // 18
-            (MOS6502::ora, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 19
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1B
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1C
-            (MOS6502::ora, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 1D
-            (MOS6502::asl, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 1E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1F
-            (MOS6502::jsr, AddressingMode::Absolute, Cycles::Fixed(6)),             // 20
-            (MOS6502::and, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 21
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 22
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 23
-            (MOS6502::bit, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 24
-            (MOS6502::and, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 25
-            (MOS6502::rol, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 26
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 27
-            (MOS6502::plp, AddressingMode::Implied, Cycles::Fixed(4)),              // 28
-            (MOS6502::and, AddressingMode::Immediate, Cycles::Fixed(2)),            // 29
-            (MOS6502::rol, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 2A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 2B
-            (MOS6502::bit, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2C
-            (MOS6502::and, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2D
-            (MOS6502::rol, AddressingMode::Absolute, Cycles::Fixed(6)),             // 2E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 2F
-            (MOS6502::bmi, AddressingMode::Relative, Cycles::Variable(2)),          // 30
-            (MOS6502::and, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 31
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 32
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 33
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 34
-            (MOS6502::and, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 35
-            (MOS6502::rol, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 36
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 37
-            (MOS6502::sec, AddressingMode::Implied, Cycles::Fixed(2)),              // 38
-            (MOS6502::and, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 39
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3B
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3C
-            (MOS6502::and, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 3D
-            (MOS6502::rol, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 3E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3F
-            (MOS6502::rti, AddressingMode::Implied, Cycles::Fixed(6)),              // 40
-            (MOS6502::eor, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 41
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 42
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 43
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 44
-            (MOS6502::eor, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 45
-            (MOS6502::lsr, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 46
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 47
-            (MOS6502::pha, AddressingMode::Implied, Cycles::Fixed(3)),              // 48
-            (MOS6502::eor, AddressingMode::Immediate, Cycles::Fixed(2)),            // 49
-            (MOS6502::lsr, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 4A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 4B
-            (MOS6502::jmp, AddressingMode::Absolute, Cycles::Fixed(3)),             // 4C
-            (MOS6502::eor, AddressingMode::Absolute, Cycles::Fixed(4)),             // 4D
-            (MOS6502::lsr, AddressingMode::Absolute, Cycles::Fixed(6)),             // 4E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 4F
-            (MOS6502::bvc, AddressingMode::Relative, Cycles::Variable(2)),          // 50
-            (MOS6502::eor, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 51
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 52
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 53
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 54
-            (MOS6502::eor, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 55
-            (MOS6502::lsr, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 56
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 57
-            (MOS6502::cli, AddressingMode::Implied, Cycles::Fixed(2)),              // 58
-            (MOS6502::eor, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 59
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5B
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5C
-            (MOS6502::eor, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 5D
-            (MOS6502::lsr, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 5E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5F
-            (MOS6502::rts, AddressingMode::Implied, Cycles::Fixed(6)),              // 60
-            (MOS6502::adc, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 61
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 62
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 63
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 64
-            (MOS6502::adc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 65
-            (MOS6502::ror, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 66
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 67
-            (MOS6502::pla, AddressingMode::Implied, Cycles::Fixed(4)),              // 68
-            (MOS6502::adc, AddressingMode::Immediate, Cycles::Fixed(2)),            // 69
-            (MOS6502::ror, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 6A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 6B
-            (MOS6502::jmp, AddressingMode::Indirect, Cycles::Fixed(5)),             // 6C
-            (MOS6502::adc, AddressingMode::Absolute, Cycles::Fixed(4)),             // 6D
-            (MOS6502::ror, AddressingMode::Absolute, Cycles::Fixed(6)),             // 6E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 6F
-            (MOS6502::bvs, AddressingMode::Relative, Cycles::Variable(2)),          // 70
-            (MOS6502::adc, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 71
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 72
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 73
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 74
-            (MOS6502::adc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 75
-            (MOS6502::ror, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 76
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 77
-            (MOS6502::sei, AddressingMode::Implied, Cycles::Fixed(2)),              // 78
-            (MOS6502::adc, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 79
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7B
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7C
-            (MOS6502::adc, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 7D
-            (MOS6502::ror, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 7E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7F
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 80
-            (MOS6502::sta, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 81
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 82
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 83
-            (MOS6502::sty, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 84
-            (MOS6502::sta, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 85
-            (MOS6502::stx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 86
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 87
-            (MOS6502::dey, AddressingMode::Implied, Cycles::Fixed(2)),              // 88
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 89
-            (MOS6502::txa, AddressingMode::Implied, Cycles::Fixed(2)),              // 8A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 8B
-            (MOS6502::sty, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8C
-            (MOS6502::sta, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8D
-            (MOS6502::stx, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 8F
-            (MOS6502::bcc, AddressingMode::Relative, Cycles::Variable(2)),          // 90
-            (MOS6502::sta, AddressingMode::IndirectYIndex, Cycles::Fixed(6)),       // 91
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 92
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 93
-            (MOS6502::sty, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 94
-            (MOS6502::sta, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 95
-            (MOS6502::stx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // 96
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 97
-            (MOS6502::tya, AddressingMode::Implied, Cycles::Fixed(2)),              // 98
-            (MOS6502::sta, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 99
-            (MOS6502::txs, AddressingMode::Implied, Cycles::Fixed(2)),              // 9A
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9B
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9C
-            (MOS6502::sta, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 9D
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9E
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9F
-            (MOS6502::ldy, AddressingMode::Immediate, Cycles::Fixed(2)),            // A0
-            (MOS6502::lda, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // A1
-            (MOS6502::ldx, AddressingMode::Immediate, Cycles::Fixed(2)),            // A2
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // A3
-            (MOS6502::ldy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A4
-            (MOS6502::lda, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A5
-            (MOS6502::ldx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A6
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // A7
-            (MOS6502::tay, AddressingMode::Implied, Cycles::Fixed(2)),              // A8
-            (MOS6502::lda, AddressingMode::Immediate, Cycles::Fixed(2)),            // A9
-            (MOS6502::tax, AddressingMode::Implied, Cycles::Fixed(2)),              // AA
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // AB
-            (MOS6502::ldy, AddressingMode::Absolute, Cycles::Fixed(4)),             // AC
-            (MOS6502::lda, AddressingMode::Absolute, Cycles::Fixed(4)),             // AD
-            (MOS6502::ldx, AddressingMode::Absolute, Cycles::Fixed(4)),             // AE
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // AF
-            (MOS6502::bcs, AddressingMode::Relative, Cycles::Variable(2)),          // B0
-            (MOS6502::lda, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // B1
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // B2
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // B3
-            (MOS6502::ldy, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B4
-            (MOS6502::lda, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B5
-            (MOS6502::ldx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // B6
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // B7
-            (MOS6502::clv, AddressingMode::Implied, Cycles::Fixed(2)),              // B8
-            (MOS6502::lda, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // B9
-            (MOS6502::tsx, AddressingMode::Implied, Cycles::Fixed(2)),              // BA
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // BB
-            (MOS6502::ldy, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // BC
-            (MOS6502::lda, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // BD
-            (MOS6502::ldx, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // BE
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // BF
-            (MOS6502::cpy, AddressingMode::Immediate, Cycles::Fixed(2)),            // C0
-            (MOS6502::cmp, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // C1
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // C2
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // C3
-            (MOS6502::cpy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C4
-            (MOS6502::cmp, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C5
-            (MOS6502::dec, AddressingMode::Zeropage, Cycles::Fixed(5)),             // C6
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // C7
-            (MOS6502::iny, AddressingMode::Implied, Cycles::Fixed(2)),              // C8
-            (MOS6502::cmp, AddressingMode::Immediate, Cycles::Fixed(2)),            // C9
-            (MOS6502::dex, AddressingMode::Implied, Cycles::Fixed(2)),              // CA
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // CB
-            (MOS6502::cpy, AddressingMode::Absolute, Cycles::Fixed(4)),             // CC
-            (MOS6502::cmp, AddressingMode::Absolute, Cycles::Fixed(4)),             // CD
-            (MOS6502::dec, AddressingMode::Absolute, Cycles::Fixed(6)),             // CE
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // CF
-            (MOS6502::bne, AddressingMode::Relative, Cycles::Variable(2)),          // D0
-            (MOS6502::cmp, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // D1
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D2
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D3
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D4
-            (MOS6502::cmp, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // D5
-            (MOS6502::dec, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // D6
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D7
-            (MOS6502::cld, AddressingMode::Implied, Cycles::Fixed(2)),              // D8
-            (MOS6502::cmp, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // D9
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DA
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DB
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DC
-            (MOS6502::cmp, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // DD
-            (MOS6502::dec, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // DE
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DF
-            (MOS6502::cpx, AddressingMode::Immediate, Cycles::Fixed(2)),            // E0
-            (MOS6502::sbc, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // E1
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // E2
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // E3
-            (MOS6502::cpx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E4
-            (MOS6502::sbc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E5
-            (MOS6502::inc, AddressingMode::Zeropage, Cycles::Fixed(5)),             // E6
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // E7
-            (MOS6502::inx, AddressingMode::Implied, Cycles::Fixed(2)),              // E8
-            (MOS6502::sbc, AddressingMode::Immediate, Cycles::Fixed(2)),            // E9
-            (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // EA
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // EB
-            (MOS6502::cpx, AddressingMode::Absolute, Cycles::Fixed(4)),             // EC
-            (MOS6502::sbc, AddressingMode::Absolute, Cycles::Fixed(4)),             // ED
-            (MOS6502::inc, AddressingMode::Absolute, Cycles::Fixed(6)),             // EE
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // EF
-            (MOS6502::beq, AddressingMode::Relative, Cycles::Variable(2)),          // F0
-            (MOS6502::sbc, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // F1
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F2
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F3
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F4
-            (MOS6502::sbc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // F5
-            (MOS6502::inc, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // F6
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F7
-            (MOS6502::sed, AddressingMode::Implied, Cycles::Fixed(2)),              // F8
-            (MOS6502::sbc, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // F9
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FA
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FB
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FC
-            (MOS6502::sbc, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // FD
-            (MOS6502::inc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // FE
-            (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FF
-        ])
-    }
+    pub(in crate::mos6502) const NMOS6502: Self = OpcodeFunctionArray([
+        (MOS6502::brk, AddressingMode::Implied, Cycles::Fixed(7)),              // 00
+        (MOS6502::ora, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 01
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 02
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 03
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 04
+        (MOS6502::ora, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 05
+        (MOS6502::asl, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 06
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 07
+        (MOS6502::php, AddressingMode::Implied, Cycles::Fixed(3)),              // 08
+        (MOS6502::ora, AddressingMode::Immediate, Cycles::Fixed(2)),            // 09
+        (MOS6502::asl, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 0A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 0B
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 0C
+        (MOS6502::ora, AddressingMode::Absolute, Cycles::Fixed(4)),             // 0D
+        (MOS6502::asl, AddressingMode::Absolute, Cycles::Fixed(6)),             // 0E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 0F
+        (MOS6502::bpl, AddressingMode::Relative, Cycles::Variable(2)),          // 10
+        (MOS6502::ora, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 11
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 12
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 13
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 14
+        (MOS6502::ora, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 15
+        (MOS6502::asl, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 16
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 17
+        (MOS6502::clc, AddressingMode::Implied, Cycles::Fixed(2)),              // 18
+        (MOS6502::ora, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 19
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1B
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1C
+        (MOS6502::ora, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 1D
+        (MOS6502::asl, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 1E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 1F
+        (MOS6502::jsr, AddressingMode::Absolute, Cycles::Fixed(6)),             // 20
+        (MOS6502::and, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 21
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 22
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 23
+        (MOS6502::bit, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 24
+        (MOS6502::and, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 25
+        (MOS6502::rol, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 26
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 27
+        (MOS6502::plp, AddressingMode::Implied, Cycles::Fixed(4)),              // 28
+        (MOS6502::and, AddressingMode::Immediate, Cycles::Fixed(2)),            // 29
+        (MOS6502::rol, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 2A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 2B
+        (MOS6502::bit, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2C
+        (MOS6502::and, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2D
+        (MOS6502::rol, AddressingMode::Absolute, Cycles::Fixed(6)),             // 2E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 2F
+        (MOS6502::bmi, AddressingMode::Relative, Cycles::Variable(2)),          // 30
+        (MOS6502::and, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 31
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 32
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 33
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 34
+        (MOS6502::and, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 35
+        (MOS6502::rol, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 36
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 37
+        (MOS6502::sec, AddressingMode::Implied, Cycles::Fixed(2)),              // 38
+        (MOS6502::and, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 39
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3B
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3C
+        (MOS6502::and, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 3D
+        (MOS6502::rol, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 3E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 3F
+        (MOS6502::rti, AddressingMode::Implied, Cycles::Fixed(6)),              // 40
+        (MOS6502::eor, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 41
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 42
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 43
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 44
+        (MOS6502::eor, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 45
+        (MOS6502::lsr, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 46
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 47
+        (MOS6502::pha, AddressingMode::Implied, Cycles::Fixed(3)),              // 48
+        (MOS6502::eor, AddressingMode::Immediate, Cycles::Fixed(2)),            // 49
+        (MOS6502::lsr, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 4A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 4B
+        (MOS6502::jmp, AddressingMode::Absolute, Cycles::Fixed(3)),             // 4C
+        (MOS6502::eor, AddressingMode::Absolute, Cycles::Fixed(4)),             // 4D
+        (MOS6502::lsr, AddressingMode::Absolute, Cycles::Fixed(6)),             // 4E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 4F
+        (MOS6502::bvc, AddressingMode::Relative, Cycles::Variable(2)),          // 50
+        (MOS6502::eor, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 51
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 52
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 53
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 54
+        (MOS6502::eor, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 55
+        (MOS6502::lsr, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 56
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 57
+        (MOS6502::cli, AddressingMode::Implied, Cycles::Fixed(2)),              // 58
+        (MOS6502::eor, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 59
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5B
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5C
+        (MOS6502::eor, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 5D
+        (MOS6502::lsr, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 5E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 5F
+        (MOS6502::rts, AddressingMode::Implied, Cycles::Fixed(6)),              // 60
+        (MOS6502::adc, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 61
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 62
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 63
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 64
+        (MOS6502::adc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 65
+        (MOS6502::ror, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 66
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 67
+        (MOS6502::pla, AddressingMode::Implied, Cycles::Fixed(4)),              // 68
+        (MOS6502::adc, AddressingMode::Immediate, Cycles::Fixed(2)),            // 69
+        (MOS6502::ror, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 6A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 6B
+        (MOS6502::jmp, AddressingMode::Indirect, Cycles::Fixed(5)),             // 6C
+        (MOS6502::adc, AddressingMode::Absolute, Cycles::Fixed(4)),             // 6D
+        (MOS6502::ror, AddressingMode::Absolute, Cycles::Fixed(6)),             // 6E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 6F
+        (MOS6502::bvs, AddressingMode::Relative, Cycles::Variable(2)),          // 70
+        (MOS6502::adc, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 71
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 72
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 73
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 74
+        (MOS6502::adc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 75
+        (MOS6502::ror, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 76
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 77
+        (MOS6502::sei, AddressingMode::Implied, Cycles::Fixed(2)),              // 78
+        (MOS6502::adc, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 79
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7B
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7C
+        (MOS6502::adc, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 7D
+        (MOS6502::ror, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // 7E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 7F
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 80
+        (MOS6502::sta, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 81
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 82
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 83
+        (MOS6502::sty, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 84
+        (MOS6502::sta, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 85
+        (MOS6502::stx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 86
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 87
+        (MOS6502::dey, AddressingMode::Implied, Cycles::Fixed(2)),              // 88
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 89
+        (MOS6502::txa, AddressingMode::Implied, Cycles::Fixed(2)),              // 8A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 8B
+        (MOS6502::sty, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8C
+        (MOS6502::sta, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8D
+        (MOS6502::stx, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 8F
+        (MOS6502::bcc, AddressingMode::Relative, Cycles::Variable(2)),          // 90
+        (MOS6502::sta, AddressingMode::IndirectYIndex, Cycles::Fixed(6)),       // 91
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 92
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 93
+        (MOS6502::sty, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 94
+        (MOS6502::sta, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 95
+        (MOS6502::stx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // 96
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 97
+        (MOS6502::tya, AddressingMode::Implied, Cycles::Fixed(2)),              // 98
+        (MOS6502::sta, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 99
+        (MOS6502::txs, AddressingMode::Implied, Cycles::Fixed(2)),              // 9A
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9B
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9C
+        (MOS6502::sta, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 9D
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9E
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // 9F
+        (MOS6502::ldy, AddressingMode::Immediate, Cycles::Fixed(2)),            // A0
+        (MOS6502::lda, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // A1
+        (MOS6502::ldx, AddressingMode::Immediate, Cycles::Fixed(2)),            // A2
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // A3
+        (MOS6502::ldy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A4
+        (MOS6502::lda, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A5
+        (MOS6502::ldx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A6
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // A7
+        (MOS6502::tay, AddressingMode::Implied, Cycles::Fixed(2)),              // A8
+        (MOS6502::lda, AddressingMode::Immediate, Cycles::Fixed(2)),            // A9
+        (MOS6502::tax, AddressingMode::Implied, Cycles::Fixed(2)),              // AA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // AB
+        (MOS6502::ldy, AddressingMode::Absolute, Cycles::Fixed(4)),             // AC
+        (MOS6502::lda, AddressingMode::Absolute, Cycles::Fixed(4)),             // AD
+        (MOS6502::ldx, AddressingMode::Absolute, Cycles::Fixed(4)),             // AE
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // AF
+        (MOS6502::bcs, AddressingMode::Relative, Cycles::Variable(2)),          // B0
+        (MOS6502::lda, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // B1
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // B2
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // B3
+        (MOS6502::ldy, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B4
+        (MOS6502::lda, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B5
+        (MOS6502::ldx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // B6
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // B7
+        (MOS6502::clv, AddressingMode::Implied, Cycles::Fixed(2)),              // B8
+        (MOS6502::lda, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // B9
+        (MOS6502::tsx, AddressingMode::Implied, Cycles::Fixed(2)),              // BA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // BB
+        (MOS6502::ldy, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // BC
+        (MOS6502::lda, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // BD
+        (MOS6502::ldx, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // BE
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // BF
+        (MOS6502::cpy, AddressingMode::Immediate, Cycles::Fixed(2)),            // C0
+        (MOS6502::cmp, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // C1
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // C2
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // C3
+        (MOS6502::cpy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C4
+        (MOS6502::cmp, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C5
+        (MOS6502::dec, AddressingMode::Zeropage, Cycles::Fixed(5)),             // C6
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // C7
+        (MOS6502::iny, AddressingMode::Implied, Cycles::Fixed(2)),              // C8
+        (MOS6502::cmp, AddressingMode::Immediate, Cycles::Fixed(2)),            // C9
+        (MOS6502::dex, AddressingMode::Implied, Cycles::Fixed(2)),              // CA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // CB
+        (MOS6502::cpy, AddressingMode::Absolute, Cycles::Fixed(4)),             // CC
+        (MOS6502::cmp, AddressingMode::Absolute, Cycles::Fixed(4)),             // CD
+        (MOS6502::dec, AddressingMode::Absolute, Cycles::Fixed(6)),             // CE
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // CF
+        (MOS6502::bne, AddressingMode::Relative, Cycles::Variable(2)),          // D0
+        (MOS6502::cmp, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // D1
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D2
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D3
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D4
+        (MOS6502::cmp, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // D5
+        (MOS6502::dec, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // D6
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // D7
+        (MOS6502::cld, AddressingMode::Implied, Cycles::Fixed(2)),              // D8
+        (MOS6502::cmp, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // D9
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DB
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DC
+        (MOS6502::cmp, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // DD
+        (MOS6502::dec, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // DE
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DF
+        (MOS6502::cpx, AddressingMode::Immediate, Cycles::Fixed(2)),            // E0
+        (MOS6502::sbc, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // E1
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // E2
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // E3
+        (MOS6502::cpx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E4
+        (MOS6502::sbc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E5
+        (MOS6502::inc, AddressingMode::Zeropage, Cycles::Fixed(5)),             // E6
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // E7
+        (MOS6502::inx, AddressingMode::Implied, Cycles::Fixed(2)),              // E8
+        (MOS6502::sbc, AddressingMode::Immediate, Cycles::Fixed(2)),            // E9
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // EA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // EB
+        (MOS6502::cpx, AddressingMode::Absolute, Cycles::Fixed(4)),             // EC
+        (MOS6502::sbc, AddressingMode::Absolute, Cycles::Fixed(4)),             // ED
+        (MOS6502::inc, AddressingMode::Absolute, Cycles::Fixed(6)),             // EE
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // EF
+        (MOS6502::beq, AddressingMode::Relative, Cycles::Variable(2)),          // F0
+        (MOS6502::sbc, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // F1
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F2
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F3
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F4
+        (MOS6502::sbc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // F5
+        (MOS6502::inc, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // F6
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // F7
+        (MOS6502::sed, AddressingMode::Implied, Cycles::Fixed(2)),              // F8
+        (MOS6502::sbc, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // F9
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FB
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FC
+        (MOS6502::sbc, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // FD
+        (MOS6502::inc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // FE
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // FF
+    ]);
 }
 
-impl<T: Bus> Default for MOS6502<T> {
+impl<T: AccessBus> Default for MOS6502<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     /// Create new instance of MOS6502
     pub fn new() -> Self {
+        Self::with_variant(Variant::default())
+    }
+
+    /// Create new instance of the given chip variant
+    pub fn with_variant(variant: Variant) -> Self {
         Self {
             accumulator: u8::MIN,
             x_register: u8::MIN,
@@ -373,7 +582,20 @@ impl<T: Bus> MOS6502<T> {
             program_counter: u16::MIN,
             stack_pointer: u8::MAX,
             status_register: CpuFlags::Unused | CpuFlags::Break,
-            opcode_array: OpcodeFunctionArray::default(),
+            rdy_line: true,
+            so_line: true,
+            nmi_pending: false,
+            wait_cycles: 0,
+            variant,
+            extension: match variant {
+                Variant::Mos6510 => Extension::Mos6510(IoPort::new()),
+                Variant::Csg65ce02 | Variant::Mega45gs02 => {
+                    Extension::Csg65ce02(Csg65ce02Extension::new())
+                }
+                Variant::Huc6280 => Extension::Huc6280(Huc6280Extension::new()),
+                _ => Extension::None,
+            },
+            _bus: PhantomData,
         }
     }
 
@@ -402,25 +624,407 @@ impl<T: Bus> MOS6502<T> {
         self.stack_pointer
     }
 
+    /// Z register of the 65CE02 family; always 0 on other variants
+    #[inline]
+    pub fn z_register(&self) -> u8 {
+        match &self.extension {
+            Extension::Csg65ce02(csg65ce02) => csg65ce02.z_register,
+            _ => 0,
+        }
+    }
+
+    /// High byte of the zero ("base") page: the B register on the 65CE02 family, $20 on
+    /// the HuC6280 and $00 elsewhere
+    #[inline]
+    pub fn base_page(&self) -> u8 {
+        match &self.extension {
+            Extension::Csg65ce02(csg65ce02) => csg65ce02.base_page,
+            Extension::Huc6280(_) => 0x20,
+            _ => 0x00,
+        }
+    }
+
+    /// High byte of the stack pointer: the SPH register on the 65CE02 family, $21 on
+    /// the HuC6280 and $01 elsewhere
+    #[inline]
+    pub fn stack_pointer_high(&self) -> u8 {
+        match &self.extension {
+            Extension::Csg65ce02(csg65ce02) => csg65ce02.stack_pointer_high,
+            Extension::Huc6280(_) => 0x21,
+            _ => 0x01,
+        }
+    }
+
+    /// Whether the 65CE02 runs with a 16-bit stack pointer (E flag cleared)
+    #[inline]
+    pub fn extended_stack(&self) -> bool {
+        match &self.extension {
+            Extension::Csg65ce02(csg65ce02) => csg65ce02.extended_stack,
+            _ => false,
+        }
+    }
+
+    /// A, X, Y and Z as latched by the last 45GS02 MAP instruction.
+    /// Translating them into physical addresses is left to the bus.
+    #[inline]
+    pub fn memory_map(&self) -> [u8; 4] {
+        match &self.extension {
+            Extension::Csg65ce02(csg65ce02) => csg65ce02.memory_map,
+            _ => [0; 4],
+        }
+    }
+
+    /// Chip variant being emulated
+    #[inline]
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// On-chip I/O port, if the variant has one
+    #[inline]
+    pub fn io_port(&self) -> Option<&IoPort> {
+        match &self.extension {
+            Extension::Mos6510(port) => Some(port),
+            _ => None,
+        }
+    }
+
+    /// Mutable access to the on-chip I/O port, e.g. to drive its input pins
+    #[inline]
+    pub fn io_port_mut(&mut self) -> Option<&mut IoPort> {
+        match &mut self.extension {
+            Extension::Mos6510(port) => Some(port),
+            _ => None,
+        }
+    }
+
+    /// HuC6280 memory paging registers. MPR n selects the physical 8 KiB bank seen at
+    /// logical addresses n * $2000 onwards.
+    #[inline]
+    pub fn mpr(&self) -> [u8; 8] {
+        match &self.extension {
+            Extension::Huc6280(huc6280) => huc6280.mpr,
+            _ => [0; 8],
+        }
+    }
+
+    /// Set a HuC6280 memory paging register, as TAM would. Only the low three bits of
+    /// `index` select the register. Does nothing on other variants.
+    #[inline]
+    pub fn set_mpr(&mut self, index: u8, value: u8) {
+        if let Extension::Huc6280(huc6280) = &mut self.extension {
+            huc6280.mpr[(index & 7) as usize] = value;
+        }
+    }
+
+    /// Whether the HuC6280 runs at 7.16 MHz (CSH) rather than 1.79 MHz (CSL)
+    #[inline]
+    pub fn high_speed(&self) -> bool {
+        match &self.extension {
+            Extension::Huc6280(huc6280) => huc6280.high_speed,
+            _ => false,
+        }
+    }
+
+    /// HuC6280 T flag, set by SET for the following instruction only
+    #[inline]
+    pub fn memory_operation(&self) -> bool {
+        match &self.extension {
+            Extension::Huc6280(huc6280) => huc6280.memory_operation,
+            _ => false,
+        }
+    }
+
+    /// On-die timer, if the variant has one
+    #[inline]
+    pub fn timer(&self) -> Option<&Timer> {
+        match &self.extension {
+            Extension::Huc6280(huc6280) => Some(&huc6280.timer),
+            _ => None,
+        }
+    }
+
+    /// On-die interrupt controller, if the variant has one
+    #[inline]
+    pub fn interrupt_controller(&self) -> Option<&InterruptController> {
+        match &self.extension {
+            Extension::Huc6280(huc6280) => Some(&huc6280.interrupt_controller),
+            _ => None,
+        }
+    }
+
+    /// Mutable access to the on-die interrupt controller, e.g. to drive IRQ1/IRQ2
+    #[inline]
+    pub fn interrupt_controller_mut(&mut self) -> Option<&mut InterruptController> {
+        match &mut self.extension {
+            Extension::Huc6280(huc6280) => Some(&mut huc6280.interrupt_controller),
+            _ => None,
+        }
+    }
+
+    /// Registers of the 65CE02 family, for the handlers only its opcode table uses
+    #[inline]
+    fn csg65ce02(&mut self) -> &mut Csg65ce02Extension {
+        match &mut self.extension {
+            Extension::Csg65ce02(csg65ce02) => csg65ce02,
+            _ => unreachable!("65CE02 instruction on {:?}", self.variant),
+        }
+    }
+
+    /// State of the HuC6280, for the handlers only its opcode table uses
+    #[inline]
+    fn huc6280(&mut self) -> &mut Huc6280Extension {
+        match &mut self.extension {
+            Extension::Huc6280(huc6280) => huc6280,
+            _ => unreachable!("HuC6280 instruction on {:?}", self.variant),
+        }
+    }
+
+    /// Current level of the RDY input
+    #[inline]
+    pub fn rdy(&self) -> bool {
+        self.rdy_line
+    }
+
+    /// Current level of the SO (set overflow) input
+    #[inline]
+    pub fn so(&self) -> bool {
+        self.so_line
+    }
+
+    /// Drive the RDY input. While it is low, [`MOS6502::step`] runs no instruction and
+    /// reports one stalled cycle, and interrupts wait for it to go high again.
+    ///
+    /// Between steps the next read cycle is the opcode fetch, which is where the halt
+    /// takes effect. To pull RDY in the middle of an instruction, the bus reports the
+    /// stall from [`AccessBus::rdy_wait`] instead.
+    #[inline]
+    pub fn set_rdy(&mut self, level: bool) {
+        self.rdy_line = level;
+    }
+
+    /// Drive the SO input. A falling edge sets the overflow flag.
+    #[inline]
+    pub fn set_so(&mut self, level: bool) {
+        if self.so_line && !level {
+            self.flag_set(CpuFlags::Overflow, true);
+        }
+        self.so_line = level;
+    }
+
     /// Change value of program counter
     #[inline]
     pub fn set_program_counter(&mut self, value: u16) {
         self.program_counter = value;
     }
 
+    /// Change value of accumulator
+    #[inline]
+    pub fn set_accumulator(&mut self, value: u8) {
+        self.accumulator = value;
+    }
+
+    /// Change value of X register
+    #[inline]
+    pub fn set_x_register(&mut self, value: u8) {
+        self.x_register = value;
+    }
+
+    /// Change value of Y register
+    #[inline]
+    pub fn set_y_register(&mut self, value: u8) {
+        self.y_register = value;
+    }
+
+    /// Change value of stack pointer
+    #[inline]
+    pub fn set_stack_pointer(&mut self, value: u8) {
+        self.stack_pointer = value;
+    }
+
+    #[inline]
+    fn read_bus(&mut self, bus: &mut T, address: u16, kind: AccessKind) -> Result<u8, BusError> {
+        self.wait_cycles += bus.rdy_wait()?;
+        let address = address & self.variant.address_mask();
+        match &self.extension {
+            Extension::Huc6280(huc6280) => {
+                let address = huc6280.physical_address(address);
+                self.read_physical_bus(bus, address, kind)
+            }
+            Extension::Mos6510(port) if address <= 1 => Ok(port.read(address)),
+            _ => bus.read_access(address, kind),
+        }
+    }
+
+    #[inline]
+    fn write_bus(
+        &mut self,
+        bus: &mut T,
+        address: u16,
+        value: u8,
+        kind: AccessKind,
+    ) -> Result<(), BusError> {
+        // NMOS parts ignore RDY during writes
+        if self.variant == Variant::Wdc65c02 {
+            self.wait_cycles += bus.rdy_wait()?;
+        }
+        let address = address & self.variant.address_mask();
+        match &mut self.extension {
+            Extension::Huc6280(huc6280) => {
+                let address = huc6280.physical_address(address);
+                self.write_physical_bus(bus, address, value, kind)
+            }
+            Extension::Mos6510(port) if address <= 1 => {
+                port.write(address, value);
+                Ok(())
+            }
+            _ => bus.write_access(address, value, kind),
+        }
+    }
+
+    /// Read half of a read-modify-write cycle, followed on buses that want dummy cycles
+    /// by the write-back of the unmodified value that NMOS parts perform, or the second
+    /// read of the 65C02 and the HuC6280 derived from it
+    #[inline]
+    fn read_modify_bus(&mut self, bus: &mut T, address: u16) -> Result<u8, BusError> {
+        let value = self.read_bus(bus, address, AccessKind::ReadModifyWrite)?;
+        if bus.dummy_accesses() {
+            match self.variant {
+                Variant::Csg65ce02 | Variant::Mega45gs02 => {}
+                Variant::Wdc65c02 | Variant::Huc6280 => {
+                    self.read_bus(bus, address, AccessKind::Dummy)?;
+                }
+                _ => self.write_bus(bus, address, value, AccessKind::Dummy)?,
+            }
+        }
+        Ok(value)
+    }
+
+    /// Whether the dead cycles of the NMOS core are performed as [`AccessKind::Dummy`]
+    /// accesses. The CMOS parts idle differently and only make the read-modify-write
+    /// and one-byte opcode dummy accesses.
+    #[inline]
+    fn nmos_dummy_cycles(&self, bus: &T) -> bool {
+        bus.dummy_accesses()
+            && matches!(
+                self.variant,
+                Variant::Nmos6502
+                    | Variant::Mos6510
+                    | Variant::Mos6507
+                    | Variant::Mos6504
+                    | Variant::Mos6503
+            )
+    }
+
+    /// Dummy read of an indexed address before its high byte is fixed up, made by every
+    /// NMOS read that crosses a page
+    #[inline]
+    fn page_cross_dummy_read(
+        &mut self,
+        bus: &mut T,
+        low_byte: u8,
+        high_byte: u8,
+    ) -> Result<(), BusError> {
+        if self.nmos_dummy_cycles(bus) {
+            self.read_bus(
+                bus,
+                u16::from_le_bytes([low_byte, high_byte]),
+                AccessKind::Dummy,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Dummy read NMOS stores and read-modify-write instructions make at an indexed
+    /// address that didn't cross a page, as they always spend the fix-up cycle
+    #[inline]
+    fn indexed_write_dummy_read(
+        &mut self,
+        bus: &mut T,
+        address: u16,
+        overflow: bool,
+    ) -> Result<(), BusError> {
+        if !overflow && self.nmos_dummy_cycles(bus) {
+            self.read_bus(bus, address, AccessKind::Dummy)?;
+        }
+        Ok(())
+    }
+
+    /// Dummy read of the stack NMOS parts make before a pull, and in JSR before pushing
+    /// the return address
+    #[inline]
+    fn stack_dummy_read(&mut self, bus: &mut T) -> Result<(), BusError> {
+        if self.nmos_dummy_cycles(bus) {
+            self.read_bus(bus, self.stack_address(), AccessKind::Dummy)?;
+        }
+        Ok(())
+    }
+
+    /// Whether interrupts are inhibited between a 45GS02 MAP and the following EOM
+    #[inline]
+    fn map_in_progress(&self) -> bool {
+        match &self.extension {
+            Extension::Csg65ce02(csg65ce02) => csg65ce02.map_in_progress,
+            _ => false,
+        }
+    }
+
+    #[inline]
+    fn is_65ce02(&self) -> bool {
+        matches!(self.variant, Variant::Csg65ce02 | Variant::Mega45gs02)
+    }
+
+    /// Address of `offset` within the zero page, which the 65CE02 relocates with B
+    #[inline]
+    fn base_page_address(&self, offset: u8) -> u16 {
+        u16::from_le_bytes([offset, self.base_page()])
+    }
+
+    #[inline]
+    fn stack_address(&self) -> u16 {
+        u16::from_le_bytes([self.stack_pointer, self.stack_pointer_high()])
+    }
+
+    /// Move the 16-bit stack pointer of the 65CE02 family
+    #[inline]
+    fn set_stack_address(&mut self, address: u16) {
+        [self.stack_pointer, self.csg65ce02().stack_pointer_high] = address.to_le_bytes();
+    }
+
     #[inline]
     fn pop_from_stack(&mut self, bus: &mut T) -> Result<u8, BusError> {
-        self.stack_pointer = self.stack_pointer.wrapping_add(1);
-        bus.read(STACK_BASE + self.stack_pointer as u16)
+        if self.extended_stack() {
+            self.set_stack_address(self.stack_address().wrapping_add(1));
+        } else {
+            self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        }
+        self.read_bus(bus, self.stack_address(), AccessKind::Stack)
     }
 
     #[inline]
     fn push_to_stack(&mut self, bus: &mut T, value: u8) -> Result<(), BusError> {
-        let result = bus.write(STACK_BASE + self.stack_pointer as u16, value);
-        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        let result = self.write_bus(bus, self.stack_address(), value, AccessKind::Stack);
+        if self.extended_stack() {
+            self.set_stack_address(self.stack_address().wrapping_sub(1));
+        } else {
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        }
         result
     }
 
+    /// Status register as pushed to the stack. Bit 5 is the E flag on the 65CE02 and
+    /// always reads 1 elsewhere.
+    #[inline]
+    fn pushed_status(&self, status: CpuFlags) -> u8 {
+        let mut status = status;
+        // On the HuC6280 it is the T flag, which no instruction sees set when pushing
+        status.set(
+            CpuFlags::Unused,
+            !self.extended_stack() && self.variant != Variant::Huc6280,
+        );
+        status.into()
+    }
+
     fn perform_interrupt(
         &mut self,
         return_address: u16,
@@ -429,50 +1033,283 @@ impl<T: Bus> MOS6502<T> {
     ) -> Result<u32, CpuError> {
         let (return_address_lo, return_address_hi): (u8, u8) = return_address.to_le_bytes().into();
 
+        // In place of the opcode and operand fetches of BRK, an NMOS part reads the
+        // interrupted instruction twice
+        if !matches!(kind, InterruptKind::Brk) && self.nmos_dummy_cycles(bus) {
+            self.read_bus(bus, self.program_counter, AccessKind::Dummy)?;
+            self.read_bus(bus, self.program_counter, AccessKind::Dummy)?;
+        }
         self.push_to_stack(bus, return_address_hi)?;
         self.push_to_stack(bus, return_address_lo)?;
 
+        let huc6280 = self.variant == Variant::Huc6280;
         let (vector_address, status_register_value): (u16, CpuFlags) = match kind {
+            InterruptKind::Irq if huc6280 => (0xFFF6, self.status_register & !CpuFlags::Break),
             InterruptKind::Irq => (0xFFFE, self.status_register & !CpuFlags::Break),
+            InterruptKind::Nmi if huc6280 => (0xFFFC, self.status_register & !CpuFlags::Break),
             InterruptKind::Nmi => (0xFFFA, self.status_register & !CpuFlags::Break),
+            InterruptKind::Brk if huc6280 => (0xFFF6, self.status_register | CpuFlags::Break),
             InterruptKind::Brk => (0xFFFE, self.status_register | CpuFlags::Break),
+            InterruptKind::Irq1 => (0xFFF8, self.status_register & !CpuFlags::Break),
+            InterruptKind::TimerIrq => (0xFFFA, self.status_register & !CpuFlags::Break),
         };
-        self.push_to_stack(bus, (status_register_value | CpuFlags::Unused).into())?;
+        self.push_to_stack(bus, self.pushed_status(status_register_value))?;
 
-        let divert_address_lo = bus.read(vector_address)?;
-        let divert_address_hi = bus.read(vector_address + 1)?;
+        let divert_address_lo = self.read_bus(bus, vector_address, AccessKind::VectorPull)?;
+        let divert_address_hi = self.read_bus(bus, vector_address + 1, AccessKind::VectorPull)?;
 
         self.set_program_counter(u16::from_le_bytes([divert_address_lo, divert_address_hi]));
         self.flag_set(CpuFlags::NoInterrupts, true);
+        if self.is_65ce02() || huc6280 || self.variant == Variant::Wdc65c02 {
+            self.flag_set(CpuFlags::Decimal, false);
+        }
 
-        Ok(7)
+        Ok(if huc6280 { 8 } else { 7 })
     }
 
+    /// Perform the reset sequence: the three stack pushes of an interrupt are suppressed
+    /// but still move the stack pointer, interrupts are disabled and the program counter
+    /// is loaded from the reset vector. CMOS parts also clear the decimal flag, the
+    /// 65CE02 returns to an 8-bit stack, the HuC6280 maps bank 0 at $E000 and the 6510
+    /// turns its I/O port into inputs.
+    pub fn reset(&mut self, bus: &mut T) -> Result<u32, CpuError> {
+        if self.nmos_dummy_cycles(bus) {
+            self.read_bus(bus, self.program_counter, AccessKind::Dummy)?;
+            self.read_bus(bus, self.program_counter, AccessKind::Dummy)?;
+        }
+        for _ in 0..3 {
+            // The pushes turn into reads
+            if self.nmos_dummy_cycles(bus) {
+                self.read_bus(bus, self.stack_address(), AccessKind::Dummy)?;
+            }
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        }
+        self.flag_set(CpuFlags::NoInterrupts, true);
+        self.nmi_pending = false;
+        let vector_address = match &mut self.extension {
+            Extension::Csg65ce02(csg65ce02) => {
+                csg65ce02.extended_stack = false;
+                csg65ce02.map_in_progress = false;
+                self.flag_set(CpuFlags::Decimal, false);
+                0xFFFC
+            }
+            Extension::Huc6280(huc6280) => {
+                huc6280.mpr[7] = 0x00;
+                huc6280.high_speed = false;
+                huc6280.memory_operation = false;
+                self.flag_set(CpuFlags::Decimal, false);
+                0xFFFE
+            }
+            Extension::Mos6510(port) => {
+                port.write(0, 0);
+                0xFFFC
+            }
+            Extension::None if self.variant == Variant::Wdc65c02 => {
+                self.flag_set(CpuFlags::Decimal, false);
+                0xFFFC
+            }
+            Extension::None => 0xFFFC,
+        };
+
+        let low_byte = self.read_bus(bus, vector_address, AccessKind::VectorPull)?;
+        let high_byte = self.read_bus(bus, vector_address + 1, AccessKind::VectorPull)?;
+        self.set_program_counter(u16::from_le_bytes([low_byte, high_byte]));
+        Ok(self.elapse(7))
+    }
+
+    /// Take an IRQ unless interrupts are disabled. Does nothing on packages without an
+    /// IRQ pin, or while RDY is low; IRQ is level-triggered, so the caller keeps it
+    /// asserted until it is taken. On the HuC6280 the pin is IRQ2: it is ignored while
+    /// the interrupt controller disables IRQ2, and a pending timer or IRQ1 request is
+    /// taken ahead of it.
     pub fn irq(&mut self, bus: &mut T) -> Result<u32, CpuError> {
-        if self.flag_check(CpuFlags::NoInterrupts) {
+        if self.flag_check(CpuFlags::NoInterrupts)
+            || self.map_in_progress()
+            || !self.variant.has_irq()
+            || !self.rdy_line
+        {
             return Ok(0);
         }
-        self.perform_interrupt(self.program_counter, InterruptKind::Irq, bus)
+        let kind = if self.variant == Variant::Huc6280 {
+            // The HuC6280's IRQ input is IRQ2, behind the controller's mask and priority
+            match self.pending_controller_interrupt(true) {
+                Some(kind) => kind,
+                None => return Ok(0),
+            }
+        } else {
+            InterruptKind::Irq
+        };
+        let cycles = self.perform_interrupt(self.program_counter, kind, bus)?;
+        Ok(self.elapse(cycles))
     }
 
+    /// Take an NMI. Does nothing on packages without an NMI pin. While RDY is low, or
+    /// between a 45GS02 MAP and the following EOM, the edge is latched, and the NMI is
+    /// taken by the first [`MOS6502::step`] after RDY goes high or EOM has run.
     pub fn nmi(&mut self, bus: &mut T) -> Result<u32, CpuError> {
-        self.perform_interrupt(self.program_counter, InterruptKind::Nmi, bus)
+        if !self.variant.has_nmi() {
+            return Ok(0);
+        }
+        if !self.rdy_line || self.map_in_progress() {
+            self.nmi_pending = true;
+            return Ok(0);
+        }
+        let cycles = self.perform_interrupt(self.program_counter, InterruptKind::Nmi, bus)?;
+        Ok(self.elapse(cycles))
+    }
+
+    /// Let on-chip peripherals observe the passage of `cycles`, plus any the bus held
+    /// RDY low for
+    #[inline]
+    fn elapse(&mut self, cycles: u32) -> u32 {
+        let cycles = cycles + std::mem::take(&mut self.wait_cycles);
+        match &mut self.extension {
+            Extension::Mos6510(port) => port.tick(cycles),
+            Extension::Huc6280(huc6280) => huc6280.tick(cycles),
+            _ => {}
+        }
+        cycles
     }
 
-    fn not_implemented(&mut self, _: &mut impl Bus, _: AddressingMode) -> Result<u32, CpuError> {
+    fn not_implemented(&mut self, _: &mut T, _: AddressingMode) -> Result<u32, CpuError> {
         Err(CpuError::OpcodeNotImplemented)
     }
 
-    /// Step over one CPU instruction
+    /// Step over one CPU instruction.
+    ///
+    /// If RDY is low, no instruction is executed and a single stalled cycle is reported.
+    /// An NMI that arrived while RDY was low or a MAP was in progress is taken instead of
+    /// the next instruction, as is, on the HuC6280, a pending request from the on-die
+    /// interrupt controller.
     #[inline]
     pub fn step(&mut self, bus: &mut T) -> Result<u32, CpuError> {
-        let opcode = bus.read(self.program_counter)? as usize;
+        if !self.rdy_line {
+            return Ok(self.elapse(1));
+        }
+        if self.nmi_pending && !self.map_in_progress() {
+            self.nmi_pending = false;
+            let cycles = self.perform_interrupt(self.program_counter, InterruptKind::Nmi, bus)?;
+            return Ok(self.elapse(cycles));
+        }
+        if let Some(kind) = self.pending_controller_interrupt(false) {
+            self.huc6280().memory_operation = false;
+            let cycles = self.perform_interrupt(self.program_counter, kind, bus)?;
+            return Ok(self.elapse(cycles));
+        }
+        let opcode_address = self.program_counter;
+        let opcode = self.read_bus(bus, self.program_counter, AccessKind::OpcodeFetch)? as usize;
         self.increment_program_counter(1);
-        let (ref opcode_func, address_mode, base_cycles) = self.opcode_array.0[opcode];
-        let spent_cycles = opcode_func(self, bus, address_mode)?;
-        match base_cycles {
-            Cycles::Fixed(n) => Ok(n),
-            Cycles::Variable(n) => Ok(spent_cycles + n),
+        let opcode_array = match self.variant {
+            Variant::Csg65ce02 | Variant::Mega45gs02 => &OpcodeFunctionArray::CSG65CE02,
+            Variant::Huc6280 => &OpcodeFunctionArray::HUC6280,
+            Variant::Wdc65c02 => &OpcodeFunctionArray::WDC65C02,
+            _ => &OpcodeFunctionArray::NMOS6502,
+        };
+        let (opcode_func, address_mode, base_cycles) = opcode_array.0[opcode];
+        if let AddressingMode::Implied | AddressingMode::Accumulator = address_mode {
+            // The one-cycle NOPs of the 65C02 are over before they could make it
+            let one_cycle = matches!(base_cycles, Cycles::Fixed(1));
+            if !self.is_65ce02() && !one_cycle && bus.dummy_accesses() {
+                self.read_bus(bus, self.program_counter, AccessKind::Dummy)?;
+            }
+        }
+        let memory_operation = match &mut self.extension {
+            Extension::Huc6280(huc6280) => std::mem::take(&mut huc6280.memory_operation),
+            _ => false,
+        };
+        let (spent_cycles, extra_cycles) =
+            if memory_operation && huc6280::uses_memory_operation(opcode as u8) {
+                (
+                    self.with_memory_operand(bus, |cpu, bus| opcode_func(cpu, bus, address_mode))?,
+                    3,
+                )
+            } else {
+                (opcode_func(self, bus, address_mode)?, 0)
+            };
+        let cycles = match base_cycles {
+            Cycles::Fixed(n) => n,
+            Cycles::Variable(n) => spent_cycles + n,
+            Cycles::Branch(n, taken_cycles) => {
+                let operand_length = match address_mode {
+                    AddressingMode::Relative => 1,
+                    _ => 2,
+                };
+                let taken = self.program_counter != opcode_address.wrapping_add(1 + operand_length);
+                n + taken_cycles * taken as u32
+            }
+        };
+        Ok(self.elapse(cycles + extra_cycles))
+    }
+
+    /// Trace line for what the next [`MOS6502::step`] does: the address of the
+    /// instruction, followed on packages with fewer address lines by the one the bus
+    /// sees, its bytes and disassembly, or `RDY stall` while RDY is low, and the
+    /// registers. `peek` reads memory as for [`disassemble`].
+    pub fn trace(&self, peek: impl FnMut(u16) -> u8) -> String {
+        let address = format!(
+            "{:04X}{}",
+            self.program_counter,
+            disassembler::physical(self.program_counter, self.variant.address_mask())
+        );
+        let (bytes, text) = if self.rdy_line {
+            let instruction = disassemble(self.variant, self.program_counter, peek);
+            let bytes: Vec<String> = instruction
+                .bytes
+                .iter()
+                .map(|byte| format!("{byte:02X}"))
+                .collect();
+            (bytes.join(" "), instruction.text)
+        } else {
+            (String::new(), "RDY stall".to_string())
+        };
+        format!(
+            "{address}  {bytes:<8}  {text:<24}  A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+            self.accumulator,
+            self.x_register,
+            self.y_register,
+            u8::from(self.status_register),
+            self.stack_pointer
+        )
+    }
+
+    /// Encode the CPU state, prefixed with [`STATE_VERSION`]
+    pub fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(bincode::serialize(&(STATE_VERSION, self))?)
+    }
+
+    /// Restore a state produced by [`MOS6502::save_state`]
+    ///
+    /// A 0.1.0 state, the bincode encoding of the registers alone, is recognised by its
+    /// length and loaded as an NMOS 6502 with the pins at their power-on levels.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        if state.len() == UNVERSIONED_STATE_LENGTH {
+            type Registers = (u8, u8, u8, u8, CpuFlags, u16);
+            let (
+                accumulator,
+                x_register,
+                y_register,
+                stack_pointer,
+                status_register,
+                program_counter,
+            ): Registers = bincode::deserialize(state)?;
+            *self = Self {
+                accumulator,
+                x_register,
+                y_register,
+                stack_pointer,
+                status_register,
+                program_counter,
+                ..Self::new()
+            };
+            return Ok(());
+        }
+        match bincode::deserialize(state)? {
+            STATE_VERSION => {
+                let (_, cpu): (u32, Self) = bincode::deserialize(state)?;
+                *self = cpu;
+                Ok(())
+            }
+            version => Err(StateError::UnsupportedVersion(version)),
         }
     }
 
@@ -503,9 +1340,10 @@ impl<T: Bus> MOS6502<T> {
         match address_mode {
             AddressingMode::Accumulator => Ok(OpcodeOperand::Byte(self.accumulator)),
             AddressingMode::Absolute => {
-                let low_byte: u8 = bus.read(self.program_counter)?;
+                let low_byte: u8 = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
-                let high_byte: u8 = bus.read(self.program_counter)?;
+                let high_byte: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
                 let address = u16::from_le_bytes([low_byte, high_byte]);
@@ -513,70 +1351,116 @@ impl<T: Bus> MOS6502<T> {
                 Ok(OpcodeOperand::Address(address))
             }
             AddressingMode::AbsoluteXIndex => {
-                let low_byte: u8 = bus.read(self.program_counter)?;
+                let low_byte: u8 = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
-                let mut high_byte: u8 = bus.read(self.program_counter)?;
+                let mut high_byte: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
                 let (low_byte, overflow) = low_byte.overflowing_add(self.x_register);
+                if overflow {
+                    self.page_cross_dummy_read(bus, low_byte, high_byte)?;
+                }
                 high_byte = high_byte.wrapping_add(overflow as u8);
 
                 let address = u16::from_le_bytes([low_byte, high_byte]);
                 Ok(OpcodeOperand::AddressWithOverflow(address, overflow))
             }
             AddressingMode::AbsoluteYIndex => {
-                let low_byte: u8 = bus.read(self.program_counter)?;
+                let low_byte: u8 = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
-                let mut high_byte: u8 = bus.read(self.program_counter)?;
+                let mut high_byte: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
                 let (low_byte, overflow) = low_byte.overflowing_add(self.y_register);
+                if overflow {
+                    self.page_cross_dummy_read(bus, low_byte, high_byte)?;
+                }
                 high_byte = high_byte.wrapping_add(overflow as u8);
 
                 let address = u16::from_le_bytes([low_byte, high_byte]);
                 Ok(OpcodeOperand::AddressWithOverflow(address, overflow))
             }
             AddressingMode::Immediate => {
-                let byte: u8 = bus.read(self.program_counter)?;
+                let byte: u8 = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
                 Ok(OpcodeOperand::Byte(byte))
             }
             AddressingMode::Implied => Ok(OpcodeOperand::None),
             AddressingMode::Indirect => {
-                let mut low_byte: u8 = bus.read(self.program_counter)?;
+                let mut low_byte: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
-                let mut high_byte: u8 = bus.read(self.program_counter)?;
+                let mut high_byte: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
                 let address = u16::from_le_bytes([low_byte, high_byte]);
 
-                low_byte = bus.read(address)?;
-                high_byte = bus.read(address.wrapping_add(1))?;
+                low_byte = self.read_bus(bus, address, AccessKind::Data)?;
+                high_byte = self.read_bus(bus, address.wrapping_add(1), AccessKind::Data)?;
 
                 let operand = OpcodeOperand::Address(u16::from_le_bytes([low_byte, high_byte]));
                 Ok(operand)
             }
             AddressingMode::XIndexIndirect => {
-                let mut zeropage_address: u8 = bus.read(self.program_counter)?;
+                let mut zeropage_address: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
+                // NMOS parts read the unindexed pointer while adding X
+                if self.nmos_dummy_cycles(bus) {
+                    self.read_bus(
+                        bus,
+                        self.base_page_address(zeropage_address),
+                        AccessKind::Dummy,
+                    )?;
+                }
                 zeropage_address = zeropage_address.wrapping_add(self.x_register);
 
-                let low_byte = bus.read(zeropage_address as u16)?;
-                let high_byte = bus.read(zeropage_address.wrapping_add(1) as u16)?;
+                let low_byte = self.read_bus(
+                    bus,
+                    self.base_page_address(zeropage_address),
+                    AccessKind::Data,
+                )?;
+                let high_byte = self.read_bus(
+                    bus,
+                    self.base_page_address(zeropage_address.wrapping_add(1)),
+                    AccessKind::Data,
+                )?;
 
                 let operand = OpcodeOperand::Address(u16::from_le_bytes([low_byte, high_byte]));
                 Ok(operand)
             }
-            AddressingMode::IndirectYIndex => {
-                let zeropage_address = bus.read(self.program_counter)?;
+            AddressingMode::IndirectYIndex
+            | AddressingMode::IndirectZIndex
+            | AddressingMode::ZeropageIndirect => {
+                let zeropage_address =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
-                let low_byte = bus.read(zeropage_address as u16)?;
-                let mut high_byte = bus.read(zeropage_address.wrapping_add(1) as u16)?;
-
-                let (low_byte, overflow) = low_byte.overflowing_add(self.y_register);
+                let low_byte = self.read_bus(
+                    bus,
+                    self.base_page_address(zeropage_address),
+                    AccessKind::Data,
+                )?;
+                let mut high_byte = self.read_bus(
+                    bus,
+                    self.base_page_address(zeropage_address.wrapping_add(1)),
+                    AccessKind::Data,
+                )?;
+
+                let index = match address_mode {
+                    AddressingMode::IndirectZIndex => self.z_register(),
+                    AddressingMode::ZeropageIndirect => 0,
+                    _ => self.y_register,
+                };
+                let (low_byte, overflow) = low_byte.overflowing_add(index);
+                if overflow {
+                    self.page_cross_dummy_read(bus, low_byte, high_byte)?;
+                }
                 high_byte = high_byte.wrapping_add(overflow as u8);
 
                 let operand = OpcodeOperand::AddressWithOverflow(
@@ -586,7 +1470,7 @@ impl<T: Bus> MOS6502<T> {
                 Ok(operand)
             }
             AddressingMode::Relative => {
-                let offset = bus.read(self.program_counter)?;
+                let offset = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
                 let offset = (offset as i8) as i16;
@@ -595,30 +1479,98 @@ impl<T: Bus> MOS6502<T> {
                 Ok(OpcodeOperand::Address(new_program_counter))
             }
             AddressingMode::Zeropage => {
-                let zeropage_address = bus.read(self.program_counter)?;
+                let zeropage_address =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
-                Ok(OpcodeOperand::Address(zeropage_address as u16))
+                Ok(OpcodeOperand::Address(
+                    self.base_page_address(zeropage_address),
+                ))
             }
             AddressingMode::ZeropageXIndex => {
                 let offset = self.x_register;
 
-                let zeropage_address = bus.read(self.program_counter)?;
+                let zeropage_address =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
+                // NMOS parts read the unindexed address while adding the index
+                if self.nmos_dummy_cycles(bus) {
+                    self.read_bus(
+                        bus,
+                        self.base_page_address(zeropage_address),
+                        AccessKind::Dummy,
+                    )?;
+                }
                 let address = zeropage_address.wrapping_add(offset);
 
-                Ok(OpcodeOperand::Address(address as u16))
+                Ok(OpcodeOperand::Address(self.base_page_address(address)))
             }
             AddressingMode::ZeropageYIndex => {
                 let offset = self.y_register;
 
-                let zeropage_address = bus.read(self.program_counter)?;
+                let zeropage_address =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
                 self.increment_program_counter(1);
 
+                // NMOS parts read the unindexed address while adding the index
+                if self.nmos_dummy_cycles(bus) {
+                    self.read_bus(
+                        bus,
+                        self.base_page_address(zeropage_address),
+                        AccessKind::Dummy,
+                    )?;
+                }
                 let address = zeropage_address.wrapping_add(offset);
 
-                Ok(OpcodeOperand::Address(address as u16))
+                Ok(OpcodeOperand::Address(self.base_page_address(address)))
+            }
+            AddressingMode::AbsoluteXIndexIndirect => {
+                let low_byte: u8 = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.increment_program_counter(1);
+                let high_byte: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.increment_program_counter(1);
+
+                let address =
+                    u16::from_le_bytes([low_byte, high_byte]).wrapping_add(self.x_register as u16);
+
+                let low_byte = self.read_bus(bus, address, AccessKind::Data)?;
+                let high_byte = self.read_bus(bus, address.wrapping_add(1), AccessKind::Data)?;
+
+                Ok(OpcodeOperand::Address(u16::from_le_bytes([
+                    low_byte, high_byte,
+                ])))
+            }
+            AddressingMode::StackRelativeIndirectYIndex => {
+                let offset = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.increment_program_counter(1);
+
+                let pointer = self.stack_address().wrapping_add(offset as u16);
+                let low_byte = self.read_bus(bus, pointer, AccessKind::Data)?;
+                let mut high_byte =
+                    self.read_bus(bus, pointer.wrapping_add(1), AccessKind::Data)?;
+
+                let (low_byte, overflow) = low_byte.overflowing_add(self.y_register);
+                high_byte = high_byte.wrapping_add(overflow as u8);
+
+                Ok(OpcodeOperand::AddressWithOverflow(
+                    u16::from_le_bytes([low_byte, high_byte]),
+                    overflow,
+                ))
+            }
+            AddressingMode::RelativeLong => {
+                let low_byte: u8 = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.increment_program_counter(1);
+                let high_byte: u8 =
+                    self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.increment_program_counter(1);
+
+                // The 65CE02 counts word offsets from the last byte of the instruction
+                let offset = u16::from_le_bytes([low_byte, high_byte]);
+                let new_program_counter = self.program_counter.wrapping_sub(1).wrapping_add(offset);
+
+                Ok(OpcodeOperand::Address(new_program_counter))
             }
         }
     }
@@ -2,7 +2,7 @@ use crate::error::CpuError;
 
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     #[inline(always)]
     fn add_to_accumulator_with_carry(&mut self, value: u8) -> Result<u32, CpuError> {
         let old_value = self.accumulator;
@@ -39,10 +39,10 @@ impl<T: Bus> MOS6502<T> {
         let mut extra_cycles = 0;
         let value = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(b) => b,
-            OpcodeOperand::Address(addr) => bus.read(addr)?,
+            OpcodeOperand::Address(addr) => self.read_bus(bus, addr, AccessKind::Data)?,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
                 extra_cycles += overflow as u32;
-                bus.read(addr)?
+                self.read_bus(bus, addr, AccessKind::Data)?
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
@@ -58,13 +58,36 @@ impl<T: Bus> MOS6502<T> {
         let mut extra_cycles = 0;
         let value = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(b) => b,
-            OpcodeOperand::Address(addr) => bus.read(addr)?,
+            OpcodeOperand::Address(addr) => self.read_bus(bus, addr, AccessKind::Data)?,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
                 extra_cycles += overflow as u32;
-                bus.read(addr)?
+                self.read_bus(bus, addr, AccessKind::Data)?
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
         Ok(self.add_to_accumulator_with_carry(!value)? + extra_cycles)
     }
+
+    // negate accumulator; on the 45GS02 a doubled NEG prefixes a quad instruction
+    pub(in crate::mos6502) fn neg(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        if self.variant == Variant::Mega45gs02
+            && self.read_bus(bus, self.program_counter, AccessKind::Dummy)? == 0x42
+        {
+            self.increment_program_counter(1);
+            let opcode = self.read_bus(bus, self.program_counter, AccessKind::OpcodeFetch)?;
+            self.increment_program_counter(1);
+            return self.quad_instruction(bus, opcode);
+        }
+        self.accumulator = self.accumulator.wrapping_neg();
+        self.flag_set(
+            CpuFlags::Negative,
+            self.accumulator & NEGATIVE_BIT_MASK != 0,
+        );
+        self.flag_set(CpuFlags::Zero, self.accumulator == 0);
+        Ok(1)
+    }
 }
@@ -0,0 +1,114 @@
+use crate::mos6502::*;
+
+/// Offset of the nth byte from the start address
+type BlockStep = fn(u16) -> u16;
+
+fn increment(n: u16) -> u16 {
+    n
+}
+
+fn decrement(n: u16) -> u16 {
+    n.wrapping_neg()
+}
+
+fn fixed(_: u16) -> u16 {
+    0
+}
+
+fn alternate(n: u16) -> u16 {
+    n & 1
+}
+
+impl<T: AccessBus> MOS6502<T> {
+    /// Copy a block described by the source, destination and length operands.
+    ///
+    /// Like the HuC6280, Y, A and X are pushed before the copy and pulled afterwards,
+    /// and a length of zero copies 64 KiB.
+    fn block_transfer(
+        &mut self,
+        bus: &mut T,
+        source_step: BlockStep,
+        destination_step: BlockStep,
+    ) -> Result<u32, CpuError> {
+        let mut words = [0u16; 3];
+        for word in words.iter_mut() {
+            let low_byte = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+            self.increment_program_counter(1);
+            let high_byte = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+            self.increment_program_counter(1);
+            *word = u16::from_le_bytes([low_byte, high_byte]);
+        }
+        let [source, destination, length] = words;
+        let length = match length {
+            0 => 0x1_0000,
+            n => n as u32,
+        };
+
+        self.push_to_stack(bus, self.y_register)?;
+        self.push_to_stack(bus, self.accumulator)?;
+        self.push_to_stack(bus, self.x_register)?;
+
+        for n in 0..length {
+            let n = n as u16;
+            let value =
+                self.read_bus(bus, source.wrapping_add(source_step(n)), AccessKind::Data)?;
+            self.write_bus(
+                bus,
+                destination.wrapping_add(destination_step(n)),
+                value,
+                AccessKind::Data,
+            )?;
+        }
+
+        self.x_register = self.pop_from_stack(bus)?;
+        self.accumulator = self.pop_from_stack(bus)?;
+        self.y_register = self.pop_from_stack(bus)?;
+
+        Ok(6 * length)
+    }
+
+    // transfer, incrementing both addresses
+    pub(in crate::mos6502) fn tii(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.block_transfer(bus, increment, increment)
+    }
+
+    // transfer, decrementing both addresses
+    pub(in crate::mos6502) fn tdd(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.block_transfer(bus, decrement, decrement)
+    }
+
+    // transfer from incrementing source to a fixed destination
+    pub(in crate::mos6502) fn tin(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.block_transfer(bus, increment, fixed)
+    }
+
+    // transfer from incrementing source to an alternating pair of destination bytes
+    pub(in crate::mos6502) fn tia(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.block_transfer(bus, increment, alternate)
+    }
+
+    // transfer from an alternating pair of source bytes to incrementing destination
+    pub(in crate::mos6502) fn tai(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.block_transfer(bus, alternate, increment)
+    }
+}
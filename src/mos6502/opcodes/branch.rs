@@ -1,6 +1,25 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
+    /// Jump to `addr` if `condition` holds, returning the cycle a taken branch adds and
+    /// the one a page crossing adds on top. NMOS parts read the next opcode while adding
+    /// the offset, and the target with its high byte not yet fixed if it crosses a page.
+    fn branch_if(&mut self, bus: &mut T, addr: u16, condition: bool) -> Result<u32, CpuError> {
+        if !condition {
+            return Ok(0);
+        }
+        let page_changed = self.program_counter & 0xFF00 != addr & 0xFF00;
+        if self.nmos_dummy_cycles(bus) {
+            self.read_bus(bus, self.program_counter, AccessKind::Dummy)?;
+            if page_changed {
+                let unfixed = self.program_counter & 0xFF00 | addr & 0x00FF;
+                self.read_bus(bus, unfixed, AccessKind::Dummy)?;
+            }
+        }
+        self.set_program_counter(addr);
+        Ok(1 + page_changed as u32)
+    }
+
     pub(in crate::mos6502) fn bcc(
         &mut self,
         bus: &mut T,
@@ -10,15 +29,7 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-
-        let mut page_changed = false;
-        if !self.flag_check(CpuFlags::Carry) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, !self.flag_check(CpuFlags::Carry))
     }
 
     pub(in crate::mos6502) fn bcs(
@@ -30,14 +41,7 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let mut page_changed = false;
-        if self.flag_check(CpuFlags::Carry) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, self.flag_check(CpuFlags::Carry))
     }
 
     pub(in crate::mos6502) fn beq(
@@ -49,14 +53,7 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let mut page_changed = false;
-        if self.flag_check(CpuFlags::Zero) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, self.flag_check(CpuFlags::Zero))
     }
 
     pub(in crate::mos6502) fn bmi(
@@ -68,14 +65,7 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let mut page_changed = false;
-        if self.flag_check(CpuFlags::Negative) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, self.flag_check(CpuFlags::Negative))
     }
 
     pub(in crate::mos6502) fn bne(
@@ -87,14 +77,7 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let mut page_changed = false;
-        if !self.flag_check(CpuFlags::Zero) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, !self.flag_check(CpuFlags::Zero))
     }
 
     pub(in crate::mos6502) fn bpl(
@@ -106,14 +89,7 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let mut page_changed = false;
-        if !self.flag_check(CpuFlags::Negative) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, !self.flag_check(CpuFlags::Negative))
     }
 
     pub(in crate::mos6502) fn bvc(
@@ -125,14 +101,7 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let mut page_changed = false;
-        if !self.flag_check(CpuFlags::Overflow) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, !self.flag_check(CpuFlags::Overflow))
     }
 
     pub(in crate::mos6502) fn bvs(
@@ -144,13 +113,56 @@ impl<T: Bus> MOS6502<T> {
             OpcodeOperand::Address(w) => w,
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let mut page_changed = false;
-        if self.flag_check(CpuFlags::Overflow) {
-            let current_page = self.program_counter & 0xFF00;
-            self.set_program_counter(addr);
-            let new_page = self.program_counter & 0xFF00;
-            page_changed = current_page != new_page;
-        }
-        Ok(1 + page_changed as u32)
+        self.branch_if(bus, addr, self.flag_check(CpuFlags::Overflow))
+    }
+
+    // branch always
+    pub(in crate::mos6502) fn bra(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let addr = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) => w,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        self.branch_if(bus, addr, true)
+    }
+
+    // branch to subroutine; pushes the address of the last instruction byte like JSR
+    pub(in crate::mos6502) fn bsr(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let addr = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) => w,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let [return_address_lo, return_address_hi] =
+            self.program_counter.wrapping_sub(1).to_le_bytes();
+        self.push_to_stack(bus, return_address_hi)?;
+        self.push_to_stack(bus, return_address_lo)?;
+        self.set_program_counter(addr);
+        Ok(0)
+    }
+
+    /// Test one bit of a base page byte and branch if it equals `set` (BBRn/BBSn)
+    pub(in crate::mos6502) fn branch_on_bit(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        bit: u8,
+        set: bool,
+    ) -> Result<u32, CpuError> {
+        let value = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) => self.read_bus(bus, w, AccessKind::Data)?,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let addr = match self.resolve_operand(bus, AddressingMode::Relative)? {
+            OpcodeOperand::Address(w) => w,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        self.branch_if(bus, addr, (value & (1 << bit) != 0) == set)
     }
 }
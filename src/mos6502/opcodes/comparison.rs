@@ -1,7 +1,7 @@
 use crate::mos6502::*;
 use std::cmp::Ordering;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     #[inline(always)]
     fn compare_register(
         &mut self,
@@ -12,10 +12,10 @@ impl<T: Bus> MOS6502<T> {
         let mut extra_cycles = 0;
         let operand: u8 = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(b) => b,
-            OpcodeOperand::Address(w) => bus.read(w)?,
+            OpcodeOperand::Address(w) => self.read_bus(bus, w, AccessKind::Data)?,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
                 extra_cycles += overflow as u32;
-                bus.read(addr)?
+                self.read_bus(bus, addr, AccessKind::Data)?
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
@@ -63,4 +63,12 @@ impl<T: Bus> MOS6502<T> {
     ) -> Result<u32, CpuError> {
         self.compare_register(self.y_register, bus, address_mode)
     }
+
+    pub(in crate::mos6502) fn cpz(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.compare_register(self.z_register(), bus, address_mode)
+    }
 }
@@ -2,10 +2,11 @@ use crate::mos6502::*;
 
 macro_rules! decrement_register {
     ($cpu:expr, $register:expr) => {
-        $register = $register.wrapping_sub(1);
+        let value = $register.wrapping_sub(1);
+        $register = value;
 
-        $cpu.flag_set(CpuFlags::Negative, $register & NEGATIVE_BIT_MASK != 0);
-        $cpu.flag_set(CpuFlags::Zero, $register == 0);
+        $cpu.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
+        $cpu.flag_set(CpuFlags::Zero, value == 0);
 
         return Ok(2);
     };
@@ -13,16 +14,17 @@ macro_rules! decrement_register {
 
 macro_rules! increment_register {
     ($cpu:expr, $register:expr) => {
-        $register = $register.wrapping_add(1);
+        let value = $register.wrapping_add(1);
+        $register = value;
 
-        $cpu.flag_set(CpuFlags::Negative, $register & NEGATIVE_BIT_MASK != 0);
-        $cpu.flag_set(CpuFlags::Zero, $register == 0);
+        $cpu.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
+        $cpu.flag_set(CpuFlags::Zero, value == 0);
 
         return Ok(2);
     };
 }
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     pub(in crate::mos6502) fn dec(
         &mut self,
         bus: &mut T,
@@ -30,11 +32,14 @@ impl<T: Bus> MOS6502<T> {
     ) -> Result<u32, CpuError> {
         let addr = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Address(addr) => addr,
-            OpcodeOperand::AddressWithOverflow(addr, _) => addr,
+            OpcodeOperand::AddressWithOverflow(addr, overflow) => {
+                self.indexed_write_dummy_read(bus, addr, overflow)?;
+                addr
+            }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let value = bus.read(addr)?.wrapping_sub(1);
-        bus.write(addr, value)?;
+        let value = self.read_modify_bus(bus, addr)?.wrapping_sub(1);
+        self.write_bus(bus, addr, value, AccessKind::ReadModifyWrite)?;
 
         self.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
         self.flag_set(CpuFlags::Zero, value == 0);
@@ -65,11 +70,14 @@ impl<T: Bus> MOS6502<T> {
     ) -> Result<u32, CpuError> {
         let addr = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Address(addr) => addr,
-            OpcodeOperand::AddressWithOverflow(addr, _) => addr,
+            OpcodeOperand::AddressWithOverflow(addr, overflow) => {
+                self.indexed_write_dummy_read(bus, addr, overflow)?;
+                addr
+            }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        let value = bus.read(addr)?.wrapping_add(1);
-        bus.write(addr, value)?;
+        let value = self.read_modify_bus(bus, addr)?.wrapping_add(1);
+        self.write_bus(bus, addr, value, AccessKind::ReadModifyWrite)?;
 
         self.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
         self.flag_set(CpuFlags::Zero, value == 0);
@@ -92,4 +100,82 @@ impl<T: Bus> MOS6502<T> {
     ) -> Result<u32, CpuError> {
         increment_register!(self, self.y_register);
     }
+
+    // decrement accumulator
+    pub(in crate::mos6502) fn dea(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        decrement_register!(self, self.accumulator);
+    }
+
+    pub(in crate::mos6502) fn dez(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        decrement_register!(self, self.csg65ce02().z_register);
+    }
+
+    // increment accumulator
+    pub(in crate::mos6502) fn ina(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        increment_register!(self, self.accumulator);
+    }
+
+    pub(in crate::mos6502) fn inz(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        increment_register!(self, self.csg65ce02().z_register);
+    }
+
+    /// Add `delta` to the little-endian word at a base page address, as INW/DEW do
+    fn step_word(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        delta: i16,
+    ) -> Result<u32, CpuError> {
+        let addr = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(addr) => addr,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let high_addr = self.base_page_address((addr as u8).wrapping_add(1));
+        let low_byte = self.read_modify_bus(bus, addr)?;
+        let high_byte = self.read_modify_bus(bus, high_addr)?;
+
+        let value = u16::from_le_bytes([low_byte, high_byte]).wrapping_add_signed(delta);
+        let [low_byte, high_byte] = value.to_le_bytes();
+        self.write_bus(bus, addr, low_byte, AccessKind::ReadModifyWrite)?;
+        self.write_bus(bus, high_addr, high_byte, AccessKind::ReadModifyWrite)?;
+
+        self.flag_set(CpuFlags::Negative, high_byte & NEGATIVE_BIT_MASK != 0);
+        self.flag_set(CpuFlags::Zero, value == 0);
+
+        Ok(0)
+    }
+
+    // decrement word in base page
+    pub(in crate::mos6502) fn dew(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.step_word(bus, address_mode, -1)
+    }
+
+    // increment word in base page
+    pub(in crate::mos6502) fn inw(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.step_word(bus, address_mode, 1)
+    }
 }
@@ -1,6 +1,6 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     pub(in crate::mos6502) fn clc(
         &mut self,
         _: &mut T,
@@ -63,4 +63,24 @@ impl<T: Bus> MOS6502<T> {
         self.flag_set(CpuFlags::NoInterrupts, true);
         Ok(2)
     }
+
+    // clear E, switching the 65CE02 to a 16-bit stack pointer
+    pub(in crate::mos6502) fn cle(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.csg65ce02().extended_stack = true;
+        Ok(1)
+    }
+
+    // set E, confining the 65CE02 stack to the page in SPH
+    pub(in crate::mos6502) fn see(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.csg65ce02().extended_stack = false;
+        Ok(1)
+    }
 }
@@ -1,6 +1,6 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     pub(in crate::mos6502) fn brk(
         &mut self,
         bus: &mut T,
@@ -14,6 +14,7 @@ impl<T: Bus> MOS6502<T> {
         bus: &mut T,
         _: AddressingMode,
     ) -> Result<u32, CpuError> {
+        self.stack_dummy_read(bus)?;
         self.status_register =
             CpuFlags::from(self.pop_from_stack(bus)?) | CpuFlags::Break | CpuFlags::Unused;
         let return_address_lo = self.pop_from_stack(bus)?;
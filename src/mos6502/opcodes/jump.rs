@@ -1,6 +1,6 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     pub(in crate::mos6502) fn jmp(
         &mut self,
         bus: &mut T,
@@ -23,12 +23,29 @@ impl<T: Bus> MOS6502<T> {
 
         let (return_address_lo, return_address_hi): (u8, u8) = return_address.to_le_bytes().into();
 
+        // The low byte of an absolute target is fetched before the return address is
+        // pushed, and the high byte after
+        let target_low_byte = match address_mode {
+            AddressingMode::Absolute => {
+                let low_byte = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.stack_dummy_read(bus)?;
+                Some(low_byte)
+            }
+            _ => None,
+        };
+
         self.push_to_stack(bus, return_address_hi)?;
         self.push_to_stack(bus, return_address_lo)?;
 
-        let new_pc_value = match self.resolve_operand(bus, address_mode)? {
-            OpcodeOperand::Address(w) => w,
-            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        let new_pc_value = match target_low_byte {
+            Some(low_byte) => {
+                let high_byte = self.read_bus(bus, return_address, AccessKind::Operand)?;
+                u16::from_le_bytes([low_byte, high_byte])
+            }
+            None => match self.resolve_operand(bus, address_mode)? {
+                OpcodeOperand::Address(w) => w,
+                _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+            },
         };
         self.set_program_counter(new_pc_value);
         Ok(0)
@@ -39,11 +56,35 @@ impl<T: Bus> MOS6502<T> {
         bus: &mut T,
         _: AddressingMode,
     ) -> Result<u32, CpuError> {
+        self.stack_dummy_read(bus)?;
         let return_address_lo = self.pop_from_stack(bus)?;
         let return_address_hi = self.pop_from_stack(bus)?;
 
         let return_address = u16::from_le_bytes([return_address_lo, return_address_hi]);
+        // NMOS parts read the byte at the pulled address while incrementing it
+        if self.nmos_dummy_cycles(bus) {
+            self.read_bus(bus, return_address, AccessKind::Dummy)?;
+        }
         self.set_program_counter(return_address.wrapping_add(1));
         Ok(6)
     }
+
+    // return from subroutine and drop an immediate number of bytes from the stack
+    pub(in crate::mos6502) fn rtn(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let drop = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Byte(b) => b,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        self.rts(bus, address_mode)?;
+        if self.extended_stack() {
+            self.set_stack_address(self.stack_address().wrapping_add(drop as u16));
+        } else {
+            self.stack_pointer = self.stack_pointer.wrapping_add(drop);
+        }
+        Ok(0)
+    }
 }
@@ -1,6 +1,6 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     pub(in crate::mos6502) fn and(
         &mut self,
         bus: &mut T,
@@ -9,10 +9,10 @@ impl<T: Bus> MOS6502<T> {
         let mut extra_cycles = 0;
         let operand = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(b) => b,
-            OpcodeOperand::Address(w) => bus.read(w)?,
+            OpcodeOperand::Address(w) => self.read_bus(bus, w, AccessKind::Data)?,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
                 extra_cycles += overflow as u32;
-                bus.read(addr)?
+                self.read_bus(bus, addr, AccessKind::Data)?
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
@@ -33,10 +33,10 @@ impl<T: Bus> MOS6502<T> {
         let mut extra_cycles = 0;
         let operand = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(b) => b,
-            OpcodeOperand::Address(w) => bus.read(w)?,
+            OpcodeOperand::Address(w) => self.read_bus(bus, w, AccessKind::Data)?,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
                 extra_cycles += overflow as u32;
-                bus.read(addr)?
+                self.read_bus(bus, addr, AccessKind::Data)?
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
@@ -57,10 +57,10 @@ impl<T: Bus> MOS6502<T> {
         let mut extra_cycles = 0;
         let operand = match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(b) => b,
-            OpcodeOperand::Address(w) => bus.read(w)?,
+            OpcodeOperand::Address(w) => self.read_bus(bus, w, AccessKind::Data)?,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
                 extra_cycles += overflow as u32;
-                bus.read(addr)?
+                self.read_bus(bus, addr, AccessKind::Data)?
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
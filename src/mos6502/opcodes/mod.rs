@@ -1,4 +1,5 @@
 pub(in crate::mos6502) mod arithmetic;
+pub(in crate::mos6502) mod block_transfer;
 pub(in crate::mos6502) mod branch;
 pub(in crate::mos6502) mod comparison;
 pub(in crate::mos6502) mod dec_and_inc;
@@ -7,6 +8,7 @@ pub(in crate::mos6502) mod interrupt;
 pub(in crate::mos6502) mod jump;
 pub(in crate::mos6502) mod logical;
 pub(in crate::mos6502) mod other;
+pub(in crate::mos6502) mod quad;
 pub(in crate::mos6502) mod shift_and_rotate;
 pub(in crate::mos6502) mod stack;
 pub(in crate::mos6502) mod transfer;
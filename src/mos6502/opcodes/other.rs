@@ -1,11 +1,69 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+macro_rules! memory_bit_instructions {
+    ($($rmb:ident, $smb:ident, $bbr:ident, $bbs:ident => $bit:expr;)*) => {
+        impl<T: AccessBus> MOS6502<T> {
+            $(
+                pub(in crate::mos6502) fn $rmb(
+                    &mut self,
+                    bus: &mut T,
+                    address_mode: AddressingMode,
+                ) -> Result<u32, CpuError> {
+                    self.modify_memory_bits(bus, address_mode, 1 << $bit, false)
+                }
+
+                pub(in crate::mos6502) fn $smb(
+                    &mut self,
+                    bus: &mut T,
+                    address_mode: AddressingMode,
+                ) -> Result<u32, CpuError> {
+                    self.modify_memory_bits(bus, address_mode, 1 << $bit, true)
+                }
+
+                pub(in crate::mos6502) fn $bbr(
+                    &mut self,
+                    bus: &mut T,
+                    address_mode: AddressingMode,
+                ) -> Result<u32, CpuError> {
+                    self.branch_on_bit(bus, address_mode, $bit, false)
+                }
+
+                pub(in crate::mos6502) fn $bbs(
+                    &mut self,
+                    bus: &mut T,
+                    address_mode: AddressingMode,
+                ) -> Result<u32, CpuError> {
+                    self.branch_on_bit(bus, address_mode, $bit, true)
+                }
+            )*
+        }
+    };
+}
+
+memory_bit_instructions! {
+    rmb0, smb0, bbr0, bbs0 => 0;
+    rmb1, smb1, bbr1, bbs1 => 1;
+    rmb2, smb2, bbr2, bbs2 => 2;
+    rmb3, smb3, bbr3, bbs3 => 3;
+    rmb4, smb4, bbr4, bbs4 => 4;
+    rmb5, smb5, bbr5, bbs5 => 5;
+    rmb6, smb6, bbr6, bbs6 => 6;
+    rmb7, smb7, bbr7, bbs7 => 7;
+}
+
+impl<T: AccessBus> MOS6502<T> {
+    // no operation; the 65C02 also fetches the operand of its multi-byte NOPs
     pub(in crate::mos6502) fn nop(
         &mut self,
-        _: &mut T,
-        _: AddressingMode,
+        bus: &mut T,
+        address_mode: AddressingMode,
     ) -> Result<u32, CpuError> {
+        match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) | OpcodeOperand::AddressWithOverflow(w, _) => {
+                self.read_bus(bus, w, AccessKind::Data)?;
+            }
+            _ => {}
+        }
         Ok(2)
     }
 
@@ -14,8 +72,19 @@ impl<T: Bus> MOS6502<T> {
         bus: &mut T,
         address_mode: AddressingMode,
     ) -> Result<u32, CpuError> {
+        let mut extra_cycles = 0;
         let operand = match self.resolve_operand(bus, address_mode)? {
-            OpcodeOperand::Address(w) => bus.read(w)?,
+            // BIT #imm on the 65C02 and 65CE02 only affects Z; the HuC6280 treats it like memory
+            OpcodeOperand::Byte(b) if self.variant != Variant::Huc6280 => {
+                self.flag_set(CpuFlags::Zero, b & self.accumulator == 0);
+                return Ok(0);
+            }
+            OpcodeOperand::Byte(b) => b,
+            OpcodeOperand::Address(w) => self.read_bus(bus, w, AccessKind::Data)?,
+            OpcodeOperand::AddressWithOverflow(w, overflow) => {
+                extra_cycles += overflow as u32;
+                self.read_bus(bus, w, AccessKind::Data)?
+            }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
 
@@ -23,6 +92,152 @@ impl<T: Bus> MOS6502<T> {
         self.flag_set(CpuFlags::Overflow, operand & (1 << 6) != 0);
         self.flag_set(CpuFlags::Zero, operand & self.accumulator == 0);
 
+        Ok(extra_cycles)
+    }
+
+    /// Set or clear the `mask` bits of a memory byte (TSB/TRB, SMBn/RMBn)
+    fn modify_memory_bits(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        mask: u8,
+        set: bool,
+    ) -> Result<u32, CpuError> {
+        let addr = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) => w,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let value = self.read_modify_bus(bus, addr)?;
+        let new_value = if set { value | mask } else { value & !mask };
+        self.write_bus(bus, addr, new_value, AccessKind::ReadModifyWrite)?;
         Ok(0)
     }
+
+    // test and set memory bits against accumulator
+    pub(in crate::mos6502) fn tsb(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.test_memory_bits(bus, address_mode, true)
+    }
+
+    // test and reset memory bits against accumulator
+    pub(in crate::mos6502) fn trb(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.test_memory_bits(bus, address_mode, false)
+    }
+
+    fn test_memory_bits(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        set: bool,
+    ) -> Result<u32, CpuError> {
+        let addr = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) => w,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let value = self.read_modify_bus(bus, addr)?;
+        self.flag_set(CpuFlags::Zero, value & self.accumulator == 0);
+        let new_value = if set {
+            value | self.accumulator
+        } else {
+            value & !self.accumulator
+        };
+        self.write_bus(bus, addr, new_value, AccessKind::ReadModifyWrite)?;
+        Ok(0)
+    }
+
+    // AUG, a four byte no-op on the 65CE02; MAP on the 4510-derived 45GS02
+    pub(in crate::mos6502) fn aug(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        if self.variant == Variant::Mega45gs02 {
+            let memory_map = [
+                self.accumulator,
+                self.x_register,
+                self.y_register,
+                self.z_register(),
+            ];
+            let csg65ce02 = self.csg65ce02();
+            csg65ce02.memory_map = memory_map;
+            csg65ce02.map_in_progress = true;
+            return Ok(1);
+        }
+        for _ in 0..3 {
+            self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+            self.increment_program_counter(1);
+        }
+        Ok(4)
+    }
+
+    // NOP, or EOM ending the interrupt inhibit after MAP on the 45GS02
+    pub(in crate::mos6502) fn eom(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.csg65ce02().map_in_progress = false;
+        Ok(1)
+    }
+
+    // test an immediate mask against memory
+    pub(in crate::mos6502) fn tst(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let mask = match self.resolve_operand(bus, AddressingMode::Immediate)? {
+            OpcodeOperand::Byte(b) => b,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let value = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) | OpcodeOperand::AddressWithOverflow(w, _) => {
+                self.read_bus(bus, w, AccessKind::Data)?
+            }
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+
+        self.flag_set(CpuFlags::Negative, value & (1 << 7) != 0);
+        self.flag_set(CpuFlags::Overflow, value & (1 << 6) != 0);
+        self.flag_set(CpuFlags::Zero, value & mask == 0);
+
+        Ok(0)
+    }
+
+    // set T, so the next ALU instruction operates on zero page X instead of A
+    pub(in crate::mos6502) fn set(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.huc6280().memory_operation = true;
+        Ok(2)
+    }
+
+    // switch to the 1.79 MHz clock
+    pub(in crate::mos6502) fn csl(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.huc6280().high_speed = false;
+        Ok(3)
+    }
+
+    // switch to the 7.16 MHz clock
+    pub(in crate::mos6502) fn csh(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.huc6280().high_speed = true;
+        Ok(3)
+    }
 }
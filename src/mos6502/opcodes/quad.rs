@@ -0,0 +1,227 @@
+use crate::mos6502::*;
+
+const QUAD_NEGATIVE_BIT_MASK: u32 = 1 << 31;
+
+#[derive(Clone, Copy)]
+enum QuadOperation {
+    Load,
+    Store,
+    Add,
+    Subtract,
+    And,
+    Or,
+    ExclusiveOr,
+    Compare,
+    Bit,
+    ShiftLeft,
+    ShiftRight,
+    RotateLeft,
+    RotateRight,
+    ArithmeticShiftRight,
+    Increment,
+    Decrement,
+}
+
+impl<T: AccessBus> MOS6502<T> {
+    /// The 45GS02 Q register, made of A (low byte), X, Y and Z (high byte)
+    #[inline]
+    fn quad(&self) -> u32 {
+        u32::from_le_bytes([
+            self.accumulator,
+            self.x_register,
+            self.y_register,
+            self.z_register(),
+        ])
+    }
+
+    #[inline]
+    fn set_quad(&mut self, value: u32) {
+        [
+            self.accumulator,
+            self.x_register,
+            self.y_register,
+            self.csg65ce02().z_register,
+        ] = value.to_le_bytes();
+    }
+
+    #[inline]
+    fn set_quad_flags(&mut self, value: u32) {
+        self.flag_set(CpuFlags::Negative, value & QUAD_NEGATIVE_BIT_MASK != 0);
+        self.flag_set(CpuFlags::Zero, value == 0);
+    }
+
+    fn read_quad(&mut self, bus: &mut T, address: u16, kind: AccessKind) -> Result<u32, CpuError> {
+        let mut bytes = [0; 4];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.read_bus(bus, address.wrapping_add(i as u16), kind)?;
+        }
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn write_quad(
+        &mut self,
+        bus: &mut T,
+        address: u16,
+        value: u32,
+        kind: AccessKind,
+    ) -> Result<(), CpuError> {
+        for (i, byte) in value.to_le_bytes().into_iter().enumerate() {
+            self.write_bus(bus, address.wrapping_add(i as u16), byte, kind)?;
+        }
+        Ok(())
+    }
+
+    /// Execute the instruction following a NEG NEG prefix on the Q register.
+    ///
+    /// Only base page, absolute and accumulator forms are supported; the flat 32-bit
+    /// pointer forms need a bus wider than 16 bits.
+    pub(in crate::mos6502) fn quad_instruction(
+        &mut self,
+        bus: &mut T,
+        opcode: u8,
+    ) -> Result<u32, CpuError> {
+        use AddressingMode::*;
+        use QuadOperation::*;
+
+        let (operation, address_mode) = match opcode {
+            0xA5 => (Load, Zeropage),
+            0xAD => (Load, Absolute),
+            0x85 => (Store, Zeropage),
+            0x8D => (Store, Absolute),
+            0x65 => (Add, Zeropage),
+            0x6D => (Add, Absolute),
+            0xE5 => (Subtract, Zeropage),
+            0xED => (Subtract, Absolute),
+            0x25 => (And, Zeropage),
+            0x2D => (And, Absolute),
+            0x05 => (Or, Zeropage),
+            0x0D => (Or, Absolute),
+            0x45 => (ExclusiveOr, Zeropage),
+            0x4D => (ExclusiveOr, Absolute),
+            0xC5 => (Compare, Zeropage),
+            0xCD => (Compare, Absolute),
+            0x24 => (Bit, Zeropage),
+            0x2C => (Bit, Absolute),
+            0x0A => (ShiftLeft, Accumulator),
+            0x06 => (ShiftLeft, Zeropage),
+            0x0E => (ShiftLeft, Absolute),
+            0x4A => (ShiftRight, Accumulator),
+            0x46 => (ShiftRight, Zeropage),
+            0x4E => (ShiftRight, Absolute),
+            0x2A => (RotateLeft, Accumulator),
+            0x26 => (RotateLeft, Zeropage),
+            0x2E => (RotateLeft, Absolute),
+            0x6A => (RotateRight, Accumulator),
+            0x66 => (RotateRight, Zeropage),
+            0x6E => (RotateRight, Absolute),
+            0x43 => (ArithmeticShiftRight, Accumulator),
+            0x44 => (ArithmeticShiftRight, Zeropage),
+            0x1A => (Increment, Accumulator),
+            0xE6 => (Increment, Zeropage),
+            0xEE => (Increment, Absolute),
+            0x3A => (Decrement, Accumulator),
+            0xC6 => (Decrement, Zeropage),
+            0xCE => (Decrement, Absolute),
+            _ => return Err(CpuError::OpcodeNotImplemented),
+        };
+
+        let address = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) => Some(w),
+            OpcodeOperand::Byte(_) => None,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        // prefix and opcode, operand bytes, then four bytes per memory transfer
+        let mut cycles = 3 + match address_mode {
+            Zeropage => 1,
+            Absolute => 2,
+            _ => 0,
+        };
+
+        if let (Store, Some(addr)) = (operation, address) {
+            self.write_quad(bus, addr, self.quad(), AccessKind::Data)?;
+            return Ok(cycles + 4);
+        }
+
+        let read_kind = match operation {
+            ShiftLeft | ShiftRight | RotateLeft | RotateRight | ArithmeticShiftRight
+            | Increment | Decrement => AccessKind::ReadModifyWrite,
+            _ => AccessKind::Data,
+        };
+        let value = match address {
+            Some(addr) => {
+                cycles += 4;
+                self.read_quad(bus, addr, read_kind)?
+            }
+            None => self.quad(),
+        };
+        let carry = self.flag_check(CpuFlags::Carry);
+
+        let result = match operation {
+            Load | And | Or | ExclusiveOr => {
+                let result = match operation {
+                    Load => value,
+                    And => self.quad() & value,
+                    Or => self.quad() | value,
+                    _ => self.quad() ^ value,
+                };
+                self.set_quad(result);
+                self.set_quad_flags(result);
+                return Ok(cycles);
+            }
+            Add | Subtract => {
+                let operand = match operation {
+                    Add => value,
+                    _ => !value,
+                };
+                let quad = self.quad();
+                let sum = quad as u64 + operand as u64 + carry as u64;
+                let result = sum as u32;
+                let overflow = !(quad ^ operand) & (quad ^ result) & QUAD_NEGATIVE_BIT_MASK != 0;
+                self.flag_set(CpuFlags::Carry, sum > u32::MAX as u64);
+                self.flag_set(CpuFlags::Overflow, overflow);
+                self.set_quad(result);
+                self.set_quad_flags(result);
+                return Ok(cycles);
+            }
+            Compare => {
+                let quad = self.quad();
+                self.flag_set(CpuFlags::Carry, quad >= value);
+                self.set_quad_flags(quad.wrapping_sub(value));
+                return Ok(cycles);
+            }
+            Bit => {
+                self.flag_set(CpuFlags::Negative, value & QUAD_NEGATIVE_BIT_MASK != 0);
+                self.flag_set(CpuFlags::Overflow, value & (1 << 30) != 0);
+                self.flag_set(CpuFlags::Zero, value & self.quad() == 0);
+                return Ok(cycles);
+            }
+            ShiftLeft | RotateLeft => {
+                self.flag_set(CpuFlags::Carry, value & QUAD_NEGATIVE_BIT_MASK != 0);
+                let carry_in = matches!(operation, RotateLeft) && carry;
+                value << 1 | carry_in as u32
+            }
+            ShiftRight | RotateRight => {
+                self.flag_set(CpuFlags::Carry, value & 1 != 0);
+                let carry_in = matches!(operation, RotateRight) && carry;
+                value >> 1 | (carry_in as u32) << 31
+            }
+            ArithmeticShiftRight => {
+                self.flag_set(CpuFlags::Carry, value & 1 != 0);
+                ((value as i32) >> 1) as u32
+            }
+            Increment => value.wrapping_add(1),
+            Decrement => value.wrapping_sub(1),
+            Store => unreachable!("stores always have a memory operand"),
+        };
+
+        self.set_quad_flags(result);
+        match address {
+            Some(addr) => {
+                self.write_quad(bus, addr, result, AccessKind::ReadModifyWrite)?;
+                cycles += 4;
+            }
+            None => self.set_quad(result),
+        }
+        Ok(cycles)
+    }
+}
@@ -1,11 +1,12 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     pub(in crate::mos6502) fn asl(
         &mut self,
         bus: &mut T,
         address_mode: AddressingMode,
     ) -> Result<u32, CpuError> {
+        let mut extra_cycles = 0;
         match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(_) => {
                 self.flag_set(CpuFlags::Carry, self.accumulator & NEGATIVE_BIT_MASK != 0);
@@ -17,24 +18,26 @@ impl<T: Bus> MOS6502<T> {
                 self.flag_set(CpuFlags::Zero, self.accumulator == 0);
             }
             OpcodeOperand::Address(w) => {
-                let mut value = bus.read(w)?;
+                let mut value = self.read_modify_bus(bus, w)?;
                 self.flag_set(CpuFlags::Carry, value & NEGATIVE_BIT_MASK != 0);
                 value = value.wrapping_shl(1);
                 self.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
                 self.flag_set(CpuFlags::Zero, value == 0);
-                bus.write(w, value)?;
+                self.write_bus(bus, w, value, AccessKind::ReadModifyWrite)?;
             }
-            OpcodeOperand::AddressWithOverflow(w, _) => {
-                let mut value = bus.read(w)?;
+            OpcodeOperand::AddressWithOverflow(w, overflow) => {
+                self.indexed_write_dummy_read(bus, w, overflow)?;
+                extra_cycles += overflow as u32;
+                let mut value = self.read_modify_bus(bus, w)?;
                 self.flag_set(CpuFlags::Carry, value & NEGATIVE_BIT_MASK != 0);
                 value = value.wrapping_shl(1);
                 self.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
                 self.flag_set(CpuFlags::Zero, value == 0);
-                bus.write(w, value)?;
+                self.write_bus(bus, w, value, AccessKind::ReadModifyWrite)?;
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
-        Ok(0)
+        Ok(extra_cycles)
     }
 
     pub(in crate::mos6502) fn lsr(
@@ -42,6 +45,7 @@ impl<T: Bus> MOS6502<T> {
         bus: &mut T,
         address_mode: AddressingMode,
     ) -> Result<u32, CpuError> {
+        let mut extra_cycles = 0;
         match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(_) => {
                 let bit0_is_set = self.accumulator & 1 != 0;
@@ -50,25 +54,27 @@ impl<T: Bus> MOS6502<T> {
                 self.flag_set(CpuFlags::Carry, bit0_is_set);
             }
             OpcodeOperand::Address(w) => {
-                let mut value = bus.read(w)?;
+                let mut value = self.read_modify_bus(bus, w)?;
                 let bit0_is_set = value & 1 != 0;
                 value = value.wrapping_shr(1);
                 self.flag_set(CpuFlags::Zero, value == 0);
                 self.flag_set(CpuFlags::Carry, bit0_is_set);
-                bus.write(w, value)?;
+                self.write_bus(bus, w, value, AccessKind::ReadModifyWrite)?;
             }
-            OpcodeOperand::AddressWithOverflow(w, _) => {
-                let mut value = bus.read(w)?;
+            OpcodeOperand::AddressWithOverflow(w, overflow) => {
+                self.indexed_write_dummy_read(bus, w, overflow)?;
+                extra_cycles += overflow as u32;
+                let mut value = self.read_modify_bus(bus, w)?;
                 let bit0_is_set = value & 1 != 0;
                 value = value.wrapping_shr(1);
                 self.flag_set(CpuFlags::Zero, value == 0);
                 self.flag_set(CpuFlags::Carry, bit0_is_set);
-                bus.write(w, value)?;
+                self.write_bus(bus, w, value, AccessKind::ReadModifyWrite)?;
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         };
         self.flag_set(CpuFlags::Negative, false);
-        Ok(0)
+        Ok(extra_cycles)
     }
 
     pub(in crate::mos6502) fn rol(
@@ -77,6 +83,7 @@ impl<T: Bus> MOS6502<T> {
         address_mode: AddressingMode,
     ) -> Result<u32, CpuError> {
         let carry_bit_mask = self.flag_check(CpuFlags::Carry) as u8;
+        let mut extra_cycles = 0;
         match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(_) => {
                 let bit7_is_set = self.accumulator & NEGATIVE_BIT_MASK != 0;
@@ -89,26 +96,28 @@ impl<T: Bus> MOS6502<T> {
                 );
             }
             OpcodeOperand::Address(w) => {
-                let value = bus.read(w)?;
+                let value = self.read_modify_bus(bus, w)?;
                 let bit7_is_set = value & NEGATIVE_BIT_MASK != 0;
                 let new_value: u8 = value.wrapping_shl(1) | carry_bit_mask;
                 self.flag_set(CpuFlags::Carry, bit7_is_set);
                 self.flag_set(CpuFlags::Zero, new_value == 0);
                 self.flag_set(CpuFlags::Negative, new_value & NEGATIVE_BIT_MASK != 0);
-                bus.write(w, new_value)?;
+                self.write_bus(bus, w, new_value, AccessKind::ReadModifyWrite)?;
             }
-            OpcodeOperand::AddressWithOverflow(w, _) => {
-                let value = bus.read(w)?;
+            OpcodeOperand::AddressWithOverflow(w, overflow) => {
+                self.indexed_write_dummy_read(bus, w, overflow)?;
+                extra_cycles += overflow as u32;
+                let value = self.read_modify_bus(bus, w)?;
                 let bit7_is_set = value & NEGATIVE_BIT_MASK != 0;
                 let new_value: u8 = value.wrapping_shl(1) | carry_bit_mask;
                 self.flag_set(CpuFlags::Carry, bit7_is_set);
                 self.flag_set(CpuFlags::Zero, new_value == 0);
                 self.flag_set(CpuFlags::Negative, new_value & NEGATIVE_BIT_MASK != 0);
-                bus.write(w, new_value)?;
+                self.write_bus(bus, w, new_value, AccessKind::ReadModifyWrite)?;
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
         }
-        Ok(0)
+        Ok(extra_cycles)
     }
 
     pub(in crate::mos6502) fn ror(
@@ -117,6 +126,7 @@ impl<T: Bus> MOS6502<T> {
         address_mode: AddressingMode,
     ) -> Result<u32, CpuError> {
         let carry_bit_mask = (self.flag_check(CpuFlags::Carry) as u8) << 7;
+        let mut extra_cycles = 0;
         match self.resolve_operand(bus, address_mode)? {
             OpcodeOperand::Byte(_) => {
                 let bit0_is_set = self.accumulator & 1 == 1;
@@ -129,25 +139,101 @@ impl<T: Bus> MOS6502<T> {
                 );
             }
             OpcodeOperand::Address(w) => {
-                let value = bus.read(w)?;
+                let value = self.read_modify_bus(bus, w)?;
                 let bit0_is_set = value & 1 == 1;
                 let new_value: u8 = value.wrapping_shr(1) | carry_bit_mask;
                 self.flag_set(CpuFlags::Carry, bit0_is_set);
                 self.flag_set(CpuFlags::Zero, new_value == 0);
                 self.flag_set(CpuFlags::Negative, new_value & NEGATIVE_BIT_MASK != 0);
-                bus.write(w, new_value)?;
+                self.write_bus(bus, w, new_value, AccessKind::ReadModifyWrite)?;
             }
-            OpcodeOperand::AddressWithOverflow(w, _) => {
-                let value = bus.read(w)?;
+            OpcodeOperand::AddressWithOverflow(w, overflow) => {
+                self.indexed_write_dummy_read(bus, w, overflow)?;
+                extra_cycles += overflow as u32;
+                let value = self.read_modify_bus(bus, w)?;
                 let bit0_is_set = value & 1 == 1;
                 let new_value: u8 = value.wrapping_shr(1) | carry_bit_mask;
                 self.flag_set(CpuFlags::Carry, bit0_is_set);
                 self.flag_set(CpuFlags::Zero, new_value == 0);
                 self.flag_set(CpuFlags::Negative, new_value & NEGATIVE_BIT_MASK != 0);
-                bus.write(w, new_value)?;
+                self.write_bus(bus, w, new_value, AccessKind::ReadModifyWrite)?;
+            }
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        }
+        Ok(extra_cycles)
+    }
+
+    // arithmetic shift right, keeping the sign bit
+    pub(in crate::mos6502) fn asr(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let (value, addr) = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Byte(b) => (b, None),
+            OpcodeOperand::Address(w) | OpcodeOperand::AddressWithOverflow(w, _) => {
+                (self.read_modify_bus(bus, w)?, Some(w))
             }
             _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let new_value = ((value as i8) >> 1) as u8;
+        self.flag_set(CpuFlags::Carry, value & 1 != 0);
+        self.flag_set(CpuFlags::Zero, new_value == 0);
+        self.flag_set(CpuFlags::Negative, new_value & NEGATIVE_BIT_MASK != 0);
+        match addr {
+            Some(w) => self.write_bus(bus, w, new_value, AccessKind::ReadModifyWrite)?,
+            None => self.accumulator = new_value,
         }
         Ok(0)
     }
+
+    /// Shift or rotate the word at an absolute address left, as ASW/ROW do
+    fn shift_word_left(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        carry_in: bool,
+    ) -> Result<u32, CpuError> {
+        let addr = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Address(w) => w,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let low_byte = self.read_modify_bus(bus, addr)?;
+        let high_byte = self.read_modify_bus(bus, addr.wrapping_add(1))?;
+
+        let value = u16::from_le_bytes([low_byte, high_byte]);
+        let new_value = value << 1 | carry_in as u16;
+        let [low_byte, high_byte] = new_value.to_le_bytes();
+        self.write_bus(bus, addr, low_byte, AccessKind::ReadModifyWrite)?;
+        self.write_bus(
+            bus,
+            addr.wrapping_add(1),
+            high_byte,
+            AccessKind::ReadModifyWrite,
+        )?;
+
+        self.flag_set(CpuFlags::Carry, value & 0x8000 != 0);
+        self.flag_set(CpuFlags::Zero, new_value == 0);
+        self.flag_set(CpuFlags::Negative, high_byte & NEGATIVE_BIT_MASK != 0);
+        Ok(0)
+    }
+
+    // arithmetic shift word left
+    pub(in crate::mos6502) fn asw(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.shift_word_left(bus, address_mode, false)
+    }
+
+    // rotate word left through carry
+    pub(in crate::mos6502) fn row(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let carry = self.flag_check(CpuFlags::Carry);
+        self.shift_word_left(bus, address_mode, carry)
+    }
 }
@@ -1,6 +1,16 @@
 use crate::mos6502::*;
 
-impl<T: Bus> MOS6502<T> {
+macro_rules! pull_register {
+    ($cpu:expr, $register:expr, $bus:expr) => {
+        let value = $cpu.pop_from_stack($bus)?;
+        $register = value;
+        $cpu.flag_set(CpuFlags::Zero, value == 0);
+        $cpu.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
+        return Ok(3);
+    };
+}
+
+impl<T: AccessBus> MOS6502<T> {
     pub(in crate::mos6502) fn pha(
         &mut self,
         bus: &mut T,
@@ -17,7 +27,7 @@ impl<T: Bus> MOS6502<T> {
     ) -> Result<u32, CpuError> {
         self.push_to_stack(
             bus,
-            (self.status_register | CpuFlags::Break | CpuFlags::Unused).into(),
+            self.pushed_status(self.status_register | CpuFlags::Break),
         )?;
         Ok(3)
     }
@@ -27,6 +37,7 @@ impl<T: Bus> MOS6502<T> {
         bus: &mut T,
         _: AddressingMode,
     ) -> Result<u32, CpuError> {
+        self.stack_dummy_read(bus)?;
         self.accumulator = self.pop_from_stack(bus)?;
         self.flag_set(CpuFlags::Zero, self.accumulator == 0);
         self.flag_set(
@@ -41,8 +52,87 @@ impl<T: Bus> MOS6502<T> {
         bus: &mut T,
         _: AddressingMode,
     ) -> Result<u32, CpuError> {
+        self.stack_dummy_read(bus)?;
         self.status_register =
             CpuFlags::from(self.pop_from_stack(bus)?) | CpuFlags::Break | CpuFlags::Unused;
         Ok(4)
     }
+
+    pub(in crate::mos6502) fn phx(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.push_to_stack(bus, self.x_register)?;
+        Ok(2)
+    }
+
+    pub(in crate::mos6502) fn phy(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.push_to_stack(bus, self.y_register)?;
+        Ok(2)
+    }
+
+    pub(in crate::mos6502) fn phz(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.push_to_stack(bus, self.z_register())?;
+        Ok(2)
+    }
+
+    pub(in crate::mos6502) fn plx(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        pull_register!(self, self.x_register, bus);
+    }
+
+    pub(in crate::mos6502) fn ply(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        pull_register!(self, self.y_register, bus);
+    }
+
+    pub(in crate::mos6502) fn plz(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        pull_register!(self, self.csg65ce02().z_register, bus);
+    }
+
+    // push a word, either immediate or read from memory, high byte first
+    pub(in crate::mos6502) fn phw(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let word = match address_mode {
+            AddressingMode::Immediate => {
+                let low_byte = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.increment_program_counter(1);
+                let high_byte = self.read_bus(bus, self.program_counter, AccessKind::Operand)?;
+                self.increment_program_counter(1);
+                [low_byte, high_byte]
+            }
+            _ => match self.resolve_operand(bus, address_mode)? {
+                OpcodeOperand::Address(addr) => [
+                    self.read_bus(bus, addr, AccessKind::Data)?,
+                    self.read_bus(bus, addr.wrapping_add(1), AccessKind::Data)?,
+                ],
+                _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+            },
+        };
+        self.push_to_stack(bus, word[1])?;
+        self.push_to_stack(bus, word[0])?;
+        Ok(0)
+    }
 }
@@ -6,12 +6,13 @@ macro_rules! store_register_value {
         let addr = match $cpu.resolve_operand($bus, $address_mode)? {
             OpcodeOperand::Address(addr) => addr,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
+                $cpu.indexed_write_dummy_read($bus, addr, overflow)?;
                 extra_cycles += overflow as u32;
                 addr
             }
             _ => return Err(CpuError::InvalidAddressingMode($address_mode)),
         };
-        $bus.write(addr, $register)?;
+        $cpu.write_bus($bus, addr, $register, AccessKind::Data)?;
         return Ok(extra_cycles);
     };
 }
@@ -19,36 +20,35 @@ macro_rules! store_register_value {
 macro_rules! load_value_to_register {
     ($cpu:expr, $register:expr, $address_mode:ident, $bus:expr) => {
         let mut extra_cycles = 0;
-        $register = match $cpu.resolve_operand($bus, $address_mode)? {
+        let value = match $cpu.resolve_operand($bus, $address_mode)? {
             OpcodeOperand::Byte(b) => b,
-            OpcodeOperand::Address(addr) => $bus.read(addr)?,
+            OpcodeOperand::Address(addr) => $cpu.read_bus($bus, addr, AccessKind::Data)?,
             OpcodeOperand::AddressWithOverflow(addr, overflow) => {
                 extra_cycles += overflow as u32;
-                $bus.read(addr)?
+                $cpu.read_bus($bus, addr, AccessKind::Data)?
             }
             _ => return Err(CpuError::InvalidAddressingMode($address_mode)),
         };
+        $register = value;
 
-        $cpu.flag_set(CpuFlags::Zero, $register == 0);
-        $cpu.flag_set(CpuFlags::Negative, $register & NEGATIVE_BIT_MASK != 0);
+        $cpu.flag_set(CpuFlags::Zero, value == 0);
+        $cpu.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
         return Ok(extra_cycles);
     };
 }
 
 macro_rules! transfer_register {
     ($cpu:expr, $source_register:expr, $target_register:expr) => {
-        $target_register = $source_register;
+        let value = $source_register;
+        $target_register = value;
 
-        $cpu.flag_set(CpuFlags::Zero, $target_register == 0);
-        $cpu.flag_set(
-            CpuFlags::Negative,
-            $target_register & NEGATIVE_BIT_MASK != 0,
-        );
+        $cpu.flag_set(CpuFlags::Zero, value == 0);
+        $cpu.flag_set(CpuFlags::Negative, value & NEGATIVE_BIT_MASK != 0);
         return Ok(2);
     };
 }
 
-impl<T: Bus> MOS6502<T> {
+impl<T: AccessBus> MOS6502<T> {
     // load value into accumulator
     pub(in crate::mos6502) fn lda(
         &mut self,
@@ -157,4 +157,215 @@ impl<T: Bus> MOS6502<T> {
     ) -> Result<u32, CpuError> {
         transfer_register!(self, self.y_register, self.accumulator);
     }
+
+    // load value into Z register
+    pub(in crate::mos6502) fn ldz(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        load_value_to_register!(self, self.csg65ce02().z_register, address_mode, bus);
+    }
+
+    // store Z register in memory; with Z = 0 this is the 65C02 STZ
+    pub(in crate::mos6502) fn stz(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        store_register_value!(self, self.z_register(), address_mode, bus);
+    }
+
+    // transfer accumulator to Z register
+    pub(in crate::mos6502) fn taz(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_register!(self, self.accumulator, self.csg65ce02().z_register);
+    }
+
+    // transfer Z register to accumulator
+    pub(in crate::mos6502) fn tza(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_register!(self, self.z_register(), self.accumulator);
+    }
+
+    // transfer accumulator to base page register
+    pub(in crate::mos6502) fn tab(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.csg65ce02().base_page = self.accumulator;
+        Ok(1)
+    }
+
+    // transfer base page register to accumulator
+    pub(in crate::mos6502) fn tba(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_register!(self, self.base_page(), self.accumulator);
+    }
+
+    // transfer stack pointer high byte to Y register
+    pub(in crate::mos6502) fn tsy(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_register!(self, self.stack_pointer_high(), self.y_register);
+    }
+
+    // transfer Y register to stack pointer high byte
+    pub(in crate::mos6502) fn tys(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.csg65ce02().stack_pointer_high = self.y_register;
+        Ok(1)
+    }
+
+    // swap accumulator and X register
+    pub(in crate::mos6502) fn sax(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        std::mem::swap(&mut self.accumulator, &mut self.x_register);
+        Ok(3)
+    }
+
+    // swap accumulator and Y register
+    pub(in crate::mos6502) fn say(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        std::mem::swap(&mut self.accumulator, &mut self.y_register);
+        Ok(3)
+    }
+
+    // swap X and Y registers
+    pub(in crate::mos6502) fn sxy(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        std::mem::swap(&mut self.x_register, &mut self.y_register);
+        Ok(3)
+    }
+
+    // clear accumulator without touching flags
+    pub(in crate::mos6502) fn cla(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.accumulator = 0;
+        Ok(2)
+    }
+
+    // clear X register without touching flags
+    pub(in crate::mos6502) fn clx(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.x_register = 0;
+        Ok(2)
+    }
+
+    // clear Y register without touching flags
+    pub(in crate::mos6502) fn cly(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.y_register = 0;
+        Ok(2)
+    }
+
+    // transfer accumulator to every MPR selected in the immediate mask
+    pub(in crate::mos6502) fn tam(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let mask = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Byte(b) => b,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        let accumulator = self.accumulator;
+        for (index, register) in self.huc6280().mpr.iter_mut().enumerate() {
+            if mask & (1 << index) != 0 {
+                *register = accumulator;
+            }
+        }
+        Ok(5)
+    }
+
+    // transfer the lowest MPR selected in the immediate mask to accumulator
+    pub(in crate::mos6502) fn tma(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let mask = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Byte(b) => b,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        if mask != 0 {
+            self.accumulator = self.huc6280().mpr[mask.trailing_zeros() as usize];
+        }
+        Ok(4)
+    }
+
+    /// Store an immediate byte to a VDC port in the hardware page, bypassing the MPRs
+    fn store_vdc(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        port: u32,
+    ) -> Result<u32, CpuError> {
+        let value = match self.resolve_operand(bus, address_mode)? {
+            OpcodeOperand::Byte(b) => b,
+            _ => return Err(CpuError::InvalidAddressingMode(address_mode)),
+        };
+        self.write_physical_bus(bus, huc6280::HARDWARE_PAGE + port, value, AccessKind::Data)?;
+        Ok(5)
+    }
+
+    // store immediate to VDC address register
+    pub(in crate::mos6502) fn st0(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.store_vdc(bus, address_mode, 0)
+    }
+
+    // store immediate to VDC data register, low byte
+    pub(in crate::mos6502) fn st1(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.store_vdc(bus, address_mode, 2)
+    }
+
+    // store immediate to VDC data register, high byte
+    pub(in crate::mos6502) fn st2(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.store_vdc(bus, address_mode, 3)
+    }
 }
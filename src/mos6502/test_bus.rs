@@ -0,0 +1,50 @@
+use crate::error::BusError;
+use crate::mos6502::*;
+
+/// Flat memory for the CPU tests, indexed by logical address, or by physical address on
+/// the HuC6280, with the physical addresses written in order
+#[derive(Default)]
+pub(in crate::mos6502) struct TestBus(pub Vec<u8>, pub Vec<u32>);
+
+impl TestBus {
+    /// `size` bytes of zeroed memory
+    pub fn new(size: usize) -> Self {
+        Self(vec![0; size], Vec::new())
+    }
+}
+
+impl AccessBus for TestBus {
+    fn read_access(&mut self, address: u16, kind: AccessKind) -> Result<u8, BusError> {
+        self.read_physical(address as u32, kind)
+    }
+
+    fn write_access(&mut self, address: u16, value: u8, kind: AccessKind) -> Result<(), BusError> {
+        self.write_physical(address as u32, value, kind)
+    }
+
+    fn read_physical(&mut self, address: u32, _: AccessKind) -> Result<u8, BusError> {
+        Ok(self.0[address as usize])
+    }
+
+    fn write_physical(&mut self, address: u32, value: u8, _: AccessKind) -> Result<(), BusError> {
+        self.0[address as usize] = value;
+        self.1.push(address);
+        Ok(())
+    }
+}
+
+/// A `variant` CPU about to run `program` from $0200 in 64 KiB of memory
+pub(in crate::mos6502) fn setup(variant: Variant, program: &[u8]) -> (MOS6502<TestBus>, TestBus) {
+    let mut bus = TestBus::new(0x10000);
+    bus.0[0x0200..0x0200 + program.len()].copy_from_slice(program);
+    let mut cpu = MOS6502::with_variant(variant);
+    cpu.set_program_counter(0x0200);
+    (cpu, bus)
+}
+
+/// Run `steps` instructions, returning the cycles they took
+pub(in crate::mos6502) fn run(cpu: &mut MOS6502<TestBus>, bus: &mut TestBus, steps: usize) -> u32 {
+    (0..steps)
+        .map(|_| cpu.step(bus).expect("Failed to step CPU"))
+        .sum()
+}
@@ -0,0 +1,296 @@
+use crate::mos6502::*;
+
+impl<T: AccessBus> OpcodeFunctionArray<T> {
+    /// Opcode table of the WDC 65C02, the NMOS set extended with STZ, BRA, PHX/PHY,
+    /// TSB/TRB and the Rockwell bit instructions.
+    ///
+    /// The undefined opcodes of the NMOS core are NOPs of fixed length, and page
+    /// crossings add a cycle to BRA, BBRn/BBSn and the indexed shifts as well. WAI and
+    /// STP are not implemented.
+    #[rustfmt::skip]
+    pub(in crate::mos6502) const WDC65C02: Self = OpcodeFunctionArray([
+        (MOS6502::brk, AddressingMode::Implied, Cycles::Fixed(7)),              // 00
+        (MOS6502::ora, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 01
+        (MOS6502::nop, AddressingMode::Immediate, Cycles::Fixed(2)),            // 02
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 03
+        (MOS6502::tsb, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 04
+        (MOS6502::ora, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 05
+        (MOS6502::asl, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 06
+        (MOS6502::rmb0, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 07
+        (MOS6502::php, AddressingMode::Implied, Cycles::Fixed(3)),              // 08
+        (MOS6502::ora, AddressingMode::Immediate, Cycles::Fixed(2)),            // 09
+        (MOS6502::asl, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 0A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 0B
+        (MOS6502::tsb, AddressingMode::Absolute, Cycles::Fixed(6)),             // 0C
+        (MOS6502::ora, AddressingMode::Absolute, Cycles::Fixed(4)),             // 0D
+        (MOS6502::asl, AddressingMode::Absolute, Cycles::Fixed(6)),             // 0E
+        (MOS6502::bbr0, AddressingMode::Zeropage, Cycles::Variable(5)),         // 0F
+        (MOS6502::bpl, AddressingMode::Relative, Cycles::Variable(2)),          // 10
+        (MOS6502::ora, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 11
+        (MOS6502::ora, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // 12
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 13
+        (MOS6502::trb, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 14
+        (MOS6502::ora, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 15
+        (MOS6502::asl, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 16
+        (MOS6502::rmb1, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 17
+        (MOS6502::clc, AddressingMode::Implied, Cycles::Fixed(2)),              // 18
+        (MOS6502::ora, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 19
+        (MOS6502::ina, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 1A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 1B
+        (MOS6502::trb, AddressingMode::Absolute, Cycles::Fixed(6)),             // 1C
+        (MOS6502::ora, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 1D
+        (MOS6502::asl, AddressingMode::AbsoluteXIndex, Cycles::Variable(6)),    // 1E
+        (MOS6502::bbr1, AddressingMode::Zeropage, Cycles::Variable(5)),         // 1F
+        (MOS6502::jsr, AddressingMode::Absolute, Cycles::Fixed(6)),             // 20
+        (MOS6502::and, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 21
+        (MOS6502::nop, AddressingMode::Immediate, Cycles::Fixed(2)),            // 22
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 23
+        (MOS6502::bit, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 24
+        (MOS6502::and, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 25
+        (MOS6502::rol, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 26
+        (MOS6502::rmb2, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 27
+        (MOS6502::plp, AddressingMode::Implied, Cycles::Fixed(4)),              // 28
+        (MOS6502::and, AddressingMode::Immediate, Cycles::Fixed(2)),            // 29
+        (MOS6502::rol, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 2A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 2B
+        (MOS6502::bit, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2C
+        (MOS6502::and, AddressingMode::Absolute, Cycles::Fixed(4)),             // 2D
+        (MOS6502::rol, AddressingMode::Absolute, Cycles::Fixed(6)),             // 2E
+        (MOS6502::bbr2, AddressingMode::Zeropage, Cycles::Variable(5)),         // 2F
+        (MOS6502::bmi, AddressingMode::Relative, Cycles::Variable(2)),          // 30
+        (MOS6502::and, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 31
+        (MOS6502::and, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // 32
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 33
+        (MOS6502::bit, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 34
+        (MOS6502::and, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 35
+        (MOS6502::rol, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 36
+        (MOS6502::rmb3, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 37
+        (MOS6502::sec, AddressingMode::Implied, Cycles::Fixed(2)),              // 38
+        (MOS6502::and, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 39
+        (MOS6502::dea, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 3A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 3B
+        (MOS6502::bit, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 3C
+        (MOS6502::and, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 3D
+        (MOS6502::rol, AddressingMode::AbsoluteXIndex, Cycles::Variable(6)),    // 3E
+        (MOS6502::bbr3, AddressingMode::Zeropage, Cycles::Variable(5)),         // 3F
+        (MOS6502::rti, AddressingMode::Implied, Cycles::Fixed(6)),              // 40
+        (MOS6502::eor, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 41
+        (MOS6502::nop, AddressingMode::Immediate, Cycles::Fixed(2)),            // 42
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 43
+        (MOS6502::nop, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 44
+        (MOS6502::eor, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 45
+        (MOS6502::lsr, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 46
+        (MOS6502::rmb4, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 47
+        (MOS6502::pha, AddressingMode::Implied, Cycles::Fixed(3)),              // 48
+        (MOS6502::eor, AddressingMode::Immediate, Cycles::Fixed(2)),            // 49
+        (MOS6502::lsr, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 4A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 4B
+        (MOS6502::jmp, AddressingMode::Absolute, Cycles::Fixed(3)),             // 4C
+        (MOS6502::eor, AddressingMode::Absolute, Cycles::Fixed(4)),             // 4D
+        (MOS6502::lsr, AddressingMode::Absolute, Cycles::Fixed(6)),             // 4E
+        (MOS6502::bbr4, AddressingMode::Zeropage, Cycles::Variable(5)),         // 4F
+        (MOS6502::bvc, AddressingMode::Relative, Cycles::Variable(2)),          // 50
+        (MOS6502::eor, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 51
+        (MOS6502::eor, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // 52
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 53
+        (MOS6502::nop, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 54
+        (MOS6502::eor, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 55
+        (MOS6502::lsr, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 56
+        (MOS6502::rmb5, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 57
+        (MOS6502::cli, AddressingMode::Implied, Cycles::Fixed(2)),              // 58
+        (MOS6502::eor, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 59
+        (MOS6502::phy, AddressingMode::Implied, Cycles::Fixed(3)),              // 5A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 5B
+        (MOS6502::nop, AddressingMode::Absolute, Cycles::Fixed(8)),             // 5C
+        (MOS6502::eor, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 5D
+        (MOS6502::lsr, AddressingMode::AbsoluteXIndex, Cycles::Variable(6)),    // 5E
+        (MOS6502::bbr5, AddressingMode::Zeropage, Cycles::Variable(5)),         // 5F
+        (MOS6502::rts, AddressingMode::Implied, Cycles::Fixed(6)),              // 60
+        (MOS6502::adc, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 61
+        (MOS6502::nop, AddressingMode::Immediate, Cycles::Fixed(2)),            // 62
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 63
+        (MOS6502::stz, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 64
+        (MOS6502::adc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 65
+        (MOS6502::ror, AddressingMode::Zeropage, Cycles::Fixed(5)),             // 66
+        (MOS6502::rmb6, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 67
+        (MOS6502::pla, AddressingMode::Implied, Cycles::Fixed(4)),              // 68
+        (MOS6502::adc, AddressingMode::Immediate, Cycles::Fixed(2)),            // 69
+        (MOS6502::ror, AddressingMode::Accumulator, Cycles::Fixed(2)),          // 6A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 6B
+        (MOS6502::jmp, AddressingMode::Indirect, Cycles::Fixed(6)),             // 6C
+        (MOS6502::adc, AddressingMode::Absolute, Cycles::Fixed(4)),             // 6D
+        (MOS6502::ror, AddressingMode::Absolute, Cycles::Fixed(6)),             // 6E
+        (MOS6502::bbr6, AddressingMode::Zeropage, Cycles::Variable(5)),         // 6F
+        (MOS6502::bvs, AddressingMode::Relative, Cycles::Variable(2)),          // 70
+        (MOS6502::adc, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // 71
+        (MOS6502::adc, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // 72
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 73
+        (MOS6502::stz, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 74
+        (MOS6502::adc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 75
+        (MOS6502::ror, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // 76
+        (MOS6502::rmb7, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 77
+        (MOS6502::sei, AddressingMode::Implied, Cycles::Fixed(2)),              // 78
+        (MOS6502::adc, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // 79
+        (MOS6502::ply, AddressingMode::Implied, Cycles::Fixed(4)),              // 7A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 7B
+        (MOS6502::jmp, AddressingMode::AbsoluteXIndexIndirect, Cycles::Fixed(6)),// 7C
+        (MOS6502::adc, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // 7D
+        (MOS6502::ror, AddressingMode::AbsoluteXIndex, Cycles::Variable(6)),    // 7E
+        (MOS6502::bbr7, AddressingMode::Zeropage, Cycles::Variable(5)),         // 7F
+        (MOS6502::bra, AddressingMode::Relative, Cycles::Variable(2)),          // 80
+        (MOS6502::sta, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // 81
+        (MOS6502::nop, AddressingMode::Immediate, Cycles::Fixed(2)),            // 82
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 83
+        (MOS6502::sty, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 84
+        (MOS6502::sta, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 85
+        (MOS6502::stx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // 86
+        (MOS6502::smb0, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 87
+        (MOS6502::dey, AddressingMode::Implied, Cycles::Fixed(2)),              // 88
+        (MOS6502::bit, AddressingMode::Immediate, Cycles::Fixed(2)),            // 89
+        (MOS6502::txa, AddressingMode::Implied, Cycles::Fixed(2)),              // 8A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 8B
+        (MOS6502::sty, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8C
+        (MOS6502::sta, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8D
+        (MOS6502::stx, AddressingMode::Absolute, Cycles::Fixed(4)),             // 8E
+        (MOS6502::bbs0, AddressingMode::Zeropage, Cycles::Variable(5)),         // 8F
+        (MOS6502::bcc, AddressingMode::Relative, Cycles::Variable(2)),          // 90
+        (MOS6502::sta, AddressingMode::IndirectYIndex, Cycles::Fixed(6)),       // 91
+        (MOS6502::sta, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // 92
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 93
+        (MOS6502::sty, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 94
+        (MOS6502::sta, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // 95
+        (MOS6502::stx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // 96
+        (MOS6502::smb1, AddressingMode::Zeropage, Cycles::Fixed(5)),            // 97
+        (MOS6502::tya, AddressingMode::Implied, Cycles::Fixed(2)),              // 98
+        (MOS6502::sta, AddressingMode::AbsoluteYIndex, Cycles::Fixed(5)),       // 99
+        (MOS6502::txs, AddressingMode::Implied, Cycles::Fixed(2)),              // 9A
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // 9B
+        (MOS6502::stz, AddressingMode::Absolute, Cycles::Fixed(4)),             // 9C
+        (MOS6502::sta, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 9D
+        (MOS6502::stz, AddressingMode::AbsoluteXIndex, Cycles::Fixed(5)),       // 9E
+        (MOS6502::bbs1, AddressingMode::Zeropage, Cycles::Variable(5)),         // 9F
+        (MOS6502::ldy, AddressingMode::Immediate, Cycles::Fixed(2)),            // A0
+        (MOS6502::lda, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // A1
+        (MOS6502::ldx, AddressingMode::Immediate, Cycles::Fixed(2)),            // A2
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // A3
+        (MOS6502::ldy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A4
+        (MOS6502::lda, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A5
+        (MOS6502::ldx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // A6
+        (MOS6502::smb2, AddressingMode::Zeropage, Cycles::Fixed(5)),            // A7
+        (MOS6502::tay, AddressingMode::Implied, Cycles::Fixed(2)),              // A8
+        (MOS6502::lda, AddressingMode::Immediate, Cycles::Fixed(2)),            // A9
+        (MOS6502::tax, AddressingMode::Implied, Cycles::Fixed(2)),              // AA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // AB
+        (MOS6502::ldy, AddressingMode::Absolute, Cycles::Fixed(4)),             // AC
+        (MOS6502::lda, AddressingMode::Absolute, Cycles::Fixed(4)),             // AD
+        (MOS6502::ldx, AddressingMode::Absolute, Cycles::Fixed(4)),             // AE
+        (MOS6502::bbs2, AddressingMode::Zeropage, Cycles::Variable(5)),         // AF
+        (MOS6502::bcs, AddressingMode::Relative, Cycles::Variable(2)),          // B0
+        (MOS6502::lda, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // B1
+        (MOS6502::lda, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // B2
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // B3
+        (MOS6502::ldy, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B4
+        (MOS6502::lda, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // B5
+        (MOS6502::ldx, AddressingMode::ZeropageYIndex, Cycles::Fixed(4)),       // B6
+        (MOS6502::smb3, AddressingMode::Zeropage, Cycles::Fixed(5)),            // B7
+        (MOS6502::clv, AddressingMode::Implied, Cycles::Fixed(2)),              // B8
+        (MOS6502::lda, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // B9
+        (MOS6502::tsx, AddressingMode::Implied, Cycles::Fixed(2)),              // BA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // BB
+        (MOS6502::ldy, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // BC
+        (MOS6502::lda, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // BD
+        (MOS6502::ldx, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // BE
+        (MOS6502::bbs3, AddressingMode::Zeropage, Cycles::Variable(5)),         // BF
+        (MOS6502::cpy, AddressingMode::Immediate, Cycles::Fixed(2)),            // C0
+        (MOS6502::cmp, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // C1
+        (MOS6502::nop, AddressingMode::Immediate, Cycles::Fixed(2)),            // C2
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // C3
+        (MOS6502::cpy, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C4
+        (MOS6502::cmp, AddressingMode::Zeropage, Cycles::Fixed(3)),             // C5
+        (MOS6502::dec, AddressingMode::Zeropage, Cycles::Fixed(5)),             // C6
+        (MOS6502::smb4, AddressingMode::Zeropage, Cycles::Fixed(5)),            // C7
+        (MOS6502::iny, AddressingMode::Implied, Cycles::Fixed(2)),              // C8
+        (MOS6502::cmp, AddressingMode::Immediate, Cycles::Fixed(2)),            // C9
+        (MOS6502::dex, AddressingMode::Implied, Cycles::Fixed(2)),              // CA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // CB
+        (MOS6502::cpy, AddressingMode::Absolute, Cycles::Fixed(4)),             // CC
+        (MOS6502::cmp, AddressingMode::Absolute, Cycles::Fixed(4)),             // CD
+        (MOS6502::dec, AddressingMode::Absolute, Cycles::Fixed(6)),             // CE
+        (MOS6502::bbs4, AddressingMode::Zeropage, Cycles::Variable(5)),         // CF
+        (MOS6502::bne, AddressingMode::Relative, Cycles::Variable(2)),          // D0
+        (MOS6502::cmp, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // D1
+        (MOS6502::cmp, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // D2
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // D3
+        (MOS6502::nop, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // D4
+        (MOS6502::cmp, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // D5
+        (MOS6502::dec, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // D6
+        (MOS6502::smb5, AddressingMode::Zeropage, Cycles::Fixed(5)),            // D7
+        (MOS6502::cld, AddressingMode::Implied, Cycles::Fixed(2)),              // D8
+        (MOS6502::cmp, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // D9
+        (MOS6502::phx, AddressingMode::Implied, Cycles::Fixed(3)),              // DA
+        (MOS6502::not_implemented, AddressingMode::Implied, Cycles::Fixed(0)),  // DB
+        (MOS6502::nop, AddressingMode::Absolute, Cycles::Fixed(4)),             // DC
+        (MOS6502::cmp, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // DD
+        (MOS6502::dec, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // DE
+        (MOS6502::bbs5, AddressingMode::Zeropage, Cycles::Variable(5)),         // DF
+        (MOS6502::cpx, AddressingMode::Immediate, Cycles::Fixed(2)),            // E0
+        (MOS6502::sbc, AddressingMode::XIndexIndirect, Cycles::Fixed(6)),       // E1
+        (MOS6502::nop, AddressingMode::Immediate, Cycles::Fixed(2)),            // E2
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // E3
+        (MOS6502::cpx, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E4
+        (MOS6502::sbc, AddressingMode::Zeropage, Cycles::Fixed(3)),             // E5
+        (MOS6502::inc, AddressingMode::Zeropage, Cycles::Fixed(5)),             // E6
+        (MOS6502::smb6, AddressingMode::Zeropage, Cycles::Fixed(5)),            // E7
+        (MOS6502::inx, AddressingMode::Implied, Cycles::Fixed(2)),              // E8
+        (MOS6502::sbc, AddressingMode::Immediate, Cycles::Fixed(2)),            // E9
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(2)),              // EA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // EB
+        (MOS6502::cpx, AddressingMode::Absolute, Cycles::Fixed(4)),             // EC
+        (MOS6502::sbc, AddressingMode::Absolute, Cycles::Fixed(4)),             // ED
+        (MOS6502::inc, AddressingMode::Absolute, Cycles::Fixed(6)),             // EE
+        (MOS6502::bbs6, AddressingMode::Zeropage, Cycles::Variable(5)),         // EF
+        (MOS6502::beq, AddressingMode::Relative, Cycles::Variable(2)),          // F0
+        (MOS6502::sbc, AddressingMode::IndirectYIndex, Cycles::Variable(5)),    // F1
+        (MOS6502::sbc, AddressingMode::ZeropageIndirect, Cycles::Fixed(5)),     // F2
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // F3
+        (MOS6502::nop, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // F4
+        (MOS6502::sbc, AddressingMode::ZeropageXIndex, Cycles::Fixed(4)),       // F5
+        (MOS6502::inc, AddressingMode::ZeropageXIndex, Cycles::Fixed(6)),       // F6
+        (MOS6502::smb7, AddressingMode::Zeropage, Cycles::Fixed(5)),            // F7
+        (MOS6502::sed, AddressingMode::Implied, Cycles::Fixed(2)),              // F8
+        (MOS6502::sbc, AddressingMode::AbsoluteYIndex, Cycles::Variable(4)),    // F9
+        (MOS6502::plx, AddressingMode::Implied, Cycles::Fixed(4)),              // FA
+        (MOS6502::nop, AddressingMode::Implied, Cycles::Fixed(1)),              // FB
+        (MOS6502::nop, AddressingMode::Absolute, Cycles::Fixed(4)),             // FC
+        (MOS6502::sbc, AddressingMode::AbsoluteXIndex, Cycles::Variable(4)),    // FD
+        (MOS6502::inc, AddressingMode::AbsoluteXIndex, Cycles::Fixed(7)),       // FE
+        (MOS6502::bbs7, AddressingMode::Zeropage, Cycles::Variable(5)),         // FF
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mos6502::test_bus::setup;
+    use crate::mos6502::*;
+
+    #[test]
+    fn test_cycles() {
+        // LDX #$20; ASL $02F0,X; ASL $0200,X; SMB0 $10; BBS0 $10,+2; NOP; NOP;
+        // BIT $0200,X; NOP #$FF; NOP; BRA $01FF
+        let program = [
+            0xA2, 0x20, 0x1E, 0xF0, 0x02, 0x1E, 0x00, 0x02, 0x87, 0x10, 0x8F, 0x10, 0x02, 0xEA,
+            0xEA, 0x3C, 0x00, 0x02, 0x02, 0xFF, 0x03, 0x80, 0xE8,
+        ];
+        let (mut cpu, mut bus) = setup(Variant::Wdc65c02, &program);
+        bus.0[0x0310] = 0x41;
+
+        let cycles: Vec<u32> = (0..9)
+            .map(|_| cpu.step(&mut bus).expect("Failed to step CPU"))
+            .collect();
+
+        assert_eq!(cycles, [2, 7, 6, 5, 6, 4, 2, 1, 4]);
+        assert_eq!(bus.0[0x0310], 0x82);
+        assert_eq!(bus.0[0x0010], 0x01);
+        assert_eq!(cpu.program_counter(), 0x01FF);
+    }
+}
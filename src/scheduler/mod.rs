@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::CpuError;
+use crate::mos6502::{AccessBus, MOS6502};
+
+/// Handle of a scheduled event, used to reschedule or cancel it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EventId(u64);
+
+#[derive(Clone, Serialize, Deserialize)]
+struct ScheduledEvent<E> {
+    cycle: u64,
+    id: EventId,
+    event: E,
+}
+
+/// Queue of events keyed by absolute CPU cycle.
+///
+/// Events are plain values (typically an enum naming the device and what it should do)
+/// rather than closures, so the whole queue can be serialized into a save state. The
+/// handler passed to [`Scheduler::run`] acts as the callback for every event.
+///
+/// Events due on the same cycle fire in the order they were scheduled.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Scheduler<E> {
+    now: u64,
+    next_id: u64,
+    // Sorted by cycle, then by id
+    events: Vec<ScheduledEvent<E>>,
+}
+
+impl<E> Default for Scheduler<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Scheduler<E> {
+    pub fn new() -> Self {
+        Self {
+            now: 0,
+            next_id: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Number of cycles elapsed since the scheduler was created
+    #[inline]
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Cycle of the earliest pending event
+    #[inline]
+    pub fn next_event_cycle(&self) -> Option<u64> {
+        self.events.first().map(|e| e.cycle)
+    }
+
+    /// Number of pending events
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Schedule `event` at an absolute cycle. Cycles in the past fire on the next check.
+    pub fn schedule_at(&mut self, cycle: u64, event: E) -> EventId {
+        let id = EventId(self.next_id);
+        self.next_id += 1;
+        self.insert(ScheduledEvent { cycle, id, event });
+        id
+    }
+
+    /// Schedule `event` `delay` cycles from now
+    pub fn schedule_in(&mut self, delay: u64, event: E) -> EventId {
+        self.schedule_at(self.now + delay, event)
+    }
+
+    /// Move a pending event to another cycle. Returns false if it already fired or was
+    /// cancelled.
+    pub fn reschedule(&mut self, id: EventId, cycle: u64) -> bool {
+        match self.remove(id) {
+            Some(mut scheduled) => {
+                scheduled.cycle = cycle;
+                self.insert(scheduled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a pending event, returning it if it had not fired yet
+    pub fn cancel(&mut self, id: EventId) -> Option<E> {
+        self.remove(id).map(|scheduled| scheduled.event)
+    }
+
+    /// Let `cycles` pass without firing anything
+    #[inline]
+    pub fn advance(&mut self, cycles: u32) {
+        self.now += cycles as u64;
+    }
+
+    /// Take the earliest event that is due by now
+    pub fn pop_due(&mut self) -> Option<(EventId, E)> {
+        match self.events.first() {
+            Some(first) if first.cycle <= self.now => {
+                let scheduled = self.events.remove(0);
+                Some((scheduled.id, scheduled.event))
+            }
+            _ => None,
+        }
+    }
+
+    /// Run `cpu` until `cycle`, stopping at the first instruction boundary at or after
+    /// each pending event to hand it to `handler`.
+    ///
+    /// The handler gets the scheduler back, so it can read [`Scheduler::now`], queue
+    /// follow-up events or cancel others, as well as the CPU and bus to raise interrupts
+    /// or touch memory. Events scheduled for the current cycle from within the handler
+    /// fire before the CPU resumes.
+    pub fn run<T: AccessBus>(
+        &mut self,
+        cpu: &mut MOS6502<T>,
+        bus: &mut T,
+        cycle: u64,
+        mut handler: impl FnMut(&mut Self, &mut MOS6502<T>, &mut T, E) -> Result<(), CpuError>,
+    ) -> Result<(), CpuError> {
+        loop {
+            while let Some((_, event)) = self.pop_due() {
+                handler(self, cpu, bus, event)?;
+            }
+            if self.now >= cycle {
+                return Ok(());
+            }
+            let target = self
+                .next_event_cycle()
+                .map_or(cycle, |next| next.min(cycle));
+            while self.now < target {
+                let cycles = cpu.step(bus)?;
+                self.advance(cycles);
+            }
+        }
+    }
+
+    fn insert(&mut self, scheduled: ScheduledEvent<E>) {
+        let index = self
+            .events
+            .partition_point(|e| (e.cycle, e.id.0) < (scheduled.cycle, scheduled.id.0));
+        self.events.insert(index, scheduled);
+    }
+
+    fn remove(&mut self, id: EventId) -> Option<ScheduledEvent<E>> {
+        let index = self.events.iter().position(|e| e.id == id)?;
+        Some(self.events.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::error::BusError;
+    use crate::mos6502::{Bus, CpuFlags};
+
+    #[derive(Default)]
+    struct TestBus(Vec<u8>);
+
+    impl Bus for TestBus {
+        fn read(&mut self, address: u16) -> Result<u8, BusError> {
+            Ok(self.0[address as usize])
+        }
+
+        fn write(&mut self, address: u16, value: u8) -> Result<(), BusError> {
+            self.0[address as usize] = value;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        TimerUnderflow { period: u64 },
+        Marker(u8),
+    }
+
+    #[test]
+    fn test_ordering_reschedule_and_cancel() {
+        let mut scheduler = Scheduler::new();
+        let a = scheduler.schedule_at(10, Event::Marker(1));
+        scheduler.schedule_at(10, Event::Marker(2));
+        let c = scheduler.schedule_at(5, Event::Marker(3));
+        let d = scheduler.schedule_at(7, Event::Marker(4));
+
+        assert_eq!(scheduler.cancel(d), Some(Event::Marker(4)));
+        assert_eq!(scheduler.cancel(d), None);
+        assert!(scheduler.reschedule(c, 20));
+        assert_eq!(scheduler.next_event_cycle(), Some(10));
+
+        scheduler.advance(9);
+        assert_eq!(scheduler.pop_due(), None);
+        scheduler.advance(1);
+        assert_eq!(scheduler.pop_due(), Some((a, Event::Marker(1))));
+        assert_eq!(scheduler.pop_due().map(|(_, e)| e), Some(Event::Marker(2)));
+        assert_eq!(scheduler.pop_due(), None);
+        assert!(!scheduler.reschedule(a, 30));
+        assert_eq!(scheduler.len(), 1);
+    }
+
+    #[test]
+    fn test_timer_irq_fires_on_exact_cycle() {
+        // NOPs everywhere, IRQ handler at $0300 is also NOPs
+        let mut bus = TestBus(vec![0xEA; 0x10000]);
+        bus.0[0xFFFE..].copy_from_slice(&[0x00, 0x03]);
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(0x0200);
+
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule_at(100, Event::TimerUnderflow { period: 1000 });
+
+        let mut fired_at = Vec::new();
+        scheduler
+            .run(&mut cpu, &mut bus, 150, |scheduler, cpu, bus, event| {
+                if let Event::TimerUnderflow { period } = event {
+                    fired_at.push((scheduler.now(), cpu.program_counter()));
+                    scheduler.advance(cpu.irq(bus)?);
+                    scheduler.schedule_in(period, event);
+                }
+                Ok(())
+            })
+            .expect("Failed to run scheduler");
+
+        // 50 NOPs of two cycles each take exactly 100 cycles
+        assert_eq!(fired_at, vec![(100, 0x0232)]);
+        assert!(cpu.flag_check(CpuFlags::NoInterrupts));
+        // IRQ entry took 7 cycles, then NOPs until at least cycle 150
+        assert_eq!(scheduler.now(), 151);
+        assert_eq!(cpu.program_counter(), 0x0300 + 22);
+        assert_eq!(scheduler.next_event_cycle(), Some(1107));
+    }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut scheduler = Scheduler::new();
+        scheduler.advance(42);
+        let id = scheduler.schedule_in(8, Event::Marker(7));
+        scheduler.schedule_in(3, Event::TimerUnderflow { period: 5 });
+
+        let state = serde_json::to_string(&scheduler).expect("Failed to serialize scheduler");
+        let mut restored: Scheduler<Event> =
+            serde_json::from_str(&state).expect("Failed to deserialize scheduler");
+
+        assert_eq!(restored.now(), 42);
+        assert_eq!(restored.next_event_cycle(), Some(45));
+        assert_eq!(restored.cancel(id), Some(Event::Marker(7)));
+        let next = restored.schedule_in(1, Event::Marker(8));
+        assert_ne!(next, id);
+    }
+}
@@ -0,0 +1,698 @@
+use std::ops::Range;
+
+use crate::device::{Device, Sid6581, SidModel};
+use crate::error::{BusError, SidError};
+use crate::mos6502::{Bus, MOS6502};
+
+const HEADER_V1_SIZE: usize = 0x76;
+const HEADER_V2_SIZE: usize = 0x7C;
+
+const SID_BASE: u16 = 0xD400;
+const SID_REGISTERS: u16 = 0x20;
+const OSC3: u16 = 0x1B;
+const ENV3: u16 = 0x1C;
+const CIA1_TIMER_A: usize = 0xDC04;
+const IRQ_VECTOR: usize = 0xFFFE;
+const NMI_VECTOR: usize = 0xFFFA;
+const KERNAL_IRQ_VECTOR: usize = 0x0314;
+const KERNAL_NMI_VECTOR: usize = 0x0318;
+/// Ends of the KERNAL interrupt handler that tunes jump to when they are done
+const KERNAL_IRQ_EXITS: [usize; 2] = [0xEA31, 0xEA81];
+
+/// Places tried for the player driver when the header does not name a free page
+const DRIVER_CANDIDATES: [u16; 4] = [0x0334, 0xCF00, 0xC000, 0x0200];
+
+// Offsets of the entry points within the driver
+const DRIVER_INIT: u16 = 0x00;
+const DRIVER_SENTINEL: u16 = 0x0A;
+const DRIVER_PLAY: u16 = 0x0D;
+const DRIVER_IRQ_ENTRY: u16 = 0x13;
+const DRIVER_IRQ_EXIT: u16 = 0x1B;
+const DRIVER_NMI: u16 = 0x21;
+const DRIVER_SIZE: u16 = 0x22;
+
+/// Longest the init routine may run before the player moves on, in frames
+const INIT_BUDGET_FRAMES: u64 = 100;
+
+/// PSID or RSID
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SidFormat {
+    /// Plays in a minimal environment with the init and play routines called directly
+    Psid,
+    /// Real C64 tune that installs its own interrupt handlers
+    Rsid,
+}
+
+/// Video standard a tune was written for, which sets the CPU clock and frame rate
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum SidClock {
+    #[default]
+    Pal,
+    Ntsc,
+}
+
+impl SidClock {
+    /// CPU clock in Hz
+    pub fn clock_hz(self) -> u32 {
+        match self {
+            SidClock::Pal => 985_248,
+            SidClock::Ntsc => 1_022_727,
+        }
+    }
+
+    /// Cycles between vertical blanking interrupts
+    pub fn frame_cycles(self) -> u64 {
+        match self {
+            SidClock::Pal => 312 * 63,
+            SidClock::Ntsc => 263 * 65,
+        }
+    }
+
+    /// CIA timer A value the KERNAL sets for its 60 Hz interrupt
+    fn kernal_timer(self) -> u16 {
+        match self {
+            SidClock::Pal => 0x4025,
+            SidClock::Ntsc => 0x4295,
+        }
+    }
+}
+
+/// Header of a PSID or RSID file, versions 1 to 4
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SidHeader {
+    pub format: SidFormat,
+    pub version: u16,
+    pub load_address: u16,
+    pub init_address: u16,
+    pub play_address: u16,
+    pub songs: u16,
+    pub start_song: u16,
+    /// Bit n set means song n + 1 is timed by the CIA rather than the vertical blank
+    pub speed: u32,
+    pub name: String,
+    pub author: String,
+    pub released: String,
+    pub flags: u16,
+    /// First page free for a player driver: 0 when unknown, $FF when there is none
+    pub start_page: u8,
+    pub page_length: u8,
+    /// Base addresses of the second and third SID in multi-SID tunes
+    pub extra_sids: Vec<u16>,
+}
+
+impl SidHeader {
+    /// Video standard from flag bits 2-3, defaulting to PAL
+    pub fn clock(&self) -> SidClock {
+        if self.flags >> 2 & 0b11 == 0b10 {
+            SidClock::Ntsc
+        } else {
+            SidClock::Pal
+        }
+    }
+
+    /// SID model the tune was written for, from flag bits 4-5
+    pub fn sid_model(&self) -> Option<SidModel> {
+        match self.flags >> 4 & 0b11 {
+            0b01 => Some(SidModel::Mos6581),
+            0b10 => Some(SidModel::Mos8580),
+            _ => None,
+        }
+    }
+
+    /// Whether `song` (1-based) is timed by CIA 1 timer A
+    pub fn uses_cia_timer(&self, song: u16) -> bool {
+        let bit = (song.max(1) - 1).min(31);
+        self.speed & 1 << bit != 0
+    }
+}
+
+/// A parsed SID file: its header and the C64 program it carries
+#[derive(Clone, Debug)]
+pub struct SidFile {
+    pub header: SidHeader,
+    /// Program data, loaded at `header.load_address`
+    pub data: Vec<u8>,
+}
+
+impl SidFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, SidError> {
+        let format = match bytes.get(..4) {
+            Some(b"PSID") => SidFormat::Psid,
+            Some(b"RSID") => SidFormat::Rsid,
+            _ => return Err(SidError::InvalidMagic),
+        };
+        if bytes.len() < HEADER_V1_SIZE {
+            return Err(SidError::Truncated);
+        }
+        let word = |offset: usize| u16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
+        let text = |offset: usize| -> String {
+            bytes[offset..offset + 32]
+                .iter()
+                .take_while(|&&byte| byte != 0)
+                .map(|&byte| byte as char)
+                .collect()
+        };
+
+        let version = word(0x04);
+        let supported = match format {
+            SidFormat::Psid => 1..=4,
+            SidFormat::Rsid => 2..=4,
+        };
+        if !supported.contains(&version) {
+            return Err(SidError::UnsupportedVersion(version));
+        }
+        let data_offset = word(0x06) as usize;
+        let header_size = if version == 1 {
+            HEADER_V1_SIZE
+        } else {
+            HEADER_V2_SIZE
+        };
+        if bytes.len() < header_size.max(data_offset) {
+            return Err(SidError::Truncated);
+        }
+
+        let (flags, start_page, page_length) = if version >= 2 {
+            (word(0x76), bytes[0x78], bytes[0x79])
+        } else {
+            (0, 0, 0)
+        };
+        let extra_sids = match version {
+            3 => vec![bytes[0x7A]],
+            4 => vec![bytes[0x7A], bytes[0x7B]],
+            _ => Vec::new(),
+        }
+        .into_iter()
+        .take_while(|&page| page != 0)
+        .map(|page| 0xD000 | (page as u16) << 4)
+        .collect();
+
+        let mut data = &bytes[data_offset..];
+        let mut load_address = word(0x08);
+        if load_address == 0 {
+            // The load address is stored with the data, as in a C64 program file
+            let [low, high, ..] = *data else {
+                return Err(SidError::Truncated);
+            };
+            load_address = u16::from_le_bytes([low, high]);
+            data = &data[2..];
+        }
+        let init_address = match word(0x0A) {
+            0 => load_address,
+            address => address,
+        };
+
+        Ok(Self {
+            header: SidHeader {
+                format,
+                version,
+                load_address,
+                init_address,
+                play_address: word(0x0C),
+                songs: word(0x0E),
+                start_song: word(0x10),
+                speed: u32::from_be_bytes(bytes[0x12..0x16].try_into().unwrap()),
+                name: text(0x16),
+                author: text(0x36),
+                released: text(0x56),
+                flags,
+                start_page,
+                page_length,
+                extra_sids,
+            },
+            data: data.to_vec(),
+        })
+    }
+
+    /// Memory the program occupies once loaded
+    fn load_range(&self) -> Range<usize> {
+        let start = self.header.load_address as usize;
+        start..(start + self.data.len()).min(0x10000)
+    }
+}
+
+/// A write to a SID register, timestamped with the CPU cycle of the instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SidWrite {
+    pub cycle: u64,
+    /// 0 for the SID at $D400, 1 and 2 for the extra SIDs of multi-SID tunes
+    pub chip: u8,
+    pub register: u8,
+    pub value: u8,
+}
+
+/// 64K of RAM with the SID registers watched
+struct SidBus {
+    memory: Vec<u8>,
+    sid_bases: Vec<u16>,
+    cycle: u64,
+    writes: Vec<SidWrite>,
+    sid: Option<Sid6581>,
+}
+
+impl SidBus {
+    fn chip_at(&self, address: u16) -> Option<(u8, u16)> {
+        self.sid_bases.iter().enumerate().find_map(|(chip, &base)| {
+            address
+                .checked_sub(base)
+                .filter(|&offset| offset < SID_REGISTERS)
+                .map(|offset| (chip as u8, offset))
+        })
+    }
+}
+
+impl Bus for SidBus {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        if let (Some((0, offset @ (OSC3 | ENV3))), Some(sid)) =
+            (self.chip_at(address), self.sid.as_mut())
+        {
+            return sid.read(offset);
+        }
+        Ok(self.memory[address as usize])
+    }
+
+    fn write(&mut self, address: u16, value: u8) -> Result<(), BusError> {
+        self.memory[address as usize] = value;
+        let Some((chip, register)) = self.chip_at(address) else {
+            return Ok(());
+        };
+        self.writes.push(SidWrite {
+            cycle: self.cycle,
+            chip,
+            register: register as u8,
+            value,
+        });
+        match self.sid.as_mut() {
+            Some(sid) if chip == 0 => sid.write(register, value),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Plays a SID file on the CPU core.
+///
+/// A small driver is placed in free memory to call the init routine with the subtune
+/// number and then the play routine once per frame or per CIA timer period. Tunes
+/// without a play address, which includes every RSID, are given an interrupt at that
+/// rate instead: the CPU runs continuously and the IRQ goes through $FFFE, or through
+/// a stand-in for the KERNAL handler that jumps via $0314 and returns through $EA31.
+///
+/// There is no VIC-II or CIA, so tunes that wait on raster lines or program the CIA
+/// beyond timer A's period will not play correctly. The CIA period is taken from what
+/// init leaves at $DC04/$DC05, which starts out as the KERNAL's 60 Hz value.
+///
+/// Every write to a SID register is recorded, and also drives a [`Sid6581`] if one is
+/// given, so the tune can be rendered to audio.
+pub struct SidPlayer {
+    cpu: MOS6502<SidBus>,
+    bus: SidBus,
+    clock: SidClock,
+    driver: u16,
+    play_address: u16,
+    play_interval: u64,
+    cycles: u64,
+    next_play: u64,
+}
+
+impl SidPlayer {
+    /// Load `file` and run the init routine for `song` (1-based, 0 for the default)
+    pub fn new(file: &SidFile, song: u16, sid: Option<Sid6581>) -> Result<Self, SidError> {
+        let header = &file.header;
+        let song = if song == 0 { header.start_song } else { song };
+        if song == 0 || song > header.songs {
+            return Err(SidError::InvalidSubtune(song));
+        }
+        let load_range = file.load_range();
+        let driver = Self::driver_location(header, &load_range)?;
+        let clock = header.clock();
+
+        let mut memory = vec![0; 0x10000];
+        let timer = clock.kernal_timer().to_le_bytes();
+        memory[CIA1_TIMER_A..CIA1_TIMER_A + 2].copy_from_slice(&timer);
+        let irq_entry = (driver + DRIVER_IRQ_ENTRY).to_le_bytes();
+        let irq_exit = (driver + DRIVER_IRQ_EXIT).to_le_bytes();
+        let nmi = (driver + DRIVER_NMI).to_le_bytes();
+        memory[IRQ_VECTOR..IRQ_VECTOR + 2].copy_from_slice(&irq_entry);
+        memory[NMI_VECTOR..NMI_VECTOR + 2].copy_from_slice(&nmi);
+        memory[KERNAL_IRQ_VECTOR..KERNAL_IRQ_VECTOR + 2].copy_from_slice(&irq_exit);
+        memory[KERNAL_NMI_VECTOR..KERNAL_NMI_VECTOR + 2].copy_from_slice(&nmi);
+        for exit in KERNAL_IRQ_EXITS {
+            memory[exit..exit + 3].copy_from_slice(&[0x4C, irq_exit[0], irq_exit[1]]);
+        }
+        // The tune may replace any of the defaults above
+        memory[load_range.clone()].copy_from_slice(&file.data[..load_range.len()]);
+
+        let interrupt_driven = header.play_address == 0;
+        let code = Self::driver_code(driver, header, song, interrupt_driven);
+        let start = driver as usize;
+        memory[start..start + code.len()].copy_from_slice(&code);
+
+        let mut sid_bases = vec![SID_BASE];
+        sid_bases.extend(&header.extra_sids);
+        let mut cpu = MOS6502::new();
+        cpu.set_program_counter(driver + DRIVER_INIT);
+        let mut player = Self {
+            cpu,
+            bus: SidBus {
+                memory,
+                sid_bases,
+                cycle: 0,
+                writes: Vec::new(),
+                sid,
+            },
+            clock,
+            driver,
+            play_address: header.play_address,
+            play_interval: clock.frame_cycles(),
+            cycles: 0,
+            next_play: 0,
+        };
+
+        let budget = INIT_BUDGET_FRAMES * clock.frame_cycles();
+        player.run_until_sentinel(budget)?;
+        if header.uses_cia_timer(song) {
+            let timer = &player.bus.memory[CIA1_TIMER_A..CIA1_TIMER_A + 2];
+            player.play_interval = u16::from_le_bytes([timer[0], timer[1]]) as u64 + 1;
+        }
+        player.next_play = player.cycles;
+        Ok(player)
+    }
+
+    /// Pick memory for the driver that the tune does not use
+    fn driver_location(header: &SidHeader, load_range: &Range<usize>) -> Result<u16, SidError> {
+        let free = |start: u16| {
+            let range = start as usize..start as usize + DRIVER_SIZE as usize;
+            range.end <= load_range.start || range.start >= load_range.end
+        };
+        match header.start_page {
+            0xFF => Err(SidError::NoDriverSpace),
+            0 => DRIVER_CANDIDATES
+                .into_iter()
+                .find(|&start| free(start))
+                .ok_or(SidError::NoDriverSpace),
+            page => Ok((page as u16) << 8),
+        }
+    }
+
+    #[rustfmt::skip]
+    fn driver_code(driver: u16, header: &SidHeader, song: u16, interrupt_driven: bool) -> Vec<u8> {
+        let [init_low, init_high] = header.init_address.to_le_bytes();
+        let [play_low, play_high] = header.play_address.to_le_bytes();
+        let [sentinel_low, sentinel_high] = (driver + DRIVER_SENTINEL).to_le_bytes();
+        vec![
+            // Init: reset the stack and call init with the song number in A
+            0xA2, 0xFF,                              // LDX #$FF
+            0x9A,                                    // TXS
+            0x78,                                    // SEI
+            0xA9, (song - 1) as u8,                  // LDA #song
+            0x20, init_low, init_high,               // JSR init
+            if interrupt_driven { 0x58 } else { 0xEA }, // CLI or NOP
+            0x4C, sentinel_low, sentinel_high,       // JMP * (sentinel)
+            // Play
+            0x20, play_low, play_high,               // JSR play
+            0x4C, sentinel_low, sentinel_high,       // JMP sentinel
+            // IRQ entry, like the KERNAL's
+            0x48,                                    // PHA
+            0x8A,                                    // TXA
+            0x48,                                    // PHA
+            0x98,                                    // TYA
+            0x48,                                    // PHA
+            0x6C, 0x14, 0x03,                        // JMP ($0314)
+            // IRQ exit, like the KERNAL's $EA81
+            0x68,                                    // PLA
+            0xA8,                                    // TAY
+            0x68,                                    // PLA
+            0xAA,                                    // TAX
+            0x68,                                    // PLA
+            0x40,                                    // RTI
+            // NMI
+            0x40,                                    // RTI
+        ]
+    }
+
+    /// CPU clock of the tune's video standard
+    #[inline]
+    pub fn clock(&self) -> SidClock {
+        self.clock
+    }
+
+    /// Cycles between calls of the play routine
+    #[inline]
+    pub fn play_interval(&self) -> u64 {
+        self.play_interval
+    }
+
+    /// Cycles run so far, including the init routine
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// SID register writes recorded so far
+    #[inline]
+    pub fn writes(&self) -> &[SidWrite] {
+        &self.bus.writes
+    }
+
+    /// Take the SID register writes recorded so far
+    pub fn take_writes(&mut self) -> Vec<SidWrite> {
+        std::mem::take(&mut self.bus.writes)
+    }
+
+    #[inline]
+    pub fn sid(&self) -> Option<&Sid6581> {
+        self.bus.sid.as_ref()
+    }
+
+    #[inline]
+    pub fn sid_mut(&mut self) -> Option<&mut Sid6581> {
+        self.bus.sid.as_mut()
+    }
+
+    /// Memory as the tune sees it
+    #[inline]
+    pub fn memory(&self) -> &[u8] {
+        &self.bus.memory
+    }
+
+    /// Play for at least `cycles` CPU cycles
+    pub fn run_cycles(&mut self, cycles: u64) -> Result<(), SidError> {
+        let target = self.cycles + cycles;
+        while self.cycles < target {
+            if self.cycles >= self.next_play {
+                self.next_play += self.play_interval;
+                if self.play_address == 0 {
+                    let cycles = self.cpu.irq(&mut self.bus)?;
+                    self.advance(cycles);
+                } else {
+                    self.cpu.set_program_counter(self.driver + DRIVER_PLAY);
+                    self.run_until_sentinel(self.play_interval)?;
+                }
+                continue;
+            }
+            let idle_until = self.next_play.min(target);
+            if self.play_address == 0 {
+                // Interrupt-driven tunes may have a main loop of their own
+                while self.cycles < idle_until {
+                    self.step()?;
+                }
+            } else {
+                let idle = idle_until - self.cycles;
+                self.advance(idle as u32);
+            }
+        }
+        Ok(())
+    }
+
+    /// Play for `frames` calls of the play routine
+    pub fn run_frames(&mut self, frames: u64) -> Result<(), SidError> {
+        self.run_cycles(frames * self.play_interval)
+    }
+
+    /// Run the CPU until the routine called by the driver returns, or `budget` cycles
+    fn run_until_sentinel(&mut self, budget: u64) -> Result<(), SidError> {
+        let sentinel = self.driver + DRIVER_SENTINEL;
+        let end = self.cycles + budget;
+        while self.cpu.program_counter() != sentinel && self.cycles < end {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<(), SidError> {
+        self.bus.cycle = self.cycles;
+        let cycles = self.cpu.step(&mut self.bus)?;
+        self.advance(cycles);
+        Ok(())
+    }
+
+    fn advance(&mut self, cycles: u32) {
+        self.cycles += cycles as u64;
+        if let Some(sid) = self.bus.sid.as_mut() {
+            sid.tick(cycles);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Version 2 PSID with the load address in the data, as most files have it
+    fn psid(init: u16, play: u16, speed: u32, program: &[u8]) -> Vec<u8> {
+        let mut file = vec![0; HEADER_V2_SIZE];
+        file[..4].copy_from_slice(b"PSID");
+        file[0x04..0x06].copy_from_slice(&2u16.to_be_bytes());
+        file[0x06..0x08].copy_from_slice(&(HEADER_V2_SIZE as u16).to_be_bytes());
+        file[0x0A..0x0C].copy_from_slice(&init.to_be_bytes());
+        file[0x0C..0x0E].copy_from_slice(&play.to_be_bytes());
+        file[0x0E..0x10].copy_from_slice(&3u16.to_be_bytes());
+        file[0x10..0x12].copy_from_slice(&1u16.to_be_bytes());
+        file[0x12..0x16].copy_from_slice(&speed.to_be_bytes());
+        file[0x16..0x1B].copy_from_slice(b"Title");
+        file[0x36..0x3C].copy_from_slice(b"Author");
+        // PAL, 8580
+        file[0x76..0x78].copy_from_slice(&0x0024u16.to_be_bytes());
+        file.extend([0x00, 0x10]);
+        file.extend(program);
+        file
+    }
+
+    #[rustfmt::skip]
+    const TUNE: [u8; 16] = [
+        // $1000 init: remember the song
+        0x8D, 0x00, 0x20, // STA $2000
+        0x60,             // RTS
+        // $1004 play: count frames into the volume register
+        0xEE, 0x01, 0x20, // INC $2001
+        0xAD, 0x01, 0x20, // LDA $2001
+        0x8D, 0x18, 0xD4, // STA $D418
+        0x60,             // RTS
+        0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_parse_headers() {
+        let file = SidFile::parse(&psid(0x1000, 0x1004, 0b10, &TUNE)).unwrap();
+        let header = &file.header;
+        assert_eq!(header.format, SidFormat::Psid);
+        assert_eq!(header.load_address, 0x1000);
+        assert_eq!(file.data, TUNE);
+        assert_eq!(
+            (header.name.as_str(), header.author.as_str()),
+            ("Title", "Author")
+        );
+        assert_eq!(header.clock(), SidClock::Pal);
+        assert_eq!(header.sid_model(), Some(SidModel::Mos8580));
+        assert!(!header.uses_cia_timer(1));
+        assert!(header.uses_cia_timer(2));
+        assert!(header.extra_sids.is_empty());
+
+        // Version 4 with two extra SIDs, and an RSID without a play address
+        let mut bytes = psid(0, 0, 0, &TUNE);
+        bytes[..4].copy_from_slice(b"RSID");
+        bytes[0x05] = 4;
+        bytes[0x7A] = 0x42;
+        bytes[0x7B] = 0xE0;
+        let file = SidFile::parse(&bytes).unwrap();
+        assert_eq!(file.header.format, SidFormat::Rsid);
+        assert_eq!(file.header.init_address, 0x1000);
+        assert_eq!(file.header.extra_sids, [0xD420, 0xDE00]);
+
+        assert!(matches!(
+            SidFile::parse(b"MUS!"),
+            Err(SidError::InvalidMagic)
+        ));
+        assert!(matches!(
+            SidFile::parse(&bytes[..0x77]),
+            Err(SidError::Truncated)
+        ));
+        bytes[0x05] = 1;
+        assert!(matches!(
+            SidFile::parse(&bytes),
+            Err(SidError::UnsupportedVersion(1))
+        ));
+    }
+
+    #[test]
+    fn test_play_timing_and_capture() {
+        let file = SidFile::parse(&psid(0x1000, 0x1004, 0, &TUNE)).unwrap();
+        let mut player = SidPlayer::new(&file, 2, None).unwrap();
+        assert_eq!(player.memory()[0x2000], 1, "init got the zero-based song");
+        assert_eq!(player.driver, 0x0334);
+        player.run_frames(3).unwrap();
+
+        let writes = player.take_writes();
+        assert_eq!(writes.len(), 3);
+        assert_eq!(writes[0].register, 0x18);
+        assert_eq!(
+            writes.iter().map(|w| w.value).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(writes[1].cycle - writes[0].cycle, 19_656);
+        assert_eq!(writes[2].cycle - writes[1].cycle, 19_656);
+
+        // Song 2 is CIA timed and init sets a faster timer
+        #[rustfmt::skip]
+        let tune = [
+            0xA9, 0xFF,       // LDA #$FF
+            0x8D, 0x04, 0xDC, // STA $DC04
+            0xA9, 0x0F,       // LDA #$0F
+            0x8D, 0x05, 0xDC, // STA $DC05
+            0x60,             // RTS
+            0x8D, 0x00, 0xD4, // STA $D400 (play)
+            0x60,             // RTS
+        ];
+        let file = SidFile::parse(&psid(0x1000, 0x100B, 0b10, &tune)).unwrap();
+        let player = SidPlayer::new(&file, 1, None).unwrap();
+        assert_eq!(player.play_interval(), 19_656);
+        let mut player = SidPlayer::new(&file, 2, None).unwrap();
+        assert_eq!(player.play_interval(), 0x1000);
+        player.run_frames(4).unwrap();
+        let writes = player.writes();
+        assert_eq!(writes.len(), 4);
+        assert_eq!(writes[3].cycle - writes[2].cycle, 0x1000);
+        assert!(matches!(
+            SidPlayer::new(&file, 4, None),
+            Err(SidError::InvalidSubtune(4))
+        ));
+    }
+
+    #[test]
+    fn test_interrupt_driven_tune_renders_audio() {
+        // Installs a KERNAL-style handler that plays a note and exits through $EA31
+        #[rustfmt::skip]
+        let tune = [
+            0xA9, 0x10,       // LDA #<handler
+            0x8D, 0x14, 0x03, // STA $0314
+            0xA9, 0x10,       // LDA #>handler
+            0x8D, 0x15, 0x03, // STA $0315
+            0xA9, 0x0F,       // LDA #$0F
+            0x8D, 0x18, 0xD4, // STA $D418
+            0x60,             // RTS
+            // $1010 handler
+            0xA9, 0x20,       // LDA #$20
+            0x8D, 0x01, 0xD4, // STA FREQ_HI
+            0xA9, 0xF0,       // LDA #$F0
+            0x8D, 0x06, 0xD4, // STA SR
+            0xA9, 0x21,       // LDA #$21
+            0x8D, 0x04, 0xD4, // STA CONTROL
+            0x4C, 0x31, 0xEA, // JMP $EA31
+        ];
+        let mut bytes = psid(0, 0, 0, &tune);
+        bytes[..4].copy_from_slice(b"RSID");
+        let file = SidFile::parse(&bytes).unwrap();
+        let sid = Sid6581::new(SidModel::Mos8580, SidClock::Pal.clock_hz(), 44_100);
+        let mut player = SidPlayer::new(&file, 0, Some(sid)).unwrap();
+        player.run_frames(5).unwrap();
+
+        let writes = player.writes();
+        assert_eq!(writes.len(), 1 + 3 * 5);
+        // The interrupt waits for the JMP * in progress to finish
+        let period = writes[4].cycle - writes[1].cycle;
+        assert!((19_656..19_656 + 3).contains(&period), "{period} cycles");
+        let cycles = player.cycles();
+        let samples = player.sid_mut().unwrap().take_samples();
+        assert_eq!(samples.len() as u64, cycles * 44_100 / 985_248);
+        assert!(samples.iter().any(|&sample| sample > 1_000));
+    }
+}
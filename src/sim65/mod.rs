@@ -0,0 +1,662 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+
+use crate::error::{BusError, SimError};
+use crate::mos6502::{Bus, Variant, MOS6502};
+
+const MAGIC: &[u8; 5] = b"sim65";
+const VERSION: u8 = 2;
+const HEADER_SIZE: usize = 12;
+
+const RESET_VECTOR: usize = 0xFFFC;
+const STACK_PAGE: u16 = 0x0100;
+
+/// First of the addresses that are trapped as calls into the host
+const PARAVIRT_BASE: u16 = 0xFFF4;
+// Hooks, in order from PARAVIRT_BASE
+const PV_OPEN: u16 = 0;
+const PV_CLOSE: u16 = 1;
+const PV_READ: u16 = 2;
+const PV_WRITE: u16 = 3;
+const PV_ARGS: u16 = 4;
+const PV_EXIT: u16 = 5;
+const PARAVIRT_HOOKS: u16 = 6;
+/// Cycles counted for a hook, those of the RTS that returns from it
+const HOOK_CYCLES: u32 = 6;
+
+// Flags of cc65's open()
+const O_ACCESS: u16 = 0x03;
+const O_RDONLY: u16 = 0x01;
+const O_WRONLY: u16 = 0x02;
+const O_RDWR: u16 = 0x03;
+const O_CREAT: u16 = 0x10;
+const O_TRUNC: u16 = 0x20;
+const O_APPEND: u16 = 0x40;
+const O_EXCL: u16 = 0x80;
+
+/// Returned by the hooks on failure, -1 as a C int
+const FAILURE: u16 = 0xFFFF;
+
+/// Exit code sim65 uses for its own errors, kept clear of the codes tests return
+pub const SIM65_ERROR: u8 = 0x7F;
+/// Exit code sim65 uses when the cycle limit is reached
+pub const SIM65_ERROR_TIMEOUT: u8 = 0x7E;
+
+/// CPU a program was built for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimCpu {
+    Mos6502,
+    Wdc65c02,
+}
+
+/// A program in sim65's format: a header followed by the code, loaded in one piece
+#[derive(Clone, Debug)]
+pub struct SimProgram {
+    pub cpu: SimCpu,
+    /// Zero-page address of cc65's C stack pointer, `sp`
+    pub sp_address: u8,
+    pub load_address: u16,
+    pub reset_address: u16,
+    pub data: Vec<u8>,
+}
+
+impl SimProgram {
+    pub fn parse(bytes: &[u8]) -> Result<Self, SimError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(SimError::InvalidMagic);
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(SimError::Truncated);
+        }
+        if bytes[5] != VERSION {
+            return Err(SimError::UnsupportedVersion(bytes[5]));
+        }
+        let cpu = match bytes[6] {
+            0 => SimCpu::Mos6502,
+            1 => SimCpu::Wdc65c02,
+            cpu => return Err(SimError::UnsupportedCpu(cpu)),
+        };
+        let word = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let program = Self {
+            cpu,
+            sp_address: bytes[7],
+            load_address: word(8),
+            reset_address: word(10),
+            data: bytes[HEADER_SIZE..].to_vec(),
+        };
+        if program.load_address as usize + program.data.len() > 0x10000 {
+            return Err(SimError::TooLarge);
+        }
+        Ok(program)
+    }
+}
+
+/// 64K of RAM
+struct SimBus {
+    memory: Vec<u8>,
+}
+
+impl SimBus {
+    fn word(&self, address: u16) -> u16 {
+        u16::from_le_bytes([
+            self.memory[address as usize],
+            self.memory[address.wrapping_add(1) as usize],
+        ])
+    }
+
+    fn set_word(&mut self, address: u16, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        self.memory[address as usize] = low;
+        self.memory[address.wrapping_add(1) as usize] = high;
+    }
+}
+
+impl Bus for SimBus {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        Ok(self.memory[address as usize])
+    }
+
+    fn write(&mut self, address: u16, value: u8) -> Result<(), BusError> {
+        self.memory[address as usize] = value;
+        Ok(())
+    }
+}
+
+/// A file descriptor of the program
+enum HostFile {
+    Stdin,
+    Stdout,
+    Stderr,
+    File(File),
+}
+
+/// Standard streams kept in memory instead of the process's own
+struct Captured {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+/// Runs sim65 programs the way cc65's simulator does.
+///
+/// Jumps to $FFF4-$FFF9 are trapped as calls to open, close, read, write, the
+/// argument setup of cc65's startup code and exit, which take their parameters in A/X
+/// and on the C stack as cc65's fastcall convention passes them, and return to the
+/// caller as if by RTS. Files are opened on the host, with descriptors 0-2 being the
+/// standard streams. Programs built for the 65C02 run on the 65C02 core, which has no
+/// WAI or STP.
+pub struct Simulator {
+    cpu: MOS6502<SimBus>,
+    bus: SimBus,
+    sp_address: u8,
+    args: Vec<String>,
+    files: Vec<Option<HostFile>>,
+    captured: Option<Captured>,
+    cycles: u64,
+    exit_code: Option<u8>,
+}
+
+impl Simulator {
+    /// Load `program` and reset into it. `args` is the program's argv, starting with its
+    /// name.
+    pub fn new(program: &SimProgram, args: Vec<String>) -> Self {
+        let mut bus = SimBus {
+            memory: vec![0; 0x10000],
+        };
+        let start = program.load_address as usize;
+        bus.memory[start..start + program.data.len()].copy_from_slice(&program.data);
+        bus.set_word(RESET_VECTOR as u16, program.reset_address);
+
+        let variant = match program.cpu {
+            SimCpu::Mos6502 => Variant::Nmos6502,
+            SimCpu::Wdc65c02 => Variant::Wdc65c02,
+        };
+        let mut cpu = MOS6502::with_variant(variant);
+        cpu.set_program_counter(bus.word(RESET_VECTOR as u16));
+        Self {
+            cpu,
+            bus,
+            sp_address: program.sp_address,
+            args,
+            files: vec![
+                Some(HostFile::Stdin),
+                Some(HostFile::Stdout),
+                Some(HostFile::Stderr),
+            ],
+            captured: None,
+            cycles: 0,
+            exit_code: None,
+        }
+    }
+
+    /// Give the program `input` as standard input and keep what it writes to standard
+    /// output and error for [`Simulator::output`]
+    pub fn capture_stdio(&mut self, input: &[u8]) {
+        self.captured = Some(Captured {
+            input: input.iter().copied().collect(),
+            output: Vec::new(),
+        });
+    }
+
+    /// Standard output and error written since [`Simulator::capture_stdio`]
+    pub fn output(&self) -> &[u8] {
+        self.captured
+            .as_ref()
+            .map_or(&[], |captured| &captured.output)
+    }
+
+    #[inline]
+    pub fn memory(&self) -> &[u8] {
+        &self.bus.memory
+    }
+
+    /// Cycles run since the program was loaded
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Code the program passed to exit, once it has
+    #[inline]
+    pub fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+
+    /// Step one instruction or hook, returning the cycles it took
+    pub fn step(&mut self) -> Result<u32, SimError> {
+        let hook = self
+            .cpu
+            .program_counter()
+            .checked_sub(PARAVIRT_BASE)
+            .filter(|&hook| hook < PARAVIRT_HOOKS);
+        let cycles = match hook {
+            Some(hook) => {
+                self.paravirt(hook);
+                HOOK_CYCLES
+            }
+            None => self.cpu.step(&mut self.bus)?,
+        };
+        self.cycles += cycles as u64;
+        Ok(cycles)
+    }
+
+    /// Run until the program exits, returning its exit code, or fail with
+    /// [`SimError::CycleLimit`] once `max_cycles` have run
+    pub fn run(&mut self, max_cycles: Option<u64>) -> Result<u8, SimError> {
+        loop {
+            if let Some(code) = self.exit_code {
+                // Nothing to report if the host's stdout is already gone
+                let _ = io::stdout().flush();
+                return Ok(code);
+            }
+            if max_cycles.is_some_and(|max| self.cycles >= max) {
+                return Err(SimError::CycleLimit);
+            }
+            self.step()?;
+        }
+    }
+
+    fn paravirt(&mut self, hook: u16) {
+        match hook {
+            PV_OPEN => self.open(),
+            PV_CLOSE => self.close(),
+            PV_READ => self.read(),
+            PV_WRITE => self.write(),
+            PV_ARGS => self.args(),
+            PV_EXIT => self.exit_code = Some(self.cpu.accumulator()),
+            _ => unreachable!(),
+        }
+        // Return to the caller's JSR
+        let stack = self.cpu.stack_pointer();
+        let address = self.bus.word(STACK_PAGE + stack.wrapping_add(1) as u16);
+        self.cpu.set_stack_pointer(stack.wrapping_add(2));
+        self.cpu.set_program_counter(address.wrapping_add(1));
+    }
+
+    #[inline]
+    fn ax(&self) -> u16 {
+        u16::from_le_bytes([self.cpu.accumulator(), self.cpu.x_register()])
+    }
+
+    fn set_ax(&mut self, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        self.cpu.set_accumulator(low);
+        self.cpu.set_x_register(high);
+    }
+
+    fn c_stack(&self) -> u16 {
+        self.bus.word(self.sp_address as u16)
+    }
+
+    fn set_c_stack(&mut self, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        self.bus.memory[self.sp_address as usize] = low;
+        self.bus.memory[self.sp_address.wrapping_add(1) as usize] = high;
+    }
+
+    /// Take the word on top of the C stack and drop `size` bytes from it
+    fn pop_param(&mut self, size: u16) -> u16 {
+        let stack = self.c_stack();
+        let value = self.bus.word(stack);
+        self.set_c_stack(stack.wrapping_add(size));
+        value
+    }
+
+    fn string_at(&self, address: u16) -> String {
+        let bytes: Vec<u8> = (0..=u16::MAX)
+            .map(|offset| self.bus.memory[address.wrapping_add(offset) as usize])
+            .take_while(|&byte| byte != 0)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// `int open(const char* name, int flags, ...)`, with Y holding the size of the
+    /// parameters, which include the mode if one was given
+    fn open(&mut self) {
+        let size = self.cpu.y_register() as u16;
+        // The mode is ignored, as in sim65
+        self.pop_param(size.saturating_sub(4));
+        let flags = self.pop_param(2);
+        let name = self.pop_param(2);
+
+        let mut options = OpenOptions::new();
+        match flags & O_ACCESS {
+            O_RDONLY => options.read(true),
+            O_WRONLY => options.write(true),
+            O_RDWR => options.read(true).write(true),
+            _ => &mut options,
+        };
+        options
+            .create(flags & O_CREAT != 0)
+            .truncate(flags & O_TRUNC != 0)
+            .append(flags & O_APPEND != 0)
+            .create_new(flags & O_EXCL != 0);
+        let fd = match options.open(self.string_at(name)) {
+            Ok(file) => self.allocate(HostFile::File(file)),
+            Err(_) => FAILURE,
+        };
+        self.set_ax(fd);
+    }
+
+    /// Lowest free descriptor, as POSIX hands out
+    fn allocate(&mut self, file: HostFile) -> u16 {
+        let fd = match self.files.iter().position(Option::is_none) {
+            Some(fd) => fd,
+            None => {
+                self.files.push(None);
+                self.files.len() - 1
+            }
+        };
+        self.files[fd] = Some(file);
+        fd as u16
+    }
+
+    /// `int close(int fd)`
+    fn close(&mut self) {
+        let fd = self.ax() as usize;
+        let result = match self.files.get_mut(fd) {
+            Some(file @ Some(_)) => {
+                *file = None;
+                0
+            }
+            _ => FAILURE,
+        };
+        self.set_ax(result);
+    }
+
+    /// `int read(int fd, void* buf, unsigned count)`
+    fn read(&mut self) {
+        let count = self.ax() as usize;
+        let buffer = self.pop_param(2);
+        let fd = self.pop_param(2) as usize;
+
+        let mut data = vec![0; count];
+        let result = match (self.files.get_mut(fd), &mut self.captured) {
+            (Some(Some(HostFile::Stdin)), Some(captured)) => {
+                let length = count.min(captured.input.len());
+                for (byte, input) in data.iter_mut().zip(captured.input.drain(..length)) {
+                    *byte = input;
+                }
+                Ok(length)
+            }
+            (Some(Some(HostFile::Stdin)), None) => io::stdin().read(&mut data),
+            (Some(Some(HostFile::File(file))), _) => file.read(&mut data),
+            _ => Err(io::ErrorKind::InvalidInput.into()),
+        };
+        let result = match result {
+            Ok(length) => {
+                for (offset, &byte) in data[..length].iter().enumerate() {
+                    self.bus.memory[buffer.wrapping_add(offset as u16) as usize] = byte;
+                }
+                length as u16
+            }
+            Err(_) => FAILURE,
+        };
+        self.set_ax(result);
+    }
+
+    /// `int write(int fd, const void* buf, unsigned count)`
+    fn write(&mut self) {
+        let count = self.ax();
+        let buffer = self.pop_param(2);
+        let fd = self.pop_param(2) as usize;
+
+        let data: Vec<u8> = (0..count)
+            .map(|offset| self.bus.memory[buffer.wrapping_add(offset) as usize])
+            .collect();
+        let result = match (self.files.get_mut(fd), &mut self.captured) {
+            (Some(Some(HostFile::Stdout | HostFile::Stderr)), Some(captured)) => {
+                captured.output.extend(&data);
+                Ok(())
+            }
+            (Some(Some(HostFile::Stdout)), None) => io::stdout().write_all(&data),
+            (Some(Some(HostFile::Stderr)), None) => io::stderr().write_all(&data),
+            (Some(Some(HostFile::File(file))), _) => file.write_all(&data),
+            _ => Err(io::ErrorKind::InvalidInput.into()),
+        };
+        self.set_ax(if result.is_ok() { count } else { FAILURE });
+    }
+
+    /// Copy the arguments below the C stack and store argv at the address in A/X,
+    /// returning argc
+    fn args(&mut self) {
+        let argv_address = self.ax();
+        let argc = self.args.len() as u16;
+        let mut pointer = self.c_stack().wrapping_sub((argc + 1) * 2);
+        self.bus.set_word(argv_address, pointer);
+
+        let mut stack = pointer;
+        for arg in std::mem::take(&mut self.args).iter() {
+            stack = stack.wrapping_sub(arg.len() as u16 + 1);
+            for (offset, &byte) in arg.as_bytes().iter().chain(&[0]).enumerate() {
+                self.bus.memory[stack.wrapping_add(offset as u16) as usize] = byte;
+            }
+            self.bus.set_word(pointer, stack);
+            pointer = pointer.wrapping_add(2);
+        }
+        self.bus.set_word(pointer, 0);
+        self.set_c_stack(stack);
+        self.set_ax(argc);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SP: u8 = 0x02;
+    const C_STACK: u16 = 0xC000;
+
+    fn image(cpu: u8, load_address: u16, reset_address: u16, code: &[u8]) -> Vec<u8> {
+        let mut image = MAGIC.to_vec();
+        image.extend([VERSION, cpu, SP]);
+        image.extend(load_address.to_le_bytes());
+        image.extend(reset_address.to_le_bytes());
+        image.extend(code);
+        image
+    }
+
+    fn simulator(code: &[u8], args: &[&str]) -> Simulator {
+        let program = SimProgram::parse(&image(0, 0x0200, 0x0200, code)).unwrap();
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        let mut sim = Simulator::new(&program, args);
+        sim.set_c_stack(C_STACK);
+        sim.capture_stdio(b"");
+        sim
+    }
+
+    /// Call a hook as the cc65 runtime does: all but the last parameter pushed on the C
+    /// stack, the last in A/X, and Y holding the size of the pushed parameters
+    fn call(sim: &mut Simulator, hook: u16, params: &[u16], ax: u16) -> u16 {
+        for &param in params {
+            let stack = sim.c_stack().wrapping_sub(2);
+            sim.set_c_stack(stack);
+            sim.bus.set_word(stack, param);
+        }
+        // Return address of a JSR at $0300
+        sim.bus.set_word(STACK_PAGE + 0xFE, 0x0302);
+        sim.cpu.set_stack_pointer(0xFD);
+        sim.cpu.set_y_register(params.len() as u8 * 2);
+        sim.set_ax(ax);
+        sim.cpu.set_program_counter(PARAVIRT_BASE + hook);
+        assert_eq!(sim.step().unwrap(), HOOK_CYCLES);
+        assert_eq!(sim.cpu.program_counter(), 0x0303);
+        assert_eq!(sim.cpu.stack_pointer(), 0xFF);
+        sim.ax()
+    }
+
+    fn set_string(sim: &mut Simulator, address: u16, text: &str) {
+        let start = address as usize;
+        sim.bus.memory[start..start + text.len()].copy_from_slice(text.as_bytes());
+        sim.bus.memory[start + text.len()] = 0;
+    }
+
+    #[test]
+    fn test_parse() {
+        let program = SimProgram::parse(&image(1, 0x1000, 0x1003, &[0xEA; 4])).unwrap();
+        assert_eq!(program.cpu, SimCpu::Wdc65c02);
+        assert_eq!(program.sp_address, SP);
+        assert_eq!(program.load_address, 0x1000);
+        assert_eq!(program.reset_address, 0x1003);
+        assert_eq!(program.data, [0xEA; 4]);
+
+        assert!(matches!(
+            SimProgram::parse(b"sim66\x02"),
+            Err(SimError::InvalidMagic)
+        ));
+        assert!(matches!(
+            SimProgram::parse(b"sim65\x02\x00"),
+            Err(SimError::Truncated)
+        ));
+        let mut old = image(0, 0x0200, 0x0200, &[]);
+        old[5] = 1;
+        assert!(matches!(
+            SimProgram::parse(&old),
+            Err(SimError::UnsupportedVersion(1))
+        ));
+        assert!(matches!(
+            SimProgram::parse(&image(2, 0x0200, 0x0200, &[])),
+            Err(SimError::UnsupportedCpu(2))
+        ));
+        assert!(matches!(
+            SimProgram::parse(&image(0, 0xFFFE, 0x0200, &[0; 3])),
+            Err(SimError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_hello_world() {
+        #[rustfmt::skip]
+        let code = [
+            0xA9, 0xFE,       // LDA #<(C_STACK - 2)
+            0x85, SP,         // STA sp
+            0xA9, 0xBF,       // LDA #>(C_STACK - 2)
+            0x85, SP + 1,     // STA sp+1
+            0xA9, 0x01,       // LDA #STDOUT_FILENO
+            0x8D, 0xFE, 0xBF, // STA $BFFE
+            0xA9, 0x00,       // LDA #0
+            0x8D, 0xFF, 0xBF, // STA $BFFF
+            0x20, 0x25, 0x02, // JSR pushax_message
+            0xA9, 0x0E,       // LDA #14
+            0xA2, 0x00,       // LDX #0
+            0x20, 0xF7, 0xFF, // JSR write
+            0x8D, 0x00, 0x03, // STA $0300
+            0xA9, 0x03,       // LDA #3
+            0x20, 0xF9, 0xFF, // JSR exit
+            0x00,             // BRK
+            // pushax_message: push the message address
+            0xA5, SP,         // LDA sp
+            0x38,             // SEC
+            0xE9, 0x02,       // SBC #2
+            0x85, SP,         // STA sp
+            0xB0, 0x02,       // BCS store
+            0xC6, SP + 1,     // DEC sp+1
+            0xA0, 0x00,       // store: LDY #0
+            0xA9, 0x50,       // LDA #<message
+            0x91, SP,         // STA (sp),Y
+            0xC8,             // INY
+            0xA9, 0x02,       // LDA #>message
+            0x91, SP,         // STA (sp),Y
+            0x60,             // RTS
+        ];
+        let mut code = code.to_vec();
+        code.resize(0x50, 0x00);
+        code.extend(b"Hello, world!\n");
+        let mut sim = simulator(&code, &["hello"]);
+
+        assert_eq!(sim.run(Some(10_000)).unwrap(), 3);
+        assert_eq!(sim.exit_code(), Some(3));
+        assert_eq!(sim.output(), b"Hello, world!\n");
+        assert_eq!(sim.memory()[0x0300], 14);
+        assert_eq!(sim.c_stack(), C_STACK);
+    }
+
+    #[test]
+    fn test_cycle_limit() {
+        // JMP *
+        let mut sim = simulator(&[0x4C, 0x00, 0x02], &["loop"]);
+        assert!(matches!(sim.run(Some(1000)), Err(SimError::CycleLimit)));
+        assert_eq!(sim.cycles(), 1002);
+        assert_eq!(sim.exit_code(), None);
+    }
+
+    #[test]
+    fn test_65c02_cycles() {
+        #[rustfmt::skip]
+        let code = [
+            0xA2, 0x05,       // LDX #5
+            0xDA,             // PHX
+            0x64, 0x10,       // STZ $10
+            0xEA,             // NOP
+            0x5C, 0x00, 0x00, // NOP $0000, eight cycles
+            0x80, 0x01,       // BRA +1
+            0xDB,             // STP, skipped
+            0x68,             // PLA
+            0x20, 0xF9, 0xFF, // JSR exit
+        ];
+        let program = SimProgram::parse(&image(1, 0x0200, 0x0200, &code)).unwrap();
+        let mut sim = Simulator::new(&program, vec!["c02".to_string()]);
+        sim.bus.memory[0x10] = 0xFF;
+
+        assert_eq!(sim.run(Some(1000)).unwrap(), 5);
+        assert_eq!(sim.memory()[0x10], 0x00);
+        assert_eq!(
+            sim.cycles(),
+            2 + 3 + 3 + 2 + 8 + 3 + 4 + 6 + HOOK_CYCLES as u64
+        );
+    }
+
+    #[test]
+    fn test_args() {
+        let mut sim = simulator(&[], &["prog", "one", "two"]);
+        assert_eq!(call(&mut sim, PV_ARGS, &[], 0x0300), 3);
+        let argv = sim.bus.word(0x0300);
+        assert_eq!(argv, C_STACK - 8);
+        let args: Vec<String> = (0..3)
+            .map(|i| sim.string_at(sim.bus.word(argv + i * 2)))
+            .collect();
+        assert_eq!(args, ["prog", "one", "two"]);
+        assert_eq!(sim.bus.word(argv + 6), 0);
+        // The strings sit below the array and the C stack continues below them
+        assert_eq!(sim.c_stack(), argv - 13);
+        assert_eq!(sim.bus.word(argv), sim.c_stack() + 8);
+    }
+
+    #[test]
+    fn test_files() {
+        let path = std::env::temp_dir().join(format!("sim65-test-{}", std::process::id()));
+        let name = path.to_str().unwrap();
+        let mut sim = simulator(&[], &["files"]);
+        sim.capture_stdio(b"typed");
+        set_string(&mut sim, 0x0400, name);
+        set_string(&mut sim, 0x0500, "file contents");
+
+        let flags = O_WRONLY | O_CREAT | O_TRUNC;
+        // With a mode, which is ignored
+        let fd = call(&mut sim, PV_OPEN, &[0x0400, flags, 0o644], 0);
+        assert_eq!(fd, 3);
+        assert_eq!(sim.c_stack(), C_STACK);
+        assert_eq!(call(&mut sim, PV_WRITE, &[fd, 0x0500], 13), 13);
+        assert_eq!(call(&mut sim, PV_CLOSE, &[], fd), 0);
+        assert_eq!(call(&mut sim, PV_CLOSE, &[], fd), FAILURE);
+        assert_eq!(std::fs::read(&path).unwrap(), b"file contents");
+
+        let fd = call(&mut sim, PV_OPEN, &[0x0400, O_RDONLY], 0);
+        assert_eq!(fd, 3);
+        assert_eq!(call(&mut sim, PV_READ, &[fd, 0x0600], 100), 13);
+        assert_eq!(&sim.memory()[0x0600..0x060D], b"file contents");
+        assert_eq!(call(&mut sim, PV_READ, &[fd, 0x0600], 100), 0);
+        assert_eq!(call(&mut sim, PV_WRITE, &[fd, 0x0500], 4), FAILURE);
+        let exclusive = O_WRONLY | O_CREAT | O_EXCL;
+        assert_eq!(call(&mut sim, PV_OPEN, &[0x0400, exclusive], 0), FAILURE);
+        std::fs::remove_file(&path).unwrap();
+
+        // Standard streams
+        assert_eq!(call(&mut sim, PV_READ, &[0, 0x0700], 3), 3);
+        assert_eq!(&sim.memory()[0x0700..0x0703], b"typ");
+        assert_eq!(call(&mut sim, PV_WRITE, &[2, 0x0500], 4), 4);
+        assert_eq!(sim.output(), b"file");
+        assert_eq!(call(&mut sim, PV_READ, &[9, 0x0700], 3), FAILURE);
+        assert_eq!(sim.c_stack(), C_STACK);
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::{self, Write};
+
+/// Largest block of a stored (uncompressed) deflate stream
+const STORED_BLOCK_SIZE: usize = 0xFFFF;
+
+/// Write 8-bit RGB pixels as a binary PPM (P6) file
+pub fn write_ppm<W: Write>(
+    mut writer: W,
+    width: usize,
+    height: usize,
+    rgb: &[u8],
+) -> io::Result<()> {
+    write!(writer, "P6\n{width} {height}\n255\n")?;
+    writer.write_all(&rgb[..width * height * 3])?;
+    writer.flush()
+}
+
+/// Write 8-bit RGB pixels as a PNG file.
+///
+/// The image data is stored without compression, which keeps the encoder small and the
+/// output byte-for-byte stable for golden-image tests.
+pub fn write_png<W: Write>(
+    mut writer: W,
+    width: usize,
+    height: usize,
+    rgb: &[u8],
+) -> io::Result<()> {
+    writer.write_all(b"\x89PNG\r\n\x1A\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, deflate, adaptive filtering, no interlace
+    header.extend([8, 2, 0, 0, 0]);
+    write_chunk(&mut writer, b"IHDR", &header)?;
+
+    // Every row starts with filter type 0 (none)
+    let mut raw = Vec::with_capacity(height * (width * 3 + 1));
+    for row in rgb[..width * height * 3].chunks(width * 3) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = raw.chunks(STORED_BLOCK_SIZE).collect();
+    for (index, block) in blocks.iter().enumerate() {
+        zlib.push((index + 1 == blocks.len()) as u8);
+        zlib.extend((block.len() as u16).to_le_bytes());
+        zlib.extend((!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend(adler32(&raw).to_be_bytes());
+    write_chunk(&mut writer, b"IDAT", &zlib)?;
+    write_chunk(&mut writer, b"IEND", &[])?;
+    writer.flush()
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    writer.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ppm_and_png_encoding() {
+        let rgb = [0xFF, 0x00, 0x00, 0x00, 0xFF, 0x00];
+        let mut ppm = Vec::new();
+        write_ppm(&mut ppm, 2, 1, &rgb).unwrap();
+        assert_eq!(ppm, b"P6\n2 1\n255\n\xFF\x00\x00\x00\xFF\x00");
+
+        let mut png = Vec::new();
+        write_png(&mut png, 2, 1, &rgb).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1A\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], [0, 0, 0, 2, 0, 0, 0, 1]);
+        // The IEND chunk and its well-known CRC
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xAE\x42\x60\x82");
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
@@ -0,0 +1,1479 @@
+mod opcodes;
+
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+
+/// Bus with the 65C816's 24-bit address space
+pub trait Bus {
+    fn read(&mut self, address: u32) -> Result<u8, BusError>;
+    fn write(&mut self, address: u32, value: u8) -> Result<(), BusError>;
+}
+
+/// Addressing modes of the 65C816. "Zeropage" is the relocatable direct page, as in
+/// the 6502 names the modes share.
+#[derive(Clone, Copy, Debug)]
+pub enum AddressingMode {
+    Accumulator,
+    Absolute,
+    AbsoluteXIndex,
+    AbsoluteYIndex,
+    Immediate,
+    Implied,
+    Indirect,
+    XIndexIndirect,
+    IndirectYIndex,
+    Relative,
+    Zeropage,
+    ZeropageXIndex,
+    ZeropageYIndex,
+    AbsoluteLong,
+    AbsoluteLongXIndex,
+    AbsoluteXIndexIndirect,
+    AbsoluteIndirectLong,
+    ZeropageIndirect,
+    ZeropageIndirectLong,
+    ZeropageIndirectLongYIndex,
+    StackRelative,
+    StackRelativeIndirectYIndex,
+    RelativeLong,
+    BlockMove,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct StatusFlags(u8);
+
+bitflags! {
+    impl StatusFlags: u8 {
+        const Negative = 1 << 7;
+        const Overflow = 1 << 6;
+        /// M: 8-bit accumulator and memory when set
+        const AccumulatorWidth = 1 << 5;
+        /// X: 8-bit index registers when set. Reads as the break flag in emulation mode.
+        const IndexWidth = 1 << 4;
+        const Decimal = 1 << 3;
+        const NoInterrupts = 1 << 2;
+        const Zero = 1 << 1;
+        const Carry = 1 << 0;
+    }
+}
+
+impl From<StatusFlags> for u8 {
+    fn from(val: StatusFlags) -> Self {
+        val.0
+    }
+}
+
+impl From<u8> for StatusFlags {
+    fn from(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+const ADDRESS_MASK: u32 = 0xFF_FFFF;
+
+const EMULATION_STACK_PAGE: u16 = 0x0100;
+
+enum InterruptKind {
+    Nmi,
+    Irq,
+    Brk,
+    Cop,
+}
+
+type OpcodeFunction<T> = fn(&mut WDC65C816<T>, &mut T, AddressingMode) -> Result<u32, CpuError>;
+struct OpcodeFunctionArray<T: Bus>([(OpcodeFunction<T>, AddressingMode, u32); 256]);
+
+enum OpcodeOperand {
+    Immediate(u16),
+    Accumulator,
+    /// 24-bit effective address; the second byte of a word carries into the next bank
+    Address(u32),
+    /// Bank 0 address (direct page, stack); the second byte of a word wraps within bank 0
+    BankZeroAddress(u16),
+    None,
+}
+
+struct ResolvedOperand {
+    operand: OpcodeOperand,
+    address_mode: AddressingMode,
+    /// Extra cycle for direct page addressing when the low byte of D is not zero
+    direct_penalty: u32,
+    /// Extra cycle for indexed reads that cross a page or use 16-bit index registers
+    index_penalty: u32,
+}
+
+/// WDC 65C816 CPU core, with emulation and native modes, 8- or 16-bit registers,
+/// 24-bit addressing and decimal mode.
+#[derive(Serialize, Deserialize)]
+pub struct WDC65C816<T: Bus> {
+    accumulator: u16,
+    x_register: u16,
+    y_register: u16,
+    stack_pointer: u16,
+    direct_page: u16,
+    data_bank: u8,
+    program_bank: u8,
+    program_counter: u16,
+    status_register: StatusFlags,
+    emulation_mode: bool,
+    waiting: bool,
+    stopped: bool,
+    #[serde(skip_serializing, skip_deserializing)]
+    opcode_array: OpcodeFunctionArray<T>,
+}
+
+impl<T: Bus> Default for OpcodeFunctionArray<T> {
+    #[rustfmt::skip]
+    fn default() -> Self {
+        OpcodeFunctionArray([
+            (WDC65C816::brk, AddressingMode::Implied, 7),                           // 00
+            (WDC65C816::ora, AddressingMode::XIndexIndirect, 6),                    // 01
+            (WDC65C816::cop, AddressingMode::Implied, 7),                           // 02
+            (WDC65C816::ora, AddressingMode::StackRelative, 4),                     // 03
+            (WDC65C816::tsb, AddressingMode::Zeropage, 5),                          // 04
+            (WDC65C816::ora, AddressingMode::Zeropage, 3),                          // 05
+            (WDC65C816::asl, AddressingMode::Zeropage, 5),                          // 06
+            (WDC65C816::ora, AddressingMode::ZeropageIndirectLong, 6),              // 07
+            (WDC65C816::php, AddressingMode::Implied, 3),                           // 08
+            (WDC65C816::ora, AddressingMode::Immediate, 2),                         // 09
+            (WDC65C816::asl, AddressingMode::Accumulator, 2),                       // 0A
+            (WDC65C816::phd, AddressingMode::Implied, 4),                           // 0B
+            (WDC65C816::tsb, AddressingMode::Absolute, 6),                          // 0C
+            (WDC65C816::ora, AddressingMode::Absolute, 4),                          // 0D
+            (WDC65C816::asl, AddressingMode::Absolute, 6),                          // 0E
+            (WDC65C816::ora, AddressingMode::AbsoluteLong, 5),                      // 0F
+            (WDC65C816::bpl, AddressingMode::Relative, 2),                          // 10
+            (WDC65C816::ora, AddressingMode::IndirectYIndex, 5),                    // 11
+            (WDC65C816::ora, AddressingMode::ZeropageIndirect, 5),                  // 12
+            (WDC65C816::ora, AddressingMode::StackRelativeIndirectYIndex, 7),       // 13
+            (WDC65C816::trb, AddressingMode::Zeropage, 5),                          // 14
+            (WDC65C816::ora, AddressingMode::ZeropageXIndex, 4),                    // 15
+            (WDC65C816::asl, AddressingMode::ZeropageXIndex, 6),                    // 16
+            (WDC65C816::ora, AddressingMode::ZeropageIndirectLongYIndex, 6),        // 17
+            (WDC65C816::clc, AddressingMode::Implied, 2),                           // 18
+            (WDC65C816::ora, AddressingMode::AbsoluteYIndex, 4),                    // 19
+            (WDC65C816::inc, AddressingMode::Accumulator, 2),                       // 1A
+            (WDC65C816::tcs, AddressingMode::Implied, 2),                           // 1B
+            (WDC65C816::trb, AddressingMode::Absolute, 6),                          // 1C
+            (WDC65C816::ora, AddressingMode::AbsoluteXIndex, 4),                    // 1D
+            (WDC65C816::asl, AddressingMode::AbsoluteXIndex, 7),                    // 1E
+            (WDC65C816::ora, AddressingMode::AbsoluteLongXIndex, 5),                // 1F
+            (WDC65C816::jsr, AddressingMode::Absolute, 6),                          // 20
+            (WDC65C816::and, AddressingMode::XIndexIndirect, 6),                    // 21
+            (WDC65C816::jsl, AddressingMode::AbsoluteLong, 8),                      // 22
+            (WDC65C816::and, AddressingMode::StackRelative, 4),                     // 23
+            (WDC65C816::bit, AddressingMode::Zeropage, 3),                          // 24
+            (WDC65C816::and, AddressingMode::Zeropage, 3),                          // 25
+            (WDC65C816::rol, AddressingMode::Zeropage, 5),                          // 26
+            (WDC65C816::and, AddressingMode::ZeropageIndirectLong, 6),              // 27
+            (WDC65C816::plp, AddressingMode::Implied, 4),                           // 28
+            (WDC65C816::and, AddressingMode::Immediate, 2),                         // 29
+            (WDC65C816::rol, AddressingMode::Accumulator, 2),                       // 2A
+            (WDC65C816::pld, AddressingMode::Implied, 5),                           // 2B
+            (WDC65C816::bit, AddressingMode::Absolute, 4),                          // 2C
+            (WDC65C816::and, AddressingMode::Absolute, 4),                          // 2D
+            (WDC65C816::rol, AddressingMode::Absolute, 6),                          // 2E
+            (WDC65C816::and, AddressingMode::AbsoluteLong, 5),                      // 2F
+            (WDC65C816::bmi, AddressingMode::Relative, 2),                          // 30
+            (WDC65C816::and, AddressingMode::IndirectYIndex, 5),                    // 31
+            (WDC65C816::and, AddressingMode::ZeropageIndirect, 5),                  // 32
+            (WDC65C816::and, AddressingMode::StackRelativeIndirectYIndex, 7),       // 33
+            (WDC65C816::bit, AddressingMode::ZeropageXIndex, 4),                    // 34
+            (WDC65C816::and, AddressingMode::ZeropageXIndex, 4),                    // 35
+            (WDC65C816::rol, AddressingMode::ZeropageXIndex, 6),                    // 36
+            (WDC65C816::and, AddressingMode::ZeropageIndirectLongYIndex, 6),        // 37
+            (WDC65C816::sec, AddressingMode::Implied, 2),                           // 38
+            (WDC65C816::and, AddressingMode::AbsoluteYIndex, 4),                    // 39
+            (WDC65C816::dec, AddressingMode::Accumulator, 2),                       // 3A
+            (WDC65C816::tsc, AddressingMode::Implied, 2),                           // 3B
+            (WDC65C816::bit, AddressingMode::AbsoluteXIndex, 4),                    // 3C
+            (WDC65C816::and, AddressingMode::AbsoluteXIndex, 4),                    // 3D
+            (WDC65C816::rol, AddressingMode::AbsoluteXIndex, 7),                    // 3E
+            (WDC65C816::and, AddressingMode::AbsoluteLongXIndex, 5),                // 3F
+            (WDC65C816::rti, AddressingMode::Implied, 6),                           // 40
+            (WDC65C816::eor, AddressingMode::XIndexIndirect, 6),                    // 41
+            (WDC65C816::wdm, AddressingMode::Implied, 2),                           // 42
+            (WDC65C816::eor, AddressingMode::StackRelative, 4),                     // 43
+            (WDC65C816::mvp, AddressingMode::BlockMove, 7),                         // 44
+            (WDC65C816::eor, AddressingMode::Zeropage, 3),                          // 45
+            (WDC65C816::lsr, AddressingMode::Zeropage, 5),                          // 46
+            (WDC65C816::eor, AddressingMode::ZeropageIndirectLong, 6),              // 47
+            (WDC65C816::pha, AddressingMode::Implied, 3),                           // 48
+            (WDC65C816::eor, AddressingMode::Immediate, 2),                         // 49
+            (WDC65C816::lsr, AddressingMode::Accumulator, 2),                       // 4A
+            (WDC65C816::phk, AddressingMode::Implied, 3),                           // 4B
+            (WDC65C816::jmp, AddressingMode::Absolute, 3),                          // 4C
+            (WDC65C816::eor, AddressingMode::Absolute, 4),                          // 4D
+            (WDC65C816::lsr, AddressingMode::Absolute, 6),                          // 4E
+            (WDC65C816::eor, AddressingMode::AbsoluteLong, 5),                      // 4F
+            (WDC65C816::bvc, AddressingMode::Relative, 2),                          // 50
+            (WDC65C816::eor, AddressingMode::IndirectYIndex, 5),                    // 51
+            (WDC65C816::eor, AddressingMode::ZeropageIndirect, 5),                  // 52
+            (WDC65C816::eor, AddressingMode::StackRelativeIndirectYIndex, 7),       // 53
+            (WDC65C816::mvn, AddressingMode::BlockMove, 7),                         // 54
+            (WDC65C816::eor, AddressingMode::ZeropageXIndex, 4),                    // 55
+            (WDC65C816::lsr, AddressingMode::ZeropageXIndex, 6),                    // 56
+            (WDC65C816::eor, AddressingMode::ZeropageIndirectLongYIndex, 6),        // 57
+            (WDC65C816::cli, AddressingMode::Implied, 2),                           // 58
+            (WDC65C816::eor, AddressingMode::AbsoluteYIndex, 4),                    // 59
+            (WDC65C816::phy, AddressingMode::Implied, 3),                           // 5A
+            (WDC65C816::tcd, AddressingMode::Implied, 2),                           // 5B
+            (WDC65C816::jmp, AddressingMode::AbsoluteLong, 4),                      // 5C
+            (WDC65C816::eor, AddressingMode::AbsoluteXIndex, 4),                    // 5D
+            (WDC65C816::lsr, AddressingMode::AbsoluteXIndex, 7),                    // 5E
+            (WDC65C816::eor, AddressingMode::AbsoluteLongXIndex, 5),                // 5F
+            (WDC65C816::rts, AddressingMode::Implied, 6),                           // 60
+            (WDC65C816::adc, AddressingMode::XIndexIndirect, 6),                    // 61
+            (WDC65C816::per, AddressingMode::Implied, 6),                           // 62
+            (WDC65C816::adc, AddressingMode::StackRelative, 4),                     // 63
+            (WDC65C816::stz, AddressingMode::Zeropage, 3),                          // 64
+            (WDC65C816::adc, AddressingMode::Zeropage, 3),                          // 65
+            (WDC65C816::ror, AddressingMode::Zeropage, 5),                          // 66
+            (WDC65C816::adc, AddressingMode::ZeropageIndirectLong, 6),              // 67
+            (WDC65C816::pla, AddressingMode::Implied, 4),                           // 68
+            (WDC65C816::adc, AddressingMode::Immediate, 2),                         // 69
+            (WDC65C816::ror, AddressingMode::Accumulator, 2),                       // 6A
+            (WDC65C816::rtl, AddressingMode::Implied, 6),                           // 6B
+            (WDC65C816::jmp, AddressingMode::Indirect, 5),                          // 6C
+            (WDC65C816::adc, AddressingMode::Absolute, 4),                          // 6D
+            (WDC65C816::ror, AddressingMode::Absolute, 6),                          // 6E
+            (WDC65C816::adc, AddressingMode::AbsoluteLong, 5),                      // 6F
+            (WDC65C816::bvs, AddressingMode::Relative, 2),                          // 70
+            (WDC65C816::adc, AddressingMode::IndirectYIndex, 5),                    // 71
+            (WDC65C816::adc, AddressingMode::ZeropageIndirect, 5),                  // 72
+            (WDC65C816::adc, AddressingMode::StackRelativeIndirectYIndex, 7),       // 73
+            (WDC65C816::stz, AddressingMode::ZeropageXIndex, 4),                    // 74
+            (WDC65C816::adc, AddressingMode::ZeropageXIndex, 4),                    // 75
+            (WDC65C816::ror, AddressingMode::ZeropageXIndex, 6),                    // 76
+            (WDC65C816::adc, AddressingMode::ZeropageIndirectLongYIndex, 6),        // 77
+            (WDC65C816::sei, AddressingMode::Implied, 2),                           // 78
+            (WDC65C816::adc, AddressingMode::AbsoluteYIndex, 4),                    // 79
+            (WDC65C816::ply, AddressingMode::Implied, 4),                           // 7A
+            (WDC65C816::tdc, AddressingMode::Implied, 2),                           // 7B
+            (WDC65C816::jmp, AddressingMode::AbsoluteXIndexIndirect, 6),            // 7C
+            (WDC65C816::adc, AddressingMode::AbsoluteXIndex, 4),                    // 7D
+            (WDC65C816::ror, AddressingMode::AbsoluteXIndex, 7),                    // 7E
+            (WDC65C816::adc, AddressingMode::AbsoluteLongXIndex, 5),                // 7F
+            (WDC65C816::bra, AddressingMode::Relative, 2),                          // 80
+            (WDC65C816::sta, AddressingMode::XIndexIndirect, 6),                    // 81
+            (WDC65C816::brl, AddressingMode::RelativeLong, 4),                      // 82
+            (WDC65C816::sta, AddressingMode::StackRelative, 4),                     // 83
+            (WDC65C816::sty, AddressingMode::Zeropage, 3),                          // 84
+            (WDC65C816::sta, AddressingMode::Zeropage, 3),                          // 85
+            (WDC65C816::stx, AddressingMode::Zeropage, 3),                          // 86
+            (WDC65C816::sta, AddressingMode::ZeropageIndirectLong, 6),              // 87
+            (WDC65C816::dey, AddressingMode::Implied, 2),                           // 88
+            (WDC65C816::bit, AddressingMode::Immediate, 2),                         // 89
+            (WDC65C816::txa, AddressingMode::Implied, 2),                           // 8A
+            (WDC65C816::phb, AddressingMode::Implied, 3),                           // 8B
+            (WDC65C816::sty, AddressingMode::Absolute, 4),                          // 8C
+            (WDC65C816::sta, AddressingMode::Absolute, 4),                          // 8D
+            (WDC65C816::stx, AddressingMode::Absolute, 4),                          // 8E
+            (WDC65C816::sta, AddressingMode::AbsoluteLong, 5),                      // 8F
+            (WDC65C816::bcc, AddressingMode::Relative, 2),                          // 90
+            (WDC65C816::sta, AddressingMode::IndirectYIndex, 6),                    // 91
+            (WDC65C816::sta, AddressingMode::ZeropageIndirect, 5),                  // 92
+            (WDC65C816::sta, AddressingMode::StackRelativeIndirectYIndex, 7),       // 93
+            (WDC65C816::sty, AddressingMode::ZeropageXIndex, 4),                    // 94
+            (WDC65C816::sta, AddressingMode::ZeropageXIndex, 4),                    // 95
+            (WDC65C816::stx, AddressingMode::ZeropageYIndex, 4),                    // 96
+            (WDC65C816::sta, AddressingMode::ZeropageIndirectLongYIndex, 6),        // 97
+            (WDC65C816::tya, AddressingMode::Implied, 2),                           // 98
+            (WDC65C816::sta, AddressingMode::AbsoluteYIndex, 5),                    // 99
+            (WDC65C816::txs, AddressingMode::Implied, 2),                           // 9A
+            (WDC65C816::txy, AddressingMode::Implied, 2),                           // 9B
+            (WDC65C816::stz, AddressingMode::Absolute, 4),                          // 9C
+            (WDC65C816::sta, AddressingMode::AbsoluteXIndex, 5),                    // 9D
+            (WDC65C816::stz, AddressingMode::AbsoluteXIndex, 5),                    // 9E
+            (WDC65C816::sta, AddressingMode::AbsoluteLongXIndex, 5),                // 9F
+            (WDC65C816::ldy, AddressingMode::Immediate, 2),                         // A0
+            (WDC65C816::lda, AddressingMode::XIndexIndirect, 6),                    // A1
+            (WDC65C816::ldx, AddressingMode::Immediate, 2),                         // A2
+            (WDC65C816::lda, AddressingMode::StackRelative, 4),                     // A3
+            (WDC65C816::ldy, AddressingMode::Zeropage, 3),                          // A4
+            (WDC65C816::lda, AddressingMode::Zeropage, 3),                          // A5
+            (WDC65C816::ldx, AddressingMode::Zeropage, 3),                          // A6
+            (WDC65C816::lda, AddressingMode::ZeropageIndirectLong, 6),              // A7
+            (WDC65C816::tay, AddressingMode::Implied, 2),                           // A8
+            (WDC65C816::lda, AddressingMode::Immediate, 2),                         // A9
+            (WDC65C816::tax, AddressingMode::Implied, 2),                           // AA
+            (WDC65C816::plb, AddressingMode::Implied, 4),                           // AB
+            (WDC65C816::ldy, AddressingMode::Absolute, 4),                          // AC
+            (WDC65C816::lda, AddressingMode::Absolute, 4),                          // AD
+            (WDC65C816::ldx, AddressingMode::Absolute, 4),                          // AE
+            (WDC65C816::lda, AddressingMode::AbsoluteLong, 5),                      // AF
+            (WDC65C816::bcs, AddressingMode::Relative, 2),                          // B0
+            (WDC65C816::lda, AddressingMode::IndirectYIndex, 5),                    // B1
+            (WDC65C816::lda, AddressingMode::ZeropageIndirect, 5),                  // B2
+            (WDC65C816::lda, AddressingMode::StackRelativeIndirectYIndex, 7),       // B3
+            (WDC65C816::ldy, AddressingMode::ZeropageXIndex, 4),                    // B4
+            (WDC65C816::lda, AddressingMode::ZeropageXIndex, 4),                    // B5
+            (WDC65C816::ldx, AddressingMode::ZeropageYIndex, 4),                    // B6
+            (WDC65C816::lda, AddressingMode::ZeropageIndirectLongYIndex, 6),        // B7
+            (WDC65C816::clv, AddressingMode::Implied, 2),                           // B8
+            (WDC65C816::lda, AddressingMode::AbsoluteYIndex, 4),                    // B9
+            (WDC65C816::tsx, AddressingMode::Implied, 2),                           // BA
+            (WDC65C816::tyx, AddressingMode::Implied, 2),                           // BB
+            (WDC65C816::ldy, AddressingMode::AbsoluteXIndex, 4),                    // BC
+            (WDC65C816::lda, AddressingMode::AbsoluteXIndex, 4),                    // BD
+            (WDC65C816::ldx, AddressingMode::AbsoluteYIndex, 4),                    // BE
+            (WDC65C816::lda, AddressingMode::AbsoluteLongXIndex, 5),                // BF
+            (WDC65C816::cpy, AddressingMode::Immediate, 2),                         // C0
+            (WDC65C816::cmp, AddressingMode::XIndexIndirect, 6),                    // C1
+            (WDC65C816::rep, AddressingMode::Immediate, 3),                         // C2
+            (WDC65C816::cmp, AddressingMode::StackRelative, 4),                     // C3
+            (WDC65C816::cpy, AddressingMode::Zeropage, 3),                          // C4
+            (WDC65C816::cmp, AddressingMode::Zeropage, 3),                          // C5
+            (WDC65C816::dec, AddressingMode::Zeropage, 5),                          // C6
+            (WDC65C816::cmp, AddressingMode::ZeropageIndirectLong, 6),              // C7
+            (WDC65C816::iny, AddressingMode::Implied, 2),                           // C8
+            (WDC65C816::cmp, AddressingMode::Immediate, 2),                         // C9
+            (WDC65C816::dex, AddressingMode::Implied, 2),                           // CA
+            (WDC65C816::wai, AddressingMode::Implied, 3),                           // CB
+            (WDC65C816::cpy, AddressingMode::Absolute, 4),                          // CC
+            (WDC65C816::cmp, AddressingMode::Absolute, 4),                          // CD
+            (WDC65C816::dec, AddressingMode::Absolute, 6),                          // CE
+            (WDC65C816::cmp, AddressingMode::AbsoluteLong, 5),                      // CF
+            (WDC65C816::bne, AddressingMode::Relative, 2),                          // D0
+            (WDC65C816::cmp, AddressingMode::IndirectYIndex, 5),                    // D1
+            (WDC65C816::cmp, AddressingMode::ZeropageIndirect, 5),                  // D2
+            (WDC65C816::cmp, AddressingMode::StackRelativeIndirectYIndex, 7),       // D3
+            (WDC65C816::pei, AddressingMode::Zeropage, 6),                          // D4
+            (WDC65C816::cmp, AddressingMode::ZeropageXIndex, 4),                    // D5
+            (WDC65C816::dec, AddressingMode::ZeropageXIndex, 6),                    // D6
+            (WDC65C816::cmp, AddressingMode::ZeropageIndirectLongYIndex, 6),        // D7
+            (WDC65C816::cld, AddressingMode::Implied, 2),                           // D8
+            (WDC65C816::cmp, AddressingMode::AbsoluteYIndex, 4),                    // D9
+            (WDC65C816::phx, AddressingMode::Implied, 3),                           // DA
+            (WDC65C816::stp, AddressingMode::Implied, 3),                           // DB
+            (WDC65C816::jmp, AddressingMode::AbsoluteIndirectLong, 6),              // DC
+            (WDC65C816::cmp, AddressingMode::AbsoluteXIndex, 4),                    // DD
+            (WDC65C816::dec, AddressingMode::AbsoluteXIndex, 7),                    // DE
+            (WDC65C816::cmp, AddressingMode::AbsoluteLongXIndex, 5),                // DF
+            (WDC65C816::cpx, AddressingMode::Immediate, 2),                         // E0
+            (WDC65C816::sbc, AddressingMode::XIndexIndirect, 6),                    // E1
+            (WDC65C816::sep, AddressingMode::Immediate, 3),                         // E2
+            (WDC65C816::sbc, AddressingMode::StackRelative, 4),                     // E3
+            (WDC65C816::cpx, AddressingMode::Zeropage, 3),                          // E4
+            (WDC65C816::sbc, AddressingMode::Zeropage, 3),                          // E5
+            (WDC65C816::inc, AddressingMode::Zeropage, 5),                          // E6
+            (WDC65C816::sbc, AddressingMode::ZeropageIndirectLong, 6),              // E7
+            (WDC65C816::inx, AddressingMode::Implied, 2),                           // E8
+            (WDC65C816::sbc, AddressingMode::Immediate, 2),                         // E9
+            (WDC65C816::nop, AddressingMode::Implied, 2),                           // EA
+            (WDC65C816::xba, AddressingMode::Implied, 3),                           // EB
+            (WDC65C816::cpx, AddressingMode::Absolute, 4),                          // EC
+            (WDC65C816::sbc, AddressingMode::Absolute, 4),                          // ED
+            (WDC65C816::inc, AddressingMode::Absolute, 6),                          // EE
+            (WDC65C816::sbc, AddressingMode::AbsoluteLong, 5),                      // EF
+            (WDC65C816::beq, AddressingMode::Relative, 2),                          // F0
+            (WDC65C816::sbc, AddressingMode::IndirectYIndex, 5),                    // F1
+            (WDC65C816::sbc, AddressingMode::ZeropageIndirect, 5),                  // F2
+            (WDC65C816::sbc, AddressingMode::StackRelativeIndirectYIndex, 7),       // F3
+            (WDC65C816::pea, AddressingMode::Absolute, 5),                          // F4
+            (WDC65C816::sbc, AddressingMode::ZeropageXIndex, 4),                    // F5
+            (WDC65C816::inc, AddressingMode::ZeropageXIndex, 6),                    // F6
+            (WDC65C816::sbc, AddressingMode::ZeropageIndirectLongYIndex, 6),        // F7
+            (WDC65C816::sed, AddressingMode::Implied, 2),                           // F8
+            (WDC65C816::sbc, AddressingMode::AbsoluteYIndex, 4),                    // F9
+            (WDC65C816::plx, AddressingMode::Implied, 4),                           // FA
+            (WDC65C816::xce, AddressingMode::Implied, 2),                           // FB
+            (WDC65C816::jsr, AddressingMode::AbsoluteXIndexIndirect, 8),            // FC
+            (WDC65C816::sbc, AddressingMode::AbsoluteXIndex, 4),                    // FD
+            (WDC65C816::inc, AddressingMode::AbsoluteXIndex, 7),                    // FE
+            (WDC65C816::sbc, AddressingMode::AbsoluteLongXIndex, 5),                // FF
+        ])
+    }
+}
+
+impl<T: Bus> Default for WDC65C816<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Bus> WDC65C816<T> {
+    /// Create new instance of WDC65C816, in emulation mode as after a reset
+    pub fn new() -> Self {
+        Self {
+            accumulator: u16::MIN,
+            x_register: u16::MIN,
+            y_register: u16::MIN,
+            stack_pointer: EMULATION_STACK_PAGE | 0xFF,
+            direct_page: u16::MIN,
+            data_bank: u8::MIN,
+            program_bank: u8::MIN,
+            program_counter: u16::MIN,
+            status_register: StatusFlags::AccumulatorWidth
+                | StatusFlags::IndexWidth
+                | StatusFlags::NoInterrupts,
+            emulation_mode: true,
+            waiting: false,
+            stopped: false,
+            opcode_array: OpcodeFunctionArray::default(),
+        }
+    }
+
+    #[inline]
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    #[inline]
+    pub fn program_bank(&self) -> u8 {
+        self.program_bank
+    }
+
+    #[inline]
+    pub fn data_bank(&self) -> u8 {
+        self.data_bank
+    }
+
+    #[inline]
+    pub fn direct_page(&self) -> u16 {
+        self.direct_page
+    }
+
+    /// Full 16-bit accumulator (C), regardless of the M flag
+    #[inline]
+    pub fn accumulator(&self) -> u16 {
+        self.accumulator
+    }
+
+    #[inline]
+    pub fn x_register(&self) -> u16 {
+        self.x_register
+    }
+
+    #[inline]
+    pub fn y_register(&self) -> u16 {
+        self.y_register
+    }
+
+    #[inline]
+    pub fn stack_pointer(&self) -> u16 {
+        self.stack_pointer
+    }
+
+    #[inline]
+    pub fn status_register(&self) -> StatusFlags {
+        self.status_register
+    }
+
+    #[inline]
+    pub fn emulation_mode(&self) -> bool {
+        self.emulation_mode
+    }
+
+    /// Change value of program counter
+    #[inline]
+    pub fn set_program_counter(&mut self, value: u16) {
+        self.program_counter = value;
+    }
+
+    #[inline]
+    pub fn set_program_bank(&mut self, value: u8) {
+        self.program_bank = value;
+    }
+
+    #[inline]
+    pub fn set_data_bank(&mut self, value: u8) {
+        self.data_bank = value;
+    }
+
+    #[inline]
+    pub fn set_direct_page(&mut self, value: u16) {
+        self.direct_page = value;
+    }
+
+    #[inline]
+    pub fn set_accumulator(&mut self, value: u16) {
+        self.accumulator = value;
+    }
+
+    /// Change value of X. The high byte is dropped while index registers are 8-bit.
+    #[inline]
+    pub fn set_x_register(&mut self, value: u16) {
+        self.x_register = value & self.index_mask();
+    }
+
+    /// Change value of Y. The high byte is dropped while index registers are 8-bit.
+    #[inline]
+    pub fn set_y_register(&mut self, value: u16) {
+        self.y_register = value & self.index_mask();
+    }
+
+    /// Change value of the stack pointer. Emulation mode keeps it on page 1.
+    #[inline]
+    pub fn set_stack_pointer(&mut self, value: u16) {
+        self.stack_pointer = value;
+        self.wrap_stack_pointer();
+    }
+
+    /// Change value of P, applying the register width rules of the current mode
+    pub fn set_status_register(&mut self, value: StatusFlags) {
+        self.status_register = value;
+        if self.emulation_mode {
+            self.status_register |= StatusFlags::AccumulatorWidth | StatusFlags::IndexWidth;
+        }
+        if self.flag_check(StatusFlags::IndexWidth) {
+            self.x_register &= 0xFF;
+            self.y_register &= 0xFF;
+        }
+    }
+
+    /// Switch between emulation and native mode, as XCE would
+    pub fn set_emulation_mode(&mut self, emulation: bool) {
+        self.emulation_mode = emulation;
+        if emulation {
+            self.set_status_register(self.status_register);
+            self.wrap_stack_pointer();
+        }
+    }
+
+    /// Check if specified flag is set
+    #[inline]
+    pub fn flag_check(&self, flag: StatusFlags) -> bool {
+        flag.intersects(self.status_register)
+    }
+
+    /// Turn specified flag on/off
+    #[inline]
+    fn flag_set(&mut self, f: StatusFlags, value: bool) {
+        self.status_register.set(f, value);
+    }
+
+    /// Whether a WAI instruction is waiting for an interrupt
+    #[inline]
+    pub fn waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Whether a STP instruction has stopped the clock until the next reset
+    #[inline]
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Perform the reset sequence, entering emulation mode and loading the reset vector
+    pub fn reset(&mut self, bus: &mut T) -> Result<u32, CpuError> {
+        self.emulation_mode = true;
+        self.direct_page = 0;
+        self.data_bank = 0;
+        self.program_bank = 0;
+        self.waiting = false;
+        self.stopped = false;
+        self.set_status_register(
+            (self.status_register | StatusFlags::NoInterrupts) & !StatusFlags::Decimal,
+        );
+        self.wrap_stack_pointer();
+
+        let low_byte = self.read_byte(bus, 0xFFFC)?;
+        let high_byte = self.read_byte(bus, 0xFFFD)?;
+        self.set_program_counter(u16::from_le_bytes([low_byte, high_byte]));
+        Ok(7)
+    }
+
+    pub fn irq(&mut self, bus: &mut T) -> Result<u32, CpuError> {
+        if self.stopped {
+            return Ok(0);
+        }
+        self.waiting = false;
+        if self.flag_check(StatusFlags::NoInterrupts) {
+            return Ok(0);
+        }
+        self.perform_interrupt(bus, InterruptKind::Irq)?;
+        Ok(7 + !self.emulation_mode as u32)
+    }
+
+    pub fn nmi(&mut self, bus: &mut T) -> Result<u32, CpuError> {
+        if self.stopped {
+            return Ok(0);
+        }
+        self.waiting = false;
+        self.perform_interrupt(bus, InterruptKind::Nmi)?;
+        Ok(7 + !self.emulation_mode as u32)
+    }
+
+    /// Step over one CPU instruction.
+    ///
+    /// While the CPU is stopped or waiting for an interrupt, a single idle cycle is reported.
+    #[inline]
+    pub fn step(&mut self, bus: &mut T) -> Result<u32, CpuError> {
+        if self.waiting || self.stopped {
+            return Ok(1);
+        }
+        let opcode = self.fetch_byte(bus)? as usize;
+        let (opcode_func, address_mode, base_cycles) = self.opcode_array.0[opcode];
+        Ok(base_cycles + opcode_func(self, bus, address_mode)?)
+    }
+
+    fn perform_interrupt(&mut self, bus: &mut T, kind: InterruptKind) -> Result<(), CpuError> {
+        if !self.emulation_mode {
+            self.push_to_stack(bus, self.program_bank)?;
+        }
+        let (return_address_lo, return_address_hi): (u8, u8) =
+            self.program_counter.to_le_bytes().into();
+        self.push_to_stack(bus, return_address_hi)?;
+        self.push_to_stack(bus, return_address_lo)?;
+
+        let mut status_register_value = self.status_register;
+        if self.emulation_mode {
+            status_register_value.set(
+                StatusFlags::IndexWidth,
+                matches!(kind, InterruptKind::Brk | InterruptKind::Cop),
+            );
+        }
+        self.push_to_stack(bus, status_register_value.into())?;
+
+        let vector_address: u32 = match (kind, self.emulation_mode) {
+            (InterruptKind::Cop, true) => 0xFFF4,
+            (InterruptKind::Cop, false) => 0xFFE4,
+            (InterruptKind::Brk, true) => 0xFFFE,
+            (InterruptKind::Brk, false) => 0xFFE6,
+            (InterruptKind::Nmi, true) => 0xFFFA,
+            (InterruptKind::Nmi, false) => 0xFFEA,
+            (InterruptKind::Irq, true) => 0xFFFE,
+            (InterruptKind::Irq, false) => 0xFFEE,
+        };
+        self.flag_set(StatusFlags::NoInterrupts, true);
+        self.flag_set(StatusFlags::Decimal, false);
+        self.program_bank = 0;
+
+        let divert_address_lo = self.read_byte(bus, vector_address)?;
+        let divert_address_hi = self.read_byte(bus, vector_address + 1)?;
+        self.set_program_counter(u16::from_le_bytes([divert_address_lo, divert_address_hi]));
+        Ok(())
+    }
+
+    #[inline]
+    fn accumulator_is_wide(&self) -> bool {
+        !self.flag_check(StatusFlags::AccumulatorWidth)
+    }
+
+    #[inline]
+    fn index_is_wide(&self) -> bool {
+        !self.flag_check(StatusFlags::IndexWidth)
+    }
+
+    #[inline]
+    fn index_mask(&self) -> u16 {
+        if self.index_is_wide() {
+            0xFFFF
+        } else {
+            0x00FF
+        }
+    }
+
+    /// Write the low byte only, keeping B, unless the accumulator is 16-bit
+    #[inline]
+    fn write_accumulator(&mut self, value: u16, wide: bool) {
+        if wide {
+            self.accumulator = value;
+        } else {
+            self.accumulator = (self.accumulator & 0xFF00) | (value & 0x00FF);
+        }
+    }
+
+    #[inline]
+    fn set_negative_and_zero(&mut self, value: u16, wide: bool) {
+        let (zero, negative) = if wide {
+            (value == 0, value & 0x8000 != 0)
+        } else {
+            (value & 0xFF == 0, value & 0x80 != 0)
+        };
+        self.flag_set(StatusFlags::Zero, zero);
+        self.flag_set(StatusFlags::Negative, negative);
+    }
+
+    #[inline]
+    fn wrap_stack_pointer(&mut self) {
+        if self.emulation_mode {
+            self.stack_pointer = EMULATION_STACK_PAGE | (self.stack_pointer & 0xFF);
+        }
+    }
+
+    #[inline]
+    fn read_byte(&mut self, bus: &mut T, address: u32) -> Result<u8, BusError> {
+        bus.read(address & ADDRESS_MASK)
+    }
+
+    #[inline]
+    fn write_byte(&mut self, bus: &mut T, address: u32, value: u8) -> Result<(), BusError> {
+        bus.write(address & ADDRESS_MASK, value)
+    }
+
+    /// Read a word from bank 0, wrapping within the bank
+    #[inline]
+    fn read_bank_zero_word(&mut self, bus: &mut T, address: u16) -> Result<u16, BusError> {
+        let low_byte = self.read_byte(bus, address as u32)?;
+        let high_byte = self.read_byte(bus, address.wrapping_add(1) as u32)?;
+        Ok(u16::from_le_bytes([low_byte, high_byte]))
+    }
+
+    #[inline]
+    fn fetch_byte(&mut self, bus: &mut T) -> Result<u8, BusError> {
+        let address = (self.program_bank as u32) << 16 | self.program_counter as u32;
+        self.program_counter = self.program_counter.wrapping_add(1);
+        self.read_byte(bus, address)
+    }
+
+    #[inline]
+    fn fetch_word(&mut self, bus: &mut T) -> Result<u16, BusError> {
+        let low_byte = self.fetch_byte(bus)?;
+        let high_byte = self.fetch_byte(bus)?;
+        Ok(u16::from_le_bytes([low_byte, high_byte]))
+    }
+
+    #[inline]
+    fn fetch_long(&mut self, bus: &mut T) -> Result<u32, BusError> {
+        let low_word = self.fetch_word(bus)?;
+        let bank = self.fetch_byte(bus)?;
+        Ok((bank as u32) << 16 | low_word as u32)
+    }
+
+    /// Push a byte; in emulation mode the stack pointer stays on page 1
+    #[inline]
+    fn push_to_stack(&mut self, bus: &mut T, value: u8) -> Result<(), BusError> {
+        self.push_to_stack_unwrapped(bus, value)?;
+        self.wrap_stack_pointer();
+        Ok(())
+    }
+
+    #[inline]
+    fn pop_from_stack(&mut self, bus: &mut T) -> Result<u8, BusError> {
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        self.wrap_stack_pointer();
+        self.read_byte(bus, self.stack_pointer as u32)
+    }
+
+    /// Push used by the instructions new to the 65C816, which may leave page 1 in
+    /// emulation mode. Callers wrap the stack pointer once the instruction is done.
+    #[inline]
+    fn push_to_stack_unwrapped(&mut self, bus: &mut T, value: u8) -> Result<(), BusError> {
+        self.write_byte(bus, self.stack_pointer as u32, value)?;
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        Ok(())
+    }
+
+    #[inline]
+    fn pop_from_stack_unwrapped(&mut self, bus: &mut T) -> Result<u8, BusError> {
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        self.read_byte(bus, self.stack_pointer as u32)
+    }
+
+    #[inline]
+    fn push_word_unwrapped(&mut self, bus: &mut T, value: u16) -> Result<(), BusError> {
+        let (low_byte, high_byte): (u8, u8) = value.to_le_bytes().into();
+        self.push_to_stack_unwrapped(bus, high_byte)?;
+        self.push_to_stack_unwrapped(bus, low_byte)
+    }
+
+    #[inline]
+    fn pop_word_unwrapped(&mut self, bus: &mut T) -> Result<u16, BusError> {
+        let low_byte = self.pop_from_stack_unwrapped(bus)?;
+        let high_byte = self.pop_from_stack_unwrapped(bus)?;
+        Ok(u16::from_le_bytes([low_byte, high_byte]))
+    }
+
+    /// Emulation mode with a page-aligned direct page keeps the 6502 zeropage wrapping
+    #[inline]
+    fn direct_page_wraps(&self) -> bool {
+        self.emulation_mode && self.direct_page & 0xFF == 0
+    }
+
+    #[inline]
+    fn direct_indexed_address(&self, offset: u8, index: u16) -> u16 {
+        if self.direct_page_wraps() {
+            self.direct_page | (offset.wrapping_add(index as u8) as u16)
+        } else {
+            self.direct_page
+                .wrapping_add(offset as u16)
+                .wrapping_add(index)
+        }
+    }
+
+    /// Read a 16-bit pointer from the direct page
+    #[inline]
+    fn read_direct_pointer(&mut self, bus: &mut T, address: u16) -> Result<u16, BusError> {
+        let low_byte = self.read_byte(bus, address as u32)?;
+        let high_address = if self.direct_page_wraps() {
+            (address & 0xFF00) | (address.wrapping_add(1) & 0x00FF)
+        } else {
+            address.wrapping_add(1)
+        };
+        let high_byte = self.read_byte(bus, high_address as u32)?;
+        Ok(u16::from_le_bytes([low_byte, high_byte]))
+    }
+
+    /// Read a 24-bit pointer from the direct page
+    #[inline]
+    fn read_direct_long_pointer(&mut self, bus: &mut T, address: u16) -> Result<u32, BusError> {
+        let low_word = self.read_bank_zero_word(bus, address)?;
+        let bank = self.read_byte(bus, address.wrapping_add(2) as u32)?;
+        Ok((bank as u32) << 16 | low_word as u32)
+    }
+
+    #[inline]
+    fn data_address(&self, address: u16) -> u32 {
+        (self.data_bank as u32) << 16 | address as u32
+    }
+
+    /// Add an index to a 24-bit base address, reporting whether a page was crossed
+    #[inline]
+    fn index_address(&self, base: u32, index: u16) -> (u32, bool) {
+        let address = base.wrapping_add(index as u32) & ADDRESS_MASK;
+        (address, (base ^ address) & 0xFFFF00 != 0)
+    }
+
+    /// Read a byte or word through a resolved operand
+    fn read_operand(
+        &mut self,
+        bus: &mut T,
+        resolved: &ResolvedOperand,
+        wide: bool,
+    ) -> Result<u16, CpuError> {
+        let (low_address, high_address) = match resolved.operand {
+            OpcodeOperand::Immediate(value) => return Ok(value),
+            OpcodeOperand::Accumulator => return Ok(self.accumulator),
+            OpcodeOperand::Address(address) => (address, address.wrapping_add(1)),
+            OpcodeOperand::BankZeroAddress(address) => {
+                (address as u32, address.wrapping_add(1) as u32)
+            }
+            OpcodeOperand::None => {
+                return Err(CpuError::Invalid65816AddressingMode(resolved.address_mode))
+            }
+        };
+        let low_byte = self.read_byte(bus, low_address)?;
+        let high_byte = if wide {
+            self.read_byte(bus, high_address)?
+        } else {
+            0
+        };
+        Ok(u16::from_le_bytes([low_byte, high_byte]))
+    }
+
+    /// Write a byte or word through a resolved operand. Read-modify-write instructions
+    /// store the high byte first, as the hardware does.
+    fn write_operand(
+        &mut self,
+        bus: &mut T,
+        resolved: &ResolvedOperand,
+        value: u16,
+        wide: bool,
+        high_byte_first: bool,
+    ) -> Result<(), CpuError> {
+        let (low_address, high_address) = match resolved.operand {
+            OpcodeOperand::Accumulator => {
+                self.write_accumulator(value, wide);
+                return Ok(());
+            }
+            OpcodeOperand::Address(address) => (address, address.wrapping_add(1)),
+            OpcodeOperand::BankZeroAddress(address) => {
+                (address as u32, address.wrapping_add(1) as u32)
+            }
+            OpcodeOperand::Immediate(_) | OpcodeOperand::None => {
+                return Err(CpuError::Invalid65816AddressingMode(resolved.address_mode))
+            }
+        };
+        let (low_byte, high_byte): (u8, u8) = value.to_le_bytes().into();
+        if wide && high_byte_first {
+            self.write_byte(bus, high_address, high_byte)?;
+        }
+        self.write_byte(bus, low_address, low_byte)?;
+        if wide && !high_byte_first {
+            self.write_byte(bus, high_address, high_byte)?;
+        }
+        Ok(())
+    }
+
+    /// Shared body of the read-modify-write instructions operating at accumulator width
+    #[inline]
+    fn read_modify_write(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        operation: fn(&mut Self, u16, bool) -> u16,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let value = self.read_operand(bus, &resolved, wide)?;
+        let result = operation(self, value, wide);
+        self.write_operand(bus, &resolved, result, wide, true)?;
+        match resolved.operand {
+            OpcodeOperand::Accumulator => Ok(0),
+            _ => Ok(2 * wide as u32 + resolved.direct_penalty),
+        }
+    }
+
+    /// Given some addressing mode, fetches the operand bytes and computes the effective
+    /// address. `wide` selects the size of immediate operands.
+    fn resolve_operand(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        wide: bool,
+    ) -> Result<ResolvedOperand, CpuError> {
+        let mut resolved = ResolvedOperand {
+            operand: OpcodeOperand::None,
+            address_mode,
+            direct_penalty: 0,
+            index_penalty: 0,
+        };
+        if let AddressingMode::Zeropage
+        | AddressingMode::ZeropageXIndex
+        | AddressingMode::ZeropageYIndex
+        | AddressingMode::ZeropageIndirect
+        | AddressingMode::ZeropageIndirectLong
+        | AddressingMode::ZeropageIndirectLongYIndex
+        | AddressingMode::XIndexIndirect
+        | AddressingMode::IndirectYIndex = address_mode
+        {
+            resolved.direct_penalty = (self.direct_page & 0xFF != 0) as u32;
+        }
+
+        resolved.operand = match address_mode {
+            AddressingMode::Accumulator => OpcodeOperand::Accumulator,
+            AddressingMode::Implied => OpcodeOperand::None,
+            AddressingMode::Immediate => {
+                let value = if wide {
+                    self.fetch_word(bus)?
+                } else {
+                    self.fetch_byte(bus)? as u16
+                };
+                OpcodeOperand::Immediate(value)
+            }
+            AddressingMode::Absolute => {
+                let address = self.fetch_word(bus)?;
+                OpcodeOperand::Address(self.data_address(address))
+            }
+            AddressingMode::AbsoluteXIndex | AddressingMode::AbsoluteYIndex => {
+                let address = self.fetch_word(bus)?;
+                let base = self.data_address(address);
+                let index = match address_mode {
+                    AddressingMode::AbsoluteXIndex => self.x_register,
+                    _ => self.y_register,
+                };
+                let (address, page_crossed) = self.index_address(base, index);
+                resolved.index_penalty = (page_crossed || self.index_is_wide()) as u32;
+                OpcodeOperand::Address(address)
+            }
+            AddressingMode::AbsoluteLong => OpcodeOperand::Address(self.fetch_long(bus)?),
+            AddressingMode::AbsoluteLongXIndex => {
+                let base = self.fetch_long(bus)?;
+                OpcodeOperand::Address(self.index_address(base, self.x_register).0)
+            }
+            AddressingMode::Zeropage => {
+                let offset = self.fetch_byte(bus)?;
+                OpcodeOperand::BankZeroAddress(self.direct_page.wrapping_add(offset as u16))
+            }
+            AddressingMode::ZeropageXIndex => {
+                let offset = self.fetch_byte(bus)?;
+                OpcodeOperand::BankZeroAddress(self.direct_indexed_address(offset, self.x_register))
+            }
+            AddressingMode::ZeropageYIndex => {
+                let offset = self.fetch_byte(bus)?;
+                OpcodeOperand::BankZeroAddress(self.direct_indexed_address(offset, self.y_register))
+            }
+            AddressingMode::ZeropageIndirect => {
+                let offset = self.fetch_byte(bus)?;
+                let pointer_address = self.direct_page.wrapping_add(offset as u16);
+                let pointer = self.read_direct_pointer(bus, pointer_address)?;
+                OpcodeOperand::Address(self.data_address(pointer))
+            }
+            AddressingMode::XIndexIndirect => {
+                let offset = self.fetch_byte(bus)?;
+                let pointer_address = self.direct_indexed_address(offset, self.x_register);
+                let pointer = self.read_direct_pointer(bus, pointer_address)?;
+                OpcodeOperand::Address(self.data_address(pointer))
+            }
+            AddressingMode::IndirectYIndex => {
+                let offset = self.fetch_byte(bus)?;
+                let pointer_address = self.direct_page.wrapping_add(offset as u16);
+                let pointer = self.read_direct_pointer(bus, pointer_address)?;
+                let base = self.data_address(pointer);
+                let (address, page_crossed) = self.index_address(base, self.y_register);
+                resolved.index_penalty = (page_crossed || self.index_is_wide()) as u32;
+                OpcodeOperand::Address(address)
+            }
+            AddressingMode::ZeropageIndirectLong => {
+                let offset = self.fetch_byte(bus)?;
+                let pointer_address = self.direct_page.wrapping_add(offset as u16);
+                OpcodeOperand::Address(self.read_direct_long_pointer(bus, pointer_address)?)
+            }
+            AddressingMode::ZeropageIndirectLongYIndex => {
+                let offset = self.fetch_byte(bus)?;
+                let pointer_address = self.direct_page.wrapping_add(offset as u16);
+                let base = self.read_direct_long_pointer(bus, pointer_address)?;
+                OpcodeOperand::Address(self.index_address(base, self.y_register).0)
+            }
+            AddressingMode::StackRelative => {
+                let offset = self.fetch_byte(bus)?;
+                OpcodeOperand::BankZeroAddress(self.stack_pointer.wrapping_add(offset as u16))
+            }
+            AddressingMode::StackRelativeIndirectYIndex => {
+                let offset = self.fetch_byte(bus)?;
+                let pointer_address = self.stack_pointer.wrapping_add(offset as u16);
+                let pointer = self.read_bank_zero_word(bus, pointer_address)?;
+                let base = self.data_address(pointer);
+                OpcodeOperand::Address(self.index_address(base, self.y_register).0)
+            }
+            AddressingMode::Relative => {
+                let offset = self.fetch_byte(bus)? as i8;
+                let target = self.program_counter.wrapping_add_signed(offset as i16);
+                OpcodeOperand::Address((self.program_bank as u32) << 16 | target as u32)
+            }
+            AddressingMode::RelativeLong => {
+                let offset = self.fetch_word(bus)? as i16;
+                let target = self.program_counter.wrapping_add_signed(offset);
+                OpcodeOperand::Address((self.program_bank as u32) << 16 | target as u32)
+            }
+            AddressingMode::Indirect
+            | AddressingMode::AbsoluteXIndexIndirect
+            | AddressingMode::AbsoluteIndirectLong
+            | AddressingMode::BlockMove => {
+                return Err(CpuError::Invalid65816AddressingMode(address_mode))
+            }
+        };
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct SparseBus(HashMap<u32, u8>);
+
+    impl Bus for SparseBus {
+        fn read(&mut self, address: u32) -> Result<u8, BusError> {
+            Ok(self.0.get(&address).copied().unwrap_or(0))
+        }
+
+        fn write(&mut self, address: u32, value: u8) -> Result<(), BusError> {
+            self.0.insert(address, value);
+            Ok(())
+        }
+    }
+
+    impl SparseBus {
+        fn load(&mut self, address: u32, bytes: &[u8]) {
+            for (offset, byte) in bytes.iter().enumerate() {
+                self.0.insert(address + offset as u32, *byte);
+            }
+        }
+    }
+
+    #[test]
+    fn test_65816_native_program() {
+        let mut bus = SparseBus::default();
+        #[rustfmt::skip]
+        bus.load(0x008000, &[
+            0x18,                   // CLC
+            0xFB,                   // XCE
+            0xC2, 0x30,             // REP #$30
+            0xA9, 0x34, 0x12,       // LDA #$1234
+            0x18,                   // CLC
+            0x69, 0x11, 0x11,       // ADC #$1111
+            0x8F, 0x00, 0x20, 0x7E, // STA $7E2000
+            0xA2, 0x00, 0x20,       // LDX #$2000
+            0xA0, 0x00, 0x30,       // LDY #$3000
+            0xA9, 0x01, 0x00,       // LDA #$0001
+            0x54, 0x7F, 0x7E,       // MVN $7E,$7F
+            0x22, 0x00, 0x90, 0x01, // JSL $019000
+            0xF8,                   // SED
+            0xE2, 0x20,             // SEP #$20
+            0xA9, 0x19,             // LDA #$19
+            0x18,                   // CLC
+            0x69, 0x01,             // ADC #$01
+            0xDB,                   // STP
+        ]);
+        bus.load(0x019000, &[0xA9, 0xEF, 0xBE, 0x6B]); // LDA #$BEEF; RTL
+        bus.load(0x00FFFC, &[0x00, 0x80]);
+
+        let mut cpu = WDC65C816::new();
+        cpu.reset(&mut bus).expect("Failed to reset CPU");
+        let mut cycles = 0;
+        while !cpu.stopped() {
+            cycles += cpu.step(&mut bus).expect("Failed to step CPU");
+            assert!(cycles < 1000, "program did not reach STP");
+        }
+
+        assert!(!cpu.emulation_mode());
+        assert_eq!(bus.0[&0x7E2000], 0x45);
+        assert_eq!(bus.0[&0x7E2001], 0x23);
+        assert_eq!(bus.0[&0x7F3000], 0x45);
+        assert_eq!(bus.0[&0x7F3001], 0x23);
+        assert_eq!(cpu.data_bank(), 0x7F);
+        assert_eq!(cpu.x_register(), 0x2002);
+        assert_eq!(cpu.y_register(), 0x3002);
+        assert_eq!(cpu.accumulator(), 0xBE20);
+        assert_eq!(cpu.stack_pointer(), 0x01FF);
+        assert_eq!(cpu.program_bank(), 0x00);
+    }
+
+    /// CPU about to run `code` from $00:8000, in native mode with status `p` unless
+    /// `emulation` is set
+    fn cpu_with_code(
+        bus: &mut SparseBus,
+        emulation: bool,
+        p: u8,
+        code: &[u8],
+    ) -> WDC65C816<SparseBus> {
+        bus.load(0x008000, code);
+        let mut cpu = WDC65C816::new();
+        cpu.set_emulation_mode(emulation);
+        cpu.set_status_register(StatusFlags::from(p));
+        cpu.set_program_counter(0x8000);
+        cpu
+    }
+
+    #[test]
+    fn test_65816_register_widths() {
+        let mut bus = SparseBus::default();
+        bus.load(0x000011, &[0xCD, 0xAB]);
+        #[rustfmt::skip]
+        let mut cpu = cpu_with_code(&mut bus, false, 0x00, &[
+            0xA9, 0x34, 0x12, // LDA #$1234
+            0xA5, 0x10,       // LDA $10
+            0xE2, 0x20,       // SEP #$20
+            0xA9, 0x56,       // LDA #$56
+            0xEB,             // XBA
+            0xBD, 0x00, 0x20, // LDA $2000,X
+        ]);
+        cpu.set_direct_page(0x0001);
+        cpu.set_x_register(0x0100);
+        bus.load(0x002100, &[0x99]);
+
+        // 16-bit immediate, then direct page with the penalty for a D not on a page
+        assert_eq!(cpu.step(&mut bus).unwrap(), 3);
+        assert_eq!(cpu.accumulator(), 0x1234);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 5);
+        assert_eq!(cpu.accumulator(), 0xABCD);
+        // An 8-bit accumulator leaves B alone
+        assert_eq!(cpu.step(&mut bus).unwrap(), 3);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 2);
+        assert_eq!(cpu.accumulator(), 0xAB56);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 3);
+        assert_eq!(cpu.accumulator(), 0x56AB);
+        assert!(cpu.flag_check(StatusFlags::Negative));
+        // 16-bit index registers always take the indexing cycle
+        assert_eq!(cpu.step(&mut bus).unwrap(), 5);
+        assert_eq!(cpu.accumulator(), 0x5699);
+        assert_eq!(cpu.program_counter(), 0x800D);
+    }
+
+    #[test]
+    fn test_65816_long_and_stack_relative_modes() {
+        let mut bus = SparseBus::default();
+        bus.load(0x123456, &[0x11, 0x22]);
+        bus.load(0x000020, &[0x00, 0x10, 0x7E]);
+        bus.load(0x7E1002, &[0x33, 0x44]);
+        bus.load(0x0001F3, &[0x00, 0x20]);
+        bus.load(0x7F2010, &[0x55, 0x66]);
+        #[rustfmt::skip]
+        let mut cpu = cpu_with_code(&mut bus, false, 0x10, &[
+            0xAF, 0x56, 0x34, 0x12, // LDA $123456
+            0xB7, 0x20,             // LDA [$20],Y
+            0xA3, 0x03,             // LDA $03,S
+            0xB3, 0x03,             // LDA ($03,S),Y
+        ]);
+        cpu.set_stack_pointer(0x01F0);
+        cpu.set_data_bank(0x7F);
+
+        cpu.set_y_register(0x02);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 6);
+        assert_eq!(cpu.accumulator(), 0x2211);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 7);
+        assert_eq!(cpu.accumulator(), 0x4433);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 5);
+        assert_eq!(cpu.accumulator(), 0x2000);
+        cpu.set_y_register(0x10);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 8);
+        assert_eq!(cpu.accumulator(), 0x6655);
+    }
+
+    #[test]
+    fn test_65816_stack_instructions() {
+        let mut bus = SparseBus::default();
+        #[rustfmt::skip]
+        let mut cpu = cpu_with_code(&mut bus, false, 0x30, &[
+            0xF4, 0x34, 0x12, // PEA $1234
+            0x62, 0xFD, 0xFF, // PER *
+            0x8B,             // PHB
+            0xAB,             // PLB
+            0x2B,             // PLD
+        ]);
+        cpu.set_stack_pointer(0x1FFF);
+        cpu.set_data_bank(0x7E);
+
+        assert_eq!(cpu.step(&mut bus).unwrap(), 5);
+        assert_eq!((bus.0[&0x1FFF], bus.0[&0x1FFE]), (0x12, 0x34));
+        assert_eq!(cpu.step(&mut bus).unwrap(), 6);
+        assert_eq!((bus.0[&0x1FFD], bus.0[&0x1FFC]), (0x80, 0x03));
+        assert_eq!(cpu.step(&mut bus).unwrap(), 3);
+        assert_eq!(bus.0[&0x1FFB], 0x7E);
+        cpu.set_data_bank(0x00);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 4);
+        assert_eq!(cpu.data_bank(), 0x7E);
+        // PLD pulls the PER result
+        assert_eq!(cpu.step(&mut bus).unwrap(), 5);
+        assert_eq!(cpu.direct_page(), 0x8003);
+        assert_eq!(cpu.stack_pointer(), 0x1FFD);
+    }
+
+    #[test]
+    fn test_65816_block_move_backwards() {
+        let mut bus = SparseBus::default();
+        bus.load(0x7E1000, &[0xAA, 0xBB, 0xCC]);
+        // MVP $7F,$7E
+        let mut cpu = cpu_with_code(&mut bus, false, 0x00, &[0x44, 0x7F, 0x7E]);
+        cpu.set_accumulator(0x0002);
+        cpu.set_x_register(0x1002);
+        cpu.set_y_register(0x2002);
+
+        // One byte per step, repeating the instruction until A wraps to $FFFF
+        for _ in 0..3 {
+            assert_eq!(cpu.step(&mut bus).unwrap(), 7);
+        }
+        assert_eq!(cpu.accumulator(), 0xFFFF);
+        assert_eq!((cpu.x_register(), cpu.y_register()), (0x0FFF, 0x1FFF));
+        assert_eq!(bus.0[&0x7F2000], 0xAA);
+        assert_eq!(bus.0[&0x7F2002], 0xCC);
+        assert_eq!(cpu.data_bank(), 0x7F);
+        assert_eq!(cpu.program_counter(), 0x8003);
+    }
+
+    #[test]
+    fn test_65816_interrupt_vectors() {
+        let mut bus = SparseBus::default();
+        bus.load(0x00FFE6, &[0x00, 0x90]); // Native BRK
+        bus.load(0x00FFF4, &[0x00, 0xA0]); // Emulation COP
+        let mut cpu = cpu_with_code(&mut bus, false, 0x08, &[0x00, 0xEA]);
+        cpu.set_program_bank(0x00);
+        cpu.set_stack_pointer(0x01FF);
+
+        // Native mode pushes the program bank too, and BRK has no B flag to set
+        assert_eq!(cpu.step(&mut bus).unwrap(), 8);
+        assert_eq!(cpu.program_counter(), 0x9000);
+        assert_eq!(
+            [
+                bus.0[&0x01FF],
+                bus.0[&0x01FE],
+                bus.0[&0x01FD],
+                bus.0[&0x01FC]
+            ],
+            [0x00, 0x80, 0x02, 0x08]
+        );
+        assert!(cpu.flag_check(StatusFlags::NoInterrupts));
+        assert!(!cpu.flag_check(StatusFlags::Decimal));
+
+        let mut bus = SparseBus::default();
+        bus.load(0x00FFF4, &[0x00, 0xA0]);
+        let mut cpu = cpu_with_code(&mut bus, true, 0x00, &[0x02, 0x00]);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 7);
+        assert_eq!(cpu.program_counter(), 0xA000);
+        assert_eq!(cpu.stack_pointer(), 0x01FC);
+    }
+
+    #[test]
+    fn test_65816_mode_switch_and_decimal() {
+        let mut bus = SparseBus::default();
+        #[rustfmt::skip]
+        let mut cpu = cpu_with_code(&mut bus, false, 0x08, &[
+            0x69, 0x01, 0x00, // ADC #$0001
+            0x38,             // SEC
+            0xFB,             // XCE
+        ]);
+        cpu.set_accumulator(0x1999);
+        cpu.set_x_register(0x1234);
+        cpu.set_stack_pointer(0x1FF0);
+
+        assert_eq!(cpu.step(&mut bus).unwrap(), 3);
+        assert_eq!(cpu.accumulator(), 0x2000);
+        assert!(!cpu.flag_check(StatusFlags::Carry));
+        cpu.step(&mut bus).unwrap();
+        // Entering emulation mode forces 8-bit registers and the stack onto page 1
+        assert_eq!(cpu.step(&mut bus).unwrap(), 2);
+        assert!(cpu.emulation_mode());
+        assert!(!cpu.flag_check(StatusFlags::Carry));
+        assert_eq!(cpu.x_register(), 0x34);
+        assert_eq!(cpu.stack_pointer(), 0x01F0);
+        assert!(cpu.flag_check(StatusFlags::AccumulatorWidth | StatusFlags::IndexWidth));
+    }
+
+    #[test]
+    fn test_65816_branches_and_jumps() {
+        let mut bus = SparseBus::default();
+        bus.load(0x0080FD, &[0x80, 0x10]); // BRA +$10
+        bus.load(0x00810F, &[0x82, 0xEE, 0x0E]); // BRL +$0EEE
+        bus.load(0x009000, &[0xDC, 0x00, 0x10]); // JML [$1000]
+        bus.load(0x001000, &[0x00, 0x50, 0x02]);
+        let mut cpu = cpu_with_code(&mut bus, true, 0x00, &[]);
+        cpu.set_program_counter(0x80FD);
+
+        // Crossing a page costs a cycle in emulation mode only
+        assert_eq!(cpu.step(&mut bus).unwrap(), 4);
+        assert_eq!(cpu.program_counter(), 0x810F);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 4);
+        assert_eq!(cpu.program_counter(), 0x9000);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 6);
+        assert_eq!((cpu.program_bank(), cpu.program_counter()), (0x02, 0x5000));
+
+        let mut bus = SparseBus::default();
+        bus.load(0x0080FD, &[0x80, 0x10]);
+        let mut cpu = cpu_with_code(&mut bus, false, 0x30, &[]);
+        cpu.set_program_counter(0x80FD);
+        assert_eq!(cpu.step(&mut bus).unwrap(), 3);
+    }
+
+    #[derive(Deserialize)]
+    struct SingleStepState {
+        pc: u16,
+        s: u16,
+        p: u8,
+        a: u16,
+        x: u16,
+        y: u16,
+        dbr: u8,
+        d: u16,
+        pbr: u8,
+        e: u8,
+        ram: Vec<(u32, u8)>,
+    }
+
+    #[derive(Deserialize)]
+    struct SingleStepTest {
+        name: String,
+        initial: SingleStepState,
+        #[serde(rename = "final")]
+        expected: SingleStepState,
+        /// Address, data and pin states of every cycle. The pin states have `d` for VDA,
+        /// `p` for VPA and `r` or `w` for the direction, so cycles with neither VDA nor
+        /// VPA are internal operations.
+        cycles: Vec<(u32, Option<u8>, String)>,
+    }
+
+    /// Keeps every read and write, with the address, data and `r` or `w`
+    #[derive(Default)]
+    struct RecordingBus {
+        ram: SparseBus,
+        accesses: Vec<(u32, u8, char)>,
+    }
+
+    impl Bus for RecordingBus {
+        fn read(&mut self, address: u32) -> Result<u8, BusError> {
+            let value = self.ram.read(address)?;
+            self.accesses.push((address, value, 'r'));
+            Ok(value)
+        }
+
+        fn write(&mut self, address: u32, value: u8) -> Result<(), BusError> {
+            self.accesses.push((address, value, 'w'));
+            self.ram.write(address, value)
+        }
+    }
+
+    /// Run every test in a SingleStepTests JSON file: one instruction from the initial
+    /// state, compared against the final state and every bus cycle. The core doesn't put
+    /// internal operations on the bus, so those are only counted; every cycle with VDA
+    /// or VPA must match a read or write in order, with its address and data.
+    fn run_single_step_tests(test_data: &[u8]) {
+        let tests: Vec<SingleStepTest> =
+            serde_json::from_slice(test_data).expect("Failed to parse test suite");
+
+        for test in tests {
+            let mut bus = RecordingBus::default();
+            for &(address, value) in &test.initial.ram {
+                bus.ram.0.insert(address, value);
+            }
+            let mut cpu = WDC65C816::new();
+            cpu.set_emulation_mode(test.initial.e != 0);
+            cpu.set_status_register(StatusFlags::from(test.initial.p));
+            cpu.set_accumulator(test.initial.a);
+            cpu.set_x_register(test.initial.x);
+            cpu.set_y_register(test.initial.y);
+            cpu.set_stack_pointer(test.initial.s);
+            cpu.set_direct_page(test.initial.d);
+            cpu.set_data_bank(test.initial.dbr);
+            cpu.set_program_bank(test.initial.pbr);
+            cpu.set_program_counter(test.initial.pc);
+
+            let cycles = cpu.step(&mut bus).expect("Failed to step CPU");
+
+            let expected = &test.expected;
+            let name = &test.name;
+            assert_eq!(cpu.program_counter(), expected.pc, "{name}: pc");
+            assert_eq!(cpu.stack_pointer(), expected.s, "{name}: s");
+            assert_eq!(u8::from(cpu.status_register()), expected.p, "{name}: p");
+            assert_eq!(cpu.accumulator(), expected.a, "{name}: a");
+            assert_eq!(cpu.x_register(), expected.x, "{name}: x");
+            assert_eq!(cpu.y_register(), expected.y, "{name}: y");
+            assert_eq!(cpu.data_bank(), expected.dbr, "{name}: dbr");
+            assert_eq!(cpu.direct_page(), expected.d, "{name}: d");
+            assert_eq!(cpu.program_bank(), expected.pbr, "{name}: pbr");
+            assert_eq!(cpu.emulation_mode(), expected.e != 0, "{name}: e");
+            for &(address, value) in &expected.ram {
+                assert_eq!(bus.ram.0[&address], value, "{name}: ram at {address:06X}");
+            }
+
+            let expected_accesses: Vec<(u32, u8, char)> = test
+                .cycles
+                .iter()
+                .filter(|(_, _, pins)| pins.contains('d') || pins.contains('p'))
+                .map(|(address, value, pins)| {
+                    let direction = if pins.contains('w') { 'w' } else { 'r' };
+                    (*address, value.unwrap_or_default(), direction)
+                })
+                .collect();
+            assert_eq!(bus.accesses, expected_accesses, "{name}: bus cycles");
+            assert_eq!(cycles as usize, test.cycles.len(), "{name}: cycles");
+        }
+    }
+
+    /// A few cases in the SingleStepTests format, written by hand from the data sheet,
+    /// that cover both modes, 16-bit registers, long jumps, the direct page penalty and
+    /// block moves. They are no substitute for the full suite below.
+    #[test]
+    fn test_65816_single_step_sample() {
+        run_single_step_tests(include_bytes!("single_step.json"));
+    }
+
+    /// Runs Tom Harte's SingleStepTests for the 65816. Clone
+    /// https://github.com/SingleStepTests/65816 into `65816_tests` to enable it. The core
+    /// has not been run against the suite yet, so it is not known to pass; every opcode
+    /// file is run and the failing ones are listed together.
+    #[test]
+    #[ignore]
+    fn test_65816_single_step() {
+        let mut paths: Vec<_> = std::fs::read_dir("65816_tests/v1")
+            .expect("Failed to find SingleStepTests")
+            .map(|entry| entry.expect("Failed to list SingleStepTests").path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .collect();
+        paths.sort();
+
+        let failures: Vec<_> = paths
+            .iter()
+            .filter(|path| {
+                let test_data = std::fs::read(path).expect("Failed to load test suite");
+                std::panic::catch_unwind(|| run_single_step_tests(&test_data)).is_err()
+            })
+            .map(|path| path.file_stem().unwrap_or_default().to_string_lossy())
+            .collect();
+        assert!(
+            failures.is_empty(),
+            "{} of {} opcode files failed: {}",
+            failures.len(),
+            paths.len(),
+            failures.join(", ")
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    /// Binary or BCD addition into the accumulator. Subtraction adds the complement,
+    /// with the decimal adjustment running the other way.
+    fn add_to_accumulator_with_carry(&mut self, value: u16, wide: bool, subtract: bool) {
+        let (sign_bit, mask, nibbles): (i32, i32, u32) = if wide {
+            (0x8000, 0xFFFF, 4)
+        } else {
+            (0x80, 0xFF, 2)
+        };
+        let accumulator = self.accumulator as i32 & mask;
+        let operand = if subtract { !value } else { value } as i32 & mask;
+        let mut carry = self.flag_check(StatusFlags::Carry) as i32;
+
+        let (result, overflow) = if self.flag_check(StatusFlags::Decimal) {
+            let mut result = 0;
+            let mut overflow = false;
+            for nibble in 0..nibbles {
+                let shift = nibble * 4;
+                let nibble_mask = 0xF << shift;
+                let lower_digits = (1 << shift) - 1;
+                result = (accumulator & nibble_mask)
+                    + (operand & nibble_mask)
+                    + (carry << shift)
+                    + (result & lower_digits);
+                if nibble == nibbles - 1 {
+                    overflow = !(accumulator ^ operand) & (accumulator ^ result) & sign_bit != 0;
+                }
+                if subtract && result < 0x10 << shift {
+                    result -= 0x6 << shift;
+                } else if !subtract && result > (0x9 << shift) | lower_digits {
+                    result += 0x6 << shift;
+                }
+                carry = (result >= 0x10 << shift) as i32;
+            }
+            (result, overflow)
+        } else {
+            let result = accumulator + operand + carry;
+            carry = (result > mask) as i32;
+            let overflow = !(accumulator ^ operand) & (accumulator ^ result) & sign_bit != 0;
+            (result, overflow)
+        };
+
+        let result = (result & mask) as u16;
+        self.write_accumulator(result, wide);
+        self.flag_set(StatusFlags::Carry, carry != 0);
+        self.flag_set(StatusFlags::Overflow, overflow);
+        self.set_negative_and_zero(result, wide);
+    }
+
+    // add to accumulator with carry
+    pub(in crate::wdc65c816) fn adc(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let value = self.read_operand(bus, &resolved, wide)?;
+        self.add_to_accumulator_with_carry(value, wide, false);
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+
+    // subtract from accumulator with carry
+    pub(in crate::wdc65c816) fn sbc(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let value = self.read_operand(bus, &resolved, wide)?;
+        self.add_to_accumulator_with_carry(value, wide, true);
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+}
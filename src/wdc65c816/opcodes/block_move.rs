@@ -0,0 +1,52 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    /// Move one byte from source bank:X to destination bank:Y and decrement C. The
+    /// instruction re-executes until C wraps to $FFFF, so interrupts can be taken
+    /// between bytes.
+    fn move_block_byte(&mut self, bus: &mut T, increment: bool) -> Result<u32, CpuError> {
+        let destination_bank = self.fetch_byte(bus)?;
+        let source_bank = self.fetch_byte(bus)?;
+        self.data_bank = destination_bank;
+
+        let value = self.read_byte(bus, (source_bank as u32) << 16 | self.x_register as u32)?;
+        self.write_byte(
+            bus,
+            (destination_bank as u32) << 16 | self.y_register as u32,
+            value,
+        )?;
+
+        let index_mask = self.index_mask();
+        if increment {
+            self.x_register = self.x_register.wrapping_add(1) & index_mask;
+            self.y_register = self.y_register.wrapping_add(1) & index_mask;
+        } else {
+            self.x_register = self.x_register.wrapping_sub(1) & index_mask;
+            self.y_register = self.y_register.wrapping_sub(1) & index_mask;
+        }
+
+        self.accumulator = self.accumulator.wrapping_sub(1);
+        if self.accumulator != 0xFFFF {
+            self.program_counter = self.program_counter.wrapping_sub(3);
+        }
+        Ok(0)
+    }
+
+    // block move, incrementing
+    pub(in crate::wdc65c816) fn mvn(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.move_block_byte(bus, true)
+    }
+
+    // block move, decrementing
+    pub(in crate::wdc65c816) fn mvp(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.move_block_byte(bus, false)
+    }
+}
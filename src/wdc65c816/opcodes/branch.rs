@@ -0,0 +1,109 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    /// Taken branches cost a cycle, plus one more for crossing a page in emulation mode
+    #[inline(always)]
+    fn branch(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+        condition: bool,
+    ) -> Result<u32, CpuError> {
+        let target = match self.resolve_operand(bus, address_mode, false)?.operand {
+            OpcodeOperand::Address(address) => address as u16,
+            _ => return Err(CpuError::Invalid65816AddressingMode(address_mode)),
+        };
+        if !condition {
+            return Ok(0);
+        }
+        let page_changed = self.emulation_mode && (self.program_counter ^ target) & 0xFF00 != 0;
+        self.set_program_counter(target);
+        Ok(1 + page_changed as u32)
+    }
+
+    pub(in crate::wdc65c816) fn bcc(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, !self.flag_check(StatusFlags::Carry))
+    }
+
+    pub(in crate::wdc65c816) fn bcs(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, self.flag_check(StatusFlags::Carry))
+    }
+
+    pub(in crate::wdc65c816) fn beq(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, self.flag_check(StatusFlags::Zero))
+    }
+
+    pub(in crate::wdc65c816) fn bmi(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, self.flag_check(StatusFlags::Negative))
+    }
+
+    pub(in crate::wdc65c816) fn bne(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, !self.flag_check(StatusFlags::Zero))
+    }
+
+    pub(in crate::wdc65c816) fn bpl(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, !self.flag_check(StatusFlags::Negative))
+    }
+
+    pub(in crate::wdc65c816) fn bra(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, true)
+    }
+
+    pub(in crate::wdc65c816) fn bvc(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, !self.flag_check(StatusFlags::Overflow))
+    }
+
+    pub(in crate::wdc65c816) fn bvs(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.branch(bus, address_mode, self.flag_check(StatusFlags::Overflow))
+    }
+
+    // branch always, long
+    pub(in crate::wdc65c816) fn brl(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let target = match self.resolve_operand(bus, address_mode, false)?.operand {
+            OpcodeOperand::Address(address) => address as u16,
+            _ => return Err(CpuError::Invalid65816AddressingMode(address_mode)),
+        };
+        self.set_program_counter(target);
+        Ok(0)
+    }
+}
@@ -0,0 +1,47 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    #[inline(always)]
+    fn compare_register(
+        &mut self,
+        register: u16,
+        wide: bool,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let operand = self.read_operand(bus, &resolved, wide)?;
+        let register = if wide { register } else { register & 0xFF };
+
+        self.flag_set(StatusFlags::Carry, register >= operand);
+        self.set_negative_and_zero(register.wrapping_sub(operand), wide);
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+
+    pub(in crate::wdc65c816) fn cmp(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        self.compare_register(self.accumulator, wide, bus, address_mode)
+    }
+
+    pub(in crate::wdc65c816) fn cpx(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.index_is_wide();
+        self.compare_register(self.x_register, wide, bus, address_mode)
+    }
+
+    pub(in crate::wdc65c816) fn cpy(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.index_is_wide();
+        self.compare_register(self.y_register, wide, bus, address_mode)
+    }
+}
@@ -0,0 +1,68 @@
+use crate::wdc65c816::*;
+
+macro_rules! step_index_register {
+    ($cpu:expr, $register:expr, $method:ident) => {
+        let wide = $cpu.index_is_wide();
+        $register = $register.$method(1) & $cpu.index_mask();
+        $cpu.set_negative_and_zero($register, wide);
+        return Ok(0);
+    };
+}
+
+impl<T: Bus> WDC65C816<T> {
+    pub(in crate::wdc65c816) fn dec(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let value = value.wrapping_sub(1);
+            cpu.set_negative_and_zero(value, wide);
+            value
+        })
+    }
+
+    pub(in crate::wdc65c816) fn dex(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        step_index_register!(self, self.x_register, wrapping_sub);
+    }
+
+    pub(in crate::wdc65c816) fn dey(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        step_index_register!(self, self.y_register, wrapping_sub);
+    }
+
+    pub(in crate::wdc65c816) fn inc(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let value = value.wrapping_add(1);
+            cpu.set_negative_and_zero(value, wide);
+            value
+        })
+    }
+
+    pub(in crate::wdc65c816) fn inx(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        step_index_register!(self, self.x_register, wrapping_add);
+    }
+
+    pub(in crate::wdc65c816) fn iny(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        step_index_register!(self, self.y_register, wrapping_add);
+    }
+}
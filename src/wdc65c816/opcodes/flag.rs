@@ -0,0 +1,100 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    pub(in crate::wdc65c816) fn clc(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.flag_set(StatusFlags::Carry, false);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn cld(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.flag_set(StatusFlags::Decimal, false);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn cli(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.flag_set(StatusFlags::NoInterrupts, false);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn clv(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.flag_set(StatusFlags::Overflow, false);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn sec(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.flag_set(StatusFlags::Carry, true);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn sed(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.flag_set(StatusFlags::Decimal, true);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn sei(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.flag_set(StatusFlags::NoInterrupts, true);
+        Ok(0)
+    }
+
+    // reset status bits
+    pub(in crate::wdc65c816) fn rep(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let mask = self.fetch_byte(bus)?;
+        self.set_status_register(self.status_register & !StatusFlags::from(mask));
+        Ok(0)
+    }
+
+    // set status bits
+    pub(in crate::wdc65c816) fn sep(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let mask = self.fetch_byte(bus)?;
+        self.set_status_register(self.status_register | StatusFlags::from(mask));
+        Ok(0)
+    }
+
+    // exchange carry and emulation bits
+    pub(in crate::wdc65c816) fn xce(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let carry = self.flag_check(StatusFlags::Carry);
+        self.flag_set(StatusFlags::Carry, self.emulation_mode);
+        self.set_emulation_mode(carry);
+        Ok(0)
+    }
+}
@@ -0,0 +1,62 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    pub(in crate::wdc65c816) fn brk(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.fetch_byte(bus)?;
+        self.perform_interrupt(bus, InterruptKind::Brk)?;
+        Ok(!self.emulation_mode as u32)
+    }
+
+    // co-processor enable, a software interrupt with its own vector
+    pub(in crate::wdc65c816) fn cop(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.fetch_byte(bus)?;
+        self.perform_interrupt(bus, InterruptKind::Cop)?;
+        Ok(!self.emulation_mode as u32)
+    }
+
+    pub(in crate::wdc65c816) fn rti(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let status_register_value = self.pop_from_stack(bus)?;
+        self.set_status_register(StatusFlags::from(status_register_value));
+        let return_address_lo = self.pop_from_stack(bus)?;
+        let return_address_hi = self.pop_from_stack(bus)?;
+        self.set_program_counter(u16::from_le_bytes([return_address_lo, return_address_hi]));
+
+        if self.emulation_mode {
+            return Ok(0);
+        }
+        self.program_bank = self.pop_from_stack(bus)?;
+        Ok(1)
+    }
+
+    // wait for interrupt
+    pub(in crate::wdc65c816) fn wai(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.waiting = true;
+        Ok(0)
+    }
+
+    // stop the clock
+    pub(in crate::wdc65c816) fn stp(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.stopped = true;
+        Ok(0)
+    }
+}
@@ -0,0 +1,115 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    pub(in crate::wdc65c816) fn jmp(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let new_pc_value = match address_mode {
+            AddressingMode::Absolute => self.fetch_word(bus)?,
+            AddressingMode::AbsoluteLong => {
+                let address = self.fetch_long(bus)?;
+                self.program_bank = (address >> 16) as u8;
+                address as u16
+            }
+            AddressingMode::Indirect => {
+                let pointer_address = self.fetch_word(bus)?;
+                self.read_bank_zero_word(bus, pointer_address)?
+            }
+            AddressingMode::AbsoluteXIndexIndirect => {
+                let pointer_address = self.fetch_word(bus)?.wrapping_add(self.x_register);
+                self.read_program_bank_word(bus, pointer_address)?
+            }
+            AddressingMode::AbsoluteIndirectLong => {
+                let pointer_address = self.fetch_word(bus)?;
+                let new_pc_value = self.read_bank_zero_word(bus, pointer_address)?;
+                self.program_bank = self.read_byte(bus, pointer_address.wrapping_add(2) as u32)?;
+                new_pc_value
+            }
+            _ => return Err(CpuError::Invalid65816AddressingMode(address_mode)),
+        };
+        self.set_program_counter(new_pc_value);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn jsr(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let target = self.fetch_word(bus)?;
+        let return_address = self.program_counter.wrapping_sub(1);
+        let (return_address_lo, return_address_hi): (u8, u8) = return_address.to_le_bytes().into();
+
+        let new_pc_value = match address_mode {
+            AddressingMode::Absolute => {
+                self.push_to_stack(bus, return_address_hi)?;
+                self.push_to_stack(bus, return_address_lo)?;
+                target
+            }
+            AddressingMode::AbsoluteXIndexIndirect => {
+                self.push_word_unwrapped(bus, return_address)?;
+                self.wrap_stack_pointer();
+                self.read_program_bank_word(bus, target.wrapping_add(self.x_register))?
+            }
+            _ => return Err(CpuError::Invalid65816AddressingMode(address_mode)),
+        };
+        self.set_program_counter(new_pc_value);
+        Ok(0)
+    }
+
+    // jump to subroutine, long
+    pub(in crate::wdc65c816) fn jsl(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        // The program bank is pushed before the bank byte of the target is fetched
+        let target = self.fetch_word(bus)?;
+        self.push_to_stack_unwrapped(bus, self.program_bank)?;
+        let bank = self.fetch_byte(bus)?;
+        self.push_word_unwrapped(bus, self.program_counter.wrapping_sub(1))?;
+        self.wrap_stack_pointer();
+
+        self.program_bank = bank;
+        self.set_program_counter(target);
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn rts(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let return_address_lo = self.pop_from_stack(bus)?;
+        let return_address_hi = self.pop_from_stack(bus)?;
+
+        let return_address = u16::from_le_bytes([return_address_lo, return_address_hi]);
+        self.set_program_counter(return_address.wrapping_add(1));
+        Ok(0)
+    }
+
+    // return from subroutine, long
+    pub(in crate::wdc65c816) fn rtl(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let return_address = self.pop_word_unwrapped(bus)?;
+        self.program_bank = self.pop_from_stack_unwrapped(bus)?;
+        self.wrap_stack_pointer();
+
+        self.set_program_counter(return_address.wrapping_add(1));
+        Ok(0)
+    }
+
+    /// Read a word from the program bank, wrapping within the bank
+    #[inline]
+    fn read_program_bank_word(&mut self, bus: &mut T, address: u16) -> Result<u16, BusError> {
+        let bank = (self.program_bank as u32) << 16;
+        let low_byte = self.read_byte(bus, bank | address as u32)?;
+        let high_byte = self.read_byte(bus, bank | address.wrapping_add(1) as u32)?;
+        Ok(u16::from_le_bytes([low_byte, high_byte]))
+    }
+}
@@ -0,0 +1,42 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    pub(in crate::wdc65c816) fn and(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let result = self.accumulator & self.read_operand(bus, &resolved, wide)?;
+        self.write_accumulator(result, wide);
+        self.set_negative_and_zero(result, wide);
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+
+    pub(in crate::wdc65c816) fn eor(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let result = self.accumulator ^ self.read_operand(bus, &resolved, wide)?;
+        self.write_accumulator(result, wide);
+        self.set_negative_and_zero(result, wide);
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+
+    pub(in crate::wdc65c816) fn ora(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let result = self.accumulator | self.read_operand(bus, &resolved, wide)?;
+        self.write_accumulator(result, wide);
+        self.set_negative_and_zero(result, wide);
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+}
@@ -0,0 +1,13 @@
+pub(in crate::wdc65c816) mod arithmetic;
+pub(in crate::wdc65c816) mod block_move;
+pub(in crate::wdc65c816) mod branch;
+pub(in crate::wdc65c816) mod comparison;
+pub(in crate::wdc65c816) mod dec_and_inc;
+pub(in crate::wdc65c816) mod flag;
+pub(in crate::wdc65c816) mod interrupt;
+pub(in crate::wdc65c816) mod jump;
+pub(in crate::wdc65c816) mod logical;
+pub(in crate::wdc65c816) mod other;
+pub(in crate::wdc65c816) mod shift_and_rotate;
+pub(in crate::wdc65c816) mod stack;
+pub(in crate::wdc65c816) mod transfer;
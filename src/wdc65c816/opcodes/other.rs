@@ -0,0 +1,71 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    pub(in crate::wdc65c816) fn nop(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        Ok(0)
+    }
+
+    // reserved two-byte no-op
+    pub(in crate::wdc65c816) fn wdm(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.fetch_byte(bus)?;
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn bit(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let operand = self.read_operand(bus, &resolved, wide)?;
+
+        let accumulator = if wide {
+            self.accumulator
+        } else {
+            self.accumulator & 0xFF
+        };
+        self.flag_set(StatusFlags::Zero, operand & accumulator == 0);
+        // The immediate form only affects Z
+        if !matches!(resolved.operand, OpcodeOperand::Immediate(_)) {
+            let sign_bit = if wide { 15 } else { 7 };
+            self.flag_set(StatusFlags::Negative, operand & (1 << sign_bit) != 0);
+            self.flag_set(StatusFlags::Overflow, operand & (1 << (sign_bit - 1)) != 0);
+        }
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+
+    // test and reset bits
+    pub(in crate::wdc65c816) fn trb(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let mask = if wide { 0xFFFF } else { 0xFF };
+            cpu.flag_set(StatusFlags::Zero, value & cpu.accumulator & mask == 0);
+            value & !cpu.accumulator
+        })
+    }
+
+    // test and set bits
+    pub(in crate::wdc65c816) fn tsb(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let mask = if wide { 0xFFFF } else { 0xFF };
+            cpu.flag_set(StatusFlags::Zero, value & cpu.accumulator & mask == 0);
+            value | cpu.accumulator
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    pub(in crate::wdc65c816) fn asl(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let sign_bit = if wide { 0x8000 } else { 0x80 };
+            let result = value.wrapping_shl(1);
+            cpu.flag_set(StatusFlags::Carry, value & sign_bit != 0);
+            cpu.set_negative_and_zero(result, wide);
+            result
+        })
+    }
+
+    pub(in crate::wdc65c816) fn lsr(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let value = if wide { value } else { value & 0xFF };
+            let result = value.wrapping_shr(1);
+            cpu.flag_set(StatusFlags::Carry, value & 1 != 0);
+            cpu.set_negative_and_zero(result, wide);
+            result
+        })
+    }
+
+    pub(in crate::wdc65c816) fn rol(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let sign_bit = if wide { 0x8000 } else { 0x80 };
+            let result = value.wrapping_shl(1) | cpu.flag_check(StatusFlags::Carry) as u16;
+            cpu.flag_set(StatusFlags::Carry, value & sign_bit != 0);
+            cpu.set_negative_and_zero(result, wide);
+            result
+        })
+    }
+
+    pub(in crate::wdc65c816) fn ror(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.read_modify_write(bus, address_mode, |cpu, value, wide| {
+            let (value, carry_bit_mask) = if wide {
+                (value, (cpu.flag_check(StatusFlags::Carry) as u16) << 15)
+            } else {
+                (
+                    value & 0xFF,
+                    (cpu.flag_check(StatusFlags::Carry) as u16) << 7,
+                )
+            };
+            let result = value.wrapping_shr(1) | carry_bit_mask;
+            cpu.flag_set(StatusFlags::Carry, value & 1 != 0);
+            cpu.set_negative_and_zero(result, wide);
+            result
+        })
+    }
+}
@@ -0,0 +1,192 @@
+use crate::wdc65c816::*;
+
+impl<T: Bus> WDC65C816<T> {
+    /// Push a register, high byte first when it is 16-bit wide
+    #[inline(always)]
+    fn push_register(&mut self, bus: &mut T, value: u16, wide: bool) -> Result<u32, CpuError> {
+        let (low_byte, high_byte): (u8, u8) = value.to_le_bytes().into();
+        if wide {
+            self.push_to_stack(bus, high_byte)?;
+        }
+        self.push_to_stack(bus, low_byte)?;
+        Ok(wide as u32)
+    }
+
+    #[inline(always)]
+    fn pop_register(&mut self, bus: &mut T, wide: bool) -> Result<u16, CpuError> {
+        let low_byte = self.pop_from_stack(bus)?;
+        let high_byte = if wide { self.pop_from_stack(bus)? } else { 0 };
+        let value = u16::from_le_bytes([low_byte, high_byte]);
+        self.set_negative_and_zero(value, wide);
+        Ok(value)
+    }
+
+    pub(in crate::wdc65c816) fn pha(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        self.push_register(bus, self.accumulator, wide)
+    }
+
+    pub(in crate::wdc65c816) fn phx(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.index_is_wide();
+        self.push_register(bus, self.x_register, wide)
+    }
+
+    pub(in crate::wdc65c816) fn phy(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.index_is_wide();
+        self.push_register(bus, self.y_register, wide)
+    }
+
+    pub(in crate::wdc65c816) fn php(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.push_to_stack(bus, self.status_register.into())?;
+        Ok(0)
+    }
+
+    // push data bank register
+    pub(in crate::wdc65c816) fn phb(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.push_to_stack(bus, self.data_bank)?;
+        Ok(0)
+    }
+
+    // push program bank register
+    pub(in crate::wdc65c816) fn phk(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.push_to_stack(bus, self.program_bank)?;
+        Ok(0)
+    }
+
+    // push direct page register
+    pub(in crate::wdc65c816) fn phd(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.push_word_unwrapped(bus, self.direct_page)?;
+        self.wrap_stack_pointer();
+        Ok(0)
+    }
+
+    pub(in crate::wdc65c816) fn pla(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let value = self.pop_register(bus, wide)?;
+        self.write_accumulator(value, wide);
+        Ok(wide as u32)
+    }
+
+    pub(in crate::wdc65c816) fn plx(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.index_is_wide();
+        self.x_register = self.pop_register(bus, wide)?;
+        Ok(wide as u32)
+    }
+
+    pub(in crate::wdc65c816) fn ply(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.index_is_wide();
+        self.y_register = self.pop_register(bus, wide)?;
+        Ok(wide as u32)
+    }
+
+    pub(in crate::wdc65c816) fn plp(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let status_register_value = self.pop_from_stack(bus)?;
+        self.set_status_register(StatusFlags::from(status_register_value));
+        Ok(0)
+    }
+
+    // pull data bank register
+    pub(in crate::wdc65c816) fn plb(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.data_bank = self.pop_from_stack_unwrapped(bus)?;
+        self.wrap_stack_pointer();
+        self.set_negative_and_zero(self.data_bank as u16, false);
+        Ok(0)
+    }
+
+    // pull direct page register
+    pub(in crate::wdc65c816) fn pld(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.direct_page = self.pop_word_unwrapped(bus)?;
+        self.wrap_stack_pointer();
+        self.set_negative_and_zero(self.direct_page, true);
+        Ok(0)
+    }
+
+    // push effective absolute address
+    pub(in crate::wdc65c816) fn pea(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let value = self.fetch_word(bus)?;
+        self.push_word_unwrapped(bus, value)?;
+        self.wrap_stack_pointer();
+        Ok(0)
+    }
+
+    // push effective indirect address
+    pub(in crate::wdc65c816) fn pei(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let resolved = self.resolve_operand(bus, address_mode, false)?;
+        let value = self.read_operand(bus, &resolved, true)?;
+        self.push_word_unwrapped(bus, value)?;
+        self.wrap_stack_pointer();
+        Ok(resolved.direct_penalty)
+    }
+
+    // push effective PC-relative address
+    pub(in crate::wdc65c816) fn per(
+        &mut self,
+        bus: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let offset = self.fetch_word(bus)?;
+        self.push_word_unwrapped(bus, self.program_counter.wrapping_add(offset))?;
+        self.wrap_stack_pointer();
+        Ok(0)
+    }
+}
@@ -0,0 +1,267 @@
+use crate::wdc65c816::*;
+
+macro_rules! load_value_to_register {
+    ($cpu:expr, $register:expr, $wide:expr, $address_mode:ident, $bus:expr) => {
+        let wide = $wide;
+        let resolved = $cpu.resolve_operand($bus, $address_mode, wide)?;
+        let value = $cpu.read_operand($bus, &resolved, wide)?;
+        $register = value;
+        $cpu.set_negative_and_zero(value, wide);
+        return Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty);
+    };
+}
+
+macro_rules! store_register_value {
+    ($cpu:expr, $register:expr, $wide:expr, $address_mode:ident, $bus:expr) => {
+        let wide = $wide;
+        let resolved = $cpu.resolve_operand($bus, $address_mode, wide)?;
+        $cpu.write_operand($bus, &resolved, $register, wide, false)?;
+        return Ok(wide as u32 + resolved.direct_penalty);
+    };
+}
+
+macro_rules! transfer_to_index_register {
+    ($cpu:expr, $source_register:expr, $target_register:expr) => {
+        let wide = $cpu.index_is_wide();
+        $target_register = $source_register & $cpu.index_mask();
+        $cpu.set_negative_and_zero($target_register, wide);
+        return Ok(0);
+    };
+}
+
+macro_rules! transfer_to_accumulator {
+    ($cpu:expr, $source_register:expr) => {
+        let wide = $cpu.accumulator_is_wide();
+        let value = $source_register;
+        $cpu.write_accumulator(value, wide);
+        $cpu.set_negative_and_zero(value, wide);
+        return Ok(0);
+    };
+}
+
+impl<T: Bus> WDC65C816<T> {
+    // load value into accumulator
+    pub(in crate::wdc65c816) fn lda(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        let wide = self.accumulator_is_wide();
+        let resolved = self.resolve_operand(bus, address_mode, wide)?;
+        let value = self.read_operand(bus, &resolved, wide)?;
+        self.write_accumulator(value, wide);
+        self.set_negative_and_zero(value, wide);
+        Ok(wide as u32 + resolved.direct_penalty + resolved.index_penalty)
+    }
+
+    // load value into X register
+    pub(in crate::wdc65c816) fn ldx(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        load_value_to_register!(
+            self,
+            self.x_register,
+            self.index_is_wide(),
+            address_mode,
+            bus
+        );
+    }
+
+    // load value into Y register
+    pub(in crate::wdc65c816) fn ldy(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        load_value_to_register!(
+            self,
+            self.y_register,
+            self.index_is_wide(),
+            address_mode,
+            bus
+        );
+    }
+
+    // store accumulator in memory
+    pub(in crate::wdc65c816) fn sta(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        store_register_value!(
+            self,
+            self.accumulator,
+            self.accumulator_is_wide(),
+            address_mode,
+            bus
+        );
+    }
+
+    // store X register in memory
+    pub(in crate::wdc65c816) fn stx(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        store_register_value!(
+            self,
+            self.x_register,
+            self.index_is_wide(),
+            address_mode,
+            bus
+        );
+    }
+
+    // store Y register in memory
+    pub(in crate::wdc65c816) fn sty(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        store_register_value!(
+            self,
+            self.y_register,
+            self.index_is_wide(),
+            address_mode,
+            bus
+        );
+    }
+
+    // store zero in memory
+    pub(in crate::wdc65c816) fn stz(
+        &mut self,
+        bus: &mut T,
+        address_mode: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        store_register_value!(self, 0, self.accumulator_is_wide(), address_mode, bus);
+    }
+
+    // transfer accumulator to X register
+    pub(in crate::wdc65c816) fn tax(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_to_index_register!(self, self.accumulator, self.x_register);
+    }
+
+    // transfer accumulator to Y register
+    pub(in crate::wdc65c816) fn tay(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_to_index_register!(self, self.accumulator, self.y_register);
+    }
+
+    // transfer stack pointer to X register
+    pub(in crate::wdc65c816) fn tsx(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_to_index_register!(self, self.stack_pointer, self.x_register);
+    }
+
+    // transfer Y register to X register
+    pub(in crate::wdc65c816) fn tyx(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_to_index_register!(self, self.y_register, self.x_register);
+    }
+
+    // transfer X register to Y register
+    pub(in crate::wdc65c816) fn txy(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_to_index_register!(self, self.x_register, self.y_register);
+    }
+
+    // transfer X register to accumulator
+    pub(in crate::wdc65c816) fn txa(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_to_accumulator!(self, self.x_register);
+    }
+
+    // transfer Y register to accumulator
+    pub(in crate::wdc65c816) fn tya(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        transfer_to_accumulator!(self, self.y_register);
+    }
+
+    // transfer X register to stack pointer
+    pub(in crate::wdc65c816) fn txs(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.set_stack_pointer(self.x_register);
+        Ok(0)
+    }
+
+    // transfer 16-bit accumulator to stack pointer
+    pub(in crate::wdc65c816) fn tcs(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.set_stack_pointer(self.accumulator);
+        Ok(0)
+    }
+
+    // transfer stack pointer to 16-bit accumulator
+    pub(in crate::wdc65c816) fn tsc(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.accumulator = self.stack_pointer;
+        self.set_negative_and_zero(self.accumulator, true);
+        Ok(0)
+    }
+
+    // transfer 16-bit accumulator to direct page register
+    pub(in crate::wdc65c816) fn tcd(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.direct_page = self.accumulator;
+        self.set_negative_and_zero(self.direct_page, true);
+        Ok(0)
+    }
+
+    // transfer direct page register to 16-bit accumulator
+    pub(in crate::wdc65c816) fn tdc(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.accumulator = self.direct_page;
+        self.set_negative_and_zero(self.accumulator, true);
+        Ok(0)
+    }
+
+    // exchange the accumulator's high and low bytes
+    pub(in crate::wdc65c816) fn xba(
+        &mut self,
+        _: &mut T,
+        _: AddressingMode,
+    ) -> Result<u32, CpuError> {
+        self.accumulator = self.accumulator.swap_bytes();
+        self.set_negative_and_zero(self.accumulator, false);
+        Ok(0)
+    }
+}